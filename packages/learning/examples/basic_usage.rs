@@ -100,6 +100,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         confidence: 0.95,
         timestamp: chrono::Utc::now(),
         metadata: std::collections::HashMap::new(),
+        time_range: None,
+        language: None,
     };
     
     match analysis_engine.analyze_extracted_text(extracted_text).await {
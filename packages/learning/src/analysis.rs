@@ -4,6 +4,19 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 
 use crate::llm::{AnalysisResult as LLMAnalysisResult, LocalLLM, SentimentResult};
+use crate::code::{self, CODE_METADATA_KEY};
+use crate::confidence::{ConfidenceConfig, ConfidenceModel, ConfidenceReport, Signal, SignalKind, CONFIDENCE_METADATA_KEY};
+use crate::diagnostics::{self, DIAGNOSTICS_METADATA_KEY};
+use crate::export::{ExportFormat, SessionExporter};
+use crate::tasks::{TaskExporter, TaskFormat};
+use crate::fusion::{self, TimeRange, ALIGNMENTS_METADATA_KEY};
+use crate::language::{self, DetectedLanguage, LANGUAGE_METADATA_KEY};
+use crate::meeting::{self, MeetingAnalysis, MeetingMinutes, MEETING_DATE_METADATA_KEY, MEETING_MINUTES_METADATA_KEY};
+use crate::ocr::diff::{self as frame_diff, PerceptualHash, FRAME_HASH_METADATA_KEY};
+use crate::ocr::math::{self, EQUATIONS_METADATA_KEY};
+use crate::redact::{AuditEntry, RedactionAudit, RedactionConfig, Redactor, REDACTIONS_METADATA_KEY};
+use crate::rules::{RuleContext, RuleEngine};
+use crate::store::SessionStore;
 
 /// Configuration for the analysis engine
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,10 +26,30 @@ pub struct AnalysisConfig {
     pub enable_summarization: bool,
     pub enable_insight_generation: bool,
     pub max_context_length: usize,
+    /// Combined confidence below which a result is flagged as low confidence
     pub confidence_threshold: f32,
     pub enable_caching: bool,
+    /// Text similarity at or above which a capture repeats a recent one and is skipped
+    #[serde(default = "default_near_duplicate_threshold")]
+    pub near_duplicate_threshold: f32,
+    /// Recommendation rule file or directory (TOML/YAML); the built-in pack is used when unset
+    #[serde(default)]
+    pub rules_path: Option<String>,
+    /// PII and secret redaction applied before anything is cached, sent to the LLM or persisted
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    /// Calibration curves and weights for combining OCR, ASR and model confidences
+    #[serde(default)]
+    pub confidence: ConfidenceConfig,
 }
 
+fn default_near_duplicate_threshold() -> f32 {
+    0.9
+}
+
+/// Number of recent turns checked for near-duplicate captures
+const NEAR_DUPLICATE_WINDOW: usize = 5;
+
 impl Default for AnalysisConfig {
     fn default() -> Self {
         Self {
@@ -27,6 +60,10 @@ impl Default for AnalysisConfig {
             max_context_length: 10000,
             confidence_threshold: 0.6,
             enable_caching: true,
+            near_duplicate_threshold: default_near_duplicate_threshold(),
+            rules_path: None,
+            redaction: RedactionConfig::default(),
+            confidence: ConfidenceConfig::default(),
         }
     }
 }
@@ -37,6 +74,11 @@ pub struct AnalysisEngine {
     config: AnalysisConfig,
     cache: Arc<Mutex<std::collections::HashMap<String, AnalysisResult>>>,
     session_context: Arc<Mutex<SessionContext>>,
+    store: Option<Arc<SessionStore>>,
+    rules: RuleEngine,
+    redactor: Redactor,
+    redaction_log: Arc<Mutex<Vec<RedactionAudit>>>,
+    confidence: ConfidenceModel,
 }
 
 /// Session context for maintaining conversation state
@@ -62,11 +104,13 @@ pub struct ConversationTurn {
 }
 
 /// Type of content being analyzed
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ContentType {
     Screenshot,
     Audio,
     Text,
+    Document,
+    Code,
     Combined,
 }
 
@@ -78,6 +122,12 @@ pub struct ExtractedText {
     pub confidence: f32,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub metadata: std::collections::HashMap<String, String>,
+    /// When the content was on screen or spoken, on the session timeline
+    #[serde(default)]
+    pub time_range: Option<TimeRange>,
+    /// Language detected in the text; analysis detects it when OCR or ASR did not
+    #[serde(default)]
+    pub language: Option<DetectedLanguage>,
 }
 
 /// Comprehensive analysis result
@@ -128,6 +178,42 @@ pub enum Priority {
     Critical,
 }
 
+/// What stages outside the engine found alongside the text, such as a screenshot's
+/// OCR word confidences, tables and code blocks. The engine folds these into the
+/// result before it is redacted, cached or persisted.
+#[derive(Debug, Clone, Default)]
+pub struct Attachments {
+    /// Confidence signals scored together with the engine's own
+    pub signals: Vec<Signal>,
+    pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    pub insights: Vec<String>,
+    pub recommendations: Vec<Recommendation>,
+}
+
+impl Attachments {
+    /// Distinguishes cache entries for the same text with different attachments.
+    /// Attachments are not redacted yet, so only a hash of them goes into the key.
+    fn cache_suffix(&self) -> String {
+        use std::hash::{Hash, Hasher};
+
+        if self.metadata.is_empty() && self.insights.is_empty() {
+            return String::new();
+        }
+        let metadata: std::collections::BTreeMap<_, _> = self.metadata.iter().collect();
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        serde_json::to_string(&metadata).unwrap_or_default().hash(&mut hasher);
+        self.insights.hash(&mut hasher);
+        format!(":{:016x}", hasher.finish())
+    }
+
+    /// Add everything except the confidence signals to a result
+    fn attach_to(self, analysis: &mut AnalysisResult) {
+        analysis.metadata.extend(self.metadata);
+        analysis.insights.extend(self.insights);
+        analysis.recommendations.extend(self.recommendations);
+    }
+}
+
 impl AnalysisEngine {
     /// Create a new analysis engine
     pub fn new(llm: Arc<LocalLLM>) -> Self {
@@ -140,6 +226,11 @@ impl AnalysisEngine {
             config,
             cache,
             session_context,
+            store: None,
+            rules: RuleEngine::new(),
+            redactor: Redactor::new(),
+            redaction_log: Arc::new(Mutex::new(Vec::new())),
+            confidence: ConfidenceModel::new(),
         }
     }
 
@@ -147,34 +238,100 @@ impl AnalysisEngine {
     pub fn with_config(llm: Arc<LocalLLM>, config: AnalysisConfig) -> Self {
         let cache = Arc::new(Mutex::new(std::collections::HashMap::new()));
         let session_context = Arc::new(Mutex::new(SessionContext::new()));
+        let rules = match &config.rules_path {
+            Some(path) => RuleEngine::from_path(path).unwrap_or_else(|e| {
+                eprintln!("Failed to load recommendation rules from {}: {:#}", path, e);
+                RuleEngine::new()
+            }),
+            None => RuleEngine::new(),
+        };
+
+        let redactor = Redactor::with_config(config.redaction.clone());
+        let confidence = ConfidenceModel::with_config(ConfidenceConfig {
+            low_confidence_threshold: config.confidence_threshold,
+            ..config.confidence.clone()
+        });
 
         Self {
             llm,
             config,
             cache,
             session_context,
+            store: None,
+            rules,
+            redactor,
+            redaction_log: Arc::new(Mutex::new(Vec::new())),
+            confidence,
         }
     }
 
+    /// Create analysis engine that persists every session turn to a store
+    pub fn with_store(llm: Arc<LocalLLM>, config: AnalysisConfig, store: Arc<SessionStore>) -> Self {
+        let mut engine = Self::with_config(llm, config);
+        engine.store = Some(store);
+        engine
+    }
+
     /// Analyze text content
     pub async fn analyze_text(&self, text: &str) -> Result<AnalysisResult> {
+        self.analyze_text_in_language(text, None).await
+    }
+
+    /// Analyze text whose language OCR or ASR already detected; `None` detects it here
+    pub async fn analyze_text_in_language(&self, text: &str, detected: Option<DetectedLanguage>) -> Result<AnalysisResult> {
+        self.analyze_text_with(text, detected, Attachments::default()).await
+    }
+
+    /// Analyze text together with what other stages found alongside it. The
+    /// attachments are part of the result that gets redacted, cached and persisted.
+    pub async fn analyze_text_with(
+        &self,
+        text: &str,
+        detected: Option<DetectedLanguage>,
+        attachments: Attachments,
+    ) -> Result<AnalysisResult> {
+        // Strip PII and secrets before the text reaches the cache, the LLM or the store
+        let redaction = self.redactor.redact_field("original_content", text);
+        let text = redaction.text.as_str();
+        let cache_key = format!("text:{}{}", text, attachments.cache_suffix());
+
         // Check cache first
         if self.config.enable_caching {
-            let cache_key = format!("text:{}", text);
             let cache = self.cache.lock().await;
             if let Some(cached_result) = cache.get(&cache_key) {
                 return Ok(cached_result.clone());
             }
         }
 
+        // Route analysis by language; text in one the heuristics don't cover is reported instead
+        let detected = detected.or_else(|| language::detect(text));
+        if let Some(unsupported) = detected.as_ref().filter(|language| !language.supported) {
+            return self
+                .report_unsupported_language(text, None, unsupported, attachments, cache_key, redaction.entries)
+                .await;
+        }
+
         // Add to LLM context
         self.llm.add_to_context(text).await?;
 
         // Perform LLM analysis
-        let llm_analysis = self.llm.analyze_with_context(text).await?;
+        let llm_analysis = self
+            .llm
+            .analyze_with_context_in(text, language::analysis_profile(detected.as_ref()))
+            .await?;
+
+        let mut metadata = self.extract_metadata(text);
+        let confidence = self.score_confidence(attachments.signals.clone(), text, &llm_analysis);
+        metadata.insert(CONFIDENCE_METADATA_KEY.to_string(), serde_json::to_value(&confidence)?);
+        if let Some(detected) = &detected {
+            metadata.insert(LANGUAGE_METADATA_KEY.to_string(), serde_json::to_value(detected)?);
+        }
+        let recommendations = self
+            .generate_recommendations(text, &ContentType::Text, &llm_analysis, &metadata)
+            .await?;
 
         // Create comprehensive analysis result
-        let analysis_result = AnalysisResult {
+        let mut analysis_result = AnalysisResult {
             analysis_id: self.generate_analysis_id(),
             timestamp: chrono::Utc::now(),
             content_type: ContentType::Text,
@@ -184,15 +341,16 @@ impl AnalysisEngine {
             topics: llm_analysis.topics,
             summary: llm_analysis.summary,
             insights: llm_analysis.insights,
-            confidence: self.calculate_confidence(text),
+            confidence: confidence.score,
             context_used: llm_analysis.context_used,
-            recommendations: self.generate_recommendations(text, &llm_analysis).await?,
-            metadata: self.extract_metadata(text),
+            recommendations,
+            metadata,
         };
+        attachments.attach_to(&mut analysis_result);
+        self.apply_redaction(&mut analysis_result, redaction.entries).await?;
 
         // Cache the result
         if self.config.enable_caching {
-            let cache_key = format!("text:{}", text);
             let mut cache = self.cache.lock().await;
             cache.insert(cache_key, analysis_result.clone());
         }
@@ -204,26 +362,82 @@ impl AnalysisEngine {
     }
 
     /// Analyze extracted text from OCR or audio
-    pub async fn analyze_extracted_text(&self, extracted_text: ExtractedText) -> Result<AnalysisResult> {
+    pub async fn analyze_extracted_text(&self, mut extracted_text: ExtractedText) -> Result<AnalysisResult> {
+        // Strip PII and secrets before the text reaches the cache, the LLM or the store
+        let redaction_entries = self.redact_extracted_text(&mut extracted_text);
+        if extracted_text.language.is_none() {
+            extracted_text.language = language::detect(&extracted_text.text);
+        }
         let text = &extracted_text.text;
-        
+        // Minutes and their deadlines depend on the meeting date, so it is part of the key
+        let cache_key = match extracted_text.metadata.get(MEETING_DATE_METADATA_KEY) {
+            Some(held_on) => format!("extracted:meeting:{}:{}", held_on, text),
+            None => format!("extracted:{}", text),
+        };
+
         // Check cache first
         if self.config.enable_caching {
-            let cache_key = format!("extracted:{}", text);
             let cache = self.cache.lock().await;
             if let Some(cached_result) = cache.get(&cache_key) {
                 return Ok(cached_result.clone());
             }
         }
 
+        // Text in a language the heuristics don't cover is reported rather than read as English
+        if let Some(unsupported) = extracted_text.language.as_ref().filter(|language| !language.supported) {
+            return self
+                .report_unsupported_language(
+                    text,
+                    Some(&extracted_text),
+                    unsupported,
+                    Attachments::default(),
+                    cache_key,
+                    redaction_entries,
+                )
+                .await;
+        }
+
         // Add to LLM context
         self.llm.add_to_context(text).await?;
 
         // Perform LLM analysis
-        let llm_analysis = self.llm.analyze_with_context(text).await?;
+        let llm_analysis = self
+            .llm
+            .analyze_with_context_in(text, language::analysis_profile(extracted_text.language.as_ref()))
+            .await?;
+
+        let mut metadata = self.extract_metadata(text);
+        let upstream = Signal::calibrated(extraction_field(&extracted_text.source), extracted_text.confidence);
+        let confidence = self.score_confidence(vec![upstream], text, &llm_analysis);
+        metadata.insert(CONFIDENCE_METADATA_KEY.to_string(), serde_json::to_value(&confidence)?);
+        if let Some(detected) = &extracted_text.language {
+            metadata.insert(LANGUAGE_METADATA_KEY.to_string(), serde_json::to_value(detected)?);
+        }
+
+        // Meeting transcripts get minutes instead of generic remarks about the text
+        let minutes = extracted_text
+            .metadata
+            .get(MEETING_DATE_METADATA_KEY)
+            .and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .map(|held_on| meeting::extract_minutes(&meeting::utterances_from_text(text), held_on));
+        if let Some(minutes) = &minutes {
+            metadata.insert(MEETING_MINUTES_METADATA_KEY.to_string(), serde_json::to_value(minutes)?);
+        }
+
+        let mut recommendations = self
+            .generate_recommendations(text, &extracted_text.source, &llm_analysis, &metadata)
+            .await?;
+        let mut insights = llm_analysis.insights;
+        if let Some(minutes) = &minutes {
+            recommendations.extend(minutes.recommendations());
+            let highlights = minutes.highlights();
+            if !highlights.is_empty() {
+                insights = highlights;
+            }
+        }
 
         // Create comprehensive analysis result
-        let analysis_result = AnalysisResult {
+        let mut analysis_result = AnalysisResult {
             analysis_id: self.generate_analysis_id(),
             timestamp: chrono::Utc::now(),
             content_type: extracted_text.source.clone(),
@@ -232,16 +446,109 @@ impl AnalysisEngine {
             sentiment: Some(llm_analysis.sentiment),
             topics: llm_analysis.topics,
             summary: llm_analysis.summary,
-            insights: llm_analysis.insights,
-            confidence: extracted_text.confidence,
+            insights,
+            confidence: confidence.score,
             context_used: llm_analysis.context_used,
-            recommendations: self.generate_recommendations(text, &llm_analysis).await?,
-            metadata: self.extract_metadata(text),
+            recommendations,
+            metadata,
+        };
+        self.apply_redaction(&mut analysis_result, redaction_entries).await?;
+
+        // Cache the result
+        if self.config.enable_caching {
+            let mut cache = self.cache.lock().await;
+            cache.insert(cache_key, analysis_result.clone());
+        }
+
+        // Update session context
+        self.update_session_context(&analysis_result).await?;
+
+        Ok(analysis_result)
+    }
+
+    /// Analyze text OCR'd from a continuous-capture frame, which carries a frame hash.
+    ///
+    /// Returns `None` when the frame repeats one of the last few captured frames, so
+    /// an unchanged screen does not flood the session history.
+    pub async fn analyze_frame(&self, extracted_text: ExtractedText) -> Result<Option<AnalysisResult>> {
+        if self.is_repeated_frame(&extracted_text).await {
+            return Ok(None);
+        }
+        Ok(Some(self.analyze_extracted_text(extracted_text).await?))
+    }
+
+    /// Analyze a meeting transcript and extract its minutes.
+    ///
+    /// The transcript should have one `Speaker: text` turn per line (see
+    /// `meeting::render_transcript`). Minutes are extracted from the redacted text, and
+    /// relative deadlines are resolved against `held_on`.
+    pub async fn analyze_meeting(&self, mut transcript: ExtractedText, held_on: chrono::NaiveDate) -> Result<MeetingAnalysis> {
+        transcript
+            .metadata
+            .insert(MEETING_DATE_METADATA_KEY.to_string(), held_on.format("%Y-%m-%d").to_string());
+        let analysis = self.analyze_extracted_text(transcript).await?;
+        let minutes = MeetingMinutes::from_metadata(&analysis.metadata).unwrap_or_default();
+        Ok(MeetingAnalysis { analysis, minutes })
+    }
+
+    /// Analyze a code snippet: describe what it does and point out likely bugs.
+    ///
+    /// Code skips the prose-oriented LLM pass; its recommendations are technical and
+    /// quote the lines they refer to.
+    pub async fn analyze_code(&self, code: &str) -> Result<AnalysisResult> {
+        // Pasted code often carries credentials; mask them before anything else sees it
+        let redaction = self.redactor.redact_field("original_content", code);
+        let code = redaction.text.as_str();
+
+        // Check cache first
+        if self.config.enable_caching {
+            let cache_key = format!("code:{}", code);
+            let cache = self.cache.lock().await;
+            if let Some(cached_result) = cache.get(&cache_key) {
+                return Ok(cached_result.clone());
+            }
+        }
+
+        let code_analysis = code::analyze(code);
+
+        let mut topics = Vec::new();
+        if let Some(language) = code_analysis.language {
+            topics.push(language.name().to_string());
+        }
+        topics.extend(code_analysis.definitions.iter().cloned());
+
+        let confidence = self
+            .confidence
+            .score(vec![Signal::classifier("language", code_analysis.language_confidence)]);
+
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(CODE_METADATA_KEY.to_string(), serde_json::to_value(&code_analysis)?);
+        metadata.insert(CONFIDENCE_METADATA_KEY.to_string(), serde_json::to_value(&confidence)?);
+
+        let mut analysis_result = AnalysisResult {
+            analysis_id: self.generate_analysis_id(),
+            timestamp: chrono::Utc::now(),
+            content_type: ContentType::Code,
+            original_content: code.to_string(),
+            extracted_text: None,
+            sentiment: None,
+            topics,
+            summary: code_analysis.summary.clone(),
+            insights: code_analysis
+                .issues
+                .iter()
+                .map(|issue| format!("Line {}: {}", issue.line, issue.message))
+                .collect(),
+            confidence: confidence.score,
+            context_used: false,
+            recommendations: code_analysis.recommendations(),
+            metadata,
         };
+        self.apply_redaction(&mut analysis_result, redaction.entries).await?;
 
         // Cache the result
         if self.config.enable_caching {
-            let cache_key = format!("extracted:{}", text);
+            let cache_key = format!("code:{}", code);
             let mut cache = self.cache.lock().await;
             cache.insert(cache_key, analysis_result.clone());
         }
@@ -253,46 +560,89 @@ impl AnalysisEngine {
     }
 
     /// Analyze multiple content sources together
-    pub async fn analyze_combined(&self, contents: Vec<ExtractedText>) -> Result<AnalysisResult> {
+    pub async fn analyze_combined(&self, mut contents: Vec<ExtractedText>) -> Result<AnalysisResult> {
         if contents.is_empty() {
             return Err(anyhow::anyhow!("No content provided for analysis"));
         }
 
-        // Combine all texts
-        let combined_text = contents
-            .iter()
-            .map(|content| content.text.clone())
-            .collect::<Vec<_>>()
-            .join("\n\n");
+        // Strip PII and secrets from every source before they are combined
+        let mut redaction_entries = Vec::new();
+        for content in &mut contents {
+            redaction_entries.extend(self.redact_extracted_text(content));
+        }
+
+        // Place slides and speech on a shared timeline and combine them in order
+        let timeline = fusion::align(&contents);
+        let combined_text = timeline.render_text(&contents);
+
+        // Route analysis by the language of the whole; sources too short to detect on
+        // their own may still carry what OCR or ASR detected
+        let detected = language::detect(&combined_text)
+            .or_else(|| contents.iter().find_map(|content| content.language.clone()));
 
         // Add to LLM context
         self.llm.add_to_context(&combined_text).await?;
 
         // Perform LLM analysis
-        let llm_analysis = self.llm.analyze_with_context(&combined_text).await?;
-
-        // Calculate overall confidence
-        let overall_confidence = contents
+        let llm_analysis = self
+            .llm
+            .analyze_with_context_in(&combined_text, language::analysis_profile(detected.as_ref()))
+            .await?;
+
+        // Each source keeps its own confidence; slides and speech that share key terms
+        // corroborate each other
+        let mut signals: Vec<Signal> = contents
             .iter()
-            .map(|content| content.confidence)
-            .sum::<f32>() / contents.len() as f32;
+            .map(|content| Signal::calibrated(extraction_field(&content.source), content.confidence))
+            .collect();
+        let spoken: Vec<_> = timeline
+            .alignments
+            .iter()
+            .filter(|alignment| !alignment.spoken_indices.is_empty())
+            .collect();
+        if !spoken.is_empty() {
+            let shared = spoken.iter().filter(|alignment| !alignment.shared_terms.is_empty()).count();
+            let mut agreement = Signal::new("alignment", SignalKind::StageAgreement, shared as f32 / spoken.len() as f32);
+            agreement.samples = spoken.len();
+            signals.push(agreement);
+        }
+        let confidence = self.score_confidence(signals, &combined_text, &llm_analysis);
+
+        // Relate what was shown to what was said
+        let mut insights = timeline.descriptions();
+        insights.extend(llm_analysis.insights.clone());
+
+        let mut metadata = self.extract_metadata(&combined_text);
+        metadata.insert(
+            ALIGNMENTS_METADATA_KEY.to_string(),
+            serde_json::to_value(&timeline.alignments)?,
+        );
+        metadata.insert(CONFIDENCE_METADATA_KEY.to_string(), serde_json::to_value(&confidence)?);
+        if let Some(detected) = &detected {
+            metadata.insert(LANGUAGE_METADATA_KEY.to_string(), serde_json::to_value(detected)?);
+        }
+
+        let recommendations = self
+            .generate_recommendations(&combined_text, &ContentType::Combined, &llm_analysis, &metadata)
+            .await?;
 
         // Create comprehensive analysis result
-        let analysis_result = AnalysisResult {
+        let mut analysis_result = AnalysisResult {
             analysis_id: self.generate_analysis_id(),
             timestamp: chrono::Utc::now(),
             content_type: ContentType::Combined,
             original_content: combined_text.clone(),
             extracted_text: None,
-            sentiment: Some(llm_analysis.sentiment),
-            topics: llm_analysis.topics,
-            summary: llm_analysis.summary,
-            insights: llm_analysis.insights,
-            confidence: overall_confidence,
+            sentiment: Some(llm_analysis.sentiment.clone()),
+            topics: llm_analysis.topics.clone(),
+            summary: llm_analysis.summary.clone(),
+            insights,
+            confidence: confidence.score,
             context_used: llm_analysis.context_used,
-            recommendations: self.generate_recommendations(&combined_text, &llm_analysis).await?,
-            metadata: self.extract_metadata(&combined_text),
+            recommendations,
+            metadata,
         };
+        self.apply_redaction(&mut analysis_result, redaction_entries).await?;
 
         // Update session context
         self.update_session_context(&analysis_result).await?;
@@ -300,6 +650,122 @@ impl AnalysisEngine {
         Ok(analysis_result)
     }
 
+    /// Get the confidence model, for scoring extraction stages consistently with analysis
+    pub fn confidence_model(&self) -> &ConfidenceModel {
+        &self.confidence
+    }
+
+    /// Report text in a language analysis has no word lists for instead of analyzing
+    /// it as English, which would only produce noise
+    async fn report_unsupported_language(
+        &self,
+        text: &str,
+        extracted_text: Option<&ExtractedText>,
+        detected: &DetectedLanguage,
+        attachments: Attachments,
+        cache_key: String,
+        redaction_entries: Vec<AuditEntry>,
+    ) -> Result<AnalysisResult> {
+        let mut signals = vec![Signal::classifier("language", detected.confidence)];
+        signals.extend(attachments.signals.iter().cloned());
+        if let Some(extracted) = extracted_text {
+            signals.push(Signal::calibrated(extraction_field(&extracted.source), extracted.confidence));
+        }
+        let confidence = self.confidence.score(signals);
+
+        let mut metadata = self.extract_metadata(text);
+        metadata.insert(CONFIDENCE_METADATA_KEY.to_string(), serde_json::to_value(&confidence)?);
+        metadata.insert(LANGUAGE_METADATA_KEY.to_string(), serde_json::to_value(detected)?);
+
+        let message = detected.unsupported_message();
+        let mut analysis_result = AnalysisResult {
+            analysis_id: self.generate_analysis_id(),
+            timestamp: chrono::Utc::now(),
+            content_type: extracted_text.map_or(ContentType::Text, |extracted| extracted.source.clone()),
+            original_content: text.to_string(),
+            extracted_text: extracted_text.cloned(),
+            sentiment: None,
+            topics: Vec::new(),
+            summary: message.clone(),
+            insights: vec![message],
+            confidence: confidence.score,
+            context_used: false,
+            recommendations: Vec::new(),
+            metadata,
+        };
+        attachments.attach_to(&mut analysis_result);
+        self.apply_redaction(&mut analysis_result, redaction_entries).await?;
+
+        if self.config.enable_caching {
+            let mut cache = self.cache.lock().await;
+            cache.insert(cache_key, analysis_result.clone());
+        }
+
+        self.update_session_context(&analysis_result).await?;
+
+        Ok(analysis_result)
+    }
+
+    /// Get the redactions made in this process, oldest first. Persisted audits are
+    /// available from the session store.
+    pub async fn redaction_audit(&self) -> Vec<RedactionAudit> {
+        self.redaction_log.lock().await.clone()
+    }
+
+    /// Redact an extracted text and its metadata in place
+    fn redact_extracted_text(&self, extracted_text: &mut ExtractedText) -> Vec<AuditEntry> {
+        let redaction = self.redactor.redact_field("extracted_text", &extracted_text.text);
+        extracted_text.text = redaction.text;
+        let mut entries = redaction.entries;
+
+        for (key, value) in extracted_text.metadata.iter_mut() {
+            let redaction = self.redactor.redact_field(&format!("extracted_text.metadata.{}", key), value);
+            *value = redaction.text;
+            entries.extend(redaction.entries);
+        }
+        entries
+    }
+
+    /// Redact what analysis derived from the (already redacted) input, count the
+    /// redactions in the result metadata and record the audit
+    async fn apply_redaction(&self, analysis: &mut AnalysisResult, mut entries: Vec<AuditEntry>) -> Result<()> {
+        let derived = self.redactor.redact_analysis(analysis);
+        // The input fields were audited when they were redacted; a rescan only finds
+        // values kept by policy a second time
+        entries.extend(
+            derived
+                .entries
+                .into_iter()
+                .filter(|entry| entry.field != "original_content" && !entry.field.starts_with("extracted_text")),
+        );
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let mut audit = RedactionAudit::new(entries);
+        audit.analysis_id = Some(analysis.analysis_id.clone());
+
+        let mut counts: std::collections::BTreeMap<String, usize> = analysis
+            .metadata
+            .get(REDACTIONS_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default();
+        for (kind, count) in audit.counts() {
+            *counts.entry(kind.name().to_string()).or_insert(0) += count;
+        }
+        analysis
+            .metadata
+            .insert(REDACTIONS_METADATA_KEY.to_string(), serde_json::to_value(counts)?);
+
+        if let Some(store) = &self.store {
+            let session_id = self.session_context.lock().await.session_id.clone();
+            store.record_redactions(&session_id, &audit)?;
+        }
+        self.redaction_log.lock().await.push(audit);
+
+        Ok(())
+    }
+
     /// Get session summary
     pub async fn get_session_summary(&self) -> Result<SessionSummary> {
         let context = self.session_context.lock().await;
@@ -324,6 +790,57 @@ impl AnalysisEngine {
         Ok(summary)
     }
 
+    /// Get the recommendation rules, for reload diagnostics
+    pub fn rules(&self) -> &RuleEngine {
+        &self.rules
+    }
+
+    /// Get a snapshot of the current session context
+    pub async fn get_session_context(&self) -> SessionContext {
+        self.session_context.lock().await.clone()
+    }
+
+    /// Export the current session as study notes
+    pub async fn export_session(&self, exporter: &SessionExporter, format: ExportFormat) -> Result<String> {
+        let summary = self.get_session_summary().await?;
+        let context = self.get_session_context().await;
+        exporter.export(&context, &summary, format)
+    }
+
+    /// Export the action items and follow-ups extracted in the current session
+    pub async fn export_tasks(&self, exporter: &TaskExporter, format: TaskFormat) -> Result<String> {
+        let context = self.get_session_context().await;
+        exporter.export(&context, format)
+    }
+
+    /// Get the id of the current session
+    pub async fn session_id(&self) -> String {
+        self.session_context.lock().await.session_id.clone()
+    }
+
+    /// Resume a previously persisted session as the current session.
+    /// Returns false if there is no store or the session is unknown.
+    pub async fn resume_session(&self, session_id: &str) -> Result<bool> {
+        let store = match &self.store {
+            Some(store) => store,
+            None => return Ok(false),
+        };
+
+        let restored = match store.load_session(session_id)? {
+            Some(restored) => restored,
+            None => return Ok(false),
+        };
+
+        self.llm.clear_context().await;
+        for turn in &restored.conversation_history {
+            self.llm.add_to_context(&turn.content).await?;
+        }
+
+        let mut context = self.session_context.lock().await;
+        *context = restored;
+        Ok(true)
+    }
+
     /// Clear session context
     pub async fn clear_session(&self) {
         let mut context = self.session_context.lock().await;
@@ -331,119 +848,41 @@ impl AnalysisEngine {
         self.llm.clear_context().await;
     }
 
-    /// Generate analysis ID
+    /// Generate analysis ID; batch items are analyzed in parallel, so ids can't be timestamps
     fn generate_analysis_id(&self) -> String {
-        use std::time::{SystemTime, UNIX_EPOCH};
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        format!("analysis_{}", timestamp)
-    }
-
-    /// Calculate confidence score for analysis
-    fn calculate_confidence(&self, text: &str) -> f32 {
-        // Simple confidence calculation based on text characteristics
-        let word_count = text.split_whitespace().count();
-        let char_count = text.chars().count();
-        
-        if word_count == 0 {
-            return 0.0;
-        }
+        format!("analysis_{}", uuid::Uuid::new_v4())
+    }
 
-        let avg_word_length = char_count as f32 / word_count as f32;
-        let length_confidence = (word_count as f32 / 100.0).min(1.0);
-        let complexity_confidence = (avg_word_length / 8.0).min(1.0);
-        
-        (length_confidence + complexity_confidence) / 2.0
+    /// Combine extraction confidences with the sentiment classifier's score and how
+    /// well the model's topics are supported by the text
+    fn score_confidence(&self, mut signals: Vec<Signal>, text: &str, llm_analysis: &LLMAnalysisResult) -> ConfidenceReport {
+        signals.push(Signal::classifier("sentiment", llm_analysis.sentiment.score));
+        if let Some(agreement) = Signal::agreement("topics", &llm_analysis.topics, text) {
+            signals.push(agreement);
+        }
+        self.confidence.score(signals)
     }
 
     /// Generate recommendations based on analysis
     async fn generate_recommendations(
         &self,
         text: &str,
+        content_type: &ContentType,
         llm_analysis: &LLMAnalysisResult,
+        metadata: &std::collections::HashMap<String, serde_json::Value>,
     ) -> Result<Vec<Recommendation>> {
-        let mut recommendations = Vec::new();
-
-        // Analyze sentiment for recommendations
-        if let Some(sentiment) = &llm_analysis.sentiment {
-            match sentiment.label.as_str() {
-                "NEGATIVE" => {
-                    recommendations.push(Recommendation {
-                        category: RecommendationCategory::Communication,
-                        title: "Address Negative Sentiment".to_string(),
-                        description: "Consider addressing the negative aspects mentioned in the content.".to_string(),
-                        priority: Priority::High,
-                        action_items: vec![
-                            "Review the content for improvement opportunities".to_string(),
-                            "Consider alternative approaches".to_string(),
-                        ],
-                        confidence: sentiment.score,
-                    });
-                }
-                "POSITIVE" => {
-                    recommendations.push(Recommendation {
-                        category: RecommendationCategory::Learning,
-                        title: "Build on Positive Aspects".to_string(),
-                        description: "Leverage the positive elements identified in the content.".to_string(),
-                        priority: Priority::Medium,
-                        action_items: vec![
-                            "Document successful approaches".to_string(),
-                            "Share positive insights with team".to_string(),
-                        ],
-                        confidence: sentiment.score,
-                    });
-                }
-                _ => {}
-            }
-        }
-
-        // Analyze topics for recommendations
-        for topic in &llm_analysis.topics {
-            recommendations.push(Recommendation {
-                category: RecommendationCategory::Learning,
-                title: format!("Explore {}", topic),
-                description: format!("Consider diving deeper into the topic of {}.", topic),
-                priority: Priority::Medium,
-                action_items: vec![
-                    format!("Research more about {}", topic),
-                    "Document key learnings".to_string(),
-                ],
-                confidence: 0.7,
-            });
-        }
-
-        // Generate content-specific recommendations
-        if text.contains("error") || text.contains("problem") {
-            recommendations.push(Recommendation {
-                category: RecommendationCategory::Technical,
-                title: "Technical Issue Identified".to_string(),
-                description: "Technical problems or errors were mentioned in the content.".to_string(),
-                priority: Priority::High,
-                action_items: vec![
-                    "Investigate the technical issue".to_string(),
-                    "Document the problem and solution".to_string(),
-                    "Consider preventive measures".to_string(),
-                ],
-                confidence: 0.8,
-            });
-        }
-
-        if text.contains("learn") || text.contains("study") {
-            recommendations.push(Recommendation {
-                category: RecommendationCategory::Learning,
-                title: "Learning Opportunity".to_string(),
-                description: "Learning-related content was identified.".to_string(),
-                priority: Priority::Medium,
-                action_items: vec![
-                    "Create study materials".to_string(),
-                    "Schedule review sessions".to_string(),
-                    "Track learning progress".to_string(),
-                ],
-                confidence: 0.7,
-            });
-        }
+        // Sentiment, topic and keyword recommendations come from the declarative rule pack
+        let context = RuleContext::new(
+            text,
+            content_type.clone(),
+            Some(&llm_analysis.sentiment),
+            &llm_analysis.topics,
+            metadata,
+        );
+        let mut recommendations = self.rules.evaluate(&context);
+
+        // Explain compiler errors and stack traces, parsed once by `extract_metadata`
+        recommendations.extend(diagnostics::from_metadata(metadata).iter().map(|diagnostic| diagnostic.recommendation()));
 
         Ok(recommendations)
     }
@@ -470,9 +909,60 @@ impl AnalysisEngine {
         let has_numbers = text.chars().any(|c| c.is_numeric());
         metadata.insert("contains_numbers".to_string(), serde_json::Value::Bool(has_numbers));
 
+        // Keep recognized formulas verbatim so summaries and flashcards can reuse them
+        let equations = math::extract_latex(text);
+        if !equations.is_empty() {
+            metadata.insert(EQUATIONS_METADATA_KEY.to_string(), serde_json::json!(equations));
+        }
+
+        let found = diagnostics::parse(text);
+        if !found.is_empty() {
+            metadata.insert(DIAGNOSTICS_METADATA_KEY.to_string(), serde_json::json!(found));
+        }
+
         metadata
     }
 
+    /// Whether a captured frame's text is close enough to one of the last few
+    /// captured frames to treat it as a repeat. Only frames carrying a hash are compared.
+    async fn is_repeated_frame(&self, extracted_text: &ExtractedText) -> bool {
+        let Some(frame_hash) = extracted_text
+            .metadata
+            .get(FRAME_HASH_METADATA_KEY)
+            .and_then(|hash| PerceptualHash::from_hex(hash))
+        else {
+            return false;
+        };
+        // History holds redacted text, so compare against the redacted capture
+        let text = self.redactor.redact_field("extracted_text", &extracted_text.text).text;
+        let context = self.session_context.lock().await;
+
+        context
+            .conversation_history
+            .iter()
+            .rev()
+            .take(NEAR_DUPLICATE_WINDOW)
+            .filter_map(|turn| turn.analysis.as_ref()?.extracted_text.as_ref())
+            .any(|previous| {
+                let Some(previous_hash) = previous
+                    .metadata
+                    .get(FRAME_HASH_METADATA_KEY)
+                    .and_then(|hash| PerceptualHash::from_hex(hash))
+                else {
+                    return false;
+                };
+                // Identical frames tolerate OCR jitter; mostly blank slides can share
+                // a hash, so the text still has to broadly agree
+                let threshold = if previous_hash == frame_hash {
+                    self.config.near_duplicate_threshold / 2.0
+                } else {
+                    self.config.near_duplicate_threshold
+                };
+
+                frame_diff::text_similarity(&previous.text, &text) >= threshold
+            })
+    }
+
     /// Update session context with new analysis
     async fn update_session_context(&self, analysis: &AnalysisResult) -> Result<()> {
         let mut context = self.session_context.lock().await;
@@ -495,6 +985,11 @@ impl AnalysisEngine {
             context.extracted_texts.push(extracted_text.clone());
         }
 
+        // Persist the turn so the session survives restarts
+        if let Some(store) = &self.store {
+            store.record_analysis(&context.session_id, context.created_at, analysis)?;
+        }
+
         Ok(())
     }
 
@@ -621,6 +1116,16 @@ pub struct SessionSummary {
     pub key_insights: Vec<String>,
 }
 
+/// Name of the confidence field describing how a text was extracted
+fn extraction_field(source: &ContentType) -> &'static str {
+    match source {
+        ContentType::Screenshot => "ocr",
+        ContentType::Audio => "transcription",
+        ContentType::Document => "document",
+        _ => "input",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -646,6 +1151,36 @@ mod tests {
         assert!(!analysis.analysis_id.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_code_analysis() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::new(Arc::new(llm));
+
+        let code = "fn load(path: &str) -> String {\n    std::fs::read_to_string(path).unwrap()\n}";
+        let analysis = engine.analyze_code(code).await.unwrap();
+
+        assert_eq!(analysis.content_type, ContentType::Code);
+        assert!(analysis.topics.contains(&"Rust".to_string()));
+        assert!(analysis.metadata.contains_key(CODE_METADATA_KEY));
+        assert!(analysis
+            .recommendations
+            .iter()
+            .any(|rec| matches!(rec.category, RecommendationCategory::Technical) && rec.title.contains("line 2")));
+    }
+
+    #[tokio::test]
+    async fn test_error_text_gets_targeted_recommendations() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::new(Arc::new(llm));
+
+        let trace = "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    total = 1 / count\nZeroDivisionError: division by zero";
+        let analysis = engine.analyze_text(trace).await.unwrap();
+
+        assert!(analysis.metadata.contains_key(DIAGNOSTICS_METADATA_KEY));
+        assert!(analysis.recommendations.iter().any(|rec| rec.title == "Division by zero"));
+        assert!(analysis.recommendations.iter().all(|rec| rec.title != "Technical Issue Identified"));
+    }
+
     #[tokio::test]
     async fn test_session_management() {
         let llm = LocalLLM::new().await.unwrap();
@@ -660,4 +1195,221 @@ mod tests {
         let summary_after_clear = engine.get_session_summary().await.unwrap();
         assert_eq!(summary_after_clear.total_turns, 0);
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_analysis_ids_unique_within_a_millisecond() {
+        let engine = AnalysisEngine::new(Arc::new(LocalLLM::new().await.unwrap()));
+        let ids: std::collections::HashSet<String> = (0..100).map(|_| engine.generate_analysis_id()).collect();
+        assert_eq!(ids.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_session_persistence() {
+        let store = Arc::new(SessionStore::open_in_memory().unwrap());
+        let llm = Arc::new(LocalLLM::new().await.unwrap());
+
+        let engine = AnalysisEngine::with_store(Arc::clone(&llm), AnalysisConfig::default(), Arc::clone(&store));
+        engine.analyze_text("Persisted across restarts").await.unwrap();
+        let session_id = engine.session_id().await;
+
+        // A fresh engine can pick the session back up from the store
+        let restarted = AnalysisEngine::with_store(llm, AnalysisConfig::default(), store);
+        assert!(restarted.resume_session(&session_id).await.unwrap());
+        assert_eq!(restarted.get_session_summary().await.unwrap().total_turns, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_text_redacted_before_persisting() {
+        let store = Arc::new(SessionStore::open_in_memory().unwrap());
+        let llm = Arc::new(LocalLLM::new().await.unwrap());
+        let engine = AnalysisEngine::with_store(llm, AnalysisConfig::default(), Arc::clone(&store));
+
+        let text = "Email jane@example.com the receipt for card 4111 1111 1111 1111";
+        let analysis = engine.analyze_text(text).await.unwrap();
+        assert_eq!(analysis.original_content, "Email [EMAIL] the receipt for card [CARD]");
+        assert_eq!(analysis.metadata[REDACTIONS_METADATA_KEY]["card_number"], 1);
+
+        let session = store.load_session(&engine.session_id().await).unwrap().unwrap();
+        let stored = serde_json::to_string(&session).unwrap();
+        assert!(!stored.contains("jane@example.com") && !stored.contains("4111 1111"));
+
+        let audit = engine.redaction_audit().await;
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].entries.len(), 2);
+        assert_eq!(store.load_redactions(&session.session_id).unwrap()[0].entries, audit[0].entries);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_persisted_and_redacted_once() {
+        let store = Arc::new(SessionStore::open_in_memory().unwrap());
+        let llm = Arc::new(LocalLLM::new().await.unwrap());
+        let engine = AnalysisEngine::with_store(llm, AnalysisConfig::default(), Arc::clone(&store));
+
+        let mut attachments = Attachments {
+            signals: vec![Signal::calibrated("ocr", 0.4)],
+            insights: vec!["Rust code: defines 1 function".to_string()],
+            ..Default::default()
+        };
+        attachments
+            .metadata
+            .insert("tables".to_string(), serde_json::json!([{ "csv": "name,email\nJane,jane@example.com" }]));
+        let analysis = engine
+            .analyze_text_with("Contact list for the study group", None, attachments)
+            .await
+            .unwrap();
+
+        let stored = store.load_analysis(&analysis.analysis_id).unwrap().unwrap();
+        let tables = stored.metadata["tables"].to_string();
+        assert!(tables.contains("[EMAIL]") && !tables.contains("jane@example.com"));
+        assert!(stored.insights.contains(&"Rust code: defines 1 function".to_string()));
+        let report = ConfidenceReport::from_metadata(&stored.metadata).unwrap();
+        assert_eq!(report.field("ocr").unwrap().score, 0.4);
+        assert_eq!(engine.redaction_audit().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_confidence_breakdown_flags_poor_ocr() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::new(Arc::new(llm));
+
+        let blurry = ExtractedText {
+            source: ContentType::Screenshot,
+            text: "Binary search trees keep keys in sorted order".to_string(),
+            confidence: 0.2,
+            timestamp: chrono::Utc::now(),
+            metadata: std::collections::HashMap::new(),
+            time_range: None,
+            language: None,
+        };
+        let analysis = engine.analyze_extracted_text(blurry).await.unwrap();
+
+        let report = ConfidenceReport::from_metadata(&analysis.metadata).unwrap();
+        assert_eq!(analysis.confidence, report.score);
+        assert!(report.low_confidence);
+        assert_eq!(report.field("ocr").unwrap().score, 0.2);
+        assert!(report.field("sentiment").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_analysis_routed_by_detected_language() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::new(Arc::new(llm));
+
+        let german = engine
+            .analyze_text("Die neue Version ist wirklich toll und die Gesundheit der Nutzer wird besser.")
+            .await
+            .unwrap();
+        let detected: DetectedLanguage = serde_json::from_value(german.metadata[LANGUAGE_METADATA_KEY].clone()).unwrap();
+        assert_eq!(detected.code, "de");
+        assert_eq!(german.sentiment.unwrap().label, "POSITIVE");
+        assert_eq!(german.topics, vec!["health".to_string()]);
+
+        let transcript = ExtractedText {
+            source: ContentType::Audio,
+            text: "Файл не найден на сервере, и сборка не удалась. Попробуйте ещё раз.".to_string(),
+            confidence: 0.9,
+            timestamp: chrono::Utc::now(),
+            metadata: std::collections::HashMap::new(),
+            time_range: None,
+            language: None,
+        };
+        let russian = engine.analyze_extracted_text(transcript).await.unwrap();
+        assert_eq!(russian.extracted_text.unwrap().language.unwrap().code, "ru");
+        assert!(russian.sentiment.is_none());
+        assert!(russian.topics.is_empty());
+        assert!(russian.summary.contains("Russian"));
+        assert!(russian.recommendations.is_empty());
+
+        let source = |source: ContentType, text: &str| ExtractedText {
+            source,
+            text: text.to_string(),
+            confidence: 0.9,
+            timestamp: chrono::Utc::now(),
+            metadata: std::collections::HashMap::new(),
+            time_range: None,
+            language: None,
+        };
+        let combined = engine
+            .analyze_combined(vec![
+                source(ContentType::Screenshot, "Gesundheit der Nutzer"),
+                source(ContentType::Audio, "Die neue Version ist wirklich toll und die Gesundheit der Nutzer wird besser."),
+            ])
+            .await
+            .unwrap();
+        let detected: DetectedLanguage = serde_json::from_value(combined.metadata[LANGUAGE_METADATA_KEY].clone()).unwrap();
+        assert_eq!(detected.code, "de");
+        assert_eq!(combined.sentiment.unwrap().label, "POSITIVE");
+        assert!(combined.topics.contains(&"health".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_meeting_analysis_produces_minutes() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::new(Arc::new(llm));
+
+        let transcript = ExtractedText {
+            source: ContentType::Audio,
+            text: "Priya: We agreed to drop IE support.\nSam: I'll email the customers by Friday.\nPriya: What about the mobile app?".to_string(),
+            confidence: 0.9,
+            timestamp: chrono::Utc::now(),
+            metadata: std::collections::HashMap::new(),
+            time_range: None,
+            language: None,
+        };
+        // Seen first without a meeting date, then for two different meetings
+        engine.analyze_extracted_text(transcript.clone()).await.unwrap();
+        let held_on = chrono::NaiveDate::from_ymd_opt(2024, 3, 12).unwrap();
+        let meeting = engine.analyze_meeting(transcript.clone(), held_on).await.unwrap();
+
+        assert_eq!(meeting.minutes.decisions.len(), 1);
+        assert_eq!(meeting.minutes.action_items_for("Sam").len(), 1);
+        assert_eq!(meeting.minutes.open_questions.len(), 1);
+        assert!(meeting.analysis.metadata.contains_key(MEETING_MINUTES_METADATA_KEY));
+        assert!(meeting.analysis.insights.iter().any(|insight| insight.starts_with("Decision:")));
+
+        let next_week = held_on + chrono::Duration::days(7);
+        let later = engine.analyze_meeting(transcript, next_week).await.unwrap();
+        let due = |meeting: &MeetingAnalysis| meeting.minutes.action_items_for("Sam")[0].deadline.as_ref().map(|deadline| deadline.date);
+        assert_eq!(due(&meeting), chrono::NaiveDate::from_ymd_opt(2024, 3, 15));
+        assert_eq!(due(&later), chrono::NaiveDate::from_ymd_opt(2024, 3, 22));
+    }
+
+    #[tokio::test]
+    async fn test_near_duplicate_capture_skipped() {
+        let llm = LocalLLM::new().await.unwrap();
+        let engine = AnalysisEngine::with_config(
+            Arc::new(llm),
+            AnalysisConfig {
+                enable_caching: false,
+                ..AnalysisConfig::default()
+            },
+        );
+
+        let capture = |text: &str, frame_hash: Option<&str>| {
+            let mut metadata = std::collections::HashMap::new();
+            if let Some(hash) = frame_hash {
+                metadata.insert(FRAME_HASH_METADATA_KEY.to_string(), hash.to_string());
+            }
+            ExtractedText {
+                source: ContentType::Screenshot,
+                text: text.to_string(),
+                confidence: 0.9,
+                timestamp: chrono::Utc::now(),
+                metadata,
+                time_range: None,
+                language: None,
+            }
+        };
+
+        let first = engine.analyze_frame(capture("Lecture 3: Graph traversal with BFS and DFS", Some("ff00"))).await.unwrap();
+        assert!(first.is_some());
+        // Same slide with OCR jitter in the punctuation
+        let repeat = engine.analyze_frame(capture("Lecture 3 - Graph traversal with BFS and DFS.", Some("ff00"))).await.unwrap();
+        assert!(repeat.is_none());
+
+        // Separate screenshots are always analyzed, however similar their text
+        engine.analyze_extracted_text(capture("Lecture 3 - Graph traversal with BFS and DFS.", None)).await.unwrap();
+        engine.analyze_frame(capture("Dijkstra's shortest path algorithm", Some("00ff"))).await.unwrap();
+        assert_eq!(engine.get_session_summary().await.unwrap().total_turns, 3);
+    }
+}
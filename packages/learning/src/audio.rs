@@ -1,17 +1,27 @@
 use anyhow::Result;
-use cpal::{
-    traits::{DeviceTrait, HostTrait, StreamTrait},
-    SampleFormat, StreamConfig,
-};
 use hound::{WavReader, WavWriter};
 use serde::{Deserialize, Serialize};
 use std::{
     path::Path,
-    sync::{Arc, Mutex},
+    sync::{Arc, RwLock},
     time::Duration,
 };
 use tokio::sync::mpsc;
 
+pub mod devices;
+mod flac;
+pub mod keyword;
+pub mod longform;
+pub mod punctuate;
+pub mod recorder;
+pub mod source;
+
+use crate::subtitles::{SubtitleFormat, SubtitleWriter};
+use crate::vocabulary::VocabularyStore;
+use punctuate::{PunctuationConfig, PunctuationRestorer};
+use recorder::SessionRecorder;
+use source::{AudioSource, CpalSource};
+
 /// Configuration for audio transcription
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioConfig {
@@ -22,6 +32,12 @@ pub struct AudioConfig {
     pub min_audio_length: Duration,
     pub noise_reduction: bool,
     pub vad_enabled: bool, // Voice Activity Detection
+    /// Input device name or id; the default input is used when unset or missing
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Punctuation and capitalization restoration applied to every transcript
+    #[serde(default)]
+    pub punctuation: PunctuationConfig,
 }
 
 impl Default for AudioConfig {
@@ -34,13 +50,36 @@ impl Default for AudioConfig {
             min_audio_length: Duration::from_millis(500),
             noise_reduction: true,
             vad_enabled: true,
+            input_device: None,
+            punctuation: PunctuationConfig::default(),
         }
     }
 }
 
+/// A span of decoded speech with the recognizer's scores for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+    /// Speaker label from diarization, when available
+    #[serde(default)]
+    pub speaker: Option<String>,
+    /// Mean log-probability of the decoded tokens
+    pub avg_logprob: f32,
+    /// Probability that the segment contains no speech at all
+    pub no_speech_prob: f32,
+}
+
 /// Audio transcriber for converting audio to text
+#[derive(Clone)]
 pub struct AudioTranscriber {
     config: AudioConfig,
+    /// Input device for live capture; clones share it, so a new selection applies to
+    /// every copy of the transcriber
+    input_device: Arc<RwLock<Option<String>>>,
+    punctuator: PunctuationRestorer,
+    vocabulary: Arc<VocabularyStore>,
     // In a real implementation, you'd have a speech recognition model here
     // For now, we'll simulate transcription with basic audio processing
 }
@@ -48,13 +87,34 @@ pub struct AudioTranscriber {
 impl AudioTranscriber {
     /// Create a new audio transcriber
     pub fn new() -> Result<Self> {
-        let config = AudioConfig::default();
-        Ok(Self { config })
+        Ok(Self::with_config(AudioConfig::default()))
     }
 
     /// Create audio transcriber with custom configuration
     pub fn with_config(config: AudioConfig) -> Self {
-        Self { config }
+        let punctuator = PunctuationRestorer::new(&config.punctuation);
+        Self {
+            input_device: Arc::new(RwLock::new(config.input_device.clone())),
+            config,
+            punctuator,
+            vocabulary: Arc::new(VocabularyStore::in_memory()),
+        }
+    }
+
+    /// Bias transcripts toward the terms of a user vocabulary
+    pub fn with_vocabulary(mut self, vocabulary: Arc<VocabularyStore>) -> Self {
+        self.vocabulary = vocabulary;
+        self
+    }
+
+    /// The input device live capture uses; `None` is the default input
+    pub fn input_device(&self) -> Option<String> {
+        self.input_device.read().unwrap().clone()
+    }
+
+    /// Capture from another input device from the next real-time transcription on
+    pub fn set_input_device(&self, name: Option<String>) {
+        *self.input_device.write().unwrap() = name;
     }
 
     /// Transcribe audio from a file
@@ -64,6 +124,30 @@ impl AudioTranscriber {
         self.transcribe_audio_data(&processed_audio).await
     }
 
+    /// Transcribe audio from a file into timed segments with recognition scores
+    pub async fn transcribe_segments(&self, audio_path: &str) -> Result<Vec<TranscriptSegment>> {
+        let audio_data = self.load_audio_file(audio_path)?;
+        self.transcribe_samples(&audio_data).await
+    }
+
+    /// Transcribe mono samples at the configured sample rate into scored segments
+    pub async fn transcribe_samples(&self, audio_data: &[f32]) -> Result<Vec<TranscriptSegment>> {
+        let processed_audio = self.preprocess_audio(audio_data)?;
+        let text = self.transcribe_audio_data(&processed_audio).await?;
+        Ok(vec![self.score_segment(&processed_audio, text)])
+    }
+
+    /// Transcribe audio from a file into SRT or WebVTT captions
+    pub async fn transcribe_subtitles(
+        &self,
+        audio_path: &str,
+        writer: &SubtitleWriter,
+        format: SubtitleFormat,
+    ) -> Result<String> {
+        let segments = self.transcribe_segments(audio_path).await?;
+        Ok(writer.write(&segments, format))
+    }
+
     /// Transcribe audio from raw bytes
     pub async fn transcribe_bytes(&self, audio_bytes: &[u8]) -> Result<String> {
         let audio_data = self.load_audio_from_bytes(audio_bytes)?;
@@ -71,15 +155,79 @@ impl AudioTranscriber {
         self.transcribe_audio_data(&processed_audio).await
     }
 
-    /// Start real-time audio transcription
+    /// Start real-time audio transcription from the configured input device
     pub async fn start_realtime_transcription(
         &self,
+    ) -> Result<(mpsc::Receiver<String>, tokio::task::JoinHandle<()>)> {
+        let source = self.open_input_source()?;
+        self.start_realtime_transcription_from(source).await
+    }
+
+    /// Start real-time transcription from the configured input device, recording what is heard
+    pub async fn start_recorded_transcription(
+        &self,
+        recorder: SessionRecorder,
+    ) -> Result<(mpsc::Receiver<String>, tokio::task::JoinHandle<()>)> {
+        let source = self.open_input_source()?;
+        self.start_recorded_transcription_from(source, recorder).await
+    }
+
+    /// Open the configured input device, falling back to the default input
+    fn open_input_source(&self) -> Result<Box<dyn AudioSource>> {
+        let input_device = self.input_device();
+        let resolved = devices::resolve_input_device(input_device.as_deref())?;
+        if resolved.fell_back {
+            eprintln!(
+                "Input device {} not found, using {}",
+                input_device.as_deref().unwrap_or_default(),
+                resolved.name
+            );
+        }
+        let source = CpalSource::open(
+            resolved.device,
+            self.config.sample_rate,
+            self.config.channels,
+            self.config.buffer_size,
+        )?;
+        Ok(Box::new(source))
+    }
+
+    /// Start real-time audio transcription from any audio source.
+    ///
+    /// One transcript is sent per second of audio, plus one for a trailing partial
+    /// second that is at least `min_audio_length` long. The channel closes when the
+    /// source is exhausted.
+    pub async fn start_realtime_transcription_from(
+        &self,
+        source: Box<dyn AudioSource>,
+    ) -> Result<(mpsc::Receiver<String>, tokio::task::JoinHandle<()>)> {
+        self.spawn_realtime_transcription(source, None)
+    }
+
+    /// Start real-time transcription from any audio source, recording what is heard.
+    ///
+    /// Every transcribed window is written to `recorder` and linked to its transcript, so
+    /// each line can be played back from the session's recording. The recording is
+    /// finished when the source ends or nobody is receiving transcripts anymore.
+    pub async fn start_recorded_transcription_from(
+        &self,
+        source: Box<dyn AudioSource>,
+        recorder: SessionRecorder,
+    ) -> Result<(mpsc::Receiver<String>, tokio::task::JoinHandle<()>)> {
+        check_recorder(&recorder)?;
+        self.spawn_realtime_transcription(source, Some(recorder))
+    }
+
+    fn spawn_realtime_transcription(
+        &self,
+        source: Box<dyn AudioSource>,
+        recorder: Option<SessionRecorder>,
     ) -> Result<(mpsc::Receiver<String>, tokio::task::JoinHandle<()>)> {
         let (tx, rx) = mpsc::channel(100);
-        let config = self.config.clone();
+        let transcriber = self.clone();
 
         let handle = tokio::spawn(async move {
-            if let Err(e) = Self::run_realtime_transcription(config, tx).await {
+            if let Err(e) = transcriber.run_realtime_transcription(source, tx, recorder).await {
                 eprintln!("Real-time transcription error: {}", e);
             }
         });
@@ -265,7 +413,36 @@ impl AudioTranscriber {
         // Generate simulated transcription based on audio features
         let transcription = self.generate_simulated_transcription(energy, zero_crossings, spectral_centroid, duration);
         
-        Ok(transcription)
+        let transcription = self.vocabulary.bias_transcript(&transcription);
+        Ok(self.punctuator.restore(&transcription))
+    }
+
+    /// Attach recognition scores to a transcribed span of audio.
+    ///
+    /// The simulated recognizer has no decoder scores, so they are estimated from the
+    /// share of 10ms windows that survived noise gating and VAD.
+    fn score_segment(&self, audio_data: &[f32], text: String) -> TranscriptSegment {
+        let duration = audio_data.len() as f64 / self.config.sample_rate as f64;
+        let window_size = (self.config.sample_rate as usize / 100).max(1);
+        let windows = audio_data.len().div_ceil(window_size);
+        let speech_windows = audio_data
+            .chunks(window_size)
+            .filter(|window| window.iter().any(|sample| sample.abs() >= self.config.silence_threshold))
+            .count();
+        let speech_fraction = if windows > 0 && duration >= self.config.min_audio_length.as_secs_f64() {
+            speech_windows as f32 / windows as f32
+        } else {
+            0.0
+        };
+
+        TranscriptSegment {
+            start_secs: 0.0,
+            end_secs: duration,
+            text,
+            speaker: None,
+            avg_logprob: (0.35 + 0.6 * speech_fraction).ln(),
+            no_speech_prob: 1.0 - speech_fraction,
+        }
     }
 
     /// Calculate audio energy
@@ -346,46 +523,58 @@ impl AudioTranscriber {
 
     /// Run real-time transcription
     async fn run_realtime_transcription(
-        config: AudioConfig,
+        &self,
+        mut source: Box<dyn AudioSource>,
         tx: mpsc::Sender<String>,
+        mut recorder: Option<SessionRecorder>,
     ) -> Result<()> {
-        let host = cpal::default_host();
-        let device = host.default_input_device()
-            .ok_or_else(|| anyhow::anyhow!("No input device found"))?;
-
-        let config_stream = StreamConfig {
-            channels: config.channels,
-            sample_rate: cpal::SampleRate(config.sample_rate),
-            buffer_size: cpal::BufferSize::Fixed(config.buffer_size as u32),
-        };
-
-        let audio_buffer = Arc::new(Mutex::new(Vec::new()));
-        let audio_buffer_clone = Arc::clone(&audio_buffer);
-
-        let stream = device.build_input_stream(
-            &config_stream,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut buffer = audio_buffer_clone.lock().unwrap();
-                buffer.extend_from_slice(data);
-                
-                // Process buffer when it's large enough
-                if buffer.len() >= config.sample_rate as usize {
-                    let audio_data = buffer.drain(..).collect::<Vec<_>>();
-                    // In a real implementation, you'd send this to a background task for processing
+        let channels = source.channels();
+        let source_rate = source.sample_rate();
+        let window = self.config.sample_rate as usize;
+        let mut buffer = Vec::new();
+
+        'listen: while let Some(block) = source.next_block().await? {
+            let mono = source::downmix(&block, channels);
+            buffer.extend(longform::resample(&mono, source_rate, self.config.sample_rate));
+
+            while buffer.len() >= window {
+                let audio_data: Vec<f32> = buffer.drain(..window).collect();
+                if !self.send_realtime_transcript(&audio_data, &tx, recorder.as_mut()).await? {
+                    break 'listen;
                 }
-            },
-            |err| eprintln!("Audio stream error: {}", err),
-            None,
-        )?;
+            }
+        }
 
-        stream.play()?;
+        let remaining = buffer.len() as f32 / self.config.sample_rate as f32;
+        if !tx.is_closed() && remaining >= self.config.min_audio_length.as_secs_f32() {
+            self.send_realtime_transcript(&buffer, &tx, recorder.as_mut()).await?;
+        }
 
-        // Keep the stream alive
-        std::thread::sleep(Duration::from_secs(1));
-        
+        if let Some(recorder) = recorder {
+            recorder.finish()?;
+        }
         Ok(())
     }
 
+    /// Transcribe one window of live audio; false once nobody is listening
+    async fn send_realtime_transcript(
+        &self,
+        audio_data: &[f32],
+        tx: &mpsc::Sender<String>,
+        recorder: Option<&mut SessionRecorder>,
+    ) -> Result<bool> {
+        let processed_audio = self.preprocess_audio(audio_data)?;
+        let text = self.transcribe_audio_data(&processed_audio).await?;
+
+        if let Some(recorder) = recorder {
+            let offset_secs = recorder.position_secs();
+            recorder.write(&longform::resample(audio_data, self.config.sample_rate, recorder.index().sample_rate))?;
+            let segment = self.score_segment(audio_data, text.clone());
+            recorder.link_transcript(&segment, offset_secs, None)?;
+        }
+        Ok(tx.send(text).await.is_ok())
+    }
+
     /// Save audio data to WAV file
     pub fn save_audio(&self, audio_data: &[f32], output_path: &str) -> Result<()> {
         let spec = hound::WavSpec {
@@ -407,6 +596,17 @@ impl AudioTranscriber {
     }
 }
 
+/// Transcribed audio is mono, so recordings linked to transcripts must be too
+fn check_recorder(recorder: &SessionRecorder) -> Result<()> {
+    if recorder.index().channels != 1 {
+        return Err(anyhow::anyhow!(
+            "Transcribed audio is recorded in mono, not {} channels",
+            recorder.index().channels
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -426,6 +626,21 @@ mod tests {
         assert!(transcriber.is_ok());
     }
 
+    #[test]
+    fn test_input_device_selection_is_shared() {
+        let transcriber = AudioTranscriber::with_config(AudioConfig {
+            input_device: Some("USB Microphone".to_string()),
+            ..AudioConfig::default()
+        });
+        let copy = transcriber.clone();
+        assert_eq!(copy.input_device().as_deref(), Some("USB Microphone"));
+
+        transcriber.set_input_device(Some("Headset".to_string()));
+        assert_eq!(copy.input_device().as_deref(), Some("Headset"));
+        copy.set_input_device(None);
+        assert_eq!(transcriber.input_device(), None);
+    }
+
     #[test]
     fn test_audio_normalization() {
         let transcriber = AudioTranscriber::new().unwrap();
@@ -435,4 +650,66 @@ mod tests {
         assert!(!normalized.is_empty());
         assert!(normalized.iter().all(|&x| x.abs() <= 1.0));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_segment_scores_follow_speech_share() {
+        let transcriber = AudioTranscriber::new().unwrap();
+        let speech: Vec<f32> = (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let mut half_silent = speech[..8000].to_vec();
+        half_silent.extend(vec![0.0; 8000]);
+
+        let clear = transcriber.score_segment(&speech, "speech".to_string());
+        let sparse = transcriber.score_segment(&half_silent, "speech".to_string());
+        assert!((clear.end_secs - 1.0).abs() < 1e-9);
+        assert!(clear.no_speech_prob < 0.05);
+        assert!(sparse.avg_logprob < clear.avg_logprob);
+        assert!((sparse.no_speech_prob - 0.5).abs() < 0.05);
+    }
+
+    #[tokio::test]
+    async fn test_realtime_pipeline_runs_from_generated_audio() {
+        use source::{GeneratorSource, Waveform};
+
+        // 2.75s of 48kHz tone: two full windows and a 0.75s tail
+        let tone = GeneratorSource::new(Waveform::Sine { frequency: 440.0 }, 48000, Duration::from_millis(2750))
+            .with_block_frames(4800);
+        // A steady tone sits below the adaptive VAD threshold, so only gate noise
+        let transcriber = AudioTranscriber::with_config(AudioConfig {
+            vad_enabled: false,
+            ..Default::default()
+        });
+        let (mut rx, handle) = transcriber.start_realtime_transcription_from(Box::new(tone)).await.unwrap();
+
+        let mut transcripts = Vec::new();
+        while let Some(text) = rx.recv().await {
+            transcripts.push(text);
+        }
+        handle.await.unwrap();
+
+        assert_eq!(transcripts.len(), 3);
+        assert!(transcripts.iter().all(|t| t.starts_with("Detected speech content.")));
+        assert!(transcripts[2].contains("Duration: 0.8 seconds"));
+
+        let data_dir = std::env::temp_dir().join(format!("realtime-{}", uuid::Uuid::new_v4()));
+        let recorder = recorder::SessionRecorder::create(&data_dir, "live", Default::default()).unwrap();
+        let tone = GeneratorSource::new(Waveform::Sine { frequency: 440.0 }, 48000, Duration::from_millis(2750));
+        let (mut rx, handle) = transcriber
+            .start_recorded_transcription_from(Box::new(tone), recorder)
+            .await
+            .unwrap();
+        while rx.recv().await.is_some() {}
+        handle.await.unwrap();
+
+        let index = recorder::RecordingIndex::load(&data_dir, "live").unwrap();
+        assert_eq!(index.transcripts.len(), 3);
+        assert!((index.transcripts[2].start_secs - 2.0).abs() < 1e-9);
+        assert!((index.duration_secs() - 2.75).abs() < 0.01);
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        let silence = GeneratorSource::new(Waveform::Silence, 16000, Duration::from_millis(1200));
+        let (mut rx, _) = transcriber.start_realtime_transcription_from(Box::new(silence)).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "Silence or very low audio detected.");
+        // The 0.2s tail is shorter than min_audio_length
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Input device enumeration and selection.
+//!
+//! Devices are identified by their position in the host's device list (`id`) or by
+//! name. Ids can change when devices are plugged in or out, so a saved choice should
+//! be stored by name. A saved device that has disappeared falls back to the default
+//! input.
+
+use anyhow::Result;
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
+
+/// A range of stream configurations an input device accepts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupportedInputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: String,
+}
+
+/// An input device as reported by the audio host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputDeviceInfo {
+    pub id: usize,
+    pub name: String,
+    pub is_default: bool,
+    pub default_sample_rate: Option<u32>,
+    pub default_channels: Option<u16>,
+    pub supported_configs: Vec<SupportedInputConfig>,
+}
+
+impl InputDeviceInfo {
+    /// Whether the device can capture with this channel count at this rate
+    pub fn supports(&self, sample_rate: u32, channels: u16) -> bool {
+        self.supported_configs.iter().any(|config| {
+            config.channels == channels
+                && (config.min_sample_rate..=config.max_sample_rate).contains(&sample_rate)
+        })
+    }
+}
+
+/// The device chosen for capture, and whether it is the one that was asked for
+pub struct ResolvedInputDevice {
+    pub device: cpal::Device,
+    pub name: String,
+    /// True when the requested device was not found and the default was used instead
+    pub fell_back: bool,
+}
+
+/// List the host's input devices with their supported configurations
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().and_then(|d| d.name().ok());
+
+    let devices = host
+        .input_devices()?
+        .enumerate()
+        .map(|(id, device)| {
+            let name = device.name().unwrap_or_else(|_| format!("Input device {}", id));
+            let default_config = device.default_input_config().ok();
+            let supported_configs = device
+                .supported_input_configs()
+                .map(|configs| {
+                    configs
+                        .map(|config| SupportedInputConfig {
+                            channels: config.channels(),
+                            min_sample_rate: config.min_sample_rate().0,
+                            max_sample_rate: config.max_sample_rate().0,
+                            sample_format: config.sample_format().to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            InputDeviceInfo {
+                id,
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                default_sample_rate: default_config.as_ref().map(|c| c.sample_rate().0),
+                default_channels: default_config.as_ref().map(|c| c.channels()),
+                supported_configs,
+            }
+        })
+        .collect();
+
+    Ok(devices)
+}
+
+/// Find an input device by name or id
+pub fn find_input_device(selector: &str) -> Result<Option<cpal::Device>> {
+    let devices: Vec<cpal::Device> = cpal::default_host().input_devices()?.collect();
+    let names: Vec<String> = devices
+        .iter()
+        .map(|device| device.name().unwrap_or_default())
+        .collect();
+    Ok(match_device(&names, selector).map(|index| devices[index].clone()))
+}
+
+/// Resolve the preferred device, falling back to the default input when it is gone
+pub fn resolve_input_device(preferred: Option<&str>) -> Result<ResolvedInputDevice> {
+    if let Some(selector) = preferred {
+        if let Some(device) = find_input_device(selector)? {
+            let name = device.name().unwrap_or_else(|_| selector.to_string());
+            return Ok(ResolvedInputDevice {
+                device,
+                name,
+                fell_back: false,
+            });
+        }
+    }
+
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| anyhow::anyhow!("No input device found"))?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    Ok(ResolvedInputDevice {
+        device,
+        name,
+        fell_back: preferred.is_some(),
+    })
+}
+
+/// Index of the device matching `selector`.
+///
+/// An exact name wins over a case-insensitive one, and either wins over reading the
+/// selector as a numeric id, so a device literally named "1" is still found by name.
+pub fn match_device(names: &[String], selector: &str) -> Option<usize> {
+    let selector = selector.trim();
+    names
+        .iter()
+        .position(|name| name == selector)
+        .or_else(|| names.iter().position(|name| name.eq_ignore_ascii_case(selector)))
+        .or_else(|| selector.parse::<usize>().ok().filter(|&id| id < names.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_device_by_name_then_id() {
+        let names = vec![
+            "Webcam Microphone".to_string(),
+            "USB Headset".to_string(),
+            "1".to_string(),
+        ];
+
+        assert_eq!(match_device(&names, "USB Headset"), Some(1));
+        assert_eq!(match_device(&names, "usb headset "), Some(1));
+        assert_eq!(match_device(&names, "0"), Some(0));
+        // A name beats an id
+        assert_eq!(match_device(&names, "1"), Some(2));
+        assert_eq!(match_device(&names, "7"), None);
+        assert_eq!(match_device(&names, "Bluetooth Earbuds"), None);
+    }
+
+    #[test]
+    fn test_supports_checks_rate_range_and_channels() {
+        let device = InputDeviceInfo {
+            id: 0,
+            name: "USB Headset".to_string(),
+            is_default: false,
+            default_sample_rate: Some(48000),
+            default_channels: Some(1),
+            supported_configs: vec![SupportedInputConfig {
+                channels: 1,
+                min_sample_rate: 8000,
+                max_sample_rate: 48000,
+                sample_format: "i16".to_string(),
+            }],
+        };
+
+        assert!(device.supports(16000, 1));
+        assert!(!device.supports(16000, 2));
+        assert!(!device.supports(96000, 1));
+    }
+}
@@ -0,0 +1,385 @@
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Samples per channel in every frame but the last
+pub const BLOCK_FRAMES: usize = 4096;
+
+/// Highest Rice parameter tried; 15 is the escape code
+const MAX_RICE_PARAMETER: u32 = 14;
+
+/// Offset of the packed sample rate / channels / bits / total samples field in the file
+const STREAMINFO_TOTALS_OFFSET: u64 = 4 + 4 + 10;
+
+/// Minimal lossless FLAC encoder.
+///
+/// Each frame holds up to `BLOCK_FRAMES` samples per channel, coded independently per
+/// channel with the best of the fixed predictors (orders 0-4) and a single Rice partition,
+/// falling back to verbatim samples when prediction doesn't pay off. The STREAMINFO total
+/// is patched in on `finalize`; the MD5 signature is left unset, which decoders accept.
+pub struct FlacWriter {
+    file: BufWriter<File>,
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+    frame_number: u64,
+    total_frames: u64,
+    bytes_written: u64,
+}
+
+impl FlacWriter {
+    pub fn create(path: impl AsRef<Path>, sample_rate: u32, channels: u16, bits_per_sample: u16) -> Result<Self> {
+        if !(1..=8).contains(&channels) || sample_size_code(bits_per_sample).is_none() || sample_rate == 0 {
+            return Err(anyhow::anyhow!(
+                "Unsupported FLAC format: {} channels, {} bits, {} Hz",
+                channels,
+                bits_per_sample,
+                sample_rate
+            ));
+        }
+
+        let mut writer = Self {
+            file: BufWriter::new(File::create(path)?),
+            sample_rate,
+            channels,
+            bits_per_sample,
+            frame_number: 0,
+            total_frames: 0,
+            bytes_written: 0,
+        };
+
+        let mut header = BitWriter::new();
+        header.write_bytes(b"fLaC");
+        // Last metadata block, STREAMINFO, 34 bytes
+        header.write(1, 1);
+        header.write(0, 7);
+        header.write(34, 24);
+        header.write(BLOCK_FRAMES as u64, 16);
+        header.write(BLOCK_FRAMES as u64, 16);
+        header.write(0, 24);
+        header.write(0, 24);
+        writer.write_totals(&mut header);
+        header.write_bytes(&[0; 16]);
+        writer.emit(&header.into_bytes())?;
+        Ok(writer)
+    }
+
+    /// Bytes written so far
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Largest number of bytes a frame of `frames` samples per channel can take
+    pub fn frame_bound(&self, frames: usize) -> u64 {
+        let samples = frames as u64 * self.channels as u64 * self.bits_per_sample as u64;
+        16 + self.channels as u64 + samples.div_ceil(8) + 2
+    }
+
+    /// Encode one frame of interleaved samples, at most `BLOCK_FRAMES` per channel
+    pub fn write_frame(&mut self, interleaved: &[i32]) -> Result<()> {
+        let channels = self.channels as usize;
+        let frames = interleaved.len() / channels;
+        if frames == 0 || frames > BLOCK_FRAMES || !interleaved.len().is_multiple_of(channels) {
+            return Err(anyhow::anyhow!("A FLAC frame needs 1-{} whole frames of samples", BLOCK_FRAMES));
+        }
+
+        // Some decoders need the frame header to repeat the rate and sample size
+        let mut bits = BitWriter::new();
+        bits.write(0b11111111111110, 14);
+        bits.write(0, 1);
+        bits.write(0, 1); // fixed block size
+        bits.write(0b0111, 4); // 16-bit block size at the end of the header
+        bits.write(sample_rate_code(self.sample_rate), 4);
+        bits.write(channels as u64 - 1, 4); // independent channels
+        bits.write(sample_size_code(self.bits_per_sample).unwrap_or(0), 3);
+        bits.write(0, 1);
+        bits.write_utf8(self.frame_number);
+        bits.write(frames as u64 - 1, 16);
+        let crc = crc8(bits.bytes());
+        bits.write(crc as u64, 8);
+
+        for channel in 0..channels {
+            let samples: Vec<i64> = interleaved.iter().skip(channel).step_by(channels).map(|&s| s as i64).collect();
+            self.write_subframe(&mut bits, &samples);
+        }
+        bits.align();
+        let crc = crc16(bits.bytes());
+        bits.write(crc as u64, 16);
+
+        self.emit(&bits.into_bytes())?;
+        self.frame_number += 1;
+        self.total_frames += frames as u64;
+        Ok(())
+    }
+
+    /// Flush the stream and record the total number of samples, returning the file size
+    pub fn finalize(mut self) -> Result<u64> {
+        let mut totals = BitWriter::new();
+        self.write_totals(&mut totals);
+        self.file.flush()?;
+        let file = self.file.get_mut();
+        file.seek(SeekFrom::Start(STREAMINFO_TOTALS_OFFSET))?;
+        file.write_all(&totals.into_bytes())?;
+        file.flush()?;
+        Ok(self.bytes_written)
+    }
+
+    fn write_totals(&self, bits: &mut BitWriter) {
+        bits.write(self.sample_rate as u64, 20);
+        bits.write(self.channels as u64 - 1, 3);
+        bits.write(self.bits_per_sample as u64 - 1, 5);
+        bits.write(self.total_frames, 36);
+    }
+
+    fn write_subframe(&self, bits: &mut BitWriter, samples: &[i64]) {
+        let bps = self.bits_per_sample as u32;
+        let verbatim_bits = samples.len() as u64 * bps as u64;
+
+        let best = (0..=4usize)
+            .filter(|&order| order < samples.len())
+            .map(|order| {
+                let residuals = fixed_residuals(samples, order);
+                let (parameter, residual_bits) = best_rice_parameter(&residuals);
+                let total = order as u64 * bps as u64 + 2 + 4 + 4 + residual_bits;
+                (order, residuals, parameter, total)
+            })
+            .min_by_key(|(_, _, _, total)| *total);
+
+        match best {
+            Some((order, residuals, parameter, total)) if total < verbatim_bits => {
+                bits.write(0, 1);
+                bits.write(0b001000 | order as u64, 6);
+                bits.write(0, 1);
+                for &sample in &samples[..order] {
+                    bits.write_signed(sample, bps);
+                }
+                bits.write(0, 2); // Rice coding with 4-bit parameters
+                bits.write(0, 4); // one partition
+                bits.write(parameter as u64, 4);
+                for &residual in &residuals {
+                    bits.write_rice(residual, parameter);
+                }
+            }
+            _ => {
+                bits.write(0, 1);
+                bits.write(0b000001, 6);
+                bits.write(0, 1);
+                for &sample in samples {
+                    bits.write_signed(sample, bps);
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> Result<()> {
+        self.file.write_all(bytes)?;
+        self.bytes_written += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+/// Frame header code for a sample rate; 0 defers to STREAMINFO
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        88_200 => 0b0001,
+        176_400 => 0b0010,
+        192_000 => 0b0011,
+        8_000 => 0b0100,
+        16_000 => 0b0101,
+        22_050 => 0b0110,
+        24_000 => 0b0111,
+        32_000 => 0b1000,
+        44_100 => 0b1001,
+        48_000 => 0b1010,
+        96_000 => 0b1011,
+        _ => 0,
+    }
+}
+
+/// Frame header code for a sample size; only these sizes can be written
+fn sample_size_code(bits_per_sample: u16) -> Option<u64> {
+    match bits_per_sample {
+        8 => Some(0b001),
+        12 => Some(0b010),
+        16 => Some(0b100),
+        20 => Some(0b101),
+        24 => Some(0b110),
+        _ => None,
+    }
+}
+
+/// Residuals of the fixed polynomial predictor of the given order (after the warm-up samples)
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |k: usize| samples[i - k];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+/// Rice parameter giving the fewest bits for the residuals, with that bit count
+fn best_rice_parameter(residuals: &[i64]) -> (u32, u64) {
+    let folded: Vec<u64> = residuals.iter().map(|&r| zigzag(r)).collect();
+    (0..=MAX_RICE_PARAMETER)
+        .map(|k| {
+            let bits = folded.iter().map(|&u| (u >> k) + 1 + k as u64).sum::<u64>();
+            (k, bits)
+        })
+        .min_by_key(|(_, bits)| *bits)
+        .unwrap_or((0, 0))
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// MSB-first bit packer
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            accumulator: 0,
+            pending: 0,
+        }
+    }
+
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.accumulator = (self.accumulator << 1) | ((value >> i) & 1);
+            self.pending += 1;
+            if self.pending == 8 {
+                self.bytes.push(self.accumulator as u8);
+                self.accumulator = 0;
+                self.pending = 0;
+            }
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64 & ((1u64 << bits) - 1), bits);
+    }
+
+    fn write_rice(&mut self, value: i64, parameter: u32) {
+        let folded = zigzag(value);
+        let mut quotient = folded >> parameter;
+        while quotient >= 32 {
+            self.write(0, 32);
+            quotient -= 32;
+        }
+        self.write(1, quotient as u32 + 1);
+        self.write(folded, parameter);
+    }
+
+    /// Frame numbers use the extended UTF-8 coding
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+        let continuation = match value {
+            v if v < 0x800 => 1,
+            v if v < 0x1_0000 => 2,
+            v if v < 0x20_0000 => 3,
+            v if v < 0x400_0000 => 4,
+            v if v < 0x8000_0000 => 5,
+            _ => 6,
+        };
+        let lead_mask = !0u64 << (7 - continuation) & 0xFF;
+        self.write(lead_mask | (value >> (6 * continuation)), 8);
+        for i in (0..continuation).rev() {
+            self.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write(byte as u64, 8);
+        }
+    }
+
+    fn align(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    /// Whole bytes written so far
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flac_round_trip() {
+        let path = std::env::temp_dir().join(format!("flac-{}.flac", uuid::Uuid::new_v4()));
+        // Stereo 24-bit: a tone, white-ish noise and a short final block
+        let frames = BLOCK_FRAMES * 2 + 123;
+        let samples: Vec<i32> = (0..frames)
+            .flat_map(|i| {
+                let tone = ((i as f64 * 0.05).sin() * 3_000_000.0) as i32;
+                let noise = ((i as i64 * 1_103_515_245 + 12_345) % 8_000_000 - 4_000_000) as i32;
+                [tone, noise]
+            })
+            .collect();
+
+        let mut writer = FlacWriter::create(&path, 44_100, 2, 24).unwrap();
+        for block in samples.chunks(BLOCK_FRAMES * 2) {
+            writer.write_frame(block).unwrap();
+        }
+        let size = writer.finalize().unwrap();
+        assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+        // The tone channel compresses well below the verbatim size
+        assert!(size < (frames * 2 * 3) as u64);
+
+        let mut reader = claxon::FlacReader::open(&path).unwrap();
+        let info = reader.streaminfo();
+        assert_eq!((info.sample_rate, info.channels, info.bits_per_sample), (44_100, 2, 24));
+        assert_eq!(info.samples, Some(frames as u64));
+        let decoded: Vec<i32> = reader.samples().map(Result::unwrap).collect();
+        assert_eq!(decoded, samples);
+        std::fs::remove_file(path).unwrap();
+    }
+}
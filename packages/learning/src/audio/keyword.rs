@@ -0,0 +1,599 @@
+//! Always-on wake phrase spotting.
+//!
+//! Each phrase is enrolled from one or more recordings of it. Incoming audio is cut
+//! into 10ms frames, each described by its spectral shape: a log spectrum on
+//! log-spaced bins with the mean removed, so loudness does not matter. Every template
+//! is matched against the live frames with streaming subsequence DTW. That costs one
+//! pass over the template per frame, cheap enough to leave running on the CPU.
+
+use anyhow::Result;
+use std::{f32::consts::PI, path::Path, time::Duration};
+use tokio::sync::mpsc;
+
+use super::source::{downmix, AudioSource};
+
+const FRAME_MS: u32 = 25;
+const HOP_MS: u32 = 10;
+const SPECTRUM_BINS: usize = 24;
+const MIN_BIN_HZ: f32 = 150.0;
+const MAX_BIN_HZ: f32 = 5000.0;
+/// Frames to wait for a better match before reporting one
+const DECISION_FRAMES: u64 = 15;
+/// Frames quieter than this (RMS) are trimmed from the ends of templates
+const TEMPLATE_TRIM_RMS: f32 = 0.01;
+
+/// A wake phrase and the recordings it is recognized from
+#[derive(Debug, Clone)]
+pub struct KeywordPhrase {
+    pub phrase: String,
+    /// Mono recordings of the phrase at the spotter's sample rate
+    pub templates: Vec<Vec<f32>>,
+}
+
+impl KeywordPhrase {
+    pub fn new(phrase: impl Into<String>) -> Self {
+        Self {
+            phrase: phrase.into(),
+            templates: Vec::new(),
+        }
+    }
+
+    pub fn with_template(mut self, samples: Vec<f32>) -> Self {
+        self.templates.push(samples);
+        self
+    }
+
+    /// Enroll a WAV recording of the phrase, which must be at `sample_rate`
+    pub fn with_template_file(self, path: impl AsRef<Path>, sample_rate: u32) -> Result<Self> {
+        let path = path.as_ref();
+        let mut reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+        if spec.sample_rate != sample_rate {
+            return Err(anyhow::anyhow!(
+                "Template {} is {} Hz, expected {} Hz",
+                path.display(),
+                spec.sample_rate,
+                sample_rate
+            ));
+        }
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|s| s.map(|s| s as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(self.with_template(downmix(&samples, spec.channels)))
+    }
+}
+
+/// Configuration for wake phrase spotting
+#[derive(Debug, Clone)]
+pub struct KeywordConfig {
+    pub phrases: Vec<KeywordPhrase>,
+    /// 0.0 accepts only near-exact matches, 1.0 accepts loose ones
+    pub sensitivity: f32,
+    pub sample_rate: u32,
+    /// Quiet period after a detection, so one utterance fires once
+    pub refractory: Duration,
+}
+
+impl Default for KeywordConfig {
+    fn default() -> Self {
+        Self {
+            phrases: Vec::new(),
+            sensitivity: 0.5,
+            sample_rate: 16000,
+            refractory: Duration::from_secs(1),
+        }
+    }
+}
+
+impl KeywordConfig {
+    /// Highest average per-frame distance a match may have
+    fn max_cost(&self) -> f32 {
+        0.1 + 0.15 * self.sensitivity.clamp(0.0, 1.0)
+    }
+}
+
+/// A detected wake phrase
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordEvent {
+    pub phrase: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    /// Match quality, 1.0 for a perfect match down to 0.0 at the acceptance threshold
+    pub score: f32,
+}
+
+/// Audio captured after a wake phrase, ready to transcribe and analyze
+#[derive(Debug, Clone)]
+pub struct WakeCapture {
+    pub event: KeywordEvent,
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+/// What was said after a wake phrase, and its analysis
+#[derive(Debug, Clone)]
+pub struct WakeAnalysis {
+    pub event: KeywordEvent,
+    pub transcript: String,
+    pub analysis: crate::analysis::AnalysisResult,
+}
+
+/// Streaming DTW state for one enrolled template
+struct TemplateMatcher {
+    phrase: usize,
+    frames: Vec<Vec<f32>>,
+    cost: Vec<f32>,
+    length: Vec<u32>,
+    start: Vec<u64>,
+}
+
+impl TemplateMatcher {
+    fn new(phrase: usize, frames: Vec<Vec<f32>>) -> Self {
+        let n = frames.len();
+        Self {
+            phrase,
+            frames,
+            cost: vec![f32::INFINITY; n],
+            length: vec![0; n],
+            start: vec![0; n],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.cost.fill(f32::INFINITY);
+    }
+
+    /// Advance by one input frame; returns the path's average cost per input frame
+    /// and its start frame when the path has just reached the end of the template.
+    fn step(&mut self, frame: &[f32], index: u64) -> Option<(f32, u64)> {
+        let n = self.frames.len();
+        let mut cost = vec![f32::INFINITY; n];
+        let mut length = vec![0u32; n];
+        let mut start = vec![0u64; n];
+
+        for j in 0..n {
+            let distance = frame_distance(frame, &self.frames[j]);
+            // (accumulated cost, path length, start frame) of each allowed predecessor
+            // Each input frame advances the template by 0, 1 or 2 frames, so a match
+            // can stretch the phrase but compress it by at most half
+            let mut candidates = vec![(self.cost[j], self.length[j], self.start[j])];
+            if j == 0 {
+                // A match may begin at any input frame
+                candidates.push((0.0, 0, index));
+            }
+            if j >= 1 {
+                candidates.push((self.cost[j - 1], self.length[j - 1], self.start[j - 1]));
+            }
+            if j >= 2 {
+                candidates.push((self.cost[j - 2], self.length[j - 2], self.start[j - 2]));
+            }
+
+            if let Some((c, l, s)) = candidates
+                .into_iter()
+                .filter(|(c, _, _)| c.is_finite())
+                .min_by(|a, b| {
+                    let average = |(c, l, _): &(f32, u32, u64)| (c + distance) / (l + 1) as f32;
+                    average(a).total_cmp(&average(b))
+                })
+            {
+                cost[j] = c + distance;
+                length[j] = l + 1;
+                start[j] = s;
+            }
+        }
+
+        self.cost = cost;
+        self.length = length;
+        self.start = start;
+
+        let last = n - 1;
+        if !self.cost[last].is_finite() {
+            return None;
+        }
+        // Reject paths that squeeze or stretch the template beyond natural speaking rates
+        let matched_frames = (index - self.start[last] + 1) as f32;
+        if matched_frames < n as f32 * 0.6 || matched_frames > n as f32 * 1.6 {
+            return None;
+        }
+        Some((self.cost[last] / self.length[last] as f32, self.start[last]))
+    }
+}
+
+/// Spots configured wake phrases in a live mono stream
+pub struct KeywordSpotter {
+    config: KeywordConfig,
+    features: FeatureExtractor,
+    matchers: Vec<TemplateMatcher>,
+    pending: Vec<f32>,
+    frame_index: u64,
+    /// Frames to skip after a detection
+    quiet_frames: u64,
+    /// Best match not yet reported
+    candidate: Option<Candidate>,
+}
+
+/// A match below the acceptance threshold, waiting to be reported
+struct Candidate {
+    phrase: usize,
+    cost: f32,
+    start: u64,
+    end: u64,
+}
+
+impl KeywordSpotter {
+    pub fn new(config: KeywordConfig) -> Result<Self> {
+        if config.phrases.is_empty() {
+            return Err(anyhow::anyhow!("No wake phrases configured"));
+        }
+
+        let features = FeatureExtractor::new(config.sample_rate);
+        let mut matchers = Vec::new();
+        for (index, phrase) in config.phrases.iter().enumerate() {
+            if phrase.templates.is_empty() {
+                return Err(anyhow::anyhow!("Wake phrase {:?} has no enrolled recordings", phrase.phrase));
+            }
+            for template in &phrase.templates {
+                let frames = features.template_frames(template);
+                if frames.len() < 5 {
+                    return Err(anyhow::anyhow!("Recording of {:?} is too short or too quiet", phrase.phrase));
+                }
+                matchers.push(TemplateMatcher::new(index, frames));
+            }
+        }
+
+        Ok(Self {
+            config,
+            features,
+            matchers,
+            pending: Vec::new(),
+            frame_index: 0,
+            quiet_frames: 0,
+            candidate: None,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.config.sample_rate
+    }
+
+    /// Feed mono samples at the configured rate; returns the phrases detected in them
+    pub fn push(&mut self, samples: &[f32]) -> Vec<KeywordEvent> {
+        self.pending.extend_from_slice(samples);
+        let frame_len = self.features.frame_len;
+        let hop = self.features.hop;
+        let mut events = Vec::new();
+
+        let mut offset = 0;
+        while offset + frame_len <= self.pending.len() {
+            let frame = self.features.frame(&self.pending[offset..offset + frame_len]);
+            let index = self.frame_index;
+            self.frame_index += 1;
+            offset += hop;
+
+            if self.quiet_frames > 0 {
+                self.quiet_frames -= 1;
+                continue;
+            }
+
+            let max_cost = self.config.max_cost();
+            let best = self
+                .matchers
+                .iter_mut()
+                .filter_map(|matcher| {
+                    let phrase = matcher.phrase;
+                    matcher.step(&frame, index).map(|(cost, start)| Candidate {
+                        phrase,
+                        cost,
+                        start,
+                        end: index,
+                    })
+                })
+                .filter(|candidate| candidate.cost <= max_cost)
+                .min_by(|a, b| a.cost.total_cmp(&b.cost));
+
+            if let Some(best) = best {
+                if self.candidate.as_ref().is_none_or(|current| best.cost < current.cost) {
+                    self.candidate = Some(best);
+                }
+            }
+
+            // Paths keep improving while the phrase is still being spoken, so only
+            // report the best one once nothing better has turned up for a while
+            let settled = self
+                .candidate
+                .as_ref()
+                .is_some_and(|candidate| index - candidate.end >= DECISION_FRAMES);
+            if settled {
+                let candidate = self.candidate.take().unwrap();
+                let secs = |frame: u64| frame as f64 * hop as f64 / self.config.sample_rate as f64;
+                events.push(KeywordEvent {
+                    phrase: self.config.phrases[candidate.phrase].phrase.clone(),
+                    start_secs: secs(candidate.start),
+                    end_secs: secs(candidate.end) + frame_len as f64 / self.config.sample_rate as f64,
+                    score: 1.0 - candidate.cost / max_cost,
+                });
+                self.matchers.iter_mut().for_each(TemplateMatcher::reset);
+                self.quiet_frames = (self.config.refractory.as_millis() as u64 / HOP_MS as u64).max(1);
+            }
+        }
+
+        self.pending.drain(..offset.min(self.pending.len()));
+        events
+    }
+
+    /// Forget any partial match, e.g. after capture paused spotting
+    pub fn reset(&mut self) {
+        self.matchers.iter_mut().for_each(TemplateMatcher::reset);
+        self.pending.clear();
+        self.candidate = None;
+    }
+
+    /// Listen to `source` until it ends. Each detected phrase starts a capture of the
+    /// next `capture` of audio, which is sent on `captures`. Spotting pauses while
+    /// capturing.
+    pub async fn listen(
+        &mut self,
+        source: &mut dyn AudioSource,
+        capture: Duration,
+        captures: mpsc::UnboundedSender<WakeCapture>,
+    ) -> Result<()> {
+        let channels = source.channels();
+        let source_rate = source.sample_rate();
+        let capture_len = (capture.as_secs_f64() * self.config.sample_rate as f64) as usize;
+        let mut active: Option<WakeCapture> = None;
+
+        while let Some(block) = source.next_block().await? {
+            let mono = super::longform::resample(&downmix(&block, channels), source_rate, self.config.sample_rate);
+
+            if let Some(mut current) = active.take() {
+                current.samples.extend_from_slice(&mono);
+                if current.samples.len() < capture_len {
+                    active = Some(current);
+                    continue;
+                }
+                current.samples.truncate(capture_len);
+                if captures.send(current).is_err() {
+                    return Ok(());
+                }
+                self.reset();
+                continue;
+            }
+
+            if let Some(event) = self.push(&mono).into_iter().next() {
+                // Audio already buffered after the phrase belongs to the capture
+                let after_phrase = self.frame_index as f64 * self.features.hop as f64
+                    / self.config.sample_rate as f64
+                    - event.end_secs;
+                let keep = ((after_phrase.max(0.0) * self.config.sample_rate as f64) as usize).min(mono.len());
+                active = Some(WakeCapture {
+                    event,
+                    samples: mono[mono.len() - keep..].to_vec(),
+                    sample_rate: self.config.sample_rate,
+                });
+            }
+        }
+
+        if let Some(current) = active {
+            // The source ended mid-capture; what was heard is still worth analyzing
+            let _ = captures.send(current);
+        }
+        Ok(())
+    }
+}
+
+/// Turns audio frames into normalized log spectra
+struct FeatureExtractor {
+    frame_len: usize,
+    hop: usize,
+    window: Vec<f32>,
+    /// cos/sin tables per analysis frequency
+    basis: Vec<(Vec<f32>, Vec<f32>)>,
+}
+
+impl FeatureExtractor {
+    fn new(sample_rate: u32) -> Self {
+        let frame_len = (sample_rate * FRAME_MS / 1000) as usize;
+        let hop = (sample_rate * HOP_MS / 1000) as usize;
+        let window = (0..frame_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / (frame_len - 1) as f32).cos())
+            .collect();
+        let max_hz = MAX_BIN_HZ.min(sample_rate as f32 / 2.0);
+        let basis = (0..SPECTRUM_BINS)
+            .map(|k| {
+                let hz = MIN_BIN_HZ * (max_hz / MIN_BIN_HZ).powf(k as f32 / (SPECTRUM_BINS - 1) as f32);
+                let omega = 2.0 * PI * hz / sample_rate as f32;
+                (0..frame_len)
+                    .map(|i| ((omega * i as f32).cos(), (omega * i as f32).sin()))
+                    .unzip()
+            })
+            .collect();
+        Self {
+            frame_len,
+            hop,
+            window,
+            basis,
+        }
+    }
+
+    fn frame(&self, samples: &[f32]) -> Vec<f32> {
+        let windowed: Vec<f32> = samples.iter().zip(&self.window).map(|(s, w)| s * w).collect();
+        let power: Vec<f32> = self
+            .basis
+            .iter()
+            .map(|(cos, sin)| {
+                let re: f32 = windowed.iter().zip(cos).map(|(x, c)| x * c).sum();
+                let im: f32 = windowed.iter().zip(sin).map(|(x, s)| x * s).sum();
+                re * re + im * im
+            })
+            .collect();
+        // Keep 30dB below the peak so the shape is set by the peaks, not the valleys
+        let floor = power.iter().cloned().fold(0.0, f32::max) * 1e-3 + 1e-9;
+        let mut spectrum: Vec<f32> = power.iter().map(|p| (p + floor).ln()).collect();
+        let mean = spectrum.iter().sum::<f32>() / spectrum.len() as f32;
+        spectrum.iter_mut().for_each(|v| *v -= mean);
+        spectrum
+    }
+
+    /// Frames of an enrollment recording with leading and trailing silence removed
+    fn template_frames(&self, samples: &[f32]) -> Vec<Vec<f32>> {
+        if samples.len() < self.frame_len {
+            return Vec::new();
+        }
+        let starts: Vec<usize> = (0..=samples.len() - self.frame_len).step_by(self.hop).collect();
+        let loud = |&start: &usize| {
+            let frame = &samples[start..start + self.frame_len];
+            (frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32).sqrt() >= TEMPLATE_TRIM_RMS
+        };
+        let (Some(first), Some(last)) = (starts.iter().position(loud), starts.iter().rposition(loud)) else {
+            return Vec::new();
+        };
+        starts[first..=last]
+            .iter()
+            .map(|&start| self.frame(&samples[start..start + self.frame_len]))
+            .collect()
+    }
+}
+
+/// Cosine distance between two normalized spectra, 1.0 when either is flat
+fn frame_distance(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a < 1e-6 || norm_b < 1e-6 {
+        return 1.0;
+    }
+    1.0 - dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    /// A voiced "syllable": a harmonic stack on `f0` with 10ms fades
+    fn syllable(f0: f32, secs: f32, amplitude: f32) -> Vec<f32> {
+        let len = (secs * RATE as f32) as usize;
+        let fade = (0.01 * RATE as f32) as usize;
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / RATE as f32;
+                let envelope = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+                let voiced: f32 = (1..=4).map(|h| (2.0 * PI * f0 * h as f32 * t).sin() / h as f32).sum();
+                amplitude * envelope * voiced * 0.5
+            })
+            .collect()
+    }
+
+    /// Syllables spoken at `rate` (1.0 is the enrolled speed)
+    fn utterance(f0s: &[f32], rate: f32, amplitude: f32) -> Vec<f32> {
+        let durations = [0.15, 0.12, 0.18, 0.14];
+        f0s.iter()
+            .zip(durations.iter().cycle())
+            .flat_map(|(&f0, &secs)| syllable(f0, secs / rate, amplitude))
+            .collect()
+    }
+
+    fn noise(secs: f32, amplitude: f32, seed: u64) -> Vec<f32> {
+        let mut state = seed.max(1);
+        (0..(secs * RATE as f32) as usize)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                amplitude * ((state >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0)
+            })
+            .collect()
+    }
+
+    /// `speech` placed at 1.0s in 3s of background noise
+    fn scene(speech: &[f32], noise_level: f32, seed: u64) -> Vec<f32> {
+        let mut audio = noise(3.0, noise_level, seed);
+        let offset = RATE as usize;
+        for (i, s) in speech.iter().enumerate() {
+            audio[offset + i] += s;
+        }
+        audio
+    }
+
+    const WAKE: [f32; 3] = [200.0, 570.0, 310.0];
+
+    fn spotter(sensitivity: f32) -> KeywordSpotter {
+        KeywordSpotter::new(KeywordConfig {
+            phrases: vec![KeywordPhrase::new("tta note this").with_template(utterance(&WAKE, 1.0, 0.6))],
+            sensitivity,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn detections(spotter: &mut KeywordSpotter, audio: &[f32]) -> Vec<KeywordEvent> {
+        // Feed in uneven blocks, as a device would deliver them
+        audio.chunks(1234).flat_map(|block| spotter.push(block)).collect()
+    }
+
+    #[test]
+    fn test_wake_phrase_false_reject_fixtures() {
+        let fixtures = [
+            ("enrolled take", scene(&utterance(&WAKE, 1.0, 0.6), 0.01, 1)),
+            ("quieter speaker", scene(&utterance(&WAKE, 1.0, 0.15), 0.005, 2)),
+            ("spoken 15% faster", scene(&utterance(&WAKE, 1.15, 0.6), 0.01, 3)),
+            ("spoken 15% slower", scene(&utterance(&WAKE, 0.87, 0.6), 0.01, 4)),
+            ("noisy room", scene(&utterance(&WAKE, 1.0, 0.6), 0.05, 5)),
+        ];
+
+        for (name, audio) in &fixtures {
+            let events = detections(&mut spotter(0.5), audio);
+            assert_eq!(events.len(), 1, "{}: {:?}", name, events);
+            assert_eq!(events[0].phrase, "tta note this");
+            assert!((events[0].start_secs - 1.0).abs() < 0.1, "{}: {:?}", name, events[0]);
+            assert!(events[0].score > 0.0 && events[0].score <= 1.0);
+        }
+
+        // Sensitivity decides how much noise a match may carry
+        let noisy = scene(&utterance(&WAKE, 1.0, 0.6), 0.35, 11);
+        assert!(detections(&mut spotter(0.0), &noisy).is_empty());
+        assert_eq!(detections(&mut spotter(1.0), &noisy).len(), 1);
+    }
+
+    #[test]
+    fn test_wake_phrase_false_accept_fixtures() {
+        let fixtures = [
+            ("background noise", noise(3.0, 0.05, 6)),
+            ("silence", vec![0.0; 3 * RATE as usize]),
+            ("same syllables reordered", scene(&utterance(&[570.0, 200.0, 310.0], 1.0, 0.6), 0.01, 7)),
+            ("first two syllables only", scene(&utterance(&WAKE[..2], 1.0, 0.6), 0.01, 8)),
+            ("other phrase", scene(&utterance(&[260.0, 720.0, 450.0, 380.0], 1.0, 0.6), 0.01, 9)),
+            ("sustained tone", scene(&syllable(310.0, 1.0, 0.6), 0.01, 10)),
+        ];
+
+        for (name, audio) in &fixtures {
+            let events = detections(&mut spotter(0.5), audio);
+            assert!(events.is_empty(), "{}: {:?}", name, events);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wake_phrase_starts_capture() {
+        use crate::audio::source::MemorySource;
+
+        let mut audio = scene(&utterance(&WAKE, 1.0, 0.6), 0.01, 12);
+        audio.extend(syllable(500.0, 2.0, 0.4));
+        let mut source = MemorySource::new(audio, RATE, 1).with_block_frames(800);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        spotter(0.5).listen(&mut source, Duration::from_secs(2), tx).await.unwrap();
+
+        let capture = rx.recv().await.unwrap();
+        assert_eq!(capture.event.phrase, "tta note this");
+        assert_eq!(capture.samples.len(), 2 * RATE as usize);
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -0,0 +1,660 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use hound::{SampleFormat as WavSampleFormat, WavReader};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+use super::recorder::{RecordingIndex, SessionRecorder};
+use super::{check_recorder, AudioTranscriber, TranscriptSegment};
+
+/// Audio decoded per read while filling the chunk buffer
+const DECODE_BLOCK_SECS: f64 = 1.0;
+
+/// Configuration for chunked transcription of long recordings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongFormConfig {
+    /// Chunks are cut at the quietest point between the target and maximum length
+    pub target_chunk_secs: f64,
+    pub max_chunk_secs: f64,
+    /// Audio shared by consecutive chunks so words on a cut aren't lost
+    pub overlap_secs: f64,
+    /// Window used to find the quietest cut point
+    pub vad_window_ms: u32,
+    /// Maximum number of chunks transcribed at the same time
+    pub max_concurrency: usize,
+    /// Progress file written after every chunk; an interrupted run resumes from it
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Default for LongFormConfig {
+    fn default() -> Self {
+        Self {
+            target_chunk_secs: 30.0,
+            max_chunk_secs: 40.0,
+            overlap_secs: 1.5,
+            vad_window_ms: 30,
+            max_concurrency: 4,
+            checkpoint_path: None,
+        }
+    }
+}
+
+/// Progress of a long-form transcription, sent after every stitched chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongFormProgress {
+    pub chunk_index: usize,
+    pub chunks_done: usize,
+    pub processed_secs: f64,
+    pub total_secs: f64,
+    /// Where the run picked up from a checkpoint, if it did
+    pub resumed_from_secs: Option<f64>,
+}
+
+/// Stitched transcript of a long recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongFormTranscript {
+    pub segments: Vec<TranscriptSegment>,
+    pub duration_secs: f64,
+    pub chunks: usize,
+    pub resumed_from_secs: Option<f64>,
+}
+
+impl LongFormTranscript {
+    /// Text of all segments in order
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|segment| segment.text.trim())
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// What has been transcribed so far, saved so an interrupted run can resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LongFormCheckpoint {
+    pub audio_path: String,
+    /// Size and modification time of the audio file; a changed file starts over
+    pub fingerprint: String,
+    pub next_chunk_index: usize,
+    /// First sample of the next chunk, including its overlap
+    pub next_sample: u64,
+    pub segments: Vec<TranscriptSegment>,
+}
+
+impl LongFormCheckpoint {
+    /// Fingerprint of an audio file as stored in checkpoints
+    pub fn fingerprint(audio_path: &Path) -> Result<String> {
+        let metadata = std::fs::metadata(audio_path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_secs());
+        Ok(format!("{}-{}", metadata.len(), modified))
+    }
+
+    /// Load a checkpoint, `None` if there is none
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&std::fs::read_to_string(path)?)?))
+    }
+
+    /// Write the checkpoint atomically
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string(self)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+}
+
+/// A span of audio to transcribe on its own
+#[derive(Debug, Clone)]
+pub struct AudioChunk {
+    pub index: usize,
+    pub start_sample: u64,
+    pub end_sample: u64,
+    /// Where the following chunk starts; earlier than `end_sample` by the overlap
+    pub next_start: u64,
+    pub samples: Vec<f32>,
+}
+
+/// Reads a WAV file a block at a time, mixed down to mono
+pub struct WavStream {
+    reader: WavReader<BufReader<File>>,
+    channels: usize,
+    sample_rate: u32,
+    total_frames: u64,
+}
+
+impl WavStream {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let reader = WavReader::open(path)?;
+        let spec = reader.spec();
+        Ok(Self {
+            channels: spec.channels.max(1) as usize,
+            sample_rate: spec.sample_rate,
+            total_frames: reader.duration() as u64,
+            reader,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// Continue reading from the given frame
+    pub fn seek(&mut self, frame: u64) -> Result<()> {
+        self.reader.seek(frame.min(self.total_frames) as u32)?;
+        Ok(())
+    }
+
+    /// Read up to `max_frames` mono samples; empty at the end of the file
+    pub fn read_frames(&mut self, max_frames: usize) -> Result<Vec<f32>> {
+        let spec = self.reader.spec();
+        let count = max_frames * self.channels;
+        let interleaved: Vec<f32> = match spec.sample_format {
+            WavSampleFormat::Float => self.reader.samples::<f32>().take(count).collect::<Result<_, _>>()?,
+            WavSampleFormat::Int => {
+                let scale = (1u64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                self.reader
+                    .samples::<i32>()
+                    .take(count)
+                    .map(|sample| sample.map(|value| value as f32 / scale))
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        Ok(interleaved
+            .chunks(self.channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect())
+    }
+}
+
+/// Splits a WAV file into overlapping chunks cut at quiet points, decoding as it goes
+pub struct ChunkStream {
+    wav: WavStream,
+    config: LongFormConfig,
+    buffer: Vec<f32>,
+    buffer_start: u64,
+    next_index: usize,
+    done: bool,
+}
+
+impl ChunkStream {
+    pub fn open(path: impl AsRef<Path>, config: LongFormConfig) -> Result<Self> {
+        if config.overlap_secs >= config.target_chunk_secs || config.target_chunk_secs > config.max_chunk_secs {
+            return Err(anyhow::anyhow!(
+                "Invalid long-form config: overlap must be shorter than the target chunk, and the target no longer than the maximum"
+            ));
+        }
+        Ok(Self {
+            wav: WavStream::open(path)?,
+            config,
+            buffer: Vec::new(),
+            buffer_start: 0,
+            next_index: 0,
+            done: false,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.wav.sample_rate()
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        self.wav.total_frames()
+    }
+
+    /// Skip to a chunk recorded in a checkpoint
+    pub fn resume_at(&mut self, chunk_index: usize, sample: u64) -> Result<()> {
+        self.wav.seek(sample)?;
+        self.buffer.clear();
+        self.buffer_start = sample;
+        self.next_index = chunk_index;
+        Ok(())
+    }
+
+    fn samples(&self, secs: f64) -> usize {
+        (secs * self.wav.sample_rate() as f64).round() as usize
+    }
+
+    /// Cut the buffered audio at the quietest window between the target and maximum length
+    fn cut(&mut self) -> AudioChunk {
+        let target = self.samples(self.config.target_chunk_secs);
+        let max = self.samples(self.config.max_chunk_secs).min(self.buffer.len());
+        let window = self.samples(self.config.vad_window_ms as f64 / 1000.0).max(1);
+
+        let mut cut = max;
+        let mut quietest = f32::MAX;
+        let mut end = target.max(window);
+        while end <= max {
+            let energy = self.buffer[end - window..end].iter().map(|x| x * x).sum::<f32>();
+            if energy < quietest {
+                quietest = energy;
+                cut = end - window / 2;
+            }
+            end += window;
+        }
+
+        let next = cut - self.samples(self.config.overlap_secs).min(cut - 1);
+        let chunk = AudioChunk {
+            index: self.next_index,
+            start_sample: self.buffer_start,
+            end_sample: self.buffer_start + cut as u64,
+            next_start: self.buffer_start + next as u64,
+            samples: self.buffer[..cut].to_vec(),
+        };
+        self.buffer.drain(..next);
+        self.buffer_start += next as u64;
+        self.next_index += 1;
+        chunk
+    }
+}
+
+impl Iterator for ChunkStream {
+    type Item = Result<AudioChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let max = self.samples(self.config.max_chunk_secs);
+        let block = self.samples(DECODE_BLOCK_SECS).max(1);
+
+        loop {
+            if self.buffer.len() >= max {
+                return Some(Ok(self.cut()));
+            }
+            let samples = match self.wav.read_frames(block) {
+                Ok(samples) => samples,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+            if samples.is_empty() {
+                self.done = true;
+                // After the first chunk the buffer always starts with audio already
+                // transcribed as overlap; only emit it if there's something new
+                let seen = if self.next_index == 0 { 0 } else { self.samples(self.config.overlap_secs) };
+                if self.buffer.len() <= seen {
+                    return None;
+                }
+                let end = self.buffer_start + self.buffer.len() as u64;
+                return Some(Ok(AudioChunk {
+                    index: self.next_index,
+                    start_sample: self.buffer_start,
+                    end_sample: end,
+                    next_start: end,
+                    samples: std::mem::take(&mut self.buffer),
+                }));
+            }
+            self.buffer.extend(samples);
+        }
+    }
+}
+
+/// A transcribed chunk, in chunk order
+struct ChunkTranscript {
+    index: usize,
+    next_start: u64,
+    segment: TranscriptSegment,
+}
+
+impl AudioTranscriber {
+    /// Transcribe a long WAV recording in overlapping chunks.
+    ///
+    /// The file is decoded incrementally, cut at quiet points, and up to
+    /// `config.max_concurrency` chunks are transcribed in parallel. Chunk hypotheses are
+    /// stitched in order, dropping words repeated across the overlap. With a
+    /// `checkpoint_path`, progress is saved after every chunk and a rerun on the same file
+    /// continues where the last one stopped; the checkpoint is removed once done.
+    pub async fn transcribe_long_form(
+        &self,
+        audio_path: &str,
+        config: &LongFormConfig,
+        progress: Option<mpsc::UnboundedSender<LongFormProgress>>,
+    ) -> Result<LongFormTranscript> {
+        let mut chunks = ChunkStream::open(audio_path, config.clone())?;
+        let sample_rate = chunks.sample_rate();
+        let total_secs = chunks.total_samples() as f64 / sample_rate as f64;
+        let fingerprint = LongFormCheckpoint::fingerprint(Path::new(audio_path))?;
+        let seam_words = seam_words(config);
+
+        let mut segments = Vec::new();
+        let mut chunks_done = 0;
+        let mut resumed_from_secs = None;
+        let checkpoint = match &config.checkpoint_path {
+            Some(path) => LongFormCheckpoint::load(path)?,
+            None => None,
+        };
+        if let Some(checkpoint) = checkpoint.filter(|c| c.audio_path == audio_path && c.fingerprint == fingerprint) {
+            chunks.resume_at(checkpoint.next_chunk_index, checkpoint.next_sample)?;
+            resumed_from_secs = Some(checkpoint.next_sample as f64 / sample_rate as f64);
+            chunks_done = checkpoint.next_chunk_index;
+            segments = checkpoint.segments;
+        }
+
+        let transcriptions = stream::iter(chunks)
+            .map(|chunk| {
+                let transcriber = self.clone();
+                async move {
+                    let chunk = chunk?;
+                    tokio::spawn(async move { transcriber.transcribe_chunk(chunk, sample_rate).await }).await?
+                }
+            })
+            .buffered(config.max_concurrency.max(1));
+        let mut transcriptions = std::pin::pin!(transcriptions);
+
+        while let Some(transcript) = transcriptions.next().await {
+            let transcript = transcript?;
+            let processed_secs = transcript.segment.end_secs;
+            stitch(&mut segments, transcript.segment, seam_words);
+            chunks_done = transcript.index + 1;
+
+            if let Some(path) = &config.checkpoint_path {
+                LongFormCheckpoint {
+                    audio_path: audio_path.to_string(),
+                    fingerprint: fingerprint.clone(),
+                    next_chunk_index: chunks_done,
+                    next_sample: transcript.next_start,
+                    segments: segments.clone(),
+                }
+                .save(path)?;
+            }
+            if let Some(sender) = &progress {
+                // A dropped receiver only means nobody is watching anymore
+                let _ = sender.send(LongFormProgress {
+                    chunk_index: transcript.index,
+                    chunks_done,
+                    processed_secs,
+                    total_secs,
+                    resumed_from_secs,
+                });
+            }
+        }
+
+        if let Some(path) = config.checkpoint_path.as_ref().filter(|path| path.exists()) {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(LongFormTranscript {
+            segments,
+            duration_secs: total_secs,
+            chunks: chunks_done,
+            resumed_from_secs,
+        })
+    }
+
+    /// Transcribe a long WAV recording and keep it as a session recording.
+    ///
+    /// Once transcribed, the file is copied into `recorder` at the recording's sample rate
+    /// and every stitched segment is linked to where it was heard, so each line of the
+    /// transcript can be played back. A resumed run still records the whole file.
+    pub async fn transcribe_long_form_recorded(
+        &self,
+        audio_path: &str,
+        config: &LongFormConfig,
+        progress: Option<mpsc::UnboundedSender<LongFormProgress>>,
+        mut recorder: SessionRecorder,
+    ) -> Result<(LongFormTranscript, RecordingIndex)> {
+        check_recorder(&recorder)?;
+        let transcript = self.transcribe_long_form(audio_path, config, progress).await?;
+
+        let offset_secs = recorder.position_secs();
+        let mut wav = WavStream::open(audio_path)?;
+        let block = (DECODE_BLOCK_SECS * wav.sample_rate() as f64).round().max(1.0) as usize;
+        loop {
+            let samples = wav.read_frames(block)?;
+            if samples.is_empty() {
+                break;
+            }
+            recorder.write(&resample(&samples, wav.sample_rate(), recorder.index().sample_rate))?;
+        }
+        for segment in &transcript.segments {
+            recorder.link_transcript(segment, offset_secs, None)?;
+        }
+
+        let index = recorder.finish()?;
+        Ok((transcript, index))
+    }
+
+    async fn transcribe_chunk(&self, chunk: AudioChunk, sample_rate: u32) -> Result<ChunkTranscript> {
+        let audio = resample(&chunk.samples, sample_rate, self.config.sample_rate);
+        let processed = self.preprocess_audio(&audio)?;
+        let text = self.transcribe_audio_data(&processed).await?;
+
+        let mut segment = self.score_segment(&processed, text);
+        segment.start_secs = chunk.start_sample as f64 / sample_rate as f64;
+        segment.end_secs = chunk.end_sample as f64 / sample_rate as f64;
+        Ok(ChunkTranscript {
+            index: chunk.index,
+            next_start: chunk.next_start,
+            segment,
+        })
+    }
+}
+
+/// Most words that can repeat across a seam: the overlap at a fast speaking rate
+fn seam_words(config: &LongFormConfig) -> usize {
+    (config.overlap_secs * 4.0).ceil() as usize + 2
+}
+
+/// Append a chunk's segment, dropping the words it repeats from the previous one
+fn stitch(segments: &mut Vec<TranscriptSegment>, mut next: TranscriptSegment, max_words: usize) {
+    if let Some(previous) = segments.last() {
+        let previous_words: Vec<&str> = previous.text.split_whitespace().collect();
+        let next_words: Vec<&str> = next.text.split_whitespace().collect();
+        let repeated = seam_overlap(&previous_words, &next_words, max_words);
+        if repeated > 0 {
+            next.text = next_words[repeated..].join(" ");
+        }
+        next.start_secs = next.start_secs.max(previous.end_secs);
+    }
+    if !next.text.trim().is_empty() {
+        segments.push(next);
+    }
+}
+
+/// Number of leading words of `next` that repeat the trailing words of `previous`.
+///
+/// Words are compared ignoring case and punctuation. A single repeated word only counts
+/// if it is at least four letters long, so "the ... the" isn't taken for a seam.
+pub fn seam_overlap(previous: &[&str], next: &[&str], max_words: usize) -> usize {
+    let normalize = |word: &str| {
+        word.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let longest = max_words.min(previous.len()).min(next.len());
+    (1..=longest)
+        .rev()
+        .find(|&k| {
+            let tail = &previous[previous.len() - k..];
+            let matches = tail.iter().zip(&next[..k]).all(|(a, b)| normalize(a) == normalize(b));
+            matches && (k > 1 || normalize(tail[0]).chars().count() >= 4)
+        })
+        .unwrap_or(0)
+}
+
+/// Linear resampling between sample rates
+pub(super) fn resample(samples: &[f32], from: u32, to: u32) -> Vec<f32> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let last = samples.len() - 1;
+    let length = (samples.len() as u64 * to as u64 / from as u64) as usize;
+    (0..length)
+        .map(|i| {
+            let position = i as f64 * from as f64 / to as f64;
+            let j = position.floor() as usize;
+            let fraction = (position - j as f64) as f32;
+            let (a, b) = (samples[j.min(last)], samples[(j + 1).min(last)]);
+            a + (b - a) * fraction
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2.5s tone bursts separated by 0.5s of silence
+    fn write_lecture(path: &Path, secs: usize) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for i in 0..secs * 16000 {
+            let t = i as f32 / 16000.0;
+            let sample = if t % 3.0 < 2.5 { 0.5 * (t * 440.0 * std::f32::consts::TAU).sin() } else { 0.0 };
+            writer.write_sample((sample * 32767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    fn temp_wav(name: &str, secs: usize) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("longform-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        write_lecture(&path, secs);
+        path
+    }
+
+    #[test]
+    fn test_chunks_cut_at_silence_with_overlap() {
+        let path = temp_wav("lecture.wav", 90);
+        let config = LongFormConfig::default();
+        let chunks: Vec<AudioChunk> = ChunkStream::open(&path, config.clone()).unwrap().map(Result::unwrap).collect();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks.last().unwrap().end_sample, 90 * 16000);
+        for pair in chunks.windows(2) {
+            let (chunk, next) = (&pair[0], &pair[1]);
+            assert!(chunk.samples.len() <= 40 * 16000 && chunk.samples.len() >= 29 * 16000);
+            assert_eq!(chunk.end_sample - next.start_sample, (config.overlap_secs * 16000.0) as u64);
+            // Cut inside a gap between bursts
+            let cut_secs = chunk.end_sample as f64 / 16000.0;
+            assert!(cut_secs % 3.0 > 2.5, "cut at {}s is not in a silent gap", cut_secs);
+        }
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recorded_long_form_links_segments() {
+        let path = temp_wav("lecture.wav", 90);
+        let data_dir = path.parent().unwrap().join("data");
+        let recorder = SessionRecorder::create(&data_dir, "lecture", Default::default()).unwrap();
+
+        let transcriber = AudioTranscriber::new().unwrap();
+        let (transcript, index) = transcriber
+            .transcribe_long_form_recorded(&path.to_string_lossy(), &LongFormConfig::default(), None, recorder)
+            .await
+            .unwrap();
+
+        assert!((index.duration_secs() - 90.0).abs() < 0.01);
+        assert_eq!(index.transcripts.len(), transcript.segments.len());
+        let dir = RecordingIndex::session_dir(&data_dir, "lecture");
+        let last = index.transcripts.len() - 1;
+        let playback = RecordingIndex::load(&data_dir, "lecture").unwrap().playback_for(&dir, last).unwrap();
+        assert_eq!(playback[0].path, dir.join(&index.segments[0].file));
+        assert_eq!(playback[0].start_secs, transcript.segments[last].start_secs);
+        assert_eq!(playback[0].end_secs, transcript.segments[last].end_secs);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_seam_words_removed() {
+        let previous: Vec<&str> = "so the gradient of the loss function".split_whitespace().collect();
+        let next: Vec<&str> = "Loss function, which we minimise".split_whitespace().collect();
+        assert_eq!(seam_overlap(&previous, &next, 8), 2);
+
+        let the: Vec<&str> = vec!["the", "model"];
+        assert_eq!(seam_overlap(&["read", "the"], &the, 8), 0);
+
+        let mut segments = vec![TranscriptSegment {
+            start_secs: 0.0,
+            end_secs: 30.0,
+            text: previous.join(" "),
+            speaker: None,
+            avg_logprob: -0.2,
+            no_speech_prob: 0.0,
+        }];
+        let following = TranscriptSegment {
+            start_secs: 28.5,
+            end_secs: 60.0,
+            text: next.join(" "),
+            ..segments[0].clone()
+        };
+        stitch(&mut segments, following, 8);
+        assert_eq!(segments[1].text, "which we minimise");
+        assert_eq!(segments[1].start_secs, 30.0);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_checkpoint() {
+        let path = temp_wav("lecture.wav", 90);
+        let checkpoint_path = path.with_file_name("lecture.progress.json");
+        let audio_path = path.to_string_lossy().to_string();
+        let config = LongFormConfig {
+            checkpoint_path: Some(checkpoint_path.clone()),
+            ..Default::default()
+        };
+
+        // The first chunk was transcribed before the previous run was interrupted
+        let first = ChunkStream::open(&path, config.clone()).unwrap().next().unwrap().unwrap();
+        LongFormCheckpoint {
+            audio_path: audio_path.clone(),
+            fingerprint: LongFormCheckpoint::fingerprint(&path).unwrap(),
+            next_chunk_index: 1,
+            next_sample: first.next_start,
+            segments: vec![TranscriptSegment {
+                start_secs: 0.0,
+                end_secs: first.end_sample as f64 / 16000.0,
+                text: "Transcribed before the interruption.".to_string(),
+                speaker: None,
+                avg_logprob: -0.2,
+                no_speech_prob: 0.0,
+            }],
+        }
+        .save(&checkpoint_path)
+        .unwrap();
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let transcriber = AudioTranscriber::new().unwrap();
+        let transcript = transcriber
+            .transcribe_long_form(&audio_path, &config, Some(sender))
+            .await
+            .unwrap();
+
+        assert_eq!(transcript.chunks, 3);
+        assert_eq!(transcript.segments[0].text, "Transcribed before the interruption.");
+        assert!(transcript.segments.windows(2).all(|pair| pair[1].start_secs >= pair[0].end_secs));
+
+        let mut events = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            events.push(event);
+        }
+        assert_eq!(events.iter().map(|e| e.chunk_index).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(events[0].resumed_from_secs, Some(first.next_start as f64 / 16000.0));
+        assert_eq!(events.last().unwrap().processed_secs, 90.0);
+        assert!(!checkpoint_path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,317 @@
+//! Punctuation and capitalization restoration for raw recognizer output.
+//!
+//! Recognizers emit lowercase word streams with no sentence boundaries, which the
+//! downstream sentence splitters cannot work with. A token-classification model
+//! predicts the punctuation after each word when one is configured. Otherwise, or if
+//! it fails to load, rules based on sentence openers and question words are used.
+//! Either way, truecasing then capitalizes sentence starts, "I" and known terms.
+
+use anyhow::Result;
+use rust_bert::{
+    pipelines::{
+        common::{ModelResource, ModelType},
+        token_classification::{LabelAggregationOption, TokenClassificationConfig, TokenClassificationModel},
+    },
+    resources::LocalResource,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// How transcripts are punctuated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PunctuationMode {
+    /// Leave recognizer output untouched
+    Off,
+    /// Rule-based restoration
+    #[default]
+    Rules,
+    /// Model-based restoration, falling back to rules when the model is unavailable
+    Model,
+}
+
+/// Configuration for punctuation restoration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PunctuationConfig {
+    pub mode: PunctuationMode,
+    /// Directory holding `rust_model.ot`, `config.json` and `sentencepiece.bpe.model`
+    /// of an XLM-RoBERTa punctuation model whose labels are the mark that follows each
+    /// word ("0", ",", ".", "?", ...)
+    #[serde(default)]
+    pub model_dir: Option<PathBuf>,
+}
+
+/// Discourse markers that begin a new sentence when they follow a few words of
+/// another one. Words that also occur mid-sentence ("now", "then", "so") are left out.
+const SENTENCE_OPENERS: &[&str] = &["okay", "ok", "alright", "anyway", "anyways", "however", "meanwhile"];
+/// Sentence-initial words followed by a comma ("So, ...")
+const INTRODUCTORY: &[&str] = &[
+    "so", "okay", "ok", "alright", "anyway", "anyways", "however", "meanwhile", "actually", "basically",
+    "finally", "yes", "yeah",
+];
+/// Words that make a sentence a question when it starts with them
+const QUESTION_STARTERS: &[&str] = &[
+    "what", "why", "how", "when", "where", "who", "whom", "whose", "which", "is", "are", "was", "were",
+    "do", "does", "did", "can", "could", "would", "should", "will", "shall", "have", "has", "may",
+];
+/// Fewest words a sentence needs before an opener may end it
+const MIN_SENTENCE_WORDS: usize = 4;
+/// Longest run of words before a sentence is broken at the next conjunction
+const MAX_SENTENCE_WORDS: usize = 25;
+/// Properly cased forms of common words recognizers emit in lowercase
+const KNOWN_TERMS: &[&str] = &[
+    "I", "I'm", "I'll", "I've", "I'd", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday",
+    "Sunday", "January", "February", "April", "June", "July", "August", "September", "October", "November",
+    "December", "API", "APIs", "URL", "SQL", "JSON", "HTTP", "HTTPS", "HTML", "CSS", "CPU", "GPU", "OCR",
+    "PDF", "UI", "JavaScript", "TypeScript", "GitHub", "Linux", "English",
+];
+
+/// Restores punctuation and capitalization in transcripts
+#[derive(Clone)]
+pub struct PunctuationRestorer {
+    mode: PunctuationMode,
+    model: Option<Arc<Mutex<TokenClassificationModel>>>,
+    terms: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for PunctuationRestorer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PunctuationRestorer")
+            .field("mode", &self.mode)
+            .field("model_loaded", &self.model.is_some())
+            .finish()
+    }
+}
+
+impl PunctuationRestorer {
+    pub fn new(config: &PunctuationConfig) -> Self {
+        let model = match (config.mode, config.model_dir.as_deref()) {
+            (PunctuationMode::Model, Some(dir)) => match load_model(dir) {
+                Ok(model) => Some(Arc::new(Mutex::new(model))),
+                Err(e) => {
+                    eprintln!("Punctuation model unavailable, using rules: {}", e);
+                    None
+                }
+            },
+            (PunctuationMode::Model, None) => {
+                eprintln!("No punctuation model directory configured, using rules");
+                None
+            }
+            _ => None,
+        };
+
+        Self {
+            mode: config.mode,
+            model,
+            terms: KNOWN_TERMS
+                .iter()
+                .map(|term| (term.to_lowercase(), term.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Add terms whose casing should be kept, e.g. product names
+    pub fn with_terms<I, S>(mut self, terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for term in terms {
+            let term = term.into();
+            self.terms.insert(term.to_lowercase(), term);
+        }
+        self
+    }
+
+    /// Punctuate and truecase a transcript
+    pub fn restore(&self, text: &str) -> String {
+        if self.mode == PunctuationMode::Off || text.trim().is_empty() {
+            return text.to_string();
+        }
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let marks = self
+            .model
+            .as_ref()
+            .and_then(|model| predict_marks(&model.lock().unwrap(), &words))
+            .unwrap_or_else(|| rule_marks(&words));
+
+        self.truecase(&words, &marks)
+    }
+
+    /// Join words with their marks, capitalizing sentence starts and known terms
+    fn truecase(&self, words: &[&str], marks: &[Option<char>]) -> String {
+        let mut out = String::with_capacity(words.iter().map(|w| w.len() + 2).sum());
+        let mut sentence_start = 0;
+
+        for (i, (word, mark)) in words.iter().zip(marks).enumerate() {
+            let (core, trailing) = split_trailing_punctuation(word);
+            let mut cased = self
+                .terms
+                .get(&core.to_lowercase())
+                // Mixed case from the recognizer (e.g. "iPhone") is kept as is
+                .filter(|_| core.chars().all(|c| !c.is_uppercase()))
+                .cloned()
+                .unwrap_or_else(|| core.to_string());
+            if i == sentence_start {
+                cased = capitalize(&cased);
+            }
+
+            if !out.is_empty() {
+                out.push(' ');
+            }
+            out.push_str(&cased);
+            out.push_str(trailing);
+            if trailing.is_empty() {
+                if let Some(mark) = mark {
+                    out.push(*mark);
+                }
+            }
+
+            if matches!(trailing.chars().last().or(*mark), Some('.' | '?' | '!')) {
+                sentence_start = i + 1;
+            }
+        }
+
+        if sentence_start < words.len() {
+            out.push(if is_question(&words[sentence_start..]) { '?' } else { '.' });
+        }
+        out
+    }
+}
+
+/// Punctuation after each word from sentence openers, conjunctions and question words
+fn rule_marks(words: &[&str]) -> Vec<Option<char>> {
+    let mut marks = vec![None; words.len()];
+    let mut sentence_start = 0;
+
+    for i in 0..words.len() {
+        let (core, trailing) = split_trailing_punctuation(words[i]);
+        if !trailing.is_empty() {
+            if trailing.ends_with(['.', '?', '!']) {
+                sentence_start = i + 1;
+            }
+            continue;
+        }
+
+        let lower = core.to_lowercase();
+        let next = words.get(i + 1).map(|w| split_trailing_punctuation(w).0.to_lowercase());
+        let sentence_len = i + 1 - sentence_start;
+
+        // Break before an opener once the current sentence has some substance
+        let opener_follows = next
+            .as_deref()
+            .is_some_and(|next| SENTENCE_OPENERS.contains(&next) && sentence_len >= MIN_SENTENCE_WORDS);
+        // Break run-on sentences before "and" or "but"
+        let run_on = sentence_len >= MAX_SENTENCE_WORDS && matches!(next.as_deref(), Some("and" | "but"));
+        if opener_follows || run_on {
+            let sentence = &words[sentence_start..=i];
+            marks[i] = Some(if is_question(sentence) { '?' } else { '.' });
+            sentence_start = i + 1;
+            continue;
+        }
+
+        let introductory = i == sentence_start && INTRODUCTORY.contains(&lower.as_str()) && next.is_some();
+        if introductory || (next.as_deref() == Some("but") && sentence_len >= 3) {
+            marks[i] = Some(',');
+        }
+    }
+    marks
+}
+
+/// Whether a sentence reads as a question, skipping a leading introductory word
+fn is_question(sentence: &[&str]) -> bool {
+    sentence
+        .iter()
+        .map(|w| split_trailing_punctuation(w).0.to_lowercase())
+        .find(|w| !INTRODUCTORY.contains(&w.as_str()))
+        .is_some_and(|w| QUESTION_STARTERS.contains(&w.as_str()))
+}
+
+fn split_trailing_punctuation(word: &str) -> (&str, &str) {
+    let core = word.trim_end_matches(['.', ',', '?', '!', ':', ';']);
+    (core, &word[core.len()..])
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn load_model(dir: &Path) -> Result<TokenClassificationModel> {
+    let config = TokenClassificationConfig::new(
+        ModelType::XLMRoberta,
+        ModelResource::Torch(Box::new(LocalResource::from(dir.join("rust_model.ot")))),
+        LocalResource::from(dir.join("config.json")),
+        LocalResource::from(dir.join("sentencepiece.bpe.model")),
+        None,
+        false,
+        None,
+        None,
+        LabelAggregationOption::First,
+    );
+    Ok(TokenClassificationModel::new(config)?)
+}
+
+/// Marks predicted by the model, or `None` if its output cannot be aligned to the words
+fn predict_marks(model: &TokenClassificationModel, words: &[&str]) -> Option<Vec<Option<char>>> {
+    let tokens = model.predict(&[words.join(" ")], true, false).into_iter().next()?;
+    let mut marks = vec![None; words.len()];
+    for token in tokens {
+        let mark = match token.label.as_str() {
+            "." | "," | "?" | "!" | ":" | ";" => token.label.chars().next(),
+            _ => None,
+        };
+        *marks.get_mut(token.word_index as usize)? = mark;
+    }
+    Some(marks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> PunctuationRestorer {
+        PunctuationRestorer::new(&PunctuationConfig::default())
+    }
+
+    #[test]
+    fn test_rules_split_sentences_and_questions() {
+        let restored = rules().restore(
+            "so today we look at the json api i wrote on monday okay what happens when the request fails \
+             anyway it retries three times but the timeout is too short",
+        );
+
+        assert_eq!(
+            restored,
+            "So, today we look at the JSON API I wrote on Monday. Okay, what happens when the request fails? \
+             Anyway, it retries three times, but the timeout is too short."
+        );
+    }
+
+    #[test]
+    fn test_existing_punctuation_and_mode_off_are_respected() {
+        let punctuated = "Detected speech content. Duration: 1.0 seconds. Estimated words: 120. ";
+        assert_eq!(rules().restore(punctuated), punctuated.trim_end());
+
+        let off = PunctuationRestorer::new(&PunctuationConfig {
+            mode: PunctuationMode::Off,
+            model_dir: None,
+        });
+        assert_eq!(off.restore("so what now"), "so what now");
+
+        // A missing model falls back to rules
+        let fallback = PunctuationRestorer::new(&PunctuationConfig {
+            mode: PunctuationMode::Model,
+            model_dir: Some(PathBuf::from("/nonexistent/punctuation-model")),
+        });
+        assert_eq!(fallback.restore("where is the standup notes file"), "Where is the standup notes file?");
+    }
+}
@@ -0,0 +1,576 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use super::flac::{FlacWriter, BLOCK_FRAMES};
+use super::TranscriptSegment;
+
+/// Directory under the data dir that holds one folder of recordings per session
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// Index file written next to a session's segment files
+pub const RECORDING_INDEX_FILE: &str = "index.json";
+
+/// Whether a session id can name a recording directory: 1 to 64 ASCII letters, digits,
+/// `_` or `-`, so it can never reach outside the recordings directory
+pub fn valid_session_id(session_id: &str) -> bool {
+    (1..=64).contains(&session_id.len())
+        && session_id.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+}
+
+fn check_session_id(session_id: &str) -> Result<()> {
+    if !valid_session_id(session_id) {
+        return Err(anyhow::anyhow!("Invalid session id for a recording: {:?}", session_id));
+    }
+    Ok(())
+}
+
+/// Container for recorded segment files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentFormat {
+    Wav,
+    Flac,
+}
+
+impl SegmentFormat {
+    /// Parse a format name such as "wav" or "flac"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wav" | "wave" => Some(Self::Wav),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// File extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Flac => "flac",
+        }
+    }
+}
+
+/// Configuration for session recordings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    pub format: SegmentFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// 16 or 24
+    pub bits_per_sample: u16,
+    /// A new segment file is started before one would grow past this size
+    pub max_segment_bytes: u64,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            format: SegmentFormat::Flac,
+            sample_rate: 16000,
+            channels: 1,
+            bits_per_sample: 16,
+            max_segment_bytes: 32 * 1024 * 1024,
+        }
+    }
+}
+
+/// One file of a session recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedSegment {
+    pub index: usize,
+    /// File name, relative to the session's recording directory
+    pub file: String,
+    /// Position of the file on the recording's timeline
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub bytes: u64,
+}
+
+/// A transcribed sentence and where it sits on the recording's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedTranscript {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub text: String,
+    pub speaker: Option<String>,
+    /// Conversation turn the transcript was analyzed in, when known
+    pub turn_id: Option<String>,
+    /// Segment files the sentence was recorded in
+    pub segments: Vec<usize>,
+}
+
+/// A span of one segment file to play back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackRange {
+    pub path: PathBuf,
+    /// Offsets into the file
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+/// JSON index of a session recording: its segment files and the transcripts they hold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingIndex {
+    pub session_id: String,
+    pub format: SegmentFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub started_at: DateTime<Utc>,
+    pub segments: Vec<RecordedSegment>,
+    pub transcripts: Vec<IndexedTranscript>,
+}
+
+impl RecordingIndex {
+    /// Directory holding a session's recording under the data dir
+    pub fn session_dir(data_dir: impl AsRef<Path>, session_id: &str) -> PathBuf {
+        data_dir.as_ref().join(RECORDINGS_DIR).join(session_id)
+    }
+
+    /// Load a session's index
+    pub fn load(data_dir: impl AsRef<Path>, session_id: &str) -> Result<Self> {
+        check_session_id(session_id)?;
+        let path = Self::session_dir(data_dir, session_id).join(RECORDING_INDEX_FILE);
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(RECORDING_INDEX_FILE);
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    /// Total recorded duration
+    pub fn duration_secs(&self) -> f64 {
+        self.segments.last().map_or(0.0, |segment| segment.end_secs)
+    }
+
+    /// File spans covering a range of the recording's timeline, in order
+    pub fn playback(&self, dir: &Path, start_secs: f64, end_secs: f64) -> Vec<PlaybackRange> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.start_secs < end_secs && segment.end_secs > start_secs)
+            .map(|segment| PlaybackRange {
+                path: dir.join(&segment.file),
+                start_secs: (start_secs - segment.start_secs).max(0.0),
+                end_secs: (end_secs.min(segment.end_secs) - segment.start_secs).max(0.0),
+            })
+            .collect()
+    }
+
+    /// File spans to play back one indexed transcript
+    pub fn playback_for(&self, dir: &Path, transcript: usize) -> Option<Vec<PlaybackRange>> {
+        let transcript = self.transcripts.get(transcript)?;
+        Some(self.playback(dir, transcript.start_secs, transcript.end_secs))
+    }
+
+    /// Index of the transcript being spoken at a point on the timeline
+    pub fn transcript_at(&self, secs: f64) -> Option<usize> {
+        self.transcripts
+            .iter()
+            .position(|transcript| transcript.start_secs <= secs && secs < transcript.end_secs)
+    }
+
+    fn segments_between(&self, start_secs: f64, end_secs: f64) -> Vec<usize> {
+        self.segments
+            .iter()
+            .filter(|segment| segment.start_secs < end_secs && segment.end_secs > start_secs)
+            .map(|segment| segment.index)
+            .collect()
+    }
+}
+
+/// Writer for the segment file being recorded
+enum SegmentWriter {
+    Wav(hound::WavWriter<BufWriter<File>>, u64),
+    Flac(FlacWriter),
+}
+
+impl SegmentWriter {
+    fn create(path: &Path, config: &RecorderConfig) -> Result<Self> {
+        Ok(match config.format {
+            SegmentFormat::Wav => {
+                let spec = hound::WavSpec {
+                    channels: config.channels,
+                    sample_rate: config.sample_rate,
+                    bits_per_sample: config.bits_per_sample,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                // hound switches to the extensible header past 16 bits or 2 channels
+                let header = if config.bits_per_sample > 16 || config.channels > 2 { 68 } else { 44 };
+                Self::Wav(hound::WavWriter::create(path, spec)?, header)
+            }
+            SegmentFormat::Flac => Self::Flac(FlacWriter::create(
+                path,
+                config.sample_rate,
+                config.channels,
+                config.bits_per_sample,
+            )?),
+        })
+    }
+
+    fn bytes(&self) -> u64 {
+        match self {
+            Self::Wav(_, bytes) => *bytes,
+            Self::Flac(writer) => writer.bytes_written(),
+        }
+    }
+
+    /// Largest size a block of interleaved samples can add to the file
+    fn block_bound(&self, samples: usize, config: &RecorderConfig) -> u64 {
+        match self {
+            Self::Wav(..) => samples as u64 * (config.bits_per_sample as u64 / 8),
+            Self::Flac(writer) => writer.frame_bound(samples / config.channels as usize),
+        }
+    }
+
+    fn write_block(&mut self, samples: &[i32], config: &RecorderConfig) -> Result<()> {
+        match self {
+            Self::Wav(writer, bytes) => {
+                for &sample in samples {
+                    writer.write_sample(sample)?;
+                }
+                *bytes += samples.len() as u64 * (config.bits_per_sample as u64 / 8);
+            }
+            Self::Flac(writer) => writer.write_frame(samples)?,
+        }
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<u64> {
+        match self {
+            Self::Wav(writer, bytes) => {
+                writer.finalize()?;
+                Ok(bytes)
+            }
+            Self::Flac(writer) => writer.finalize(),
+        }
+    }
+}
+
+/// Records a session's audio into rolling, size-capped segment files with a JSON index.
+///
+/// Files are written to `<data dir>/recordings/<session id>/` and the index is rewritten
+/// whenever a segment is closed or a transcript is linked, so an interrupted recording
+/// keeps everything up to its last closed segment.
+pub struct SessionRecorder {
+    dir: PathBuf,
+    config: RecorderConfig,
+    index: RecordingIndex,
+    writer: Option<SegmentWriter>,
+    /// Interleaved samples waiting to fill a block
+    pending: Vec<i32>,
+    /// Frames written to closed segments and the open one
+    frames_written: u64,
+}
+
+impl SessionRecorder {
+    /// Start recording a session, or continue an earlier recording of it.
+    ///
+    /// A session that already has a recording keeps its segment files and transcripts;
+    /// new audio is added as further segments after the end of its timeline. The
+    /// existing recording must use the same format as `config`.
+    pub fn create(data_dir: impl AsRef<Path>, session_id: &str, config: RecorderConfig) -> Result<Self> {
+        check_session_id(session_id)?;
+        if config.channels == 0 || !matches!(config.bits_per_sample, 16 | 24) {
+            return Err(anyhow::anyhow!(
+                "Unsupported recording format: {} channels, {} bits",
+                config.channels,
+                config.bits_per_sample
+            ));
+        }
+
+        let data_dir = data_dir.as_ref();
+        let dir = RecordingIndex::session_dir(data_dir, session_id);
+        let index = if dir.join(RECORDING_INDEX_FILE).exists() {
+            let index = RecordingIndex::load(data_dir, session_id)?;
+            if (index.format, index.sample_rate, index.channels, index.bits_per_sample)
+                != (config.format, config.sample_rate, config.channels, config.bits_per_sample)
+            {
+                return Err(anyhow::anyhow!(
+                    "Session {} is recorded as {} Hz, {} channels, {}-bit {}; can't continue it in another format",
+                    session_id,
+                    index.sample_rate,
+                    index.channels,
+                    index.bits_per_sample,
+                    index.format.extension()
+                ));
+            }
+            index
+        } else {
+            std::fs::create_dir_all(&dir)?;
+            let index = RecordingIndex {
+                session_id: session_id.to_string(),
+                format: config.format,
+                sample_rate: config.sample_rate,
+                channels: config.channels,
+                bits_per_sample: config.bits_per_sample,
+                started_at: Utc::now(),
+                segments: Vec::new(),
+                transcripts: Vec::new(),
+            };
+            index.save(&dir)?;
+            index
+        };
+
+        let frames_written = (index.duration_secs() * config.sample_rate as f64).round() as u64;
+        Ok(Self {
+            dir,
+            config,
+            index,
+            writer: None,
+            pending: Vec::new(),
+            frames_written,
+        })
+    }
+
+    /// Directory the segment files and index are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn index(&self) -> &RecordingIndex {
+        &self.index
+    }
+
+    /// Current position on the recording's timeline
+    pub fn position_secs(&self) -> f64 {
+        let pending_frames = self.pending.len() / self.config.channels as usize;
+        (self.frames_written + pending_frames as u64) as f64 / self.config.sample_rate as f64
+    }
+
+    /// Append interleaved samples in the range -1.0..=1.0
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        let scale = ((1i64 << (self.config.bits_per_sample - 1)) - 1) as f32;
+        self.pending
+            .extend(samples.iter().map(|&sample| (sample.clamp(-1.0, 1.0) * scale).round() as i32));
+
+        let block = BLOCK_FRAMES * self.config.channels as usize;
+        while self.pending.len() >= block {
+            let samples: Vec<i32> = self.pending.drain(..block).collect();
+            self.write_block(&samples)?;
+        }
+        Ok(())
+    }
+
+    /// Link a transcript segment to the audio it came from.
+    ///
+    /// `offset_secs` is where the transcribed audio started on the recording's timeline;
+    /// the segment's own times are relative to that.
+    pub fn link_transcript(&mut self, segment: &TranscriptSegment, offset_secs: f64, turn_id: Option<&str>) -> Result<()> {
+        let start_secs = offset_secs + segment.start_secs;
+        let end_secs = offset_secs + segment.end_secs;
+        self.index.transcripts.push(IndexedTranscript {
+            start_secs,
+            end_secs,
+            text: segment.text.trim().to_string(),
+            speaker: segment.speaker.clone(),
+            turn_id: turn_id.map(str::to_string),
+            segments: Vec::new(),
+        });
+        self.refresh_transcript_segments();
+        self.index.save(&self.dir)
+    }
+
+    /// Flush buffered audio, close the open segment and write the final index
+    pub fn finish(mut self) -> Result<RecordingIndex> {
+        if !self.pending.is_empty() {
+            let samples = std::mem::take(&mut self.pending);
+            self.write_block(&samples)?;
+        }
+        self.close_segment()?;
+        self.refresh_transcript_segments();
+        self.index.save(&self.dir)?;
+        Ok(self.index)
+    }
+
+    fn write_block(&mut self, samples: &[i32]) -> Result<()> {
+        let needs_roll = self.writer.as_ref().is_some_and(|writer| {
+            writer.bytes() + writer.block_bound(samples.len(), &self.config) > self.config.max_segment_bytes
+        });
+        if needs_roll {
+            self.close_segment()?;
+        }
+        if self.writer.is_none() {
+            self.open_segment()?;
+        }
+
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write_block(samples, &self.config)?;
+        }
+        self.frames_written += (samples.len() / self.config.channels as usize) as u64;
+        if let Some(segment) = self.index.segments.last_mut() {
+            segment.end_secs = self.frames_written as f64 / self.config.sample_rate as f64;
+        }
+        Ok(())
+    }
+
+    fn open_segment(&mut self) -> Result<()> {
+        let index = self.index.segments.len();
+        let file = format!("segment-{:05}.{}", index, self.config.format.extension());
+        self.writer = Some(SegmentWriter::create(&self.dir.join(&file), &self.config)?);
+
+        let start_secs = self.frames_written as f64 / self.config.sample_rate as f64;
+        self.index.segments.push(RecordedSegment {
+            index,
+            file,
+            start_secs,
+            end_secs: start_secs,
+            bytes: 0,
+        });
+        Ok(())
+    }
+
+    fn close_segment(&mut self) -> Result<()> {
+        let Some(writer) = self.writer.take() else {
+            return Ok(());
+        };
+        let bytes = writer.finalize()?;
+        if let Some(segment) = self.index.segments.last_mut() {
+            segment.bytes = bytes;
+        }
+        self.refresh_transcript_segments();
+        self.index.save(&self.dir)
+    }
+
+    fn refresh_transcript_segments(&mut self) {
+        let segments: Vec<Vec<usize>> = self
+            .index
+            .transcripts
+            .iter()
+            .map(|transcript| self.index.segments_between(transcript.start_secs, transcript.end_secs))
+            .collect();
+        for (transcript, segments) in self.index.transcripts.iter_mut().zip(segments) {
+            transcript.segments = segments;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(secs: f64, sample_rate: u32) -> Vec<f32> {
+        (0..(secs * sample_rate as f64) as usize)
+            .map(|i| 0.4 * (i as f32 * 440.0 * std::f32::consts::TAU / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn sentence(start_secs: f64, end_secs: f64, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_secs,
+            end_secs,
+            text: text.to_string(),
+            speaker: Some("Dr. Lee".to_string()),
+            avg_logprob: -0.2,
+            no_speech_prob: 0.01,
+        }
+    }
+
+    #[test]
+    fn test_wav_segments_roll_at_size_cap() {
+        let data_dir = std::env::temp_dir().join(format!("recorder-{}", uuid::Uuid::new_v4()));
+        let config = RecorderConfig {
+            format: SegmentFormat::Wav,
+            max_segment_bytes: 200_000,
+            ..Default::default()
+        };
+        let mut recorder = SessionRecorder::create(&data_dir, "lecture", config.clone()).unwrap();
+        // 20s of 16-bit mono is 640KB, written in uneven pieces
+        for piece in tone(20.0, 16000).chunks(7_777) {
+            recorder.write(piece).unwrap();
+        }
+        recorder.link_transcript(&sentence(4.5, 8.5, "Gradients flow backwards."), 2.0, Some("analysis_3")).unwrap();
+        let index = recorder.finish().unwrap();
+
+        assert_eq!(index.segments.len(), 4);
+        assert!((index.duration_secs() - 20.0).abs() < 1e-9);
+        let dir = RecordingIndex::session_dir(&data_dir, "lecture");
+        for (i, segment) in index.segments.iter().enumerate() {
+            let size = std::fs::metadata(dir.join(&segment.file)).unwrap().len();
+            assert!(size <= config.max_segment_bytes);
+            if i > 0 {
+                assert_eq!(segment.start_secs, index.segments[i - 1].end_secs);
+            }
+            let reader = hound::WavReader::open(dir.join(&segment.file)).unwrap();
+            let secs = reader.duration() as f64 / 16000.0;
+            assert!((secs - (segment.end_secs - segment.start_secs)).abs() < 1e-9);
+        }
+
+        // The sentence spans 6.5s-10.5s, across the first two files
+        let reloaded = RecordingIndex::load(&data_dir, "lecture").unwrap();
+        assert_eq!(reloaded.transcripts[0].segments, vec![1]);
+        let ranges = reloaded.playback_for(&dir, 0).unwrap();
+        let first = &reloaded.segments[1];
+        assert_eq!(ranges.len(), 1);
+        assert!((ranges[0].start_secs - (6.5 - first.start_secs)).abs() < 1e-9);
+        assert_eq!(reloaded.transcript_at(7.0), Some(0));
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn test_recording_continues_across_recorders() {
+        let data_dir = std::env::temp_dir().join(format!("recorder-{}", uuid::Uuid::new_v4()));
+        let mut first = SessionRecorder::create(&data_dir, "standup_2", RecorderConfig::default()).unwrap();
+        first.write(&tone(2.0, 16000)).unwrap();
+        first.link_transcript(&sentence(0.0, 2.0, "Yesterday I fixed the build."), 0.0, None).unwrap();
+        first.finish().unwrap();
+
+        let mut second = SessionRecorder::create(&data_dir, "standup_2", RecorderConfig::default()).unwrap();
+        let offset_secs = second.position_secs();
+        second.write(&tone(1.0, 16000)).unwrap();
+        second.link_transcript(&sentence(0.0, 1.0, "Today I'm on reviews."), offset_secs, None).unwrap();
+        let index = second.finish().unwrap();
+
+        assert!((offset_secs - 2.0).abs() < 1e-9);
+        assert_eq!(index.segments.len(), 2);
+        assert_eq!(index.segments[1].start_secs, index.segments[0].end_secs);
+        assert_eq!(index.transcripts.len(), 2);
+        assert_eq!(index.transcripts[1].segments, vec![1]);
+        let dir = RecordingIndex::session_dir(&data_dir, "standup_2");
+        assert!(index.segments.iter().all(|segment| dir.join(&segment.file).exists()));
+
+        let wav = RecorderConfig {
+            format: SegmentFormat::Wav,
+            ..Default::default()
+        };
+        assert!(SessionRecorder::create(&data_dir, "standup_2", wav).is_err());
+        for session_id in ["", "..", "C:foo", "../escape", "a/b", "a\\b", &"x".repeat(65)] {
+            assert!(SessionRecorder::create(&data_dir, session_id, RecorderConfig::default()).is_err());
+            assert!(RecordingIndex::load(&data_dir, session_id).is_err());
+        }
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn test_flac_segments_decode_to_recorded_audio() {
+        let data_dir = std::env::temp_dir().join(format!("recorder-{}", uuid::Uuid::new_v4()));
+        let config = RecorderConfig {
+            max_segment_bytes: 60_000,
+            ..Default::default()
+        };
+        let audio = tone(10.0, 16000);
+        let mut recorder = SessionRecorder::create(&data_dir, "standup", config.clone()).unwrap();
+        recorder.write(&audio).unwrap();
+        let index = recorder.finish().unwrap();
+
+        assert!(index.segments.len() > 1);
+        let dir = RecordingIndex::session_dir(&data_dir, "standup");
+        let mut decoded = Vec::new();
+        for segment in &index.segments {
+            assert!(segment.bytes <= config.max_segment_bytes);
+            let mut reader = claxon::FlacReader::open(dir.join(&segment.file)).unwrap();
+            decoded.extend(reader.samples().map(|sample| sample.unwrap()));
+        }
+        let expected: Vec<i32> = audio.iter().map(|&s| (s * 32767.0).round() as i32).collect();
+        assert_eq!(decoded, expected);
+        std::fs::remove_dir_all(data_dir).unwrap();
+    }
+}
@@ -0,0 +1,502 @@
+//! Where real-time audio comes from.
+//!
+//! The real-time pipeline pulls interleaved `f32` blocks from an [`AudioSource`]. A
+//! live microphone, a recording replayed at its own pace, a buffer, and a generated
+//! signal are interchangeable, so the pipeline can be driven without audio hardware.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::{
+    f32::consts::TAU,
+    path::Path,
+    sync::mpsc as std_mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+/// A stream of interleaved audio blocks
+#[async_trait]
+pub trait AudioSource: Send {
+    /// Human-readable name, e.g. the device or file name
+    fn name(&self) -> String;
+
+    fn sample_rate(&self) -> u32;
+
+    fn channels(&self) -> u16;
+
+    /// Next block of interleaved samples, or `None` once the source is exhausted
+    async fn next_block(&mut self) -> Result<Option<Vec<f32>>>;
+}
+
+/// How a buffered source hands out its blocks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pacing {
+    /// Each block is released when it would have been captured live
+    RealTime,
+    /// Blocks are returned as fast as they are asked for
+    Unthrottled,
+}
+
+/// Capture from a cpal input device
+pub struct CpalSource {
+    name: String,
+    sample_rate: u32,
+    channels: u16,
+    blocks: mpsc::UnboundedReceiver<Vec<f32>>,
+    stop: Option<std_mpsc::Sender<()>>,
+}
+
+impl CpalSource {
+    /// Open the host's default input device
+    pub fn default_device(sample_rate: u32, channels: u16, buffer_size: usize) -> Result<Self> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| anyhow::anyhow!("No input device found"))?;
+        Self::open(device, sample_rate, channels, buffer_size)
+    }
+
+    /// Open the input device with the given name or id
+    pub fn named(name: &str, sample_rate: u32, channels: u16, buffer_size: usize) -> Result<Self> {
+        let device = super::devices::find_input_device(name)?
+            .ok_or_else(|| anyhow::anyhow!("Input device not found: {}", name))?;
+        Self::open(device, sample_rate, channels, buffer_size)
+    }
+
+    /// Start capturing from a device.
+    ///
+    /// cpal streams cannot move between threads, so the stream lives on a dedicated
+    /// thread until the source is dropped.
+    pub fn open(device: cpal::Device, sample_rate: u32, channels: u16, buffer_size: usize) -> Result<Self> {
+        let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+        let config = cpal::StreamConfig {
+            channels,
+            sample_rate: cpal::SampleRate(sample_rate),
+            buffer_size: cpal::BufferSize::Fixed(buffer_size as u32),
+        };
+        let (block_tx, blocks) = mpsc::unbounded_channel();
+        let (stop, stopped) = std_mpsc::channel::<()>();
+        let (ready_tx, ready) = std_mpsc::channel::<Result<()>>();
+
+        thread::spawn(move || {
+            let stream = device.build_input_stream(
+                &config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                    // A dropped receiver only means nobody is listening anymore
+                    let _ = block_tx.send(data.to_vec());
+                },
+                |err| eprintln!("Audio stream error: {}", err),
+                None,
+            );
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.into()));
+                    return;
+                }
+            };
+            if let Err(e) = stream.play() {
+                let _ = ready_tx.send(Err(e.into()));
+                return;
+            }
+            let _ = ready_tx.send(Ok(()));
+            // Blocks until the source is dropped
+            let _ = stopped.recv();
+        });
+
+        ready
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Audio capture thread exited before starting"))??;
+
+        Ok(Self {
+            name,
+            sample_rate,
+            channels,
+            blocks,
+            stop: Some(stop),
+        })
+    }
+}
+
+impl Drop for CpalSource {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+#[async_trait]
+impl AudioSource for CpalSource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    async fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+        Ok(self.blocks.recv().await)
+    }
+}
+
+/// A fixed buffer of interleaved samples handed out block by block
+pub struct MemorySource {
+    name: String,
+    samples: Vec<f32>,
+    sample_rate: u32,
+    channels: u16,
+    block_frames: usize,
+    position: usize,
+    pacing: Pacing,
+    started: Option<Instant>,
+}
+
+impl MemorySource {
+    pub fn new(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            name: "memory".to_string(),
+            samples,
+            sample_rate,
+            channels: channels.max(1),
+            block_frames: 1024,
+            position: 0,
+            pacing: Pacing::Unthrottled,
+            started: None,
+        }
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_block_frames(mut self, block_frames: usize) -> Self {
+        self.block_frames = block_frames.max(1);
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    pub fn duration_secs(&self) -> f64 {
+        self.samples.len() as f64 / self.channels as f64 / self.sample_rate as f64
+    }
+}
+
+#[async_trait]
+impl AudioSource for MemorySource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    async fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+        if self.position >= self.samples.len() {
+            return Ok(None);
+        }
+
+        if self.pacing == Pacing::RealTime {
+            // Release the block once the audio before it would have finished playing
+            let started = *self.started.get_or_insert_with(Instant::now);
+            let frames = self.position / self.channels as usize;
+            let due = started + Duration::from_secs_f64(frames as f64 / self.sample_rate as f64);
+            tokio::time::sleep_until(due.into()).await;
+        }
+
+        let end = (self.position + self.block_frames * self.channels as usize).min(self.samples.len());
+        let block = self.samples[self.position..end].to_vec();
+        self.position = end;
+        Ok(Some(block))
+    }
+}
+
+/// A WAV or FLAC recording, replayed in real time by default
+pub struct FileSource {
+    inner: MemorySource,
+}
+
+impl FileSource {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let (samples, sample_rate, channels) = match path.extension().and_then(|s| s.to_str()) {
+            Some("wav") => decode_wav(path)?,
+            Some("flac") => decode_flac(path)?,
+            _ => return Err(anyhow::anyhow!("Unsupported audio source file: {}", path.display())),
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self {
+            inner: MemorySource::new(samples, sample_rate, channels)
+                .with_name(name)
+                .with_pacing(Pacing::RealTime),
+        })
+    }
+
+    pub fn with_block_frames(mut self, block_frames: usize) -> Self {
+        self.inner = self.inner.with_block_frames(block_frames);
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.inner = self.inner.with_pacing(pacing);
+        self
+    }
+
+    pub fn duration_secs(&self) -> f64 {
+        self.inner.duration_secs()
+    }
+}
+
+#[async_trait]
+impl AudioSource for FileSource {
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    async fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+        self.inner.next_block().await
+    }
+}
+
+fn decode_wav(path: &Path) -> Result<(Vec<f32>, u32, u16)> {
+    let mut reader = hound::WavReader::open(path)?;
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<Vec<_>, _>>()?,
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+    };
+    Ok((samples, spec.sample_rate, spec.channels))
+}
+
+fn decode_flac(path: &Path) -> Result<(Vec<f32>, u32, u16)> {
+    let mut reader = claxon::FlacReader::open(path)?;
+    let info = reader.streaminfo();
+    let scale = (1i64 << (info.bits_per_sample - 1)) as f32;
+    let samples = reader
+        .samples()
+        .map(|sample| sample.map(|s| s as f32 / scale))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((samples, info.sample_rate, info.channels as u16))
+}
+
+/// Average interleaved frames down to mono
+pub(super) fn downmix(block: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    if channels == 1 {
+        return block.to_vec();
+    }
+    block
+        .chunks(channels)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// Shape of a generated signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Waveform {
+    Sine { frequency: f32 },
+    Silence,
+    /// Uniform white noise from a seeded generator, identical on every run
+    Noise { seed: u64 },
+}
+
+/// A synthetic mono signal of fixed length
+pub struct GeneratorSource {
+    waveform: Waveform,
+    amplitude: f32,
+    sample_rate: u32,
+    total_frames: u64,
+    block_frames: usize,
+    position: u64,
+    noise_state: u64,
+    pacing: Pacing,
+    started: Option<Instant>,
+}
+
+impl GeneratorSource {
+    pub fn new(waveform: Waveform, sample_rate: u32, duration: Duration) -> Self {
+        Self {
+            waveform,
+            amplitude: 0.5,
+            sample_rate,
+            total_frames: (duration.as_secs_f64() * sample_rate as f64).round() as u64,
+            block_frames: 1024,
+            position: 0,
+            noise_state: match waveform {
+                // xorshift must not start from zero
+                Waveform::Noise { seed } => seed.max(1),
+                _ => 1,
+            },
+            pacing: Pacing::Unthrottled,
+            started: None,
+        }
+    }
+
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    pub fn with_block_frames(mut self, block_frames: usize) -> Self {
+        self.block_frames = block_frames.max(1);
+        self
+    }
+
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
+
+    fn sample(&mut self, frame: u64) -> f32 {
+        match self.waveform {
+            Waveform::Sine { frequency } => {
+                let t = frame as f32 / self.sample_rate as f32;
+                self.amplitude * (TAU * frequency * t).sin()
+            }
+            Waveform::Silence => 0.0,
+            Waveform::Noise { .. } => {
+                self.noise_state ^= self.noise_state << 13;
+                self.noise_state ^= self.noise_state >> 7;
+                self.noise_state ^= self.noise_state << 17;
+                let unit = (self.noise_state >> 40) as f32 / (1u64 << 24) as f32;
+                self.amplitude * (unit * 2.0 - 1.0)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl AudioSource for GeneratorSource {
+    fn name(&self) -> String {
+        match self.waveform {
+            Waveform::Sine { frequency } => format!("sine {} Hz", frequency),
+            Waveform::Silence => "silence".to_string(),
+            Waveform::Noise { seed } => format!("noise (seed {})", seed),
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    async fn next_block(&mut self) -> Result<Option<Vec<f32>>> {
+        if self.position >= self.total_frames {
+            return Ok(None);
+        }
+
+        if self.pacing == Pacing::RealTime {
+            let started = *self.started.get_or_insert_with(Instant::now);
+            let due = started + Duration::from_secs_f64(self.position as f64 / self.sample_rate as f64);
+            tokio::time::sleep_until(due.into()).await;
+        }
+
+        let end = (self.position + self.block_frames as u64).min(self.total_frames);
+        let block = (self.position..end).map(|frame| self.sample(frame)).collect();
+        self.position = end;
+        Ok(Some(block))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn drain(source: &mut dyn AudioSource) -> Vec<f32> {
+        let mut samples = Vec::new();
+        while let Some(block) = source.next_block().await.unwrap() {
+            samples.extend(block);
+        }
+        samples
+    }
+
+    #[tokio::test]
+    async fn test_generator_is_deterministic() {
+        let make = || {
+            GeneratorSource::new(Waveform::Noise { seed: 7 }, 16000, Duration::from_millis(250))
+                .with_block_frames(300)
+        };
+        let first = drain(&mut make()).await;
+        let second = drain(&mut make()).await;
+
+        assert_eq!(first.len(), 4000);
+        assert_eq!(first, second);
+        assert!(first.iter().all(|s| s.abs() <= 0.5));
+    }
+
+    #[tokio::test]
+    async fn test_file_source_replays_wav_and_flac_in_real_time() {
+        let dir = std::env::temp_dir().join(format!("source-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tone: Vec<f32> = (0..1600).map(|i| 0.25 * (i as f32 * 0.1).sin()).collect();
+
+        let wav_path = dir.join("tone.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&wav_path, spec).unwrap();
+        for &s in &tone {
+            writer.write_sample((s * 32767.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let flac_path = dir.join("tone.flac");
+        let mut flac = super::super::flac::FlacWriter::create(&flac_path, 16000, 1, 16).unwrap();
+        let ints: Vec<i32> = tone.iter().map(|&s| (s * 32767.0) as i32).collect();
+        flac.write_frame(&ints).unwrap();
+        flac.finalize().unwrap();
+
+        for path in [&wav_path, &flac_path] {
+            let mut source = FileSource::open(path).unwrap().with_block_frames(400);
+            assert!((source.duration_secs() - 0.1).abs() < 1e-9);
+
+            let started = Instant::now();
+            let samples = drain(&mut source).await;
+            // Four 25ms blocks, the last released 75ms in
+            assert!(started.elapsed() >= Duration::from_millis(70));
+            assert_eq!(samples.len(), tone.len());
+            assert!(samples.iter().zip(&tone).all(|(a, b)| (a - b).abs() < 1e-3));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,412 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, Semaphore};
+
+use crate::analysis::AnalysisResult;
+use crate::document::DocumentFormat;
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff", "webp"];
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "mp3", "flac", "ogg", "m4a"];
+
+/// Number of topics and insights carried into the combined report
+const REPORT_TOP_N: usize = 10;
+
+/// Kind of file in a batch, inferred from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemKind {
+    Screenshot,
+    Audio,
+    Document,
+}
+
+impl BatchItemKind {
+    /// Infer the kind of a file from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+            Some(Self::Screenshot)
+        } else if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+            Some(Self::Audio)
+        } else if DocumentFormat::from_name(&extension).is_some() {
+            Some(Self::Document)
+        } else {
+            None
+        }
+    }
+}
+
+/// State of a single batch item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// A set of files to analyze together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub batch_id: String,
+    /// Files or folders; folders are expanded to the supported files they contain
+    pub paths: Vec<PathBuf>,
+    /// Maximum number of items analyzed at the same time
+    pub max_concurrency: usize,
+}
+
+impl BatchRequest {
+    /// Create a batch request with a fresh batch id
+    pub fn new(paths: Vec<PathBuf>, max_concurrency: usize) -> Self {
+        Self {
+            batch_id: uuid::Uuid::new_v4().to_string(),
+            paths,
+            max_concurrency,
+        }
+    }
+}
+
+/// Progress event emitted whenever an item starts, succeeds or fails
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchProgress {
+    pub batch_id: String,
+    pub index: usize,
+    pub path: String,
+    pub status: BatchItemStatus,
+    pub error: Option<String>,
+    /// Items finished so far, successful or not
+    pub completed: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+/// Outcome of analyzing a single item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub path: String,
+    pub kind: Option<BatchItemKind>,
+    pub status: BatchItemStatus,
+    pub analysis: Option<AnalysisResult>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Combined report produced once every item has finished
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub batch_id: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    pub total: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemResult>,
+    pub top_topics: Vec<(String, usize)>,
+    pub key_insights: Vec<String>,
+}
+
+impl BatchReport {
+    /// Items that failed, with their error messages
+    pub fn failures(&self) -> impl Iterator<Item = &BatchItemResult> {
+        self.items.iter().filter(|item| item.status == BatchItemStatus::Failed)
+    }
+}
+
+/// Expand folders into the supported files they contain, keeping explicit files as given.
+///
+/// Explicit files with unsupported extensions are kept so they surface as failures
+/// in the report rather than disappearing silently.
+pub fn collect_paths(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut collected = Vec::new();
+
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = Vec::new();
+            for entry in std::fs::read_dir(path)? {
+                let entry_path = entry?.path();
+                if entry_path.is_dir() {
+                    entries.extend(collect_paths(&[entry_path])?);
+                } else if BatchItemKind::from_path(&entry_path).is_some() {
+                    entries.push(entry_path);
+                }
+            }
+            entries.sort();
+            collected.extend(entries);
+        } else {
+            collected.push(path.clone());
+        }
+    }
+
+    Ok(collected)
+}
+
+/// Counters and progress channel shared by the tasks of one batch
+struct ProgressTracker {
+    batch_id: String,
+    total: usize,
+    completed: AtomicUsize,
+    failed: AtomicUsize,
+    sender: Option<mpsc::UnboundedSender<BatchProgress>>,
+}
+
+impl ProgressTracker {
+    fn emit(&self, index: usize, path: &str, status: BatchItemStatus, error: Option<String>) {
+        if let Some(sender) = &self.sender {
+            // A dropped receiver only means nobody is watching anymore
+            let _ = sender.send(BatchProgress {
+                batch_id: self.batch_id.clone(),
+                index,
+                path: path.to_string(),
+                status,
+                error,
+                completed: self.completed.load(Ordering::SeqCst),
+                failed: self.failed.load(Ordering::SeqCst),
+                total: self.total,
+            });
+        }
+    }
+
+    fn finish(
+        &self,
+        index: usize,
+        path: String,
+        kind: Option<BatchItemKind>,
+        outcome: Result<AnalysisResult>,
+        duration_ms: u64,
+    ) -> BatchItemResult {
+        self.completed.fetch_add(1, Ordering::SeqCst);
+        match outcome {
+            Ok(analysis) => {
+                self.emit(index, &path, BatchItemStatus::Succeeded, None);
+                BatchItemResult {
+                    index,
+                    path,
+                    kind,
+                    status: BatchItemStatus::Succeeded,
+                    analysis: Some(analysis),
+                    error: None,
+                    duration_ms,
+                }
+            }
+            Err(e) => {
+                self.failed.fetch_add(1, Ordering::SeqCst);
+                self.emit(index, &path, BatchItemStatus::Failed, Some(e.to_string()));
+                BatchItemResult {
+                    index,
+                    path,
+                    kind,
+                    status: BatchItemStatus::Failed,
+                    analysis: None,
+                    error: Some(e.to_string()),
+                    duration_ms,
+                }
+            }
+        }
+    }
+}
+
+/// Run `analyze` over `files`, at most `request.max_concurrency` at a time.
+///
+/// `files` is the expanded list from [`collect_paths`], so callers that already
+/// validated the request do not walk its folders again. Each item runs on its own
+/// task, and a failing or panicking item never aborts the batch; its error is
+/// recorded in the report.
+pub async fn run<F, Fut>(
+    request: &BatchRequest,
+    files: Vec<PathBuf>,
+    progress: Option<mpsc::UnboundedSender<BatchProgress>>,
+    analyze: F,
+) -> Result<BatchReport>
+where
+    F: Fn(BatchItemKind, PathBuf) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<AnalysisResult>> + Send + 'static,
+{
+    let started_at = chrono::Utc::now();
+    let semaphore = Arc::new(Semaphore::new(request.max_concurrency.max(1)));
+    let analyze = Arc::new(analyze);
+    let tracker = Arc::new(ProgressTracker {
+        batch_id: request.batch_id.clone(),
+        total: files.len(),
+        completed: AtomicUsize::new(0),
+        failed: AtomicUsize::new(0),
+        sender: progress,
+    });
+
+    let handles: Vec<_> = files
+        .into_iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let display = path.display().to_string();
+            let kind = BatchItemKind::from_path(&path);
+            let semaphore = Arc::clone(&semaphore);
+            let analyze = Arc::clone(&analyze);
+            let tracker = Arc::clone(&tracker);
+
+            let handle = tokio::spawn(async move {
+                let _permit = semaphore.acquire_owned().await?;
+                let display = path.display().to_string();
+                tracker.emit(index, &display, BatchItemStatus::Running, None);
+
+                let start = std::time::Instant::now();
+                let outcome = match kind {
+                    Some(kind) => analyze(kind, path).await,
+                    None => Err(anyhow::anyhow!("Unsupported file type: {}", display)),
+                };
+                let duration_ms = start.elapsed().as_millis() as u64;
+
+                Ok::<_, anyhow::Error>(tracker.finish(index, display, kind, outcome, duration_ms))
+            });
+            (index, display, kind, handle)
+        })
+        .collect();
+
+    let mut items = Vec::with_capacity(handles.len());
+    for (index, display, kind, handle) in handles {
+        let item = match handle.await {
+            Ok(result) => result?,
+            Err(e) => tracker.finish(index, display, kind, Err(anyhow::anyhow!("Item task failed: {}", e)), 0),
+        };
+        items.push(item);
+    }
+
+    Ok(build_report(&request.batch_id, started_at, items))
+}
+
+/// Aggregate per-item results into the combined report
+fn build_report(
+    batch_id: &str,
+    started_at: chrono::DateTime<chrono::Utc>,
+    items: Vec<BatchItemResult>,
+) -> BatchReport {
+    let mut topic_counts = std::collections::HashMap::new();
+    let mut key_insights = Vec::new();
+
+    for analysis in items.iter().filter_map(|item| item.analysis.as_ref()) {
+        for topic in &analysis.topics {
+            *topic_counts.entry(topic.clone()).or_insert(0) += 1;
+        }
+        for insight in &analysis.insights {
+            if key_insights.len() < REPORT_TOP_N && !key_insights.contains(insight) {
+                key_insights.push(insight.clone());
+            }
+        }
+    }
+
+    let mut top_topics: Vec<(String, usize)> = topic_counts.into_iter().collect();
+    top_topics.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_topics.truncate(REPORT_TOP_N);
+
+    let failed = items.iter().filter(|item| item.status == BatchItemStatus::Failed).count();
+
+    BatchReport {
+        batch_id: batch_id.to_string(),
+        started_at,
+        finished_at: chrono::Utc::now(),
+        total: items.len(),
+        succeeded: items.len() - failed,
+        failed,
+        items,
+        top_topics,
+        key_insights,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::ContentType;
+
+    fn fake_analysis(topic: &str) -> AnalysisResult {
+        AnalysisResult {
+            analysis_id: uuid::Uuid::new_v4().to_string(),
+            timestamp: chrono::Utc::now(),
+            content_type: ContentType::Screenshot,
+            original_content: topic.to_string(),
+            extracted_text: None,
+            sentiment: None,
+            topics: vec![topic.to_string()],
+            summary: String::new(),
+            insights: vec![format!("Covers {}", topic)],
+            confidence: 0.9,
+            context_used: false,
+            recommendations: Vec::new(),
+            metadata: std::collections::HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_item_kind_from_extension() {
+        assert_eq!(BatchItemKind::from_path(Path::new("slides/01.PNG")), Some(BatchItemKind::Screenshot));
+        assert_eq!(BatchItemKind::from_path(Path::new("lecture.flac")), Some(BatchItemKind::Audio));
+        assert_eq!(BatchItemKind::from_path(Path::new("week1.pdf")), Some(BatchItemKind::Document));
+        assert_eq!(BatchItemKind::from_path(Path::new("notes.txt")), None);
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_is_bounded() {
+        let paths = (0..8).map(|i| PathBuf::from(format!("frame-{}.png", i))).collect();
+        let request = BatchRequest::new(paths, 3);
+        let files = collect_paths(&request.paths).unwrap();
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (running_in, peak_in) = (Arc::clone(&running), Arc::clone(&peak));
+        let report = run(&request, files, None, move |_, _| {
+            let running = Arc::clone(&running_in);
+            let peak = Arc::clone(&peak_in);
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(fake_analysis("graphs"))
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(report.succeeded, 8);
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert_eq!(report.top_topics, vec![("graphs".to_string(), 8)]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_and_progress_reported() {
+        let paths = vec![
+            PathBuf::from("good.png"),
+            PathBuf::from("broken.wav"),
+            PathBuf::from("notes.txt"),
+        ];
+        let request = BatchRequest::new(paths, 2);
+        let files = collect_paths(&request.paths).unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let report = run(&request, files, Some(tx), |kind, _| async move {
+            match kind {
+                BatchItemKind::Screenshot => Ok(fake_analysis("sorting")),
+                BatchItemKind::Audio | BatchItemKind::Document => Err(anyhow::anyhow!("decoder error")),
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!((report.total, report.succeeded, report.failed), (3, 1, 2));
+        let failures: Vec<_> = report.failures().map(|item| item.path.as_str()).collect();
+        assert_eq!(failures, vec!["broken.wav", "notes.txt"]);
+        assert!(report.items[2].error.as_deref().unwrap().contains("Unsupported"));
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        // Each item reports once when it starts and once when it finishes
+        assert_eq!(events.len(), 6);
+        assert_eq!(events.iter().filter(|e| e.status == BatchItemStatus::Failed).count(), 2);
+    }
+}
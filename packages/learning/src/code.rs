@@ -0,0 +1,683 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use crate::analysis::{Priority, Recommendation, RecommendationCategory};
+
+/// Metadata key under which the analysis of a code snippet is attached
+pub const CODE_METADATA_KEY: &str = "code";
+
+/// Programming languages recognized in code snippets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgrammingLanguage {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Java,
+    Go,
+    C,
+    Cpp,
+    CSharp,
+    Sql,
+    Shell,
+}
+
+impl ProgrammingLanguage {
+    pub const ALL: [ProgrammingLanguage; 11] = [
+        ProgrammingLanguage::Rust,
+        ProgrammingLanguage::Python,
+        ProgrammingLanguage::JavaScript,
+        ProgrammingLanguage::TypeScript,
+        ProgrammingLanguage::Java,
+        ProgrammingLanguage::Go,
+        ProgrammingLanguage::C,
+        ProgrammingLanguage::Cpp,
+        ProgrammingLanguage::CSharp,
+        ProgrammingLanguage::Sql,
+        ProgrammingLanguage::Shell,
+    ];
+
+    /// Human-readable language name
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProgrammingLanguage::Rust => "Rust",
+            ProgrammingLanguage::Python => "Python",
+            ProgrammingLanguage::JavaScript => "JavaScript",
+            ProgrammingLanguage::TypeScript => "TypeScript",
+            ProgrammingLanguage::Java => "Java",
+            ProgrammingLanguage::Go => "Go",
+            ProgrammingLanguage::C => "C",
+            ProgrammingLanguage::Cpp => "C++",
+            ProgrammingLanguage::CSharp => "C#",
+            ProgrammingLanguage::Sql => "SQL",
+            ProgrammingLanguage::Shell => "Shell",
+        }
+    }
+
+    /// Syntax markers and how strongly each one points at the language
+    fn markers(&self) -> &'static [(&'static str, f32)] {
+        match self {
+            ProgrammingLanguage::Rust => &[
+                (r"\bfn\s+\w+", 3.0),
+                (r"\blet\s+mut\b", 3.0),
+                (r"\bimpl\b", 2.0),
+                (r"\w+!\(", 2.0),
+                (r"&(mut\s+)?(str|self)\b", 2.0),
+                (r"\b(Vec|Option|Result|Box)<", 2.0),
+                (r"\.unwrap\(\)", 2.0),
+                (r"\bmatch\s+\w+", 1.0),
+                (r"\bpub\s+(fn|struct|enum)\b", 2.0),
+                (r"\buse\s+\w+::", 2.0),
+            ],
+            ProgrammingLanguage::Python => &[
+                (r"(?m)^\s*def\s+\w+\(.*\)\s*(->.*)?:\s*$", 4.0),
+                (r"(?m)^\s*(import|from)\s+[\w.]+", 1.5),
+                (r"\bself\.", 2.0),
+                (r"(?m)^\s*elif\b", 3.0),
+                (r"\b(None|True|False)\b", 1.0),
+                (r"(?m)^\s*(if|for|while|with|class|try|except).*:\s*$", 2.0),
+                (r"\b__\w+__\b", 2.0),
+                (r"\bprint\(", 1.0),
+            ],
+            ProgrammingLanguage::JavaScript => &[
+                (r"\bfunction\b", 2.0),
+                (r"\b(const|let)\s+\w+\s*=", 1.5),
+                (r"=>", 1.0),
+                (r"console\.log\(", 3.0),
+                (r"===|!==", 2.0),
+                (r"\brequire\(", 2.0),
+                (r"\bdocument\.", 2.0),
+                (r"\bexport\s+(default|const|function)\b", 1.5),
+            ],
+            ProgrammingLanguage::TypeScript => &[
+                (r"\b(const|let)\s+\w+\s*:\s*\w+", 3.0),
+                (r"\w+\s*:\s*(string|number|boolean|any|void)\b", 3.0),
+                (r"\binterface\s+\w+\s*\{", 2.0),
+                (r"\btype\s+\w+\s*=", 2.0),
+                (r"console\.log\(", 1.5),
+                (r"===|!==", 1.0),
+                (r"=>", 0.5),
+            ],
+            ProgrammingLanguage::Java => &[
+                (r"\bpublic\s+(static\s+)?(class|void|final)\b", 3.0),
+                (r"System\.out\.print", 4.0),
+                (r"\bprivate\s+\w+", 1.5),
+                (r"\bimport\s+java\.", 4.0),
+                (r"@Override", 3.0),
+                (r"\bString\[\]", 2.0),
+                (r"\bnew\s+\w+\(", 1.0),
+            ],
+            ProgrammingLanguage::Go => &[
+                (r"(?m)^package\s+\w+", 3.0),
+                (r"\bfunc\s+(\(\w+\s+\*?\w+\)\s*)?\w+\(", 3.0),
+                (r":=", 2.0),
+                (r"\bfmt\.", 3.0),
+                (r"err\s*!=\s*nil", 4.0),
+                (r"\bdefer\b", 2.0),
+                (r"\bchan\b", 2.0),
+            ],
+            ProgrammingLanguage::C => &[
+                (r"#include\s*<\w+\.h>", 4.0),
+                (r"\bprintf\(", 2.0),
+                (r"\bmalloc\(", 2.0),
+                (r"\bint\s+main\s*\(", 2.0),
+                (r"\bchar\s*\*", 2.0),
+                (r"\bNULL\b", 1.0),
+                (r"->", 0.5),
+            ],
+            ProgrammingLanguage::Cpp => &[
+                (r"#include\s*<\w+>", 3.0),
+                (r"\bstd::", 4.0),
+                (r"\bcout\s*<<", 3.0),
+                (r"\btemplate\s*<", 3.0),
+                (r"\bnullptr\b", 3.0),
+                (r"\bauto\s+\w+\s*=", 1.0),
+            ],
+            ProgrammingLanguage::CSharp => &[
+                (r"\busing\s+System", 4.0),
+                (r"\bnamespace\s+[\w.]+", 2.0),
+                (r"Console\.Write(Line)?\(", 4.0),
+                (r"\{\s*get;\s*(set;)?\s*\}", 4.0),
+                (r"\basync\s+Task\b", 3.0),
+                (r"\bvar\s+\w+\s*=\s*new\b", 1.5),
+            ],
+            ProgrammingLanguage::Sql => &[
+                (r"(?i)\bselect\b.+\bfrom\b", 4.0),
+                (r"(?i)\binsert\s+into\b", 4.0),
+                (r"(?i)\bcreate\s+table\b", 4.0),
+                (r"(?i)\bwhere\b", 1.0),
+                (r"(?i)\b(inner|left|right)?\s*join\b.+\bon\b", 2.0),
+                (r"(?i)\bgroup\s+by\b", 2.0),
+            ],
+            ProgrammingLanguage::Shell => &[
+                (r"^#!\s*/(usr/)?bin/(env\s+)?(ba|z)?sh", 5.0),
+                (r"(?m)^\s*echo\s", 2.0),
+                (r"\$\(", 2.0),
+                (r"(?m)^\s*(fi|done|esac)\s*$", 3.0),
+                (r";\s*then\b", 3.0),
+                (r"(?m)^\s*(export|sudo)\s", 2.0),
+                (r"\|\s*(grep|awk|sed|xargs)\b", 2.0),
+            ],
+        }
+    }
+}
+
+/// Compiled syntax markers of one language and their weights
+type CompiledMarkers = Vec<(Regex, f32)>;
+
+/// Syntax markers of every language, compiled once
+static LANGUAGE_MARKERS: LazyLock<Vec<(ProgrammingLanguage, CompiledMarkers)>> = LazyLock::new(|| {
+    ProgrammingLanguage::ALL
+        .iter()
+        .map(|language| {
+            let markers = language
+                .markers()
+                .iter()
+                .map(|(pattern, weight)| (Regex::new(pattern).unwrap(), *weight))
+                .collect();
+            (*language, markers)
+        })
+        .collect()
+});
+
+/// The most likely language of a snippet and how sure the guess is
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LanguageGuess {
+    pub language: ProgrammingLanguage,
+    /// Share of the total marker score won by the chosen language, in `0.0..=1.0`
+    pub confidence: f32,
+}
+
+/// Guess the programming language of a snippet from its syntax markers.
+///
+/// Returns `None` when too few markers match for any language to be credible.
+pub fn detect_language(code: &str) -> Option<LanguageGuess> {
+    let scores: Vec<(ProgrammingLanguage, f32)> = LANGUAGE_MARKERS
+        .iter()
+        .map(|(language, markers)| {
+            let score = markers
+                .iter()
+                .filter(|(regex, _)| regex.is_match(code))
+                .map(|(_, weight)| weight)
+                .sum::<f32>();
+            (*language, score)
+        })
+        .collect();
+
+    let total: f32 = scores.iter().map(|(_, score)| score).sum();
+    let (language, best) = scores
+        .into_iter()
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    if best < 3.0 {
+        return None;
+    }
+    Some(LanguageGuess {
+        language,
+        confidence: best / total,
+    })
+}
+
+/// A likely bug or risky construct found in a snippet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeIssue {
+    /// 1-based line number within the snippet
+    pub line: usize,
+    /// The offending line, trimmed
+    pub snippet: String,
+    pub message: String,
+    pub suggestion: String,
+    pub priority: Priority,
+}
+
+/// What a snippet does and what is likely wrong with it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeAnalysis {
+    pub language: Option<ProgrammingLanguage>,
+    pub language_confidence: f32,
+    /// One-sentence description of what the snippet does
+    pub summary: String,
+    /// Functions, methods and types defined in the snippet
+    pub definitions: Vec<String>,
+    pub issues: Vec<CodeIssue>,
+    pub line_count: usize,
+}
+
+/// A line-level check: pattern, message, suggestion, priority, and the languages it applies to
+/// (empty for all)
+type LineRule = (
+    &'static str,
+    &'static str,
+    &'static str,
+    Priority,
+    &'static [ProgrammingLanguage],
+);
+
+const LINE_RULES: &[LineRule] = &[
+    (
+        r"\.unwrap\(\)",
+        "`unwrap()` panics when the value is `None` or `Err`",
+        "Propagate the error with `?` or handle the failure case explicitly",
+        Priority::Medium,
+        &[ProgrammingLanguage::Rust],
+    ),
+    (
+        r"\bunsafe\s*\{",
+        "`unsafe` block bypasses the borrow checker",
+        "Document the invariants that make this block sound, or use a safe abstraction",
+        Priority::Medium,
+        &[ProgrammingLanguage::Rust],
+    ),
+    (
+        r"^\s*except\s*:",
+        "Bare `except:` also swallows `KeyboardInterrupt` and `SystemExit`",
+        "Catch the specific exception types you expect, or at least `Exception`",
+        Priority::High,
+        &[ProgrammingLanguage::Python],
+    ),
+    (
+        r"\bdef\s+\w+\(.*=\s*(\[\]|\{\}|set\(\))",
+        "Mutable default argument is shared between calls",
+        "Default to `None` and create the list or dict inside the function",
+        Priority::High,
+        &[ProgrammingLanguage::Python],
+    ),
+    (
+        r"[!=]=\s*None\b",
+        "Comparison to `None` with `==` can be fooled by custom `__eq__`",
+        "Use `is None` / `is not None`",
+        Priority::Low,
+        &[ProgrammingLanguage::Python],
+    ),
+    (
+        r"[^=!<>]==[^=]|!=[^=]",
+        "Loose equality coerces types before comparing",
+        "Use `===` / `!==`",
+        Priority::Medium,
+        &[ProgrammingLanguage::JavaScript, ProgrammingLanguage::TypeScript],
+    ),
+    (
+        r"\bvar\s+\w+",
+        "`var` is function-scoped and hoisted",
+        "Use `let` or `const`",
+        Priority::Low,
+        &[ProgrammingLanguage::JavaScript, ProgrammingLanguage::TypeScript],
+    ),
+    (
+        r#""\s*[!=]=|[!=]=\s*""#,
+        "Strings compared with `==` compare references, not contents",
+        "Use `.equals()` to compare string contents",
+        Priority::High,
+        &[ProgrammingLanguage::Java],
+    ),
+    (
+        r"catch\s*\([^)]*\)\s*\{\s*\}",
+        "Empty catch block silently discards the error",
+        "Log or rethrow the exception",
+        Priority::High,
+        &[
+            ProgrammingLanguage::Java,
+            ProgrammingLanguage::JavaScript,
+            ProgrammingLanguage::TypeScript,
+            ProgrammingLanguage::CSharp,
+            ProgrammingLanguage::Cpp,
+        ],
+    ),
+    (
+        r",\s*_\s*:?=|^\s*_\s*=",
+        "Returned error is discarded",
+        "Check the error and return or handle it",
+        Priority::High,
+        &[ProgrammingLanguage::Go],
+    ),
+    (
+        r"\bgets\s*\(|\bstrcpy\s*\(|\bsprintf\s*\(",
+        "Unbounded copy into a fixed-size buffer can overflow",
+        "Use `fgets`, `strncpy`/`strlcpy` or `snprintf` with the buffer size",
+        Priority::Critical,
+        &[ProgrammingLanguage::C, ProgrammingLanguage::Cpp],
+    ),
+    (
+        r"(?i)\bselect\s+\*",
+        "`SELECT *` fetches every column and breaks when the schema changes",
+        "List the columns the query needs",
+        Priority::Low,
+        &[ProgrammingLanguage::Sql],
+    ),
+    (
+        r#"(?i)"\s*(select|insert|update|delete)\b[^"]*"\s*(\+|%|\.format)|f"(select|insert|update|delete)\b[^"]*\{"#,
+        "SQL built by string concatenation is open to injection",
+        "Pass values as query parameters instead of formatting them into the SQL",
+        Priority::Critical,
+        &[],
+    ),
+    (
+        r"<=\s*(\w+\.)?(len\(\w+\)|\w+\.len\(\)|\w+\.length|\w+\.size\(\))",
+        "Loop bound `<=` the length reads one element past the end",
+        "Use `<` when iterating up to a collection's length",
+        Priority::High,
+        &[],
+    ),
+    (
+        r"\b(TODO|FIXME|XXX)\b",
+        "Unfinished code is marked in the snippet",
+        "Resolve the marked work before relying on this code",
+        Priority::Low,
+        &[],
+    ),
+];
+
+/// [`LINE_RULES`] patterns, compiled once and in the same order
+static LINE_RULE_PATTERNS: LazyLock<Vec<Regex>> =
+    LazyLock::new(|| LINE_RULES.iter().map(|(pattern, ..)| Regex::new(pattern).unwrap()).collect());
+
+/// Functions, methods and types defined in a snippet: the keyword and the name
+static DEFINITION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?m)(\bfn|\bdef|\bfunction|\bfunc(?:\s*\([^)]*\))?|\bclass|\bstruct|\benum|\binterface|\btrait)\s+([A-Za-z_]\w*)",
+    )
+    .unwrap()
+});
+
+/// Keywords that define a type rather than something that can call itself
+const TYPE_KEYWORDS: &[&str] = &["class", "struct", "enum", "interface", "trait"];
+
+/// What a snippet does, recognized by the constructs it uses
+const BEHAVIOURS: &[(&str, &str)] = &[
+    (r"\b(for|while|loop)\b|\.forEach\(|\.map\(", "iterates over data"),
+    (r"\b(if|match|switch|case)\b", "branches on conditions"),
+    (r"(?i)\b(select|insert|update|delete)\b.*\b(from|into|set|where)\b", "queries a database"),
+    (r"\b(open|fopen|File::open|readFile|fs\.|fs::|ioutil\.|os\.Open)\b", "reads or writes files"),
+    (r"\b(fetch|axios|requests\.|http\.|reqwest|HttpClient|urllib)\b", "makes network requests"),
+    (r"\b(async|await|go\s+func|spawn|Thread|Promise)\b", "runs work concurrently"),
+    (r"\b(print|println!?|printf|console\.log|System\.out|fmt\.Print|echo|Console\.Write)", "prints output"),
+    (r"\b(try|catch|except|Result<|err\s*!=\s*nil)\b", "handles errors"),
+];
+
+static BEHAVIOUR_PATTERNS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    BEHAVIOURS
+        .iter()
+        .map(|(pattern, description)| (Regex::new(pattern).unwrap(), *description))
+        .collect()
+});
+
+/// An unconditional loop, and the statements that can leave one
+static INFINITE_LOOP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(while\s*\(?\s*(true|True|1)\s*\)?|loop\s*\{|for\s*\{|for\s*\(\s*;\s*;\s*\))").unwrap()
+});
+static LOOP_EXIT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(break|return|exit|panic!?|raise|throw)\b").unwrap());
+
+/// Describe a snippet and look for likely bugs.
+///
+/// Whitespace is significant: the snippet should keep the indentation it had on screen.
+pub fn analyze(code: &str) -> CodeAnalysis {
+    let guess = detect_language(code);
+    let language = guess.map(|guess| guess.language);
+    let definitions = find_definitions(code);
+    let recursive = find_recursive(code);
+
+    CodeAnalysis {
+        language,
+        language_confidence: guess.map(|guess| guess.confidence).unwrap_or(0.0),
+        summary: summarize(code, language, &definitions, &recursive),
+        issues: find_issues(code, language),
+        definitions,
+        line_count: code.lines().filter(|line| !line.trim().is_empty()).count(),
+    }
+}
+
+/// Names of functions, methods and types defined in a snippet
+fn find_definitions(code: &str) -> Vec<String> {
+    let mut definitions: Vec<String> = Vec::new();
+    for capture in DEFINITION.captures_iter(code) {
+        let name = capture[2].to_string();
+        if !definitions.contains(&name) {
+            definitions.push(name);
+        }
+    }
+    definitions
+}
+
+/// Functions that call themselves from inside their own body
+fn find_recursive(code: &str) -> Vec<String> {
+    let mut recursive: Vec<String> = Vec::new();
+    for capture in DEFINITION.captures_iter(code) {
+        let keyword = capture[1].split_whitespace().next().unwrap_or_default();
+        if TYPE_KEYWORDS.contains(&keyword) {
+            continue;
+        }
+        let name = &capture[2];
+        let body = definition_body(code, capture.get(2).map_or(0, |name| name.end()));
+        if calls(body, name) && !recursive.iter().any(|known| known == name) {
+            recursive.push(name.to_string());
+        }
+    }
+    recursive
+}
+
+/// The body of the definition whose name ends at `start`: the brace-delimited block,
+/// or the more deeply indented lines after a Python `def ...:` line
+fn definition_body(code: &str, start: usize) -> &str {
+    let rest = &code[start..];
+    let header_end = rest.find('\n').unwrap_or(rest.len());
+
+    if rest[..header_end].trim_end().ends_with(':') {
+        let line_start = code[..start].rfind('\n').map_or(0, |index| index + 1);
+        let indent = indentation(&code[line_start..]);
+        let body_start = (start + header_end + 1).min(code.len());
+        let mut body_end = body_start;
+        for line in code[body_start..].split_inclusive('\n') {
+            if !line.trim().is_empty() && indentation(line) <= indent {
+                break;
+            }
+            body_end += line.len();
+        }
+        return &code[body_start..body_end];
+    }
+
+    // A `;` before the first brace means a declaration without a body
+    let Some(open) = rest.find(['{', ';']).filter(|&index| rest.as_bytes()[index] == b'{') else {
+        return "";
+    };
+    let mut depth = 0;
+    for (index, c) in rest[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &rest[open + 1..open + index];
+                }
+            }
+            _ => {}
+        }
+    }
+    &rest[open + 1..]
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+/// Whether `body` calls `name` as a function
+fn calls(body: &str, name: &str) -> bool {
+    body.match_indices(name).any(|(index, _)| {
+        let preceded_by_word = body[..index].chars().next_back().is_some_and(|c| c.is_alphanumeric() || c == '_');
+        !preceded_by_word && body[index + name.len()..].trim_start().starts_with('(')
+    })
+}
+
+fn summarize(code: &str, language: Option<ProgrammingLanguage>, definitions: &[String], recursive: &[String]) -> String {
+    let subject = match language {
+        Some(language) => format!("{} snippet", language.name()),
+        None => "Code snippet".to_string(),
+    };
+
+    let mut parts = Vec::new();
+    if !definitions.is_empty() {
+        let names = definitions.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ");
+        if recursive.is_empty() {
+            parts.push(format!("defines {}", names));
+        } else {
+            let recursive = recursive.iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", ");
+            parts.push(format!("defines {} ({} recursive)", names, recursive));
+        }
+    }
+
+    for (regex, description) in BEHAVIOUR_PATTERNS.iter() {
+        if regex.is_match(code) {
+            parts.push(description.to_string());
+        }
+    }
+
+    if parts.is_empty() {
+        format!("{} of {} lines", subject, code.lines().count())
+    } else {
+        format!("{} that {}", subject, join_clauses(&parts))
+    }
+}
+
+fn join_clauses(parts: &[String]) -> String {
+    match parts {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn find_issues(code: &str, language: Option<ProgrammingLanguage>) -> Vec<CodeIssue> {
+    let mut issues = Vec::new();
+
+    for ((_, message, suggestion, priority, languages), regex) in LINE_RULES.iter().zip(LINE_RULE_PATTERNS.iter()) {
+        let applies = languages.is_empty() || language.is_some_and(|language| languages.contains(&language));
+        if !applies {
+            continue;
+        }
+
+        for (index, line) in code.lines().enumerate() {
+            if is_comment(line) && !message.starts_with("Unfinished") {
+                continue;
+            }
+            if regex.is_match(line) {
+                issues.push(CodeIssue {
+                    line: index + 1,
+                    snippet: line.trim().to_string(),
+                    message: message.to_string(),
+                    suggestion: suggestion.to_string(),
+                    priority: priority.clone(),
+                });
+            }
+        }
+    }
+
+    // An unconditional loop with no way out never terminates
+    if !LOOP_EXIT.is_match(code) {
+        if let Some((index, line)) = code.lines().enumerate().find(|(_, line)| INFINITE_LOOP.is_match(line)) {
+            issues.push(CodeIssue {
+                line: index + 1,
+                snippet: line.trim().to_string(),
+                message: "Infinite loop has no `break` or `return`".to_string(),
+                suggestion: "Add an exit condition to the loop".to_string(),
+                priority: Priority::High,
+            });
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    issues
+}
+
+fn is_comment(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("//") || trimmed.starts_with('#') && !trimmed.starts_with("#include") || trimmed.starts_with("--")
+}
+
+impl CodeAnalysis {
+    /// Technical recommendations quoting the lines each issue was found on
+    pub fn recommendations(&self) -> Vec<Recommendation> {
+        let language = self.language.map(|language| language.name()).unwrap_or("code");
+
+        let mut recommendations: Vec<Recommendation> = self
+            .issues
+            .iter()
+            .map(|issue| Recommendation {
+                category: RecommendationCategory::Technical,
+                title: format!("Possible bug on line {}", issue.line),
+                description: format!("{}: `{}`", issue.message, issue.snippet),
+                priority: issue.priority.clone(),
+                action_items: vec![issue.suggestion.clone()],
+                confidence: 0.6 + 0.3 * self.language_confidence,
+            })
+            .collect();
+
+        if recommendations.is_empty() && self.line_count > 0 {
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::Technical,
+                title: format!("Review {} snippet", language),
+                description: self.summary.clone(),
+                priority: Priority::Low,
+                action_items: vec![
+                    "No obvious bugs found; add tests covering the edge cases".to_string(),
+                ],
+                confidence: 0.5,
+            });
+        }
+
+        recommendations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let rust = "fn main() {\n    let mut total = 0;\n    println!(\"{}\", total);\n}";
+        let python = "def greet(name):\n    if name is None:\n        return\n    print(name)";
+        let go = "package main\n\nfunc main() {\n    x, err := load()\n    if err != nil {\n        return\n    }\n}";
+        let sql = "SELECT id, name FROM users WHERE active = 1";
+
+        assert_eq!(detect_language(rust).unwrap().language, ProgrammingLanguage::Rust);
+        assert_eq!(detect_language(python).unwrap().language, ProgrammingLanguage::Python);
+        assert_eq!(detect_language(go).unwrap().language, ProgrammingLanguage::Go);
+        assert_eq!(detect_language(sql).unwrap().language, ProgrammingLanguage::Sql);
+        assert!(detect_language("Meeting notes for Tuesday").is_none());
+    }
+
+    #[test]
+    fn test_python_issues_are_grounded_in_lines() {
+        let code = "def add(item, items=[]):\n    try:\n        items.append(item)\n    except:\n        pass\n    return items";
+        let analysis = analyze(code);
+
+        assert_eq!(analysis.language, Some(ProgrammingLanguage::Python));
+        assert_eq!(analysis.definitions, vec!["add"]);
+        assert!(analysis.issues.iter().any(|issue| issue.line == 1 && issue.message.contains("Mutable default")));
+        assert!(analysis.issues.iter().any(|issue| issue.line == 4 && issue.snippet == "except:"));
+
+        let recommendations = analysis.recommendations();
+        assert!(recommendations
+            .iter()
+            .all(|rec| matches!(rec.category, RecommendationCategory::Technical)));
+        assert!(recommendations.iter().any(|rec| rec.description.contains("`except:`")));
+    }
+
+    #[test]
+    fn test_summary_describes_snippet() {
+        let code = "fn fib(n: u64) -> u64 {\n    if n < 2 {\n        return n;\n    }\n    fib(n - 1) + fib(n - 2)\n}";
+        let analysis = analyze(code);
+
+        assert!(analysis.summary.starts_with("Rust snippet"));
+        assert!(analysis.summary.contains("`fib` recursive"));
+        assert!(analysis.summary.contains("branches on conditions"));
+
+        // Calling a function twice from elsewhere does not make it recursive
+        let code = "fn double(x: i32) -> i32 {\n    x * 2\n}\nfn main() {\n    println!(\"{}\", double(double(2)));\n}";
+        assert!(!analyze(code).summary.contains("recursive"));
+
+        let code = "def walk(node):\n    for child in node.children:\n        walk(child)\n\nwalk(root)\nwalk(other)";
+        assert!(analyze(code).summary.contains("`walk` recursive"));
+    }
+}
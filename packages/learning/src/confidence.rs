@@ -0,0 +1,385 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::audio::TranscriptSegment;
+use crate::ocr::table::WordBox;
+
+/// Metadata key under which the confidence breakdown of an analysis is stored
+pub const CONFIDENCE_METADATA_KEY: &str = "confidence";
+
+/// Where a confidence signal comes from; each kind is calibrated separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalKind {
+    /// Tesseract word confidences, weighted by word length
+    OcrWords,
+    /// ASR segment log-probabilities, weighted by segment duration
+    AsrSegments,
+    /// Top score of a classifier, such as sentiment or language detection
+    Classifier,
+    /// How well the outputs of two pipeline stages agree with each other
+    StageAgreement,
+    /// A score that an earlier stage has already calibrated
+    Calibrated,
+}
+
+/// One raw confidence reading for a field of a result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Signal {
+    pub field: String,
+    pub kind: SignalKind,
+    /// Uncalibrated score in `0.0..=1.0`
+    pub raw: f32,
+    /// Number of words, segments or items the score was aggregated from
+    pub samples: usize,
+}
+
+impl Signal {
+    pub fn new(field: impl Into<String>, kind: SignalKind, raw: f32) -> Self {
+        Self {
+            field: field.into(),
+            kind,
+            raw: raw.clamp(0.0, 1.0),
+            samples: 1,
+        }
+    }
+
+    /// Length-weighted mean of word confidences
+    pub fn ocr_words(field: impl Into<String>, words: &[WordBox]) -> Option<Self> {
+        let mut total = 0.0;
+        let mut weight = 0.0;
+        let mut samples = 0;
+        for word in words {
+            let length = word.text.trim().chars().count() as f32;
+            if length == 0.0 {
+                continue;
+            }
+            total += word.confidence.clamp(0.0, 1.0) * length;
+            weight += length;
+            samples += 1;
+        }
+        if samples == 0 {
+            return None;
+        }
+
+        let mut signal = Self::new(field, SignalKind::OcrWords, total / weight);
+        signal.samples = samples;
+        Some(signal)
+    }
+
+    /// Duration-weighted probability of the decoded segments, discounted by the chance
+    /// that a segment held no speech at all
+    pub fn asr_segments(field: impl Into<String>, segments: &[TranscriptSegment]) -> Option<Self> {
+        let mut total = 0.0;
+        let mut weight = 0.0;
+        for segment in segments {
+            let duration = (segment.end_secs - segment.start_secs).max(0.0) as f32;
+            let probability = segment.avg_logprob.exp() * (1.0 - segment.no_speech_prob.clamp(0.0, 1.0));
+            total += probability * duration;
+            weight += duration;
+        }
+        if weight <= 0.0 {
+            return None;
+        }
+
+        let mut signal = Self::new(field, SignalKind::AsrSegments, total / weight);
+        signal.samples = segments.len();
+        Some(signal)
+    }
+
+    /// Score of the label a classifier picked
+    pub fn classifier(field: impl Into<String>, score: f32) -> Self {
+        Self::new(field, SignalKind::Classifier, score)
+    }
+
+    /// Fraction of the terms one stage produced (e.g. model topics) that another
+    /// stage's text supports
+    pub fn agreement(field: impl Into<String>, terms: &[String], text: &str) -> Option<Self> {
+        let terms: Vec<&String> = terms.iter().filter(|term| !term.trim().is_empty()).collect();
+        if terms.is_empty() {
+            return None;
+        }
+
+        let text = text.to_lowercase();
+        let supported = terms
+            .iter()
+            .filter(|term| {
+                term.to_lowercase()
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|word| word.len() > 2)
+                    .any(|word| text.contains(word))
+            })
+            .count();
+
+        let mut signal = Self::new(field, SignalKind::StageAgreement, supported as f32 / terms.len() as f32);
+        signal.samples = terms.len();
+        Some(signal)
+    }
+
+    /// A score an earlier stage already calibrated, passed through unchanged
+    pub fn calibrated(field: impl Into<String>, score: f32) -> Self {
+        Self::new(field, SignalKind::Calibrated, score)
+    }
+}
+
+/// Platt scaling: maps a raw score to a probability of being correct
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub slope: f32,
+    pub intercept: f32,
+}
+
+impl Calibration {
+    pub fn apply(&self, raw: f32) -> f32 {
+        sigmoid(self.slope * raw + self.intercept)
+    }
+
+    /// Fit the curve to `(raw score, was correct)` pairs from labeled results
+    pub fn fit(samples: &[(f32, bool)]) -> Self {
+        let mut calibration = Self { slope: 1.0, intercept: 0.0 };
+        if samples.is_empty() {
+            return calibration;
+        }
+
+        let learning_rate = 1.0;
+        for _ in 0..5000 {
+            let (mut d_slope, mut d_intercept) = (0.0, 0.0);
+            for (raw, correct) in samples {
+                let error = calibration.apply(*raw) - if *correct { 1.0 } else { 0.0 };
+                d_slope += error * raw;
+                d_intercept += error;
+            }
+            calibration.slope -= learning_rate * d_slope / samples.len() as f32;
+            calibration.intercept -= learning_rate * d_intercept / samples.len() as f32;
+        }
+        calibration
+    }
+}
+
+/// Calibration curves and weights for combining confidence signals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceConfig {
+    /// Curve per signal kind; kinds without one are used as-is
+    pub calibration: HashMap<SignalKind, Calibration>,
+    /// Relative weight of each kind in the combined score
+    pub weights: HashMap<SignalKind, f32>,
+    /// Combined score below which a result is flagged as low confidence
+    pub low_confidence_threshold: f32,
+}
+
+impl Default for ConfidenceConfig {
+    fn default() -> Self {
+        // Tesseract and sentiment models are overconfident near the top of their range;
+        // agreement between stages is informative even when it is partial
+        let calibration = HashMap::from([
+            (SignalKind::OcrWords, Calibration { slope: 10.0, intercept: -6.0 }),
+            (SignalKind::AsrSegments, Calibration { slope: 8.0, intercept: -4.0 }),
+            (SignalKind::Classifier, Calibration { slope: 4.0, intercept: -2.0 }),
+            (SignalKind::StageAgreement, Calibration { slope: 5.0, intercept: -1.5 }),
+        ]);
+        let weights = HashMap::from([
+            (SignalKind::OcrWords, 1.0),
+            (SignalKind::AsrSegments, 1.0),
+            (SignalKind::Calibrated, 1.0),
+            (SignalKind::Classifier, 0.5),
+            (SignalKind::StageAgreement, 0.5),
+        ]);
+
+        Self {
+            calibration,
+            weights,
+            low_confidence_threshold: 0.6,
+        }
+    }
+}
+
+/// Calibrated confidence of one field of a result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldConfidence {
+    pub field: String,
+    pub kind: SignalKind,
+    pub raw: f32,
+    pub score: f32,
+    pub samples: usize,
+}
+
+/// Combined confidence of a result with its per-field breakdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfidenceReport {
+    pub score: f32,
+    /// Set when the score is below the configured threshold, so the UI can ask the
+    /// user to double-check the result
+    pub low_confidence: bool,
+    pub fields: Vec<FieldConfidence>,
+}
+
+impl ConfidenceReport {
+    pub fn field(&self, name: &str) -> Option<&FieldConfidence> {
+        self.fields.iter().find(|field| field.field == name)
+    }
+
+    /// Field with the lowest calibrated score
+    pub fn weakest(&self) -> Option<&FieldConfidence> {
+        self.fields.iter().min_by(|a, b| a.score.total_cmp(&b.score))
+    }
+
+    /// Restore the report stored on an analysis result
+    pub fn from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        metadata
+            .get(CONFIDENCE_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+}
+
+/// Calibrates confidence signals and combines them into one score
+#[derive(Debug, Clone, Default)]
+pub struct ConfidenceModel {
+    config: ConfidenceConfig,
+}
+
+impl ConfidenceModel {
+    /// Create a model with the default calibration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a model with custom calibration curves and weights
+    pub fn with_config(config: ConfidenceConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ConfidenceConfig {
+        &self.config
+    }
+
+    /// Calibrated probability that the field the signal describes is correct
+    pub fn calibrate(&self, signal: &Signal) -> f32 {
+        match self.config.calibration.get(&signal.kind) {
+            Some(calibration) => calibration.apply(signal.raw),
+            None => signal.raw,
+        }
+    }
+
+    /// Calibrate each signal and pool them into one score.
+    ///
+    /// Scores are averaged in log-odds space, so one confident and one doubtful stage
+    /// give a doubtful result rather than a middling one.
+    pub fn score(&self, signals: Vec<Signal>) -> ConfidenceReport {
+        let fields: Vec<FieldConfidence> = signals
+            .into_iter()
+            .map(|signal| FieldConfidence {
+                score: self.calibrate(&signal),
+                field: signal.field,
+                kind: signal.kind,
+                raw: signal.raw,
+                samples: signal.samples,
+            })
+            .collect();
+
+        let (mut total, mut weight) = (0.0, 0.0);
+        for field in &fields {
+            let field_weight = self.config.weights.get(&field.kind).copied().unwrap_or(1.0);
+            total += logit(field.score) * field_weight;
+            weight += field_weight;
+        }
+        let score = if weight > 0.0 { sigmoid(total / weight) } else { 0.0 };
+
+        ConfidenceReport {
+            score,
+            low_confidence: score < self.config.low_confidence_threshold,
+            fields,
+        }
+    }
+
+    /// Rescore a report with additional signals, e.g. OCR word confidences added after
+    /// the text was analyzed
+    pub fn extend(&self, report: &ConfidenceReport, signals: Vec<Signal>) -> ConfidenceReport {
+        let mut all: Vec<Signal> = report
+            .fields
+            .iter()
+            .map(|field| Signal {
+                field: field.field.clone(),
+                kind: field.kind,
+                raw: field.raw,
+                samples: field.samples,
+            })
+            .collect();
+        all.extend(signals);
+        self.score(all)
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn logit(p: f32) -> f32 {
+    let p = p.clamp(1e-4, 1.0 - 1e-4);
+    (p / (1.0 - p)).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::table::BoundingBox;
+
+    fn word(text: &str, confidence: f32) -> WordBox {
+        WordBox {
+            text: text.to_string(),
+            bbox: BoundingBox { x: 0, y: 0, width: 10, height: 10 },
+            confidence,
+        }
+    }
+
+    #[test]
+    fn test_signals_from_stage_outputs() {
+        // Percent-scale Tesseract confidences, weighted by word length
+        let ocr = Signal::ocr_words("ocr", &[word("Dijkstra", 0.9), word("is", 0.4)]).unwrap();
+        assert!((ocr.raw - 0.8).abs() < 1e-4);
+        assert_eq!(ocr.samples, 2);
+
+        let segments = [
+            TranscriptSegment { start_secs: 0.0, end_secs: 3.0, text: "clear".into(), speaker: None, avg_logprob: -0.1, no_speech_prob: 0.0 },
+            TranscriptSegment { start_secs: 3.0, end_secs: 4.0, text: "mumbled".into(), speaker: None, avg_logprob: -2.0, no_speech_prob: 0.5 },
+        ];
+        let asr = Signal::asr_segments("transcription", &segments).unwrap();
+        assert!(asr.raw > 0.6 && asr.raw < 0.7);
+
+        let topics = vec!["Graph algorithms".to_string(), "Cooking".to_string()];
+        let agreement = Signal::agreement("topics", &topics, "Shortest paths in graphs").unwrap();
+        assert_eq!(agreement.raw, 0.5);
+        assert!(Signal::ocr_words("ocr", &[]).is_none());
+    }
+
+    #[test]
+    fn test_combined_score_and_low_confidence_flag() {
+        let model = ConfidenceModel::new();
+
+        let clean = model.score(vec![Signal::new("ocr", SignalKind::OcrWords, 0.95), Signal::classifier("sentiment", 0.9)]);
+        assert!(clean.score > 0.8 && !clean.low_confidence);
+
+        let blurry = model.score(vec![Signal::new("ocr", SignalKind::OcrWords, 0.45), Signal::classifier("sentiment", 0.9)]);
+        assert!(blurry.low_confidence);
+        assert_eq!(blurry.weakest().unwrap().field, "ocr");
+
+        // Adding a signal later rescores the whole report
+        let extended = model.extend(&clean, vec![Signal::calibrated("ocr_fallback", 0.2)]);
+        assert_eq!(extended.fields.len(), 3);
+        assert!(extended.score < clean.score);
+        assert_eq!(model.score(Vec::new()).score, 0.0);
+    }
+
+    #[test]
+    fn test_calibration_fit_tempers_overconfidence() {
+        // A stage that reports 0.9 but is right only 60% of the time
+        let mut samples = Vec::new();
+        for i in 0..50 {
+            samples.push((0.9, i % 5 < 3));
+            samples.push((0.3, i % 10 == 0));
+        }
+        let calibration = Calibration::fit(&samples);
+
+        assert!((calibration.apply(0.9) - 0.6).abs() < 0.05);
+        assert!((calibration.apply(0.3) - 0.1).abs() < 0.05);
+    }
+}
@@ -0,0 +1,1133 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use crate::analysis::{Priority, Recommendation, RecommendationCategory};
+
+/// Metadata key under which parsed compiler and runtime errors are listed
+pub const DIAGNOSTICS_METADATA_KEY: &str = "diagnostics";
+
+/// Toolchain or runtime that produced a diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticSource {
+    Rustc,
+    Python,
+    Node,
+    TypeScript,
+    Jvm,
+    Go,
+    Msvc,
+}
+
+impl DiagnosticSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiagnosticSource::Rustc => "Rust",
+            DiagnosticSource::Python => "Python",
+            DiagnosticSource::Node => "Node.js",
+            DiagnosticSource::TypeScript => "TypeScript",
+            DiagnosticSource::Jvm => "JVM",
+            DiagnosticSource::Go => "Go",
+            DiagnosticSource::Msvc => "MSVC",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// One frame of a stack trace; frames keep the order the runtime printed them in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    pub function: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+/// A compiler error, warning or runtime exception parsed from text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub source: DiagnosticSource,
+    pub severity: Severity,
+    /// Tool-assigned error code, such as `E0382`, `TS2339` or `C2065`
+    pub code: Option<String>,
+    /// Exception or panic type, such as `KeyError` or `java.lang.NullPointerException`
+    pub kind: Option<String>,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub frames: Vec<StackFrame>,
+    /// Innermost `Caused by:` exception of a JVM trace
+    pub root_cause: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(source: DiagnosticSource, message: &str) -> Self {
+        Self {
+            source,
+            severity: Severity::Error,
+            code: None,
+            kind: None,
+            message: message.trim().to_string(),
+            file: None,
+            line: None,
+            column: None,
+            frames: Vec::new(),
+            root_cause: None,
+        }
+    }
+
+    /// `file:line:column`, as far as it is known
+    pub fn location(&self) -> Option<String> {
+        let file = self.file.as_ref()?;
+        Some(match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", file, line, column),
+            (Some(line), None) => format!("{}:{}", file, line),
+            _ => file.clone(),
+        })
+    }
+}
+
+/// Diagnostics that analysis parsed and attached under [`DIAGNOSTICS_METADATA_KEY`]
+pub fn from_metadata(metadata: &std::collections::HashMap<String, serde_json::Value>) -> Vec<Diagnostic> {
+    metadata
+        .get(DIAGNOSTICS_METADATA_KEY)
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Parse every recognized compiler error and stack trace in a block of text
+pub fn parse(text: &str) -> Vec<Diagnostic> {
+    let patterns = &*PATTERNS;
+    let lines: Vec<&str> = text.lines().collect();
+    let parsers: [Parser; 9] = [
+        parse_rustc,
+        parse_rust_panic,
+        parse_python,
+        parse_typescript,
+        parse_jvm,
+        parse_node,
+        parse_go_panic,
+        parse_go_compile,
+        parse_msvc,
+    ];
+
+    let mut diagnostics = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        match parsers.iter().find_map(|parser| parser(patterns, &lines, index)) {
+            Some((diagnostic, consumed)) => {
+                diagnostics.push(diagnostic);
+                index += consumed.max(1);
+            }
+            None => index += 1,
+        }
+    }
+    diagnostics
+}
+
+/// Tries to parse a diagnostic starting at a line, returning it and the number of lines consumed
+type Parser = fn(&Patterns, &[&str], usize) -> Option<(Diagnostic, usize)>;
+
+/// Every parser's patterns, compiled once
+static PATTERNS: LazyLock<Patterns> = LazyLock::new(Patterns::new);
+
+struct Patterns {
+    rustc_header: Regex,
+    rustc_location: Regex,
+    rust_panic: Regex,
+    rust_frame_header: Regex,
+    rust_frame_location: Regex,
+    python_file: Regex,
+    python_exception: Regex,
+    typescript: Regex,
+    jvm_header: Regex,
+    jvm_frame: Regex,
+    jvm_cause: Regex,
+    node_header: Regex,
+    node_frame: Regex,
+    go_panic: Regex,
+    go_fatal: Regex,
+    go_frame_location: Regex,
+    go_compile: Regex,
+    msvc_compile: Regex,
+    msvc_link: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        let regex = |pattern: &str| Regex::new(pattern).unwrap();
+        Self {
+            rustc_header: regex(r"^(error|warning)(?:\[(E\d{4})\])?: (.+)$"),
+            rustc_location: regex(r"^\s*--> (.+?):(\d+):(\d+)$"),
+            rust_panic: regex(r"^thread '(.+?)' panicked at (?:'(.+)', )?(.+?):(\d+):(\d+):?$"),
+            rust_frame_header: regex(r"^\s+\d+: (.+)$"),
+            rust_frame_location: regex(r"^\s+at (.+?):(\d+):(\d+)$"),
+            python_file: regex(r#"^\s*File "(.+?)", line (\d+)(?:, in (.+))?$"#),
+            python_exception: regex(r"^([A-Za-z_][\w.]*(?:Error|Exception|Warning|Interrupt|Exit|Iteration))(?::\s*(.*))?$"),
+            typescript: regex(r"^(.+?\.tsx?)(?:\((\d+),(\d+)\)|:(\d+):(\d+))(?::| -) (error|warning) (TS\d+): (.+)$"),
+            jvm_header: regex(r#"^(?:Exception in thread "(.+?)" )?((?:[a-z][\w$]*\.)+[\w$]*(?:Exception|Error))(?::\s*(.*))?$"#),
+            jvm_frame: regex(r"^\s+at ([\w$.<>/]+)\(([^:()]+)(?::(\d+))?\)$"),
+            jvm_cause: regex(r"^Caused by: ([\w$.]+)(?::\s*(.*))?$"),
+            node_header: regex(r"^(?:Uncaught )?([A-Z]\w*Error)(?: \[(\w+)\])?: (.+)$"),
+            node_frame: regex(r"^\s+at (?:(.+?) \()?(.+?):(\d+):(\d+)\)?$"),
+            go_panic: regex(r"^panic: (.+?)(?: \[recovered\])?$"),
+            go_fatal: regex(r"^fatal error: (.+)$"),
+            go_frame_location: regex(r"^\s+(.+\.go):(\d+)(?: \+0x[0-9a-f]+)?$"),
+            go_compile: regex(r"^(?:\./)?(.+\.go):(\d+):(\d+): (.+)$"),
+            msvc_compile: regex(r"^(.+?)\((\d+)(?:,(\d+))?\)\s*:\s*(fatal error|error|warning) ([A-Z]+\d+)\s*:\s*(.+)$"),
+            msvc_link: regex(r"^(.+?)\s*:\s*(fatal error|error|warning) (LNK\d+)\s*:\s*(.+)$"),
+        }
+    }
+}
+
+fn number(capture: Option<regex::Match>) -> Option<u32> {
+    capture.and_then(|value| value.as_str().parse().ok())
+}
+
+fn parse_rustc(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let header = patterns.rustc_header.captures(lines[index])?;
+    let message = &header[3];
+    // Cargo's closing summary lines repeat what was already reported
+    if message.starts_with("could not compile") || message.starts_with("aborting due to") || message.contains("generated ") {
+        return None;
+    }
+
+    let location = lines
+        .iter()
+        .enumerate()
+        .skip(index + 1)
+        .take(3)
+        .find_map(|(offset, line)| patterns.rustc_location.captures(line).map(|captures| (offset, captures)));
+    // Without an error code or a source location this is some other tool's "error:" line
+    if header.get(2).is_none() && location.is_none() {
+        return None;
+    }
+
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Rustc, message);
+    diagnostic.code = header.get(2).map(|code| code.as_str().to_string());
+    if &header[1] == "warning" {
+        diagnostic.severity = Severity::Warning;
+    }
+    let mut consumed = 1;
+    if let Some((offset, captures)) = location {
+        diagnostic.file = Some(captures[1].to_string());
+        diagnostic.line = number(captures.get(2));
+        diagnostic.column = number(captures.get(3));
+        consumed = offset - index + 1;
+    }
+    Some((diagnostic, consumed))
+}
+
+fn parse_rust_panic(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let header = patterns.rust_panic.captures(lines[index])?;
+    let mut consumed = 1;
+
+    // Since Rust 1.73 the message follows on its own line
+    let message = match header.get(2) {
+        Some(message) => message.as_str().to_string(),
+        None => {
+            consumed += 1;
+            lines.get(index + 1).map(|line| line.trim().to_string()).unwrap_or_default()
+        }
+    };
+
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Rustc, &message);
+    diagnostic.kind = Some("panic".to_string());
+    diagnostic.file = Some(header[3].to_string());
+    diagnostic.line = number(header.get(4));
+    diagnostic.column = number(header.get(5));
+
+    if lines.get(index + consumed).is_some_and(|line| line.trim() == "stack backtrace:") {
+        consumed += 1;
+        while let Some(line) = lines.get(index + consumed) {
+            if let Some(captures) = patterns.rust_frame_header.captures(line) {
+                diagnostic.frames.push(StackFrame {
+                    function: Some(captures[1].to_string()),
+                    file: None,
+                    line: None,
+                });
+            } else if let Some(captures) = patterns.rust_frame_location.captures(line) {
+                if let Some(frame) = diagnostic.frames.last_mut() {
+                    frame.file = Some(captures[1].to_string());
+                    frame.line = number(captures.get(2));
+                }
+            } else {
+                break;
+            }
+            consumed += 1;
+        }
+    }
+
+    Some((diagnostic, consumed))
+}
+
+fn parse_python(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    // Tracebacks start with a banner; syntax errors start directly at the `File` line
+    let traceback = lines[index].trim() == "Traceback (most recent call last):";
+    if !traceback && !patterns.python_file.is_match(lines[index]) {
+        return None;
+    }
+
+    let mut frames = Vec::new();
+    let mut cursor = if traceback { index + 1 } else { index };
+    while let Some(line) = lines.get(cursor) {
+        if let Some(captures) = patterns.python_file.captures(line) {
+            frames.push(StackFrame {
+                function: captures.get(3).map(|function| function.as_str().to_string()),
+                file: Some(captures[1].to_string()),
+                line: number(captures.get(2)),
+            });
+        } else if let Some(captures) = patterns.python_exception.captures(line) {
+            let mut diagnostic = Diagnostic::new(
+                DiagnosticSource::Python,
+                captures.get(2).map(|message| message.as_str()).unwrap_or(""),
+            );
+            diagnostic.kind = Some(captures[1].to_string());
+            if let Some(innermost) = frames.last() {
+                diagnostic.file = innermost.file.clone();
+                diagnostic.line = innermost.line;
+            }
+            diagnostic.frames = frames;
+            return Some((diagnostic, cursor - index + 1));
+        } else if !line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+            // Source lines and carets are indented; anything else ends the trace
+            return None;
+        }
+        cursor += 1;
+    }
+    None
+}
+
+fn parse_typescript(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let captures = patterns.typescript.captures(lines[index])?;
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::TypeScript, &captures[8]);
+    diagnostic.code = Some(captures[7].to_string());
+    diagnostic.file = Some(captures[1].to_string());
+    diagnostic.line = number(captures.get(2).or(captures.get(4)));
+    diagnostic.column = number(captures.get(3).or(captures.get(5)));
+    if &captures[6] == "warning" {
+        diagnostic.severity = Severity::Warning;
+    }
+    Some((diagnostic, 1))
+}
+
+fn parse_jvm(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let header = patterns.jvm_header.captures(lines[index])?;
+    if !lines.get(index + 1).is_some_and(|line| patterns.jvm_frame.is_match(line)) {
+        return None;
+    }
+
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Jvm, header.get(3).map(|m| m.as_str()).unwrap_or(""));
+    diagnostic.kind = Some(header[2].to_string());
+
+    let mut cursor = index + 1;
+    let mut in_cause = false;
+    while let Some(line) = lines.get(cursor) {
+        if let Some(captures) = patterns.jvm_frame.captures(line) {
+            // Keep the frames of the top-level exception; causes repeat most of them
+            if !in_cause {
+                diagnostic.frames.push(StackFrame {
+                    function: Some(captures[1].to_string()),
+                    file: Some(captures[2].to_string()),
+                    line: number(captures.get(3)),
+                });
+            }
+        } else if let Some(captures) = patterns.jvm_cause.captures(line) {
+            in_cause = true;
+            diagnostic.root_cause = Some(match captures.get(2) {
+                Some(message) => format!("{}: {}", &captures[1], message.as_str()),
+                None => captures[1].to_string(),
+            });
+        } else if !line.trim_start().starts_with("...") {
+            break;
+        }
+        cursor += 1;
+    }
+
+    if let Some(top) = diagnostic.frames.first() {
+        diagnostic.file = top.file.clone();
+        diagnostic.line = top.line;
+    }
+    Some((diagnostic, cursor - index))
+}
+
+fn parse_node(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let header = patterns.node_header.captures(lines[index])?;
+    if !lines.get(index + 1).is_some_and(|line| patterns.node_frame.is_match(line)) {
+        return None;
+    }
+
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Node, &header[3]);
+    diagnostic.kind = Some(header[1].to_string());
+    diagnostic.code = header.get(2).map(|code| code.as_str().to_string());
+
+    let mut cursor = index + 1;
+    while let Some(captures) = lines.get(cursor).and_then(|line| patterns.node_frame.captures(line)) {
+        diagnostic.frames.push(StackFrame {
+            function: captures.get(1).map(|function| function.as_str().to_string()),
+            file: Some(captures[2].to_string()),
+            line: number(captures.get(3)),
+        });
+        if diagnostic.frames.len() == 1 {
+            diagnostic.file = Some(captures[2].to_string());
+            diagnostic.line = number(captures.get(3));
+            diagnostic.column = number(captures.get(4));
+        }
+        cursor += 1;
+    }
+    Some((diagnostic, cursor - index))
+}
+
+fn parse_go_panic(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let (kind, message) = if let Some(captures) = patterns.go_panic.captures(lines[index]) {
+        ("panic", captures[1].to_string())
+    } else {
+        ("fatal error", patterns.go_fatal.captures(lines[index])?[1].to_string())
+    };
+
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Go, &message);
+    diagnostic.kind = Some(kind.to_string());
+
+    let mut cursor = index + 1;
+    let mut function: Option<String> = None;
+    while let Some(line) = lines.get(cursor) {
+        if let Some(captures) = patterns.go_frame_location.captures(line) {
+            diagnostic.frames.push(StackFrame {
+                function: function.take(),
+                file: Some(captures[1].to_string()),
+                line: number(captures.get(2)),
+            });
+        } else if line.starts_with("goroutine ") || line.starts_with("[signal ") || line.trim().is_empty() {
+            // Goroutine headers and signal details carry no frame information
+        } else if let Some(caller) = line.strip_prefix("created by ") {
+            function = Some(caller.split(' ').next().unwrap_or(caller).to_string());
+        } else if !line.starts_with(char::is_whitespace) && line.ends_with(')') && !line.contains(": ") {
+            function = Some(line.split('(').next().unwrap_or(line).to_string());
+        } else {
+            break;
+        }
+        cursor += 1;
+    }
+
+    // The first frame outside the runtime is where the program went wrong
+    if let Some(frame) = diagnostic
+        .frames
+        .iter()
+        .find(|frame| !frame.function.as_deref().is_some_and(|function| function.starts_with("runtime.") || function == "panic"))
+    {
+        diagnostic.file = frame.file.clone();
+        diagnostic.line = frame.line;
+    }
+    Some((diagnostic, cursor - index))
+}
+
+fn parse_go_compile(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let captures = patterns.go_compile.captures(lines[index])?;
+    let mut diagnostic = Diagnostic::new(DiagnosticSource::Go, &captures[4]);
+    diagnostic.kind = Some("compile".to_string());
+    diagnostic.file = Some(captures[1].to_string());
+    diagnostic.line = number(captures.get(2));
+    diagnostic.column = number(captures.get(3));
+    Some((diagnostic, 1))
+}
+
+fn parse_msvc(patterns: &Patterns, lines: &[&str], index: usize) -> Option<(Diagnostic, usize)> {
+    let line = lines[index].trim();
+    let mut diagnostic;
+    let severity;
+    if let Some(captures) = patterns.msvc_compile.captures(line) {
+        diagnostic = Diagnostic::new(DiagnosticSource::Msvc, &captures[6]);
+        diagnostic.code = Some(captures[5].to_string());
+        diagnostic.file = Some(captures[1].to_string());
+        diagnostic.line = number(captures.get(2));
+        diagnostic.column = number(captures.get(3));
+        severity = captures[4].to_string();
+    } else if let Some(captures) = patterns.msvc_link.captures(line) {
+        diagnostic = Diagnostic::new(DiagnosticSource::Msvc, &captures[4]);
+        diagnostic.code = Some(captures[3].to_string());
+        diagnostic.file = Some(captures[1].to_string());
+        severity = captures[2].to_string();
+    } else {
+        return None;
+    }
+    if severity == "warning" {
+        diagnostic.severity = Severity::Warning;
+    }
+    Some((diagnostic, 1))
+}
+
+/// What an error family means and how it is usually fixed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Explanation {
+    pub title: String,
+    pub explanation: String,
+    pub fixes: Vec<String>,
+    /// Whether the error matched a known family rather than the per-toolchain fallback
+    pub recognized: bool,
+}
+
+/// A known error family: where it comes from, a needle matched against the code, type or
+/// message, and its explanation and fixes
+struct ErrorFamily {
+    source: DiagnosticSource,
+    needle: &'static str,
+    title: &'static str,
+    explanation: &'static str,
+    fixes: &'static [&'static str],
+}
+
+const ERROR_FAMILIES: &[ErrorFamily] = &[
+    // rustc
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0382",
+        title: "Use of moved value",
+        explanation: "A value was used after ownership moved elsewhere, for example into a function call or closure.",
+        fixes: &["Borrow the value (`&value`) instead of moving it", "Clone it if both places need their own copy", "Restructure so the move happens after the last use"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0502",
+        title: "Conflicting borrows",
+        explanation: "A value is borrowed mutably while an immutable borrow is still alive (or vice versa).",
+        fixes: &["End the first borrow before taking the second, e.g. by copying the needed data out", "Split the data into separate fields borrowed independently"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0499",
+        title: "Multiple mutable borrows",
+        explanation: "The same value is mutably borrowed twice at the same time.",
+        fixes: &["Limit the scope of the first `&mut` borrow", "Use indices or `split_at_mut` to borrow disjoint parts"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0308",
+        title: "Mismatched types",
+        explanation: "An expression has a different type than the context expects.",
+        fixes: &["Check the expected and found types in the error", "Convert explicitly (`into()`, `as`, `to_string()`) or fix the signature", "Look for a missing or extra `;` turning a value into `()`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0425",
+        title: "Unresolved name",
+        explanation: "A variable or function name is not in scope.",
+        fixes: &["Check the spelling", "Import the item with `use`", "Make sure it is declared before this point and in an enclosing scope"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0433",
+        title: "Failed to resolve path",
+        explanation: "A module, crate or type in a path could not be found.",
+        fixes: &["Add the crate to `Cargo.toml`", "Add the missing `use` or `mod` declaration", "Check feature flags that gate the item"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0277",
+        title: "Trait bound not satisfied",
+        explanation: "A type is used where a trait it does not implement is required.",
+        fixes: &["Implement or derive the trait for the type", "Convert to a type that implements it", "Add the bound to the generic parameter"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0599",
+        title: "No such method",
+        explanation: "The method does not exist on this type, or the trait providing it is not in scope.",
+        fixes: &["Import the trait that defines the method", "Check the receiver type; you may need to dereference or unwrap first"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "E0106",
+        title: "Missing lifetime specifier",
+        explanation: "A returned or stored reference needs an explicit lifetime.",
+        fixes: &["Add a lifetime parameter tying the reference to an input", "Return an owned value instead of a reference"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "unwrap()` on a `None`",
+        title: "Unwrapped `None`",
+        explanation: "The program called `unwrap()` on an `Option` that was `None`.",
+        fixes: &["Handle the `None` case with `match`, `if let` or `?`", "Use `expect` with a message describing the invariant while debugging"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "unwrap()` on an `Err`",
+        title: "Unwrapped error",
+        explanation: "The program called `unwrap()` on a `Result` that held an error.",
+        fixes: &["Propagate the error with `?`", "Handle the error variant explicitly"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Rustc,
+        needle: "index out of bounds",
+        title: "Index out of bounds",
+        explanation: "A slice or vector was indexed past its length.",
+        fixes: &["Check the length before indexing", "Use `.get(index)` and handle `None`", "Look for off-by-one loop bounds"],
+    },
+    // Python
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "ModuleNotFoundError",
+        title: "Module not found",
+        explanation: "The imported package is not installed in the active interpreter, or the import path is wrong.",
+        fixes: &["Install it with `pip install <package>` in the same environment you run", "Check that the right virtualenv is activated", "Check the package vs. module name"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "ImportError",
+        title: "Import failed",
+        explanation: "The module was found but the name could not be imported, often due to a circular import or version mismatch.",
+        fixes: &["Check for circular imports between your modules", "Confirm the installed version exports the name"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "'NoneType' object",
+        title: "Operation on `None`",
+        explanation: "A variable expected to hold an object was `None`, usually because a function returned nothing.",
+        fixes: &["Find where the value is assigned and why it can be `None`", "Guard with `if value is not None`", "Make sure the function returns a value on every path"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "KeyError",
+        title: "Missing dictionary key",
+        explanation: "A dictionary was indexed with a key it does not contain.",
+        fixes: &["Use `.get(key, default)`", "Check `key in mapping` first", "Print the available keys to spot typos or casing differences"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "IndexError",
+        title: "Index out of range",
+        explanation: "A list or tuple was indexed past its end.",
+        fixes: &["Check the length before indexing", "Look for off-by-one loop bounds or an unexpectedly empty list"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "AttributeError",
+        title: "Missing attribute",
+        explanation: "The object does not have the attribute or method being accessed.",
+        fixes: &["Check the object's actual type", "Check the attribute name for typos", "Make sure it is set in `__init__` before use"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "NameError",
+        title: "Undefined name",
+        explanation: "A variable or function is used before it is defined or imported.",
+        fixes: &["Check the spelling", "Import or define the name before this line"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "TypeError",
+        title: "Wrong type",
+        explanation: "An operation or call received an argument of the wrong type or the wrong number of arguments.",
+        fixes: &["Compare the call with the function signature", "Convert the value explicitly (`int()`, `str()`)"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "IndentationError",
+        title: "Inconsistent indentation",
+        explanation: "The block structure is broken, often by mixing tabs and spaces.",
+        fixes: &["Re-indent the block with spaces only", "Check the line above for a missing body"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "SyntaxError",
+        title: "Syntax error",
+        explanation: "The file could not be parsed.",
+        fixes: &["Look at the marked position and the line before it for unbalanced brackets, quotes or a missing `:`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "ZeroDivisionError",
+        title: "Division by zero",
+        explanation: "A number was divided by zero.",
+        fixes: &["Guard the division against a zero denominator"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "FileNotFoundError",
+        title: "File not found",
+        explanation: "The path does not exist relative to the current working directory.",
+        fixes: &["Print `os.getcwd()` and the resolved path", "Build paths from `__file__` instead of relying on the working directory"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Python,
+        needle: "RecursionError",
+        title: "Recursion too deep",
+        explanation: "A recursive function never reached its base case.",
+        fixes: &["Check the base case and that each call moves toward it", "Rewrite deep recursion as a loop"],
+    },
+    // Node.js
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "Cannot read properties of undefined",
+        title: "Property access on `undefined`",
+        explanation: "An object expected at this point was `undefined`, often data that has not loaded yet or a misspelled property.",
+        fixes: &["Use optional chaining (`obj?.prop`)", "Check where the object is assigned and await any pending promise", "Log the object just before the failing line"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "Cannot read properties of null",
+        title: "Property access on `null`",
+        explanation: "An object was `null`, for example a DOM query that matched nothing.",
+        fixes: &["Check that the element or record exists before using it", "Use optional chaining (`obj?.prop`)"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "is not a function",
+        title: "Calling a non-function",
+        explanation: "The value being called is not a function, often due to a wrong import (default vs named) or a typo.",
+        fixes: &["Check default vs named imports", "Log `typeof` the value before the call"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "ReferenceError",
+        title: "Undefined variable",
+        explanation: "A variable is used that was never declared in scope.",
+        fixes: &["Declare or import the variable", "Check for typos and block scoping of `let`/`const`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "Cannot find module",
+        title: "Module not found",
+        explanation: "Node could not resolve the imported package or file.",
+        fixes: &["Run `npm install`", "Check the relative path and file extension", "Check `type: module` vs CommonJS settings"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Node,
+        needle: "ECONNREFUSED",
+        title: "Connection refused",
+        explanation: "Nothing is listening on the host and port the code tried to reach.",
+        fixes: &["Start the server or database first", "Check the host, port and environment variables"],
+    },
+    // TypeScript
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS2322",
+        title: "Type not assignable",
+        explanation: "A value's type does not match the declared type of its target.",
+        fixes: &["Fix the value or widen the declared type", "Narrow unions with a type guard before assigning"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS2339",
+        title: "Property does not exist",
+        explanation: "The property is not declared on the type TypeScript infers for the object.",
+        fixes: &["Add the property to the interface", "Narrow the type before accessing it", "Check for typos"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS2345",
+        title: "Argument type mismatch",
+        explanation: "An argument's type does not match the parameter type.",
+        fixes: &["Compare the argument with the function signature", "Convert or narrow the argument"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS2307",
+        title: "Cannot find module",
+        explanation: "The module or its type declarations could not be resolved.",
+        fixes: &["Install the package and its `@types/` package", "Check `paths` and `moduleResolution` in tsconfig"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS2532",
+        title: "Possibly undefined",
+        explanation: "The value may be `undefined` under strict null checks.",
+        fixes: &["Check for `undefined` before use", "Use optional chaining or a default value"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::TypeScript,
+        needle: "TS7006",
+        title: "Implicit `any`",
+        explanation: "A parameter has no type annotation and `noImplicitAny` is on.",
+        fixes: &["Annotate the parameter type"],
+    },
+    // JVM
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "NullPointerException",
+        title: "Null dereference",
+        explanation: "A method or field was accessed on a `null` reference.",
+        fixes: &["Find which reference is null on the top frame's line", "Initialize it or guard with a null check / `Optional`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "ClassNotFoundException",
+        title: "Class not on classpath",
+        explanation: "A class loaded by name could not be found at runtime.",
+        fixes: &["Add the missing dependency to the build", "Check the runtime classpath and shading"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "NoClassDefFoundError",
+        title: "Class missing at runtime",
+        explanation: "A class present at compile time is missing or failed to initialize at runtime.",
+        fixes: &["Check for dependency version conflicts", "Look for an earlier `ExceptionInInitializerError`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "IndexOutOfBoundsException",
+        title: "Index out of bounds",
+        explanation: "An array or list was indexed past its size.",
+        fixes: &["Check the size before indexing", "Look for `<=` loop bounds"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "ClassCastException",
+        title: "Invalid cast",
+        explanation: "An object was cast to a type it is not an instance of.",
+        fixes: &["Check the runtime type with `instanceof` before casting", "Fix generic types that hide the real type"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "ConcurrentModificationException",
+        title: "Collection modified during iteration",
+        explanation: "A collection was changed while being iterated.",
+        fixes: &["Remove through `Iterator.remove()` or `removeIf`", "Iterate over a copy", "Use a concurrent collection across threads"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "NumberFormatException",
+        title: "Invalid number format",
+        explanation: "A string could not be parsed as a number.",
+        fixes: &["Trim and validate the input before parsing", "Catch the exception and report the bad value"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "OutOfMemoryError",
+        title: "Out of memory",
+        explanation: "The heap is exhausted, from a leak or a workload larger than `-Xmx`.",
+        fixes: &["Take a heap dump and look for the dominant objects", "Raise `-Xmx` if the workload is legitimately large"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Jvm,
+        needle: "StackOverflowError",
+        title: "Stack overflow",
+        explanation: "Recursion went too deep, usually because a base case is never reached.",
+        fixes: &["Check the repeating frames for the recursive cycle", "Fix the base case or convert to iteration"],
+    },
+    // Go
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "nil pointer dereference",
+        title: "Nil pointer dereference",
+        explanation: "A nil pointer, map or interface was dereferenced.",
+        fixes: &["Check the error returned alongside the value before using it", "Initialize the pointer or struct field"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "index out of range",
+        title: "Index out of range",
+        explanation: "A slice or array was indexed past its length.",
+        fixes: &["Check `len()` before indexing", "Look for off-by-one loop bounds"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "concurrent map",
+        title: "Concurrent map access",
+        explanation: "A map was written from several goroutines without synchronization.",
+        fixes: &["Guard the map with a `sync.Mutex` or `sync.RWMutex`", "Use `sync.Map` for concurrent access", "Run with `-race` to find the writers"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "deadlock",
+        title: "Deadlock",
+        explanation: "Every goroutine is blocked, typically on a channel nobody sends to or receives from.",
+        fixes: &["Check unbuffered channel sends without a receiver", "Close channels the receiver ranges over", "Make sure `WaitGroup.Done` is called"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "declared and not used",
+        title: "Unused variable",
+        explanation: "Go rejects variables that are declared but never read.",
+        fixes: &["Use the variable or remove it", "Assign to `_` if the value is intentionally ignored"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Go,
+        needle: "undefined:",
+        title: "Undefined identifier",
+        explanation: "The identifier is not declared or not exported from its package.",
+        fixes: &["Check the spelling and capitalization (exported names start uppercase)", "Import the package that declares it"],
+    },
+    // MSVC
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "C2065",
+        title: "Undeclared identifier",
+        explanation: "The name is used without a declaration in scope.",
+        fixes: &["Include the header that declares it", "Check the spelling and namespace qualification"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "C1083",
+        title: "Cannot open include file",
+        explanation: "The compiler could not find a header on the include path.",
+        fixes: &["Add the header's directory to Additional Include Directories", "Install the SDK or package that provides it"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "C2664",
+        title: "Argument conversion failed",
+        explanation: "An argument cannot be converted to the parameter type.",
+        fixes: &["Compare the argument with the function signature", "Check `const`, pointer and character-width mismatches (e.g. `char*` vs `wchar_t*`)"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "C2143",
+        title: "Syntax error",
+        explanation: "The parser expected a token that is missing, often a `;` after a class or statement.",
+        fixes: &["Check the line before for a missing `;` or `}`"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "C4996",
+        title: "Deprecated or unsafe function",
+        explanation: "The function is deprecated, often a CRT function with a bounds-checked `_s` replacement.",
+        fixes: &["Switch to the suggested replacement", "Define `_CRT_SECURE_NO_WARNINGS` only if the call is known safe"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "LNK2019",
+        title: "Unresolved external symbol",
+        explanation: "A declared function is called but no linked object or library defines it.",
+        fixes: &["Add the library that defines it to the linker inputs", "Make sure the defining .cpp file is part of the project", "Check calling convention and `extern \"C\"` mismatches"],
+    },
+    ErrorFamily {
+        source: DiagnosticSource::Msvc,
+        needle: "LNK2001",
+        title: "Unresolved external symbol",
+        explanation: "A referenced symbol is not defined by any linked object or library.",
+        fixes: &["Add the missing library or source file", "Define static class members in a .cpp file"],
+    },
+];
+
+/// Path fragments of frames inside standard libraries and installed packages
+const LIBRARY_PATHS: &[&str] = &["site-packages", "node_modules", "node:internal", "/rustc/", "/usr/local/go/", "java.base"];
+
+/// Explain a diagnostic and suggest fixes, falling back to general advice for its toolchain
+pub fn explain(diagnostic: &Diagnostic) -> Explanation {
+    let haystacks = [
+        diagnostic.code.as_deref(),
+        diagnostic.root_cause.as_deref(),
+        diagnostic.kind.as_deref(),
+        Some(diagnostic.message.as_str()),
+    ];
+
+    let family = haystacks.iter().flatten().find_map(|haystack| {
+        ERROR_FAMILIES
+            .iter()
+            .find(|family| family.source == diagnostic.source && haystack.contains(family.needle))
+    });
+
+    if let Some(family) = family {
+        return Explanation {
+            title: family.title.to_string(),
+            explanation: family.explanation.to_string(),
+            fixes: family.fixes.iter().map(|fix| fix.to_string()).collect(),
+            recognized: true,
+        };
+    }
+
+    let lookup = match (diagnostic.source, &diagnostic.code) {
+        (DiagnosticSource::Rustc, Some(code)) => format!("Run `rustc --explain {}`", code),
+        (DiagnosticSource::TypeScript, Some(code)) => format!("Search the TypeScript docs for {}", code),
+        (DiagnosticSource::Msvc, Some(code)) => format!("Look up {} in the Microsoft C++ error reference", code),
+        _ => "Search for the exact error message".to_string(),
+    };
+    Explanation {
+        title: format!("{} {}", diagnostic.source.name(), diagnostic.severity.name()),
+        explanation: "Fix the first reported error first; later ones are often consequences of it.".to_string(),
+        fixes: vec![lookup, "Inspect the reported line and the code that feeds into it".to_string()],
+        recognized: false,
+    }
+}
+
+impl Diagnostic {
+    /// Technical recommendation explaining the error and how to fix it
+    pub fn recommendation(&self) -> Recommendation {
+        let explanation = explain(self);
+
+        let title = match &self.code {
+            Some(code) => format!("{} ({})", explanation.title, code),
+            None => explanation.title.clone(),
+        };
+        let mut description = format!("{} `{}`", explanation.explanation, self.kind_and_message());
+        if let Some(location) = self.location() {
+            description.push_str(&format!(" at {}", location));
+        }
+
+        let mut action_items = explanation.fixes.clone();
+        // Point at another frame in the user's own code, skipping libraries and the error site
+        if let Some(frame) = self.frames.iter().find(|frame| {
+            let in_user_code = frame.file.as_deref().is_some_and(|file| {
+                !LIBRARY_PATHS.iter().any(|library| file.contains(library))
+            });
+            in_user_code && (frame.file != self.file || frame.line != self.line)
+        }) {
+            action_items.push(format!(
+                "Also check the caller {} at {}:{}",
+                frame.function.as_deref().unwrap_or("frame"),
+                frame.file.as_deref().unwrap_or(""),
+                frame.line.map(|line| line.to_string()).unwrap_or_default(),
+            ));
+        }
+
+        Recommendation {
+            category: RecommendationCategory::Technical,
+            title,
+            description,
+            priority: match self.severity {
+                Severity::Error => Priority::High,
+                Severity::Warning => Priority::Medium,
+            },
+            action_items,
+            confidence: if explanation.recognized { 0.9 } else { 0.7 },
+        }
+    }
+
+    fn kind_and_message(&self) -> String {
+        match (&self.kind, self.message.is_empty()) {
+            (Some(kind), true) => kind.clone(),
+            (Some(kind), false) if kind != "panic" && kind != "compile" => format!("{}: {}", kind, self.message),
+            _ => self.message.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustc_and_panic() {
+        let text = "\
+error[E0382]: borrow of moved value: `names`
+  --> src/main.rs:7:20
+   |
+7  |     println!(\"{:?}\", names);
+   |                    ^^^^^ value borrowed here after move
+
+error: could not compile `demo` (bin \"demo\") due to 1 previous error
+thread 'main' panicked at src/lib.rs:12:5:
+called `Option::unwrap()` on a `None` value";
+
+        let diagnostics = parse(text);
+        assert_eq!(diagnostics.len(), 2);
+
+        let borrow = &diagnostics[0];
+        assert_eq!(borrow.code.as_deref(), Some("E0382"));
+        assert_eq!(borrow.location().as_deref(), Some("src/main.rs:7:20"));
+        assert_eq!(explain(borrow).title, "Use of moved value");
+
+        let panic = &diagnostics[1];
+        assert_eq!(panic.kind.as_deref(), Some("panic"));
+        assert_eq!(panic.line, Some(12));
+        assert_eq!(explain(panic).title, "Unwrapped `None`");
+
+        let metadata = std::collections::HashMap::from([(
+            DIAGNOSTICS_METADATA_KEY.to_string(),
+            serde_json::json!(diagnostics),
+        )]);
+        assert_eq!(from_metadata(&metadata).len(), 2);
+    }
+
+    #[test]
+    fn test_python_traceback() {
+        let text = "\
+Traceback (most recent call last):
+  File \"app.py\", line 20, in <module>
+    main()
+  File \"app.py\", line 14, in main
+    print(config[\"port\"])
+KeyError: 'port'";
+
+        let diagnostics = parse(text);
+        assert_eq!(diagnostics.len(), 1);
+        let error = &diagnostics[0];
+        assert_eq!(error.source, DiagnosticSource::Python);
+        assert_eq!(error.kind.as_deref(), Some("KeyError"));
+        assert_eq!(error.message, "'port'");
+        assert_eq!(error.frames.len(), 2);
+        assert_eq!((error.file.as_deref(), error.line), (Some("app.py"), Some(14)));
+
+        let recommendation = error.recommendation();
+        assert_eq!(recommendation.title, "Missing dictionary key");
+        assert!(recommendation.description.contains("app.py:14"));
+    }
+
+    #[test]
+    fn test_jvm_and_node_traces() {
+        let text = "\
+Exception in thread \"main\" java.lang.IllegalStateException: Failed to start
+\tat com.example.App.start(App.java:42)
+\tat com.example.App.main(App.java:10)
+Caused by: java.lang.NullPointerException: config is null
+\tat com.example.Config.load(Config.java:7)
+\t... 2 more
+TypeError: Cannot read properties of undefined (reading 'map')
+    at renderList (/app/src/list.js:8:17)
+    at Object.<anonymous> (/app/src/index.js:3:1)";
+
+        let diagnostics = parse(text);
+        assert_eq!(diagnostics.len(), 2);
+
+        let jvm = &diagnostics[0];
+        assert_eq!(jvm.source, DiagnosticSource::Jvm);
+        assert_eq!((jvm.file.as_deref(), jvm.line), (Some("App.java"), Some(42)));
+        assert_eq!(jvm.frames.len(), 2);
+        assert_eq!(explain(jvm).title, "Null dereference");
+
+        let node = &diagnostics[1];
+        assert_eq!(node.source, DiagnosticSource::Node);
+        assert_eq!(node.location().as_deref(), Some("/app/src/list.js:8:17"));
+        assert_eq!(explain(node).title, "Property access on `undefined`");
+    }
+
+    #[test]
+    fn test_go_typescript_and_msvc() {
+        let text = "\
+panic: runtime error: invalid memory address or nil pointer dereference
+[signal SIGSEGV: segmentation violation code=0x1 addr=0x0 pc=0x47b2c6]
+
+goroutine 1 [running]:
+main.handler(0x0)
+\t/home/dev/app/main.go:17 +0x26
+main.main()
+\t/home/dev/app/main.go:9 +0x1d
+src/api.ts(14,7): error TS2339: Property 'nme' does not exist on type 'User'.
+main.cpp(23,5): error C2065: 'count': undeclared identifier
+app.obj : error LNK2019: unresolved external symbol \"void __cdecl run(void)\"";
+
+        let diagnostics = parse(text);
+        let codes: Vec<_> = diagnostics.iter().map(|d| (d.source, d.code.clone())).collect();
+        assert_eq!(
+            codes,
+            vec![
+                (DiagnosticSource::Go, None),
+                (DiagnosticSource::TypeScript, Some("TS2339".to_string())),
+                (DiagnosticSource::Msvc, Some("C2065".to_string())),
+                (DiagnosticSource::Msvc, Some("LNK2019".to_string())),
+            ]
+        );
+
+        let go = &diagnostics[0];
+        assert_eq!((go.file.as_deref(), go.line), (Some("/home/dev/app/main.go"), Some(17)));
+        assert_eq!(go.frames[0].function.as_deref(), Some("main.handler"));
+        assert_eq!(explain(go).title, "Nil pointer dereference");
+        assert_eq!(explain(&diagnostics[3]).title, "Unresolved external symbol");
+    }
+}
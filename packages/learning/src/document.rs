@@ -0,0 +1,1035 @@
+use anyhow::{anyhow, Context, Result};
+use image::DynamicImage;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::analysis::{ContentType, ExtractedText};
+use crate::confidence::{ConfidenceModel, Signal};
+use crate::ocr::OCREngine;
+
+/// Metadata key holding the 1-based page (or slide/chapter) a section starts on
+pub const PAGE_METADATA_KEY: &str = "page";
+/// Metadata key holding the heading of the section
+pub const SECTION_METADATA_KEY: &str = "section";
+/// Metadata key holding the path of the source document
+pub const DOCUMENT_PATH_KEY: &str = "document_path";
+/// Metadata key holding the document format name
+pub const DOCUMENT_FORMAT_KEY: &str = "document_format";
+/// Metadata key set to "true" when the section text came from OCR
+pub const OCR_FALLBACK_KEY: &str = "ocr_fallback";
+
+/// Pages with fewer non-whitespace characters than this are treated as having no text layer
+const MIN_TEXT_LAYER_CHARS: usize = 16;
+
+/// Supported document formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentFormat {
+    Pdf,
+    Docx,
+    Pptx,
+    Html,
+    Epub,
+    Markdown,
+}
+
+impl DocumentFormat {
+    /// Infer the format from a file extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_name(path.extension()?.to_str()?)
+    }
+
+    /// Parse a format from its extension or name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            "pptx" => Some(Self::Pptx),
+            "html" | "htm" | "xhtml" => Some(Self::Html),
+            "epub" => Some(Self::Epub),
+            "md" | "markdown" => Some(Self::Markdown),
+            _ => None,
+        }
+    }
+
+    /// Short name used in metadata
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Docx => "docx",
+            Self::Pptx => "pptx",
+            Self::Html => "html",
+            Self::Epub => "epub",
+            Self::Markdown => "markdown",
+        }
+    }
+}
+
+/// A page, slide, chapter or heading-delimited section of a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSection {
+    /// 1-based page, slide or chapter number, when the format has one
+    pub page: Option<u32>,
+    pub title: Option<String>,
+    pub text: String,
+    /// True when the text was recovered by OCR rather than read from a text layer
+    pub ocr: bool,
+    /// Calibrated confidence of the OCR'd text; `None` for text read from a text layer
+    #[serde(default)]
+    pub ocr_confidence: Option<f32>,
+}
+
+/// Text and structure extracted from a document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestedDocument {
+    pub source: Option<String>,
+    pub format: DocumentFormat,
+    pub title: Option<String>,
+    pub sections: Vec<DocumentSection>,
+    /// Pages without a text layer that could not be recovered by OCR
+    pub pages_without_text: Vec<u32>,
+    /// Pages with embedded images in an encoding the OCR fallback cannot decode,
+    /// such as CCITT, JBIG2 or indexed color; their text may be incomplete
+    #[serde(default)]
+    pub pages_with_skipped_images: Vec<u32>,
+}
+
+impl IngestedDocument {
+    fn new(format: DocumentFormat, title: Option<String>, sections: Vec<DocumentSection>) -> Self {
+        Self {
+            source: None,
+            format,
+            title,
+            sections,
+            pages_without_text: Vec::new(),
+            pages_with_skipped_images: Vec::new(),
+        }
+    }
+
+    /// Convert non-empty sections into `ExtractedText` items that keep their page numbers
+    pub fn to_extracted_texts(&self) -> Vec<ExtractedText> {
+        let timestamp = chrono::Utc::now();
+
+        self.sections
+            .iter()
+            .filter(|section| !section.text.trim().is_empty())
+            .map(|section| {
+                let mut metadata = HashMap::new();
+                metadata.insert(DOCUMENT_FORMAT_KEY.to_string(), self.format.name().to_string());
+                if let Some(source) = &self.source {
+                    metadata.insert(DOCUMENT_PATH_KEY.to_string(), source.clone());
+                }
+                if let Some(page) = section.page {
+                    metadata.insert(PAGE_METADATA_KEY.to_string(), page.to_string());
+                }
+                if let Some(title) = &section.title {
+                    metadata.insert(SECTION_METADATA_KEY.to_string(), title.clone());
+                }
+                if section.ocr {
+                    metadata.insert(OCR_FALLBACK_KEY.to_string(), "true".to_string());
+                }
+
+                ExtractedText {
+                    source: ContentType::Document,
+                    text: section.text.clone(),
+                    confidence: section.ocr_confidence.unwrap_or(1.0),
+                    timestamp,
+                    metadata,
+                    time_range: None,
+                    language: None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Page number an `ExtractedText` produced by document ingestion starts on
+pub fn page_of(extracted: &ExtractedText) -> Option<u32> {
+    extracted.metadata.get(PAGE_METADATA_KEY)?.parse().ok()
+}
+
+/// Extracts structured text from documents, falling back to OCR for scanned pages
+pub struct DocumentIngestor {
+    ocr: Option<Arc<OCREngine>>,
+}
+
+impl DocumentIngestor {
+    /// Create an ingestor without OCR fallback
+    pub fn new() -> Self {
+        Self { ocr: None }
+    }
+
+    /// Create an ingestor that OCRs pages without a text layer
+    pub fn with_ocr(ocr: Arc<OCREngine>) -> Self {
+        Self { ocr: Some(ocr) }
+    }
+
+    /// Ingest a document from disk, inferring the format from its extension
+    pub async fn ingest(&self, path: &Path) -> Result<IngestedDocument> {
+        let format = DocumentFormat::from_path(path)
+            .ok_or_else(|| anyhow!("Unsupported document type: {}", path.display()))?;
+        let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let mut document = self.ingest_bytes(&bytes, format).await?;
+        document.source = Some(path.display().to_string());
+        Ok(document)
+    }
+
+    /// Ingest a document held in memory
+    pub async fn ingest_bytes(&self, bytes: &[u8], format: DocumentFormat) -> Result<IngestedDocument> {
+        let mut document = match format {
+            DocumentFormat::Pdf => self.ingest_pdf(bytes).await?,
+            DocumentFormat::Docx => ingest_docx(bytes)?,
+            DocumentFormat::Pptx => ingest_pptx(bytes)?,
+            DocumentFormat::Epub => ingest_epub(bytes)?,
+            DocumentFormat::Html => {
+                let (title, sections) = html_sections(&String::from_utf8_lossy(bytes), None);
+                IngestedDocument::new(format, title, sections)
+            }
+            DocumentFormat::Markdown => ingest_markdown(&String::from_utf8_lossy(bytes)),
+        };
+
+        if document.title.is_none() {
+            document.title = document.sections.iter().find_map(|section| section.title.clone());
+        }
+        Ok(document)
+    }
+
+    /// One section per page; pages without a text layer are rasterized from their
+    /// embedded images and OCR'd
+    async fn ingest_pdf(&self, bytes: &[u8]) -> Result<IngestedDocument> {
+        let pdf = lopdf::Document::load_mem(bytes).context("Failed to parse PDF")?;
+        let mut sections = Vec::new();
+        let mut pages_without_text = Vec::new();
+        let mut pages_with_skipped_images = Vec::new();
+
+        for (page_number, page_id) in pdf.get_pages() {
+            let text = pdf.extract_text(&[page_number]).unwrap_or_default();
+            let text = normalize_whitespace(&text);
+            if text.chars().filter(|c| !c.is_whitespace()).count() >= MIN_TEXT_LAYER_CHARS {
+                sections.push(DocumentSection {
+                    page: Some(page_number),
+                    title: None,
+                    text,
+                    ocr: false,
+                    ocr_confidence: None,
+                });
+                continue;
+            }
+
+            let mut words = Vec::new();
+            let ocr_text = match &self.ocr {
+                Some(ocr) => {
+                    let mut texts = Vec::new();
+                    let page_images = pdf_page_images(&pdf, page_id);
+                    if page_images.skipped > 0 {
+                        pages_with_skipped_images.push(page_number);
+                    }
+                    for image in page_images.images {
+                        let recognized = ocr.recognize_image(&image).await?;
+                        words.extend(recognized.words);
+                        if !recognized.text.trim().is_empty() {
+                            texts.push(recognized.text);
+                        }
+                    }
+                    texts.join("\n")
+                }
+                None => String::new(),
+            };
+
+            if ocr_text.trim().is_empty() {
+                pages_without_text.push(page_number);
+                sections.push(DocumentSection {
+                    page: Some(page_number),
+                    title: None,
+                    text,
+                    ocr: false,
+                    ocr_confidence: None,
+                });
+            } else {
+                sections.push(DocumentSection {
+                    page: Some(page_number),
+                    title: None,
+                    text: ocr_text,
+                    ocr: true,
+                    ocr_confidence: Some(
+                        Signal::ocr_words("ocr", &words)
+                            .map_or(0.0, |signal| ConfidenceModel::new().score(vec![signal]).score),
+                    ),
+                });
+            }
+        }
+
+        let mut document = IngestedDocument::new(DocumentFormat::Pdf, pdf_title(&pdf), sections);
+        document.pages_without_text = pages_without_text;
+        document.pages_with_skipped_images = pages_with_skipped_images;
+        Ok(document)
+    }
+}
+
+impl Default for DocumentIngestor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Title from the PDF document information dictionary
+fn pdf_title(pdf: &lopdf::Document) -> Option<String> {
+    let info = pdf.trailer.get(b"Info").ok()?;
+    let (_, info) = pdf.dereference(info).ok()?;
+    let title = info.as_dict().ok()?.get(b"Title").ok()?.as_string().ok()?;
+    Some(title.trim().to_string()).filter(|title| !title.is_empty())
+}
+
+/// Image XObjects drawn on a page
+struct PageImages {
+    images: Vec<DynamicImage>,
+    /// Images found on the page that [`decode_pdf_image`] could not decode
+    skipped: usize,
+}
+
+/// Decode the image XObjects drawn on a page, which for scanned documents is the page itself.
+///
+/// Pages are not rasterized, so text drawn as vector paths and images in unsupported
+/// encodings are not OCR'd; the latter are counted in [`PageImages::skipped`].
+fn pdf_page_images(pdf: &lopdf::Document, page_id: lopdf::ObjectId) -> PageImages {
+    let (resources, inherited) = pdf.get_page_resources(page_id);
+    let dictionaries = resources
+        .into_iter()
+        .chain(inherited.into_iter().filter_map(|id| pdf.get_dictionary(id).ok()));
+
+    let mut images = Vec::new();
+    let mut skipped = 0;
+    for resources in dictionaries {
+        let Ok(xobjects) = resources.get(b"XObject") else {
+            continue;
+        };
+        let Ok((_, xobjects)) = pdf.dereference(xobjects) else {
+            continue;
+        };
+        let Ok(xobjects) = xobjects.as_dict() else {
+            continue;
+        };
+
+        for (_, xobject) in xobjects.iter() {
+            let Ok((_, object)) = pdf.dereference(xobject) else {
+                continue;
+            };
+            let Ok(stream) = object.as_stream() else {
+                continue;
+            };
+            let is_image = stream
+                .dict
+                .get(b"Subtype")
+                .and_then(|subtype| subtype.as_name_str())
+                .map(|subtype| subtype == "Image")
+                .unwrap_or(false);
+            if is_image {
+                match decode_pdf_image(stream) {
+                    Some(image) => images.push(image),
+                    None => skipped += 1,
+                }
+            }
+        }
+    }
+
+    PageImages { images, skipped }
+}
+
+/// Decode JPEG images directly and 8-bit gray/RGB rasters from their decompressed samples.
+///
+/// CCITT fax, JBIG2 and JPEG 2000 streams, indexed and CMYK color spaces and other bit
+/// depths are not supported and return `None`.
+fn decode_pdf_image(stream: &lopdf::Stream) -> Option<DynamicImage> {
+    let filters = stream.filters().unwrap_or_default();
+    if filters.iter().any(|filter| filter == "DCTDecode") {
+        return image::load_from_memory(&stream.content).ok();
+    }
+
+    let width = stream.dict.get(b"Width").and_then(|w| w.as_i64()).ok()? as u32;
+    let height = stream.dict.get(b"Height").and_then(|h| h.as_i64()).ok()? as u32;
+    let bits = stream.dict.get(b"BitsPerComponent").and_then(|b| b.as_i64()).unwrap_or(8);
+    if bits != 8 {
+        return None;
+    }
+
+    let samples = if filters.is_empty() {
+        stream.content.clone()
+    } else {
+        stream.decompressed_content().ok()?
+    };
+    let color_space = stream
+        .dict
+        .get(b"ColorSpace")
+        .and_then(|cs| cs.as_name_str())
+        .unwrap_or("DeviceRGB");
+
+    match color_space {
+        "DeviceGray" => image::GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+        "DeviceRGB" => image::RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+        _ => None,
+    }
+}
+
+/// Read a single entry from a zip archive as UTF-8
+fn read_zip_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
+    let mut entry = archive
+        .by_name(name)
+        .with_context(|| format!("Missing {} in archive", name))?;
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Paragraph from an Office Open XML part
+struct XmlParagraph {
+    text: String,
+    style: Option<String>,
+    /// Number of explicit `w:br w:type="page"` breaks inside this paragraph
+    page_breaks: u32,
+    /// Number of `w:lastRenderedPageBreak` markers Word left inside this paragraph
+    rendered_page_breaks: u32,
+}
+
+/// Collect paragraphs from WordprocessingML (`w`) or DrawingML (`a`) markup
+fn office_paragraphs(xml: &str, prefix: &str) -> Result<Vec<XmlParagraph>> {
+    let paragraph = format!("{}:p", prefix);
+    let text_run = format!("{}:t", prefix);
+    let line_break = format!("{}:br", prefix);
+    let tab = format!("{}:tab", prefix);
+
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = Vec::new();
+    let mut current: Option<XmlParagraph> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                let name = e.name();
+                let name = name.as_ref();
+                if name == paragraph.as_bytes() {
+                    current = Some(XmlParagraph {
+                        text: String::new(),
+                        style: None,
+                        page_breaks: 0,
+                        rendered_page_breaks: 0,
+                    });
+                } else if name == text_run.as_bytes() {
+                    in_text = true;
+                }
+            }
+            Event::Empty(e) => {
+                let name = e.name();
+                let name = name.as_ref();
+                let Some(paragraph) = current.as_mut() else {
+                    continue;
+                };
+                if name == line_break.as_bytes() {
+                    if attribute(&e, b"w:type").as_deref() == Some("page") {
+                        paragraph.page_breaks += 1;
+                    } else {
+                        paragraph.text.push('\n');
+                    }
+                } else if name == b"w:lastRenderedPageBreak" {
+                    paragraph.rendered_page_breaks += 1;
+                } else if name == tab.as_bytes() {
+                    paragraph.text.push('\t');
+                } else if name == b"w:pStyle" {
+                    paragraph.style = attribute(&e, b"w:val");
+                }
+            }
+            Event::Text(t) if in_text => {
+                if let Some(paragraph) = current.as_mut() {
+                    paragraph.text.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(e) => {
+                let name = e.name();
+                let name = name.as_ref();
+                if name == text_run.as_bytes() {
+                    in_text = false;
+                } else if name == paragraph.as_bytes() {
+                    if let Some(paragraph) = current.take() {
+                        paragraphs.push(paragraph);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(paragraphs)
+}
+
+fn attribute(element: &BytesStart, key: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == key)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.to_string())
+}
+
+/// Split a Word document into heading-delimited sections, tracking page breaks.
+///
+/// Word marks the start of every rendered page, including those after an explicit
+/// break, with `w:lastRenderedPageBreak`. Those markers are used when present;
+/// documents that were never laid out by Word fall back to explicit page breaks.
+fn ingest_docx(bytes: &[u8]) -> Result<IngestedDocument> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open DOCX")?;
+    let xml = read_zip_entry(&mut archive, "word/document.xml")?;
+    let paragraphs = office_paragraphs(&xml, "w")?;
+    let rendered = paragraphs.iter().any(|paragraph| paragraph.rendered_page_breaks > 0);
+
+    let mut builder = SectionBuilder::new(Some(1));
+    let mut page = 1;
+    let mut title = None;
+
+    for paragraph in paragraphs {
+        page += if rendered { paragraph.rendered_page_breaks } else { paragraph.page_breaks };
+        let style = paragraph.style.as_deref().unwrap_or_default();
+        let text = paragraph.text.trim();
+
+        if style == "Title" && title.is_none() && !text.is_empty() {
+            title = Some(text.to_string());
+        } else if style.starts_with("Heading") && !text.is_empty() {
+            builder.start(Some(page), Some(text.to_string()));
+        } else {
+            builder.push_line(Some(page), &paragraph.text);
+        }
+    }
+
+    Ok(IngestedDocument::new(DocumentFormat::Docx, title, builder.finish()))
+}
+
+/// One section per slide, titled by the slide's first paragraph
+fn ingest_pptx(bytes: &[u8]) -> Result<IngestedDocument> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open PPTX")?;
+
+    let mut slides: Vec<(u32, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix("ppt/slides/slide")?.strip_suffix(".xml")?;
+            Some((number.parse().ok()?, name.to_string()))
+        })
+        .collect();
+    slides.sort();
+
+    let mut sections = Vec::new();
+    for (number, name) in slides {
+        let xml = read_zip_entry(&mut archive, &name)?;
+        let lines: Vec<String> = office_paragraphs(&xml, "a")?
+            .into_iter()
+            .map(|paragraph| paragraph.text.trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect();
+
+        sections.push(DocumentSection {
+            page: Some(number),
+            title: lines.first().cloned(),
+            text: lines.join("\n"),
+            ocr: false,
+            ocr_confidence: None,
+        });
+    }
+
+    Ok(IngestedDocument::new(DocumentFormat::Pptx, None, sections))
+}
+
+/// Follow the EPUB spine; each chapter's number is used as its page
+fn ingest_epub(bytes: &[u8]) -> Result<IngestedDocument> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open EPUB")?;
+    let container = read_zip_entry(&mut archive, "META-INF/container.xml")?;
+    let package_path = xml_elements(&container, b"rootfile")?
+        .into_iter()
+        .find_map(|attrs| attrs.get("full-path").cloned())
+        .ok_or_else(|| anyhow!("EPUB container has no rootfile"))?;
+    let package = read_zip_entry(&mut archive, &package_path)?;
+    let base = package_path.rsplit_once('/').map(|(dir, _)| format!("{}/", dir)).unwrap_or_default();
+
+    let manifest: HashMap<String, String> = xml_elements(&package, b"item")?
+        .into_iter()
+        .filter_map(|attrs| Some((attrs.get("id")?.clone(), attrs.get("href")?.clone())))
+        .collect();
+    let title = xml_text(&package, b"title")?;
+
+    let mut sections = Vec::new();
+    let spine = xml_elements(&package, b"itemref")?;
+    for (chapter, idref) in spine.iter().filter_map(|attrs| attrs.get("idref")).enumerate() {
+        let Some(href) = manifest.get(idref) else {
+            continue;
+        };
+        let path = format!("{}{}", base, href.split('#').next().unwrap_or(href));
+        let Ok(html) = read_zip_entry(&mut archive, &path) else {
+            continue;
+        };
+        let (_, chapter_sections) = html_sections(&html, Some(chapter as u32 + 1));
+        sections.extend(chapter_sections);
+    }
+
+    Ok(IngestedDocument::new(DocumentFormat::Epub, title, sections))
+}
+
+/// Attributes of every element with the given local name
+fn xml_elements(xml: &str, local_name: &[u8]) -> Result<Vec<HashMap<String, String>>> {
+    let mut reader = Reader::from_str(xml);
+    let mut elements = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == local_name => {
+                let attrs = e
+                    .attributes()
+                    .flatten()
+                    .filter_map(|attr| {
+                        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+                        Some((key, attr.unescape_value().ok()?.to_string()))
+                    })
+                    .collect();
+                elements.push(attrs);
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Text content of the first element with the given local name
+fn xml_text(xml: &str, local_name: &[u8]) -> Result<Option<String>> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) if e.local_name().as_ref() == local_name => inside = true,
+            Event::Text(t) if inside => return Ok(Some(t.unescape()?.trim().to_string())),
+            Event::End(e) if e.local_name().as_ref() == local_name => inside = false,
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Split Markdown on ATX headings, ignoring `#` lines inside fenced code blocks
+fn ingest_markdown(markdown: &str) -> IngestedDocument {
+    let mut builder = SectionBuilder::new(None);
+    let mut in_fence = false;
+
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+
+        let heading = (!in_fence)
+            .then(|| trimmed.strip_prefix('#'))
+            .flatten()
+            .map(|rest| rest.trim_start_matches('#'))
+            .filter(|rest| rest.starts_with(' '));
+        match heading {
+            Some(heading) => builder.start(None, Some(heading.trim().trim_end_matches('#').trim().to_string())),
+            None => builder.push_line(None, line),
+        }
+    }
+
+    IngestedDocument::new(DocumentFormat::Markdown, None, builder.finish())
+}
+
+/// Block-level tags that end a line of text
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "p", "div", "br", "li", "tr", "td", "th", "section", "article", "blockquote", "pre", "table", "ul", "ol",
+];
+
+/// Strip HTML into heading-delimited sections, skipping scripts and styles
+fn html_sections(html: &str, page: Option<u32>) -> (Option<String>, Vec<DocumentSection>) {
+    let mut builder = SectionBuilder::new(page);
+    let mut title = None;
+    let mut line = String::new();
+    let mut heading: Option<String> = None;
+    let mut in_title = false;
+    let mut skip_until: Option<&str> = None;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            push_html_text(rest, &mut line, &mut heading, &mut title, in_title, skip_until.is_some());
+            break;
+        };
+        push_html_text(&rest[..tag_start], &mut line, &mut heading, &mut title, in_title, skip_until.is_some());
+
+        let Some(tag_end) = rest[tag_start..].find('>') else {
+            break;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_end];
+        rest = &rest[tag_start + tag_end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if let Some(skipped) = skip_until {
+            if closing && name == skipped {
+                skip_until = None;
+            }
+            continue;
+        }
+
+        match name.as_str() {
+            "script" if !closing => skip_until = Some("script"),
+            "style" if !closing => skip_until = Some("style"),
+            "title" => in_title = !closing,
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if closing {
+                    if let Some(text) = heading.take() {
+                        let text = normalize_whitespace(&text);
+                        if !text.is_empty() {
+                            builder.start(page, Some(text));
+                        }
+                    }
+                } else {
+                    builder.push_line(page, &std::mem::take(&mut line));
+                    heading = Some(String::new());
+                }
+            }
+            name if HTML_BLOCK_TAGS.contains(&name) => {
+                builder.push_line(page, &std::mem::take(&mut line));
+            }
+            _ => {}
+        }
+    }
+    builder.push_line(page, &line);
+
+    (title, builder.finish())
+}
+
+fn push_html_text(
+    raw: &str,
+    line: &mut String,
+    heading: &mut Option<String>,
+    title: &mut Option<String>,
+    in_title: bool,
+    skipping: bool,
+) {
+    if skipping || raw.is_empty() {
+        return;
+    }
+
+    let text = decode_entities(raw);
+    if in_title {
+        let text = normalize_whitespace(&text);
+        if !text.is_empty() {
+            *title = Some(text);
+        }
+    } else if let Some(heading) = heading {
+        heading.push_str(&text);
+    } else {
+        line.push_str(&text);
+    }
+}
+
+/// Decode the named and numeric entities that commonly appear in lecture notes
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        let entity_end = candidate.find(';').filter(|&end| end <= 10);
+
+        let replacement = entity_end.and_then(|end| {
+            let entity = &candidate[1..end];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            decoded.map(|c| (c, end))
+        });
+
+        match replacement {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &candidate[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &candidate[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Collapse runs of spaces and blank lines left behind by text extraction
+fn normalize_whitespace(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Accumulates lines into sections, starting a new one at each heading
+struct SectionBuilder {
+    sections: Vec<DocumentSection>,
+    current: DocumentSection,
+}
+
+impl SectionBuilder {
+    fn new(page: Option<u32>) -> Self {
+        Self {
+            sections: Vec::new(),
+            current: DocumentSection {
+                page,
+                title: None,
+                text: String::new(),
+                ocr: false,
+                ocr_confidence: None,
+            },
+        }
+    }
+
+    fn start(&mut self, page: Option<u32>, title: Option<String>) {
+        let previous = std::mem::replace(
+            &mut self.current,
+            DocumentSection {
+                page,
+                title,
+                text: String::new(),
+                ocr: false,
+                ocr_confidence: None,
+            },
+        );
+        if previous.title.is_some() || !previous.text.trim().is_empty() {
+            self.sections.push(previous);
+        }
+    }
+
+    fn push_line(&mut self, page: Option<u32>, line: &str) {
+        let line = normalize_whitespace(line);
+        if line.is_empty() {
+            return;
+        }
+        // An untitled section opened on an earlier page takes the page of its first text
+        if self.current.text.is_empty() && self.current.title.is_none() {
+            self.current.page = page.or(self.current.page);
+        }
+        if !self.current.text.is_empty() {
+            self.current.text.push('\n');
+        }
+        self.current.text.push_str(&line);
+    }
+
+    fn finish(mut self) -> Vec<DocumentSection> {
+        self.start(None, None);
+        self.sections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn zip_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buffer);
+            for (name, contents) in entries {
+                writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+                writer.write_all(contents.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        buffer.into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_markdown_and_html_sections() {
+        let ingestor = DocumentIngestor::new();
+
+        let markdown = "# Sorting\nQuicksort partitions.\n\n```\n# not a heading\n```\n## Merge sort\nDivide and conquer.";
+        let doc = ingestor.ingest_bytes(markdown.as_bytes(), DocumentFormat::Markdown).await.unwrap();
+        let titles: Vec<_> = doc.sections.iter().map(|s| s.title.as_deref().unwrap()).collect();
+        assert_eq!(titles, vec!["Sorting", "Merge sort"]);
+        assert!(doc.sections[0].text.contains("# not a heading"));
+
+        let html = "<html><head><title>Week 3</title><style>p{}</style></head><body>\
+            <h1>Graphs</h1><p>BFS &amp; DFS</p><script>var x = 1;</script><h2>Trees</h2><p>Depth&nbsp;first</p></body></html>";
+        let doc = ingestor.ingest_bytes(html.as_bytes(), DocumentFormat::Html).await.unwrap();
+        assert_eq!(doc.title.as_deref(), Some("Week 3"));
+        assert_eq!(doc.sections.len(), 2);
+        assert_eq!(doc.sections[0].text, "BFS & DFS");
+        assert_eq!(doc.sections[1].title.as_deref(), Some("Trees"));
+    }
+
+    #[tokio::test]
+    async fn test_docx_keeps_page_numbers() {
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>Lecture Notes</w:t></w:r></w:p>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Introduction</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Hash tables give </w:t></w:r><w:r><w:t>O(1) lookups.</w:t></w:r></w:p>
+<w:p><w:r><w:br w:type="page"/></w:r></w:p>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Collisions</w:t></w:r></w:p>
+<w:p><w:r><w:t>Chaining and open addressing.</w:t></w:r></w:p>
+</w:body></w:document>"#;
+        let bytes = zip_archive(&[("word/document.xml", document)]);
+
+        let doc = DocumentIngestor::new().ingest_bytes(&bytes, DocumentFormat::Docx).await.unwrap();
+        assert_eq!(doc.title.as_deref(), Some("Lecture Notes"));
+        assert_eq!(doc.sections.len(), 2);
+        assert_eq!(doc.sections[0].text, "Hash tables give O(1) lookups.");
+        assert_eq!(doc.sections[1].page, Some(2));
+
+        let extracted = doc.to_extracted_texts();
+        assert_eq!(extracted[1].source, ContentType::Document);
+        assert_eq!(page_of(&extracted[1]), Some(2));
+        assert_eq!(extracted[1].metadata.get(SECTION_METADATA_KEY).map(String::as_str), Some("Collisions"));
+
+        // Word also marks the page that follows an explicit break; it is counted once
+        let rendered = document.replace(
+            "<w:r><w:t>Collisions</w:t>",
+            "<w:r><w:lastRenderedPageBreak/><w:t>Collisions</w:t>",
+        );
+        let bytes = zip_archive(&[("word/document.xml", &rendered)]);
+        let doc = DocumentIngestor::new().ingest_bytes(&bytes, DocumentFormat::Docx).await.unwrap();
+        assert_eq!(doc.sections[1].page, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_pptx_and_epub_structure() {
+        let slide = |title: &str, body: &str| {
+            format!(
+                r#"<p:sld xmlns:p="p" xmlns:a="a"><p:cSld><p:spTree><p:sp><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>"#,
+                title, body
+            )
+        };
+        let (first, second) = (slide("Agenda", "Recap"), slide("Heaps", "Binary heaps"));
+        // Slide 10 sorts after slide 2 numerically, not lexically
+        let bytes = zip_archive(&[("ppt/slides/slide10.xml", &second), ("ppt/slides/slide2.xml", &first)]);
+        let doc = DocumentIngestor::new().ingest_bytes(&bytes, DocumentFormat::Pptx).await.unwrap();
+        let slides: Vec<_> = doc.sections.iter().map(|s| (s.page, s.title.as_deref())).collect();
+        assert_eq!(slides, vec![(Some(2), Some("Agenda")), (Some(10), Some("Heaps"))]);
+
+        let container = r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#;
+        let package = r#"<package xmlns:dc="dc"><metadata><dc:title>Algorithms</dc:title></metadata>
+            <manifest><item id="c1" href="ch1.xhtml"/><item id="c2" href="ch2.xhtml"/></manifest>
+            <spine><itemref idref="c2"/><itemref idref="c1"/></spine></package>"#;
+        let bytes = zip_archive(&[
+            ("META-INF/container.xml", container),
+            ("OEBPS/content.opf", package),
+            ("OEBPS/ch1.xhtml", "<html><body><h1>Searching</h1><p>Binary search</p></body></html>"),
+            ("OEBPS/ch2.xhtml", "<html><body><h1>Preface</h1><p>Welcome</p></body></html>"),
+        ]);
+        let doc = DocumentIngestor::new().ingest_bytes(&bytes, DocumentFormat::Epub).await.unwrap();
+        assert_eq!(doc.title.as_deref(), Some("Algorithms"));
+        let chapters: Vec<_> = doc.sections.iter().map(|s| (s.page, s.title.as_deref())).collect();
+        assert_eq!(chapters, vec![(Some(1), Some("Preface")), (Some(2), Some("Searching"))]);
+    }
+
+    #[tokio::test]
+    async fn test_pdf_pages_without_text_layer() {
+        use lopdf::content::{Content, Operation};
+        use lopdf::{dictionary, Object, Stream};
+
+        let mut pdf = lopdf::Document::with_version("1.5");
+        let pages_id = pdf.new_object_id();
+        let font_id = pdf.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        // A CCITT-encoded scan, which cannot be decoded for OCR
+        let scan_id = pdf.add_object(Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => 8,
+                "Height" => 8,
+                "BitsPerComponent" => 1,
+                "Filter" => "CCITTFaxDecode",
+            },
+            vec![0; 8],
+        ));
+        let resources_id = pdf.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+            "XObject" => dictionary! { "Im1" => scan_id },
+        });
+
+        let mut kids = Vec::new();
+        for text in ["Dynamic programming solves overlapping subproblems", ""] {
+            let mut operations = Vec::new();
+            if !text.is_empty() {
+                operations = vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                    Operation::new("Td", vec![72.into(), 700.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(text)]),
+                    Operation::new("ET", vec![]),
+                ];
+            }
+            let content = Content { operations };
+            let content_id = pdf.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            kids.push(Object::from(pdf.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })));
+        }
+        pdf.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => 2,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = pdf.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        pdf.trailer.set("Root", catalog_id);
+
+        let mut bytes = Vec::new();
+        pdf.save_to(&mut bytes).unwrap();
+
+        let doc = DocumentIngestor::new().ingest_bytes(&bytes, DocumentFormat::Pdf).await.unwrap();
+        assert_eq!(doc.sections.len(), 2);
+        assert!(doc.sections[0].text.contains("overlapping subproblems"));
+        assert_eq!(doc.pages_without_text, vec![2]);
+        // Empty pages produce no ExtractedText
+        assert_eq!(doc.to_extracted_texts().len(), 1);
+
+        let pdf = lopdf::Document::load_mem(&bytes).unwrap();
+        let page_id = pdf.get_pages()[&2];
+        let page_images = pdf_page_images(&pdf, page_id);
+        assert!(page_images.images.is_empty());
+        assert_eq!(page_images.skipped, 1);
+    }
+}
@@ -0,0 +1,710 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::analysis::{ContentType, ConversationTurn, SessionContext, SessionSummary};
+
+/// Version of the JSON export schema. Bump on breaking changes only.
+pub const EXPORT_SCHEMA_VERSION: &str = "1.0";
+
+/// Metadata key on `ExtractedText` that holds the source screenshot path
+pub const SCREENSHOT_PATH_KEY: &str = "image_path";
+
+/// Metadata key on `ExtractedText` that holds the source audio path
+pub const AUDIO_PATH_KEY: &str = "audio_path";
+
+/// Default Markdown layout for study notes
+pub const DEFAULT_MARKDOWN_TEMPLATE: &str = r#"# Study Notes — {{session_id}}
+
+_{{created_at}} → {{last_updated}} · {{total_turns}} turns_
+
+{{?topics}}
+## Topics
+
+{{#topics}}- {{name}} ({{count}})
+{{/topics}}
+
+{{/topics}}
+{{?key_insights}}
+## Key Insights
+
+{{#key_insights}}- {{.}}
+{{/key_insights}}
+
+{{/key_insights}}
+{{#turns}}
+## Turn {{index}}: {{content_type}} ({{timestamp}})
+
+{{#screenshot}}![Screenshot]({{screenshot}})
+
+{{/screenshot}}
+{{#summary}}**Summary:** {{summary}}
+
+{{/summary}}
+{{?insights}}
+### Insights
+
+{{#insights}}- {{.}}
+{{/insights}}
+
+{{/insights}}
+{{#transcript}}
+### Transcript
+
+{{transcript_quote}}
+
+{{/transcript}}
+{{^transcript}}
+### Content
+
+{{fence}}
+{{content}}
+{{fence}}
+
+{{/transcript}}
+{{/turns}}
+"#;
+
+/// Default HTML layout for study notes
+pub const DEFAULT_HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Study Notes — {{session_id}}</title>
+<style>
+body { font-family: system-ui, sans-serif; max-width: 50rem; margin: 2rem auto; line-height: 1.5; }
+pre { background: #f4f4f4; padding: 1rem; white-space: pre-wrap; }
+blockquote { border-left: 4px solid #ccc; margin: 0; padding-left: 1rem; color: #444; }
+img { max-width: 100%; }
+</style>
+</head>
+<body>
+<h1>Study Notes — {{session_id}}</h1>
+<p><em>{{created_at}} → {{last_updated}} · {{total_turns}} turns</em></p>
+{{?topics}}
+<h2>Topics</h2>
+<ul>
+{{#topics}}<li>{{name}} ({{count}})</li>
+{{/topics}}
+</ul>
+{{/topics}}
+{{?key_insights}}
+<h2>Key Insights</h2>
+<ul>
+{{#key_insights}}<li>{{.}}</li>
+{{/key_insights}}
+</ul>
+{{/key_insights}}
+{{#turns}}
+<section>
+<h2>Turn {{index}}: {{content_type}} ({{timestamp}})</h2>
+{{#screenshot}}<img src="{{screenshot}}" alt="Screenshot">
+{{/screenshot}}
+{{#summary}}<p><strong>Summary:</strong> {{summary}}</p>
+{{/summary}}
+{{?insights}}
+<h3>Insights</h3>
+<ul>
+{{#insights}}<li>{{.}}</li>
+{{/insights}}
+</ul>
+{{/insights}}
+{{#transcript}}
+<h3>Transcript</h3>
+<blockquote>{{transcript}}</blockquote>
+{{/transcript}}
+{{^transcript}}
+<h3>Content</h3>
+<pre>{{content}}</pre>
+{{/transcript}}
+</section>
+{{/turns}}
+</body>
+</html>
+"#;
+
+/// Output format of a session export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    /// Parse a format name such as "md", "markdown", "html" or "json"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// File extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+
+    /// MIME type for the format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+/// Templates used to lay out Markdown and HTML exports.
+///
+/// Templates use a small mustache-style syntax: `{{name}}` inserts a value
+/// (HTML-escaped in the HTML template), `{{{name}}}` inserts it raw,
+/// `{{#name}}...{{/name}}` repeats for each list item or renders once if the
+/// value is present, `{{?name}}...{{/name}}` renders once if the value is
+/// present and non-empty (without iterating), and `{{^name}}...{{/name}}`
+/// renders if it is missing or empty. `{{.}}` refers to the current list item.
+///
+/// In the Markdown template each turn also has `transcript_quote`, the transcript
+/// with every line quoted, and `fence`, a code fence longer than any run of
+/// backticks in the turn's content.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTemplates {
+    pub markdown: String,
+    pub html: String,
+}
+
+impl Default for ExportTemplates {
+    fn default() -> Self {
+        Self {
+            markdown: DEFAULT_MARKDOWN_TEMPLATE.to_string(),
+            html: DEFAULT_HTML_TEMPLATE.to_string(),
+        }
+    }
+}
+
+impl ExportTemplates {
+    /// Load templates from a directory, falling back to the defaults for any
+    /// of `session.md.tmpl` / `session.html.tmpl` that are missing
+    pub fn load_from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut templates = Self::default();
+
+        let markdown_path = dir.join("session.md.tmpl");
+        if markdown_path.exists() {
+            templates.markdown = std::fs::read_to_string(markdown_path)?;
+        }
+
+        let html_path = dir.join("session.html.tmpl");
+        if html_path.exists() {
+            templates.html = std::fs::read_to_string(html_path)?;
+        }
+
+        Ok(templates)
+    }
+}
+
+/// Stable, versioned JSON representation of an exported session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub schema_version: String,
+    pub session_id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_updated: chrono::DateTime<chrono::Utc>,
+    pub exported_at: chrono::DateTime<chrono::Utc>,
+    pub total_turns: usize,
+    pub dominant_sentiment: String,
+    pub topics: Vec<ExportedTopic>,
+    pub key_insights: Vec<String>,
+    pub turns: Vec<ExportedTurn>,
+}
+
+/// A topic and how many turns mentioned it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTopic {
+    pub name: String,
+    pub count: usize,
+}
+
+/// A single conversation turn in an export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTurn {
+    pub index: usize,
+    pub turn_id: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub content_type: String,
+    pub content: String,
+    pub summary: Option<String>,
+    pub topics: Vec<String>,
+    pub insights: Vec<String>,
+    pub transcript: Option<String>,
+    pub screenshot: Option<String>,
+    pub audio: Option<String>,
+    pub user_feedback: Option<String>,
+}
+
+/// Renders sessions to study notes
+pub struct SessionExporter {
+    templates: ExportTemplates,
+}
+
+impl SessionExporter {
+    /// Create an exporter with the default templates
+    pub fn new() -> Self {
+        Self {
+            templates: ExportTemplates::default(),
+        }
+    }
+
+    /// Create an exporter with custom templates
+    pub fn with_templates(templates: ExportTemplates) -> Self {
+        Self { templates }
+    }
+
+    /// Build the structured export of a session
+    pub fn build(&self, context: &SessionContext, summary: &SessionSummary) -> SessionExport {
+        let turns = context
+            .conversation_history
+            .iter()
+            .enumerate()
+            .map(|(i, turn)| Self::export_turn(i + 1, turn))
+            .collect();
+
+        SessionExport {
+            schema_version: EXPORT_SCHEMA_VERSION.to_string(),
+            session_id: context.session_id.clone(),
+            created_at: context.created_at,
+            last_updated: context.last_updated,
+            exported_at: chrono::Utc::now(),
+            total_turns: summary.total_turns,
+            dominant_sentiment: summary.sentiment_summary.dominant_sentiment.clone(),
+            topics: summary
+                .topic_summary
+                .top_topics
+                .iter()
+                .map(|(name, count)| ExportedTopic {
+                    name: name.clone(),
+                    count: *count,
+                })
+                .collect(),
+            key_insights: summary.key_insights.clone(),
+            turns,
+        }
+    }
+
+    /// Render a session in the requested format
+    pub fn export(
+        &self,
+        context: &SessionContext,
+        summary: &SessionSummary,
+        format: ExportFormat,
+    ) -> Result<String> {
+        let export = self.build(context, summary);
+        self.render(&export, format)
+    }
+
+    /// Render an already built export in the requested format
+    pub fn render(&self, export: &SessionExport, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(export)?),
+            ExportFormat::Markdown => {
+                let data = markdown_data(export)?;
+                render_template(&self.templates.markdown, &data, |s| s.to_string())
+            }
+            ExportFormat::Html => {
+                let data = serde_json::to_value(export)?;
+                render_template(&self.templates.html, &data, escape_html)
+            }
+        }
+    }
+
+    fn export_turn(index: usize, turn: &ConversationTurn) -> ExportedTurn {
+        let analysis = turn.analysis.as_ref();
+        let extracted = analysis.and_then(|a| a.extracted_text.as_ref());
+
+        let transcript = match turn.content_type {
+            ContentType::Audio => Some(turn.content.clone()),
+            _ => None,
+        };
+
+        ExportedTurn {
+            index,
+            turn_id: turn.turn_id.clone(),
+            timestamp: turn.timestamp,
+            content_type: format!("{:?}", turn.content_type),
+            content: turn.content.clone(),
+            summary: analysis.map(|a| a.summary.clone()).filter(|s| !s.trim().is_empty()),
+            topics: analysis.map(|a| a.topics.clone()).unwrap_or_default(),
+            insights: analysis.map(|a| a.insights.clone()).unwrap_or_default(),
+            transcript,
+            screenshot: extracted.and_then(|e| e.metadata.get(SCREENSHOT_PATH_KEY).cloned()),
+            audio: extracted.and_then(|e| e.metadata.get(AUDIO_PATH_KEY).cloned()),
+            user_feedback: turn.user_feedback.clone(),
+        }
+    }
+}
+
+impl Default for SessionExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Template data for Markdown, with the quoted transcript and code fence of each turn
+fn markdown_data(export: &SessionExport) -> Result<Value> {
+    let mut data = serde_json::to_value(export)?;
+    if let Some(turns) = data.get_mut("turns").and_then(Value::as_array_mut) {
+        for (turn, exported) in turns.iter_mut().zip(&export.turns) {
+            let Some(fields) = turn.as_object_mut() else {
+                continue;
+            };
+            if let Some(transcript) = &exported.transcript {
+                fields.insert("transcript_quote".to_string(), Value::String(quote_lines(transcript)));
+            }
+            fields.insert("fence".to_string(), Value::String(code_fence(&exported.content)));
+        }
+    }
+    Ok(data)
+}
+
+/// Quote every line of a text as a Markdown blockquote
+fn quote_lines(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A backtick fence that cannot be closed by anything inside `content`
+fn code_fence(content: &str) -> String {
+    let longest_run = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+/// Escape text for inclusion in HTML
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render a mustache-style template against JSON data
+pub fn render_template(template: &str, data: &Value, escape: fn(&str) -> String) -> Result<String> {
+    let mut output = String::new();
+    render_into(template, &mut vec![data], escape, &mut output)?;
+    Ok(output)
+}
+
+fn render_into<'a>(
+    template: &str,
+    stack: &mut Vec<&'a Value>,
+    escape: fn(&str) -> String,
+    output: &mut String,
+) -> Result<()> {
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start..];
+
+        // Raw (unescaped) variable
+        if let Some(inner) = after.strip_prefix("{{{") {
+            let end = inner
+                .find("}}}")
+                .ok_or_else(|| anyhow::anyhow!("Unclosed '{{{{{{' tag in template"))?;
+            if let Some(value) = lookup(stack, inner[..end].trim()) {
+                output.push_str(&value_to_string(value));
+            }
+            rest = &inner[end + 3..];
+            continue;
+        }
+
+        let inner = &after[2..];
+        let end = inner
+            .find("}}")
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{{{' tag in template"))?;
+        let tag = inner[..end].trim();
+        rest = &inner[end + 2..];
+
+        if let Some(name) = tag
+            .strip_prefix('#')
+            .or_else(|| tag.strip_prefix('^'))
+            .or_else(|| tag.strip_prefix('?'))
+        {
+            let name = name.trim();
+            let (body, remaining) = split_section(rest, name)?;
+            // Standalone section tags shouldn't leave blank lines behind
+            let body = body.strip_prefix('\n').unwrap_or(body);
+            rest = remaining.strip_prefix('\n').unwrap_or(remaining);
+
+            let value = lookup(stack, name);
+            if tag.starts_with('^') {
+                if !is_truthy(value) {
+                    render_into(body, stack, escape, output)?;
+                }
+                continue;
+            }
+            if tag.starts_with('?') {
+                if is_truthy(value) {
+                    render_into(body, stack, escape, output)?;
+                }
+                continue;
+            }
+
+            match value {
+                Some(Value::Array(items)) => {
+                    for item in items {
+                        stack.push(item);
+                        render_into(body, stack, escape, output)?;
+                        stack.pop();
+                    }
+                }
+                Some(value) if is_truthy(Some(value)) => {
+                    stack.push(value);
+                    render_into(body, stack, escape, output)?;
+                    stack.pop();
+                }
+                _ => {}
+            }
+        } else if tag.starts_with('/') {
+            return Err(anyhow::anyhow!("Unexpected closing tag '{{{{{}}}}}' in template", tag));
+        } else if let Some(value) = lookup(stack, tag) {
+            output.push_str(&escape(&value_to_string(value)));
+        }
+    }
+
+    output.push_str(rest);
+    Ok(())
+}
+
+/// Split off the body of a section up to its matching close tag
+fn split_section<'t>(template: &'t str, name: &str) -> Result<(&'t str, &'t str)> {
+    let open_tags = ["#", "^", "?"].map(|kind| format!("{{{{{}{}}}}}", kind, name));
+    let close_tag = format!("{{{{/{}}}}}", name);
+
+    let mut depth = 0usize;
+    let mut pos = 0usize;
+
+    while pos < template.len() {
+        let remaining = &template[pos..];
+        if remaining.starts_with(&close_tag) {
+            if depth == 0 {
+                return Ok((&template[..pos], &template[pos + close_tag.len()..]));
+            }
+            depth -= 1;
+            pos += close_tag.len();
+        } else if let Some(open) = open_tags.iter().find(|t| remaining.starts_with(t.as_str())) {
+            depth += 1;
+            pos += open.len();
+        } else {
+            pos += remaining.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+        }
+    }
+
+    Err(anyhow::anyhow!("Section '{}' is never closed in template", name))
+}
+
+fn lookup<'a>(stack: &[&'a Value], path: &str) -> Option<&'a Value> {
+    if path == "." {
+        return stack.last().copied();
+    }
+
+    let mut parts = path.split('.');
+    let first = parts.next()?;
+
+    // Resolve the first segment against the innermost context that has it
+    let mut value = stack.iter().rev().find_map(|ctx| ctx.get(first))?;
+    for part in parts {
+        value = value.get(part)?;
+    }
+
+    Some(value)
+}
+
+fn is_truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(value_to_string).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{AnalysisResult, ExtractedText, SentimentSummary, TopicSummary};
+    use std::collections::HashMap;
+
+    fn sample_session() -> (SessionContext, SessionSummary) {
+        let now = chrono::Utc::now();
+        let mut metadata = HashMap::new();
+        metadata.insert(SCREENSHOT_PATH_KEY.to_string(), "shots/slide-4.png".to_string());
+
+        let analysis = AnalysisResult {
+            analysis_id: "analysis_1".to_string(),
+            timestamp: now,
+            content_type: ContentType::Screenshot,
+            original_content: "fn main() { println!(\"<hi>\"); }".to_string(),
+            extracted_text: Some(ExtractedText {
+                source: ContentType::Screenshot,
+                text: "fn main()".to_string(),
+                confidence: 0.9,
+                timestamp: now,
+                metadata,
+                time_range: None,
+                language: None,
+            }),
+            sentiment: None,
+            topics: vec!["technology".to_string()],
+            summary: "A tiny Rust program.".to_string(),
+            insights: vec!["Uses a macro".to_string()],
+            confidence: 0.9,
+            context_used: false,
+            recommendations: vec![],
+            metadata: HashMap::new(),
+        };
+
+        let context = SessionContext {
+            session_id: "session-1".to_string(),
+            conversation_history: vec![ConversationTurn {
+                turn_id: "analysis_1".to_string(),
+                timestamp: now,
+                content_type: ContentType::Screenshot,
+                content: analysis.original_content.clone(),
+                analysis: Some(analysis),
+                user_feedback: None,
+            }],
+            extracted_texts: vec![],
+            analysis_summary: None,
+            created_at: now,
+            last_updated: now,
+        };
+
+        let summary = SessionSummary {
+            session_id: "session-1".to_string(),
+            total_turns: 1,
+            total_texts: 1,
+            sentiment_summary: SentimentSummary {
+                positive_count: 0,
+                negative_count: 0,
+                neutral_count: 1,
+                average_score: 0.5,
+                dominant_sentiment: "NEUTRAL".to_string(),
+            },
+            topic_summary: TopicSummary {
+                top_topics: vec![("technology".to_string(), 1)],
+                total_unique_topics: 1,
+            },
+            created_at: now,
+            last_updated: now,
+            key_insights: vec!["Uses a macro".to_string()],
+        };
+
+        (context, summary)
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let (context, summary) = sample_session();
+        let markdown = SessionExporter::new()
+            .export(&context, &summary, ExportFormat::Markdown)
+            .unwrap();
+
+        assert!(markdown.starts_with("# Study Notes — session-1"));
+        assert!(markdown.contains("## Turn 1: Screenshot"));
+        assert!(markdown.contains("![Screenshot](shots/slide-4.png)"));
+        assert!(markdown.contains("- technology (1)"));
+        assert!(markdown.contains("- Uses a macro"));
+    }
+
+    #[test]
+    fn test_markdown_quotes_transcripts_and_fences_content() {
+        let (mut context, summary) = sample_session();
+        context.conversation_history[0].content = "Run:\n```sh\ncargo test\n```".to_string();
+        let mut audio = context.conversation_history[0].clone();
+        audio.content_type = ContentType::Audio;
+        audio.content = "First line.\nSecond line.".to_string();
+        context.conversation_history.push(audio);
+
+        let markdown = SessionExporter::new()
+            .export(&context, &summary, ExportFormat::Markdown)
+            .unwrap();
+
+        assert!(markdown.contains("> First line.\n> Second line."));
+        assert!(markdown.contains("````\nRun:\n```sh\ncargo test\n```\n````"));
+    }
+
+    #[test]
+    fn test_html_export_escapes_content() {
+        let (context, summary) = sample_session();
+        let html = SessionExporter::new()
+            .export(&context, &summary, ExportFormat::Html)
+            .unwrap();
+
+        assert!(html.contains("&lt;hi&gt;"));
+        assert!(!html.contains("<hi>"));
+    }
+
+    #[test]
+    fn test_json_export_is_stable() {
+        let (context, summary) = sample_session();
+        let json = SessionExporter::new()
+            .export(&context, &summary, ExportFormat::Json)
+            .unwrap();
+
+        let parsed: SessionExport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.schema_version, EXPORT_SCHEMA_VERSION);
+        assert_eq!(parsed.turns.len(), 1);
+        assert_eq!(parsed.turns[0].screenshot.as_deref(), Some("shots/slide-4.png"));
+    }
+
+    #[test]
+    fn test_custom_template_sections() {
+        let data = serde_json::json!({
+            "title": "Notes",
+            "items": ["a", "b"],
+            "empty": [],
+        });
+
+        let rendered = render_template(
+            "{{title}}:{{?items}} [{{#items}} {{.}}{{/items}} ]{{/items}}{{^empty}} none{{/empty}}",
+            &data,
+            |s| s.to_string(),
+        )
+        .unwrap();
+        assert_eq!(rendered, "Notes: [ a b ] none");
+
+        assert!(render_template("{{#items}}", &data, |s| s.to_string()).is_err());
+    }
+}
@@ -0,0 +1,377 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::analysis::{ContentType, ExtractedText};
+
+/// Metadata key under which alignments are stored on combined analysis results
+pub const ALIGNMENTS_METADATA_KEY: &str = "alignments";
+
+/// Average speaking rate used to estimate how long an untimed transcript lasted
+const WORDS_PER_SECOND: f64 = 2.5;
+
+/// How long an untimed snapshot is assumed to stay on screen if nothing follows it
+const DEFAULT_SNAPSHOT_SECS: i64 = 30;
+
+/// Words that carry no topical meaning when relating slides to speech
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "that", "this", "with", "are", "was", "were", "you", "your", "have",
+    "has", "had", "but", "not", "from", "they", "them", "then", "than", "there", "their", "what",
+    "when", "where", "which", "will", "would", "can", "could", "should", "about", "into", "just",
+    "like", "also", "more", "some", "very", "here", "its", "our", "out", "all", "any", "how",
+    "who", "why", "let", "see", "now", "one", "two", "get", "got", "use", "used", "using",
+];
+
+/// A span on the session timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    /// Create a range, swapping the bounds if they are reversed
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        if end < start {
+            Self { start: end, end: start }
+        } else {
+            Self { start, end }
+        }
+    }
+
+    /// Length of the range
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// Length of the overlap with another range (zero if they are disjoint)
+    pub fn overlap(&self, other: &TimeRange) -> Duration {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        if end > start {
+            end - start
+        } else {
+            Duration::zero()
+        }
+    }
+
+    /// Whether a point in time falls inside the range
+    pub fn contains(&self, instant: DateTime<Utc>) -> bool {
+        instant >= self.start && instant <= self.end
+    }
+}
+
+/// A visual snapshot and the speech that happened while it was on screen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alignment {
+    /// 1-based position of the snapshot among all snapshots in the input
+    pub slide_number: usize,
+    /// Index of the snapshot in the input contents
+    pub visual_index: usize,
+    /// Indices of the overlapping transcript segments in the input contents
+    pub spoken_indices: Vec<usize>,
+    pub time_range: TimeRange,
+    /// Offset of the snapshot from the start of the timeline, in seconds
+    pub offset_secs: f64,
+    /// Seconds of speech that overlapped the snapshot
+    pub overlap_secs: f64,
+    /// Key terms that appear both on screen and in the speech
+    pub shared_terms: Vec<String>,
+    /// Key terms of the speech, used when nothing is shared
+    pub spoken_terms: Vec<String>,
+    pub description: String,
+}
+
+/// Result of placing visual and spoken content on a shared timeline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FusedTimeline {
+    pub timeline_start: Option<DateTime<Utc>>,
+    pub alignments: Vec<Alignment>,
+    /// Transcript segments that did not overlap any snapshot
+    pub unaligned_spoken: Vec<usize>,
+    /// Input indices in timeline order
+    pub order: Vec<usize>,
+}
+
+impl FusedTimeline {
+    /// Restore the alignments stored on a combined analysis result
+    pub fn alignments_from_metadata(metadata: &HashMap<String, serde_json::Value>) -> Vec<Alignment> {
+        metadata
+            .get(ALIGNMENTS_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+            .unwrap_or_default()
+    }
+
+    /// Render the contents in timeline order, marking slides and speech,
+    /// so that the analysis sees what was shown next to what was said
+    pub fn render_text(&self, contents: &[ExtractedText]) -> String {
+        let slide_numbers: HashMap<usize, usize> = self
+            .alignments
+            .iter()
+            .map(|a| (a.visual_index, a.slide_number))
+            .collect();
+
+        let mut sections = Vec::new();
+        for &index in &self.order {
+            let content = &contents[index];
+            let range = effective_range(contents, index);
+            let offset = self
+                .timeline_start
+                .map(|start| format_offset((range.start - start).num_milliseconds() as f64 / 1000.0))
+                .unwrap_or_default();
+
+            let label = match (slide_numbers.get(&index), &content.source) {
+                (Some(slide), _) => format!("[Slide {} @ {}]", slide, offset),
+                (None, ContentType::Audio) => format!("[Spoken @ {}]", offset),
+                (None, source) => format!("[{:?} @ {}]", source, offset),
+            };
+            sections.push(format!("{}\n{}", label, content.text.trim()));
+        }
+
+        sections.join("\n\n")
+    }
+
+    /// Human-readable descriptions of each alignment
+    pub fn descriptions(&self) -> Vec<String> {
+        self.alignments.iter().map(|a| a.description.clone()).collect()
+    }
+}
+
+/// Align visual snapshots with transcript segments on a shared timeline.
+///
+/// Snapshots (screenshots and documents) without an explicit time range are
+/// assumed to stay on screen until the next snapshot; transcript segments
+/// without one are assumed to start at their timestamp and last as long as
+/// their words take to say.
+pub fn align(contents: &[ExtractedText]) -> FusedTimeline {
+    if contents.is_empty() {
+        return FusedTimeline::default();
+    }
+
+    let ranges: Vec<TimeRange> = (0..contents.len()).map(|i| effective_range(contents, i)).collect();
+
+    let mut order: Vec<usize> = (0..contents.len()).collect();
+    order.sort_by_key(|&i| (ranges[i].start, i));
+
+    let timeline_start = order.first().map(|&i| ranges[i].start);
+
+    let visual: Vec<usize> = order.iter().copied().filter(|&i| is_visual(&contents[i])).collect();
+    let spoken: Vec<usize> = order.iter().copied().filter(|&i| is_spoken(&contents[i])).collect();
+
+    let mut aligned_spoken = HashSet::new();
+    let mut alignments = Vec::new();
+
+    for (slide, &visual_index) in visual.iter().enumerate() {
+        let visual_range = ranges[visual_index];
+        let overlapping: Vec<usize> = spoken
+            .iter()
+            .copied()
+            .filter(|&s| ranges[s].overlap(&visual_range) > Duration::zero())
+            .collect();
+
+        if overlapping.is_empty() {
+            continue;
+        }
+
+        let overlap_secs = overlapping
+            .iter()
+            .map(|&s| ranges[s].overlap(&visual_range).num_milliseconds() as f64 / 1000.0)
+            .sum::<f64>();
+
+        let visual_terms = key_terms(&contents[visual_index].text);
+        let spoken_text = overlapping
+            .iter()
+            .map(|&s| contents[s].text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let spoken_ranked = ranked_terms(&spoken_text);
+
+        let shared_terms: Vec<String> = spoken_ranked
+            .iter()
+            .filter(|term| visual_terms.contains(*term))
+            .take(5)
+            .cloned()
+            .collect();
+        let spoken_terms: Vec<String> = spoken_ranked.into_iter().take(5).collect();
+
+        let offset_secs = timeline_start
+            .map(|start| (visual_range.start - start).num_milliseconds() as f64 / 1000.0)
+            .unwrap_or(0.0);
+
+        let slide_number = slide + 1;
+        let description = describe(slide_number, offset_secs, &shared_terms, &spoken_terms);
+
+        aligned_spoken.extend(overlapping.iter().copied());
+        alignments.push(Alignment {
+            slide_number,
+            visual_index,
+            spoken_indices: overlapping,
+            time_range: visual_range,
+            offset_secs,
+            overlap_secs,
+            shared_terms,
+            spoken_terms,
+            description,
+        });
+    }
+
+    let unaligned_spoken = spoken.into_iter().filter(|s| !aligned_spoken.contains(s)).collect();
+
+    FusedTimeline {
+        timeline_start,
+        alignments,
+        unaligned_spoken,
+        order,
+    }
+}
+
+fn describe(slide_number: usize, offset_secs: f64, shared: &[String], spoken: &[String]) -> String {
+    let at = format_offset(offset_secs);
+    if !shared.is_empty() {
+        format!("Slide {} ({}) explained while discussing {}", slide_number, at, shared.join(", "))
+    } else if !spoken.is_empty() {
+        format!(
+            "Slide {} ({}) was on screen while discussing {}, which it does not mention",
+            slide_number,
+            at,
+            spoken.join(", ")
+        )
+    } else {
+        format!("Slide {} ({}) was on screen during speech", slide_number, at)
+    }
+}
+
+fn is_visual(content: &ExtractedText) -> bool {
+    matches!(content.source, ContentType::Screenshot | ContentType::Document)
+}
+
+fn is_spoken(content: &ExtractedText) -> bool {
+    matches!(content.source, ContentType::Audio)
+}
+
+/// Time range of a content item, estimating one if none was recorded
+fn effective_range(contents: &[ExtractedText], index: usize) -> TimeRange {
+    let content = &contents[index];
+    if let Some(range) = content.time_range {
+        return range;
+    }
+
+    let start = content.timestamp;
+    if is_visual(content) {
+        // Stay on screen until the next snapshot appears
+        let next = contents
+            .iter()
+            .filter(|other| is_visual(other))
+            .map(|other| other.time_range.map(|r| r.start).unwrap_or(other.timestamp))
+            .filter(|t| *t > start)
+            .min();
+        TimeRange::new(start, next.unwrap_or(start + Duration::seconds(DEFAULT_SNAPSHOT_SECS)))
+    } else {
+        let words = content.text.split_whitespace().count() as f64;
+        let millis = ((words / WORDS_PER_SECOND) * 1000.0).max(1000.0) as i64;
+        TimeRange::new(start, start + Duration::milliseconds(millis))
+    }
+}
+
+fn normalize_term(word: &str) -> Option<String> {
+    let term: String = word
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    if term.chars().count() < 3 || STOP_WORDS.contains(&term.as_str()) || term.chars().all(|c| c.is_numeric()) {
+        None
+    } else {
+        Some(term)
+    }
+}
+
+fn key_terms(text: &str) -> HashSet<String> {
+    text.split_whitespace().filter_map(normalize_term).collect()
+}
+
+/// Terms of a text ordered by frequency, then by first appearance
+fn ranked_terms(text: &str) -> Vec<String> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for (position, term) in text.split_whitespace().filter_map(normalize_term).enumerate() {
+        let entry = counts.entry(term).or_insert((0, position));
+        entry.0 += 1;
+    }
+
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    ranked.into_iter().map(|(term, _)| term).collect()
+}
+
+fn format_offset(secs: f64) -> String {
+    let total = secs.max(0.0).round() as u64;
+    format!("{:02}:{:02}:{:02}", total / 3600, (total % 3600) / 60, total % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(source: ContentType, body: &str, start_secs: i64, end_secs: Option<i64>) -> ExtractedText {
+        let base = DateTime::parse_from_rfc3339("2025-01-01T09:00:00Z").unwrap().with_timezone(&Utc);
+        ExtractedText {
+            source,
+            text: body.to_string(),
+            confidence: 0.9,
+            timestamp: base + Duration::seconds(start_secs),
+            metadata: HashMap::new(),
+            time_range: end_secs.map(|end| {
+                TimeRange::new(base + Duration::seconds(start_secs), base + Duration::seconds(end))
+            }),
+            language: None,
+        }
+    }
+
+    #[test]
+    fn test_time_range_overlap() {
+        let base = Utc::now();
+        let a = TimeRange::new(base, base + Duration::seconds(10));
+        let b = TimeRange::new(base + Duration::seconds(5), base + Duration::seconds(20));
+        let c = TimeRange::new(base + Duration::seconds(30), base + Duration::seconds(40));
+        assert_eq!(a.overlap(&b), Duration::seconds(5));
+        assert_eq!(a.overlap(&c), Duration::zero());
+    }
+
+    #[test]
+    fn test_align_slides_with_speech() {
+        let contents = vec![
+            text(ContentType::Audio, "Recursion means a function calls itself", 5, Some(20)),
+            text(ContentType::Screenshot, "Slide: Recursion and base cases", 0, None),
+            text(ContentType::Document, "Slide: Iteration with loops", 60, None),
+            text(ContentType::Audio, "Loops repeat a block while a condition holds", 65, Some(80)),
+            text(ContentType::Audio, "Any questions before the break?", 200, Some(205)),
+        ];
+
+        let timeline = align(&contents);
+        assert_eq!(timeline.alignments.len(), 2);
+
+        let first = &timeline.alignments[0];
+        assert_eq!(first.visual_index, 1);
+        assert_eq!(first.spoken_indices, vec![0]);
+        assert!(first.shared_terms.contains(&"recursion".to_string()));
+        assert!(first.description.starts_with("Slide 1 (00:00:00) explained while discussing recursion"));
+
+        let second = &timeline.alignments[1];
+        assert_eq!(second.slide_number, 2);
+        assert_eq!(second.spoken_indices, vec![3]);
+
+        // The last snapshot stays up for the default duration only
+        assert_eq!(timeline.unaligned_spoken, vec![4]);
+        assert_eq!(timeline.order, vec![1, 0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_render_text_marks_timeline() {
+        let contents = vec![
+            text(ContentType::Screenshot, "Binary search", 0, Some(30)),
+            text(ContentType::Audio, "Binary search halves the range", 10, Some(20)),
+        ];
+        let timeline = align(&contents);
+        let rendered = timeline.render_text(&contents);
+        assert!(rendered.starts_with("[Slide 1 @ 00:00:00]\nBinary search"));
+        assert!(rendered.contains("[Spoken @ 00:00:10]"));
+    }
+}
@@ -0,0 +1,613 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key holding the language an analysis was routed by
+pub const LANGUAGE_METADATA_KEY: &str = "language";
+
+/// Fewer letters than this are too little evidence to name a language
+const MIN_LETTERS: usize = 12;
+
+/// Function-word points a Latin text needs before it is attributed to a language
+const MIN_EVIDENCE: f32 = 2.0;
+
+/// Share of a text's words a language needs before it gets its own Tesseract pack
+const MIX_MIN_SHARE: f32 = 0.2;
+
+/// Most Tesseract packs combined into one recognition pass
+const MAX_COMBINED_PACKS: usize = 3;
+
+/// Writing system of a character, the first cut when telling languages apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+impl Script {
+    /// Script of a letter, or `None` for digits, punctuation and unlisted scripts
+    pub fn of(c: char) -> Option<Self> {
+        if !c.is_alphabetic() {
+            return None;
+        }
+        match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Some(Self::Latin),
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Some(Self::Greek),
+            0x400..=0x52F => Some(Self::Cyrillic),
+            0x590..=0x5FF => Some(Self::Hebrew),
+            0x600..=0x6FF | 0x750..=0x77F => Some(Self::Arabic),
+            0x900..=0x97F => Some(Self::Devanagari),
+            0xE00..=0xE7F => Some(Self::Thai),
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Self::Hangul),
+            0x3040..=0x30FF => Some(Self::Kana),
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => Some(Self::Han),
+            _ => None,
+        }
+    }
+}
+
+/// What the detector and the analysis heuristics know about one language
+#[derive(Debug)]
+pub struct LanguageProfile {
+    /// ISO 639-1 code
+    pub code: &'static str,
+    pub name: &'static str,
+    /// Tesseract traineddata pack for the language
+    pub tesseract: &'static str,
+    pub script: Script,
+    /// Frequent function words, the main evidence between languages sharing a script
+    pub stopwords: &'static [&'static str],
+    /// Letters that are rare outside this language
+    pub letters: &'static str,
+    /// Sentiment words for the heuristic analyzer; empty when analysis is unsupported
+    pub positive: &'static [&'static str],
+    pub negative: &'static [&'static str],
+    /// Topic labels paired with the keyword that signals them in this language
+    pub topics: &'static [(&'static str, &'static str)],
+}
+
+impl LanguageProfile {
+    /// Whether analysis has word lists for this language instead of English guesses
+    pub fn is_supported(&self) -> bool {
+        !self.positive.is_empty() && !self.negative.is_empty()
+    }
+}
+
+/// English, the language analysis falls back to when detection has too little to go on
+pub static ENGLISH: LanguageProfile = LanguageProfile {
+    code: "en",
+    name: "English",
+    tesseract: "eng",
+    script: Script::Latin,
+    stopwords: &[
+        "the", "and", "is", "are", "was", "of", "to", "in", "that", "it", "for", "with", "this", "you", "not",
+        "be", "have", "on", "at", "from", "we", "they", "will", "would", "there",
+    ],
+    letters: "",
+    positive: &["good", "great", "excellent", "amazing", "wonderful", "happy", "love", "like"],
+    negative: &["bad", "terrible", "awful", "hate", "dislike", "sad", "angry", "frustrated"],
+    topics: &[
+        ("technology", "technology"),
+        ("business", "business"),
+        ("education", "education"),
+        ("health", "health"),
+        ("entertainment", "entertainment"),
+    ],
+};
+
+/// Every language the detector can name, with English first
+pub static LANGUAGES: [&LanguageProfile; 20] = [
+    &ENGLISH,
+    &LanguageProfile {
+        code: "de",
+        name: "German",
+        tesseract: "deu",
+        script: Script::Latin,
+        stopwords: &[
+            "der", "die", "das", "und", "ist", "nicht", "ich", "sie", "mit", "den", "ein", "eine", "zu", "auf",
+            "für", "auch", "sich", "wir", "dem", "wird", "oder", "aber", "werden",
+        ],
+        letters: "äöüß",
+        positive: &["gut", "toll", "super", "ausgezeichnet", "wunderbar", "glücklich", "liebe", "gefällt"],
+        negative: &["schlecht", "schrecklich", "furchtbar", "hasse", "traurig", "wütend", "frustriert", "ärgerlich"],
+        topics: &[
+            ("technology", "technik"),
+            ("business", "wirtschaft"),
+            ("education", "bildung"),
+            ("health", "gesundheit"),
+            ("entertainment", "unterhaltung"),
+        ],
+    },
+    &LanguageProfile {
+        code: "fr",
+        name: "French",
+        tesseract: "fra",
+        script: Script::Latin,
+        stopwords: &[
+            "le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "qui", "pas", "pour", "dans", "sur",
+            "avec", "nous", "vous", "ce", "il", "elle", "sont", "mais",
+        ],
+        letters: "çœèêàùâî",
+        positive: &["bon", "bien", "excellent", "génial", "merveilleux", "heureux", "aime", "super"],
+        negative: &["mauvais", "terrible", "affreux", "déteste", "triste", "colère", "frustré", "nul"],
+        topics: &[
+            ("technology", "technologie"),
+            ("business", "entreprise"),
+            ("education", "éducation"),
+            ("health", "santé"),
+            ("entertainment", "divertissement"),
+        ],
+    },
+    &LanguageProfile {
+        code: "es",
+        name: "Spanish",
+        tesseract: "spa",
+        script: Script::Latin,
+        stopwords: &[
+            "el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "para", "con", "no",
+            "del", "se", "lo", "como", "pero", "está", "muy",
+        ],
+        letters: "ñ",
+        positive: &["bueno", "genial", "excelente", "increíble", "maravilloso", "feliz", "encanta", "gusta"],
+        negative: &["malo", "terrible", "horrible", "odio", "triste", "enojado", "frustrado", "pésimo"],
+        topics: &[
+            ("technology", "tecnología"),
+            ("business", "negocio"),
+            ("education", "educación"),
+            ("health", "salud"),
+            ("entertainment", "entretenimiento"),
+        ],
+    },
+    &LanguageProfile {
+        code: "it",
+        name: "Italian",
+        tesseract: "ita",
+        script: Script::Latin,
+        stopwords: &[
+            "il", "lo", "la", "gli", "le", "e", "è", "che", "di", "non", "un", "una", "per", "con", "sono",
+            "della", "questo", "come", "ma", "anche", "nel",
+        ],
+        letters: "ìò",
+        positive: &["buono", "ottimo", "eccellente", "fantastico", "meraviglioso", "felice", "amo", "piace"],
+        negative: &["cattivo", "terribile", "orribile", "odio", "triste", "arrabbiato", "frustrato", "pessimo"],
+        topics: &[
+            ("technology", "tecnologia"),
+            ("business", "affari"),
+            ("education", "istruzione"),
+            ("health", "salute"),
+            ("entertainment", "intrattenimento"),
+        ],
+    },
+    &LanguageProfile {
+        code: "pt",
+        name: "Portuguese",
+        tesseract: "por",
+        script: Script::Latin,
+        stopwords: &[
+            "o", "os", "as", "e", "é", "que", "de", "do", "da", "não", "um", "uma", "para", "com", "em", "no",
+            "na", "se", "mas", "muito", "são", "você",
+        ],
+        letters: "ãõ",
+        positive: &["bom", "ótimo", "excelente", "incrível", "maravilhoso", "feliz", "adoro", "gosto"],
+        negative: &["ruim", "terrível", "horrível", "odeio", "triste", "bravo", "frustrado", "péssimo"],
+        topics: &[
+            ("technology", "tecnologia"),
+            ("business", "negócio"),
+            ("education", "educação"),
+            ("health", "saúde"),
+            ("entertainment", "entretenimento"),
+        ],
+    },
+    &LanguageProfile {
+        code: "nl",
+        name: "Dutch",
+        tesseract: "nld",
+        script: Script::Latin,
+        stopwords: &[
+            "de", "het", "een", "en", "is", "van", "niet", "dat", "ik", "je", "op", "te", "met", "voor", "zijn",
+            "wij", "maar", "ook", "er", "naar", "wordt",
+        ],
+        letters: "ĳ",
+        positive: &["goed", "geweldig", "uitstekend", "fantastisch", "prachtig", "blij", "hou", "leuk"],
+        negative: &["slecht", "verschrikkelijk", "vreselijk", "haat", "verdrietig", "boos", "gefrustreerd", "jammer"],
+        topics: &[
+            ("technology", "technologie"),
+            ("business", "bedrijf"),
+            ("education", "onderwijs"),
+            ("health", "gezondheid"),
+            ("entertainment", "amusement"),
+        ],
+    },
+    &LanguageProfile {
+        code: "pl",
+        name: "Polish",
+        tesseract: "pol",
+        script: Script::Latin,
+        stopwords: &["nie", "się", "jest", "że", "jak", "ale", "czy", "tak", "są", "ten", "jestem", "dla", "jego"],
+        letters: "ąęłńśźż",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "sv",
+        name: "Swedish",
+        tesseract: "swe",
+        script: Script::Latin,
+        stopwords: &["och", "är", "att", "det", "som", "en", "på", "jag", "inte", "för", "med", "har", "av", "till"],
+        letters: "å",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "tr",
+        name: "Turkish",
+        tesseract: "tur",
+        script: Script::Latin,
+        stopwords: &["ve", "bir", "bu", "da", "de", "için", "ile", "değil", "çok", "ama", "gibi", "daha", "olarak"],
+        letters: "ğış",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "ru",
+        name: "Russian",
+        tesseract: "rus",
+        script: Script::Cyrillic,
+        stopwords: &["и", "в", "не", "на", "что", "я", "с", "он", "как", "это", "по", "но", "мы", "вы", "же"],
+        letters: "ыэъё",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "uk",
+        name: "Ukrainian",
+        tesseract: "ukr",
+        script: Script::Cyrillic,
+        stopwords: &["і", "в", "не", "на", "що", "я", "з", "він", "як", "це", "та", "але", "ми", "ви"],
+        letters: "іїєґ",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "el",
+        name: "Greek",
+        tesseract: "ell",
+        script: Script::Greek,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "ar",
+        name: "Arabic",
+        tesseract: "ara",
+        script: Script::Arabic,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "he",
+        name: "Hebrew",
+        tesseract: "heb",
+        script: Script::Hebrew,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "hi",
+        name: "Hindi",
+        tesseract: "hin",
+        script: Script::Devanagari,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "th",
+        name: "Thai",
+        tesseract: "tha",
+        script: Script::Thai,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "ko",
+        name: "Korean",
+        tesseract: "kor",
+        script: Script::Hangul,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "ja",
+        name: "Japanese",
+        tesseract: "jpn",
+        script: Script::Kana,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+    &LanguageProfile {
+        code: "zh",
+        name: "Chinese",
+        tesseract: "chi_sim",
+        script: Script::Han,
+        stopwords: &[],
+        letters: "",
+        positive: &[],
+        negative: &[],
+        topics: &[],
+    },
+];
+
+/// Look up a language by its ISO 639-1 code
+pub fn profile(code: &str) -> Option<&'static LanguageProfile> {
+    LANGUAGES.iter().copied().find(|profile| profile.code == code)
+}
+
+/// Language recognized in OCR or transcription output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code
+    pub code: String,
+    pub name: String,
+    pub confidence: f32,
+    /// Whether analysis has word lists for the language; unsupported text is reported, not analyzed
+    pub supported: bool,
+    /// Tesseract packs the text was recognized with, such as `deu+eng`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_languages: Option<String>,
+}
+
+impl DetectedLanguage {
+    fn new(profile: &LanguageProfile, confidence: f32) -> Self {
+        Self {
+            code: profile.code.to_string(),
+            name: profile.name.to_string(),
+            confidence: confidence.clamp(0.0, 1.0),
+            supported: profile.is_supported(),
+            ocr_languages: None,
+        }
+    }
+
+    /// Record the Tesseract packs that produced the text
+    pub fn with_ocr_languages(mut self, packs: impl Into<String>) -> Self {
+        self.ocr_languages = Some(packs.into());
+        self
+    }
+
+    /// Profile for the detected code
+    pub fn profile(&self) -> Option<&'static LanguageProfile> {
+        profile(&self.code)
+    }
+
+    /// Sentence shown in place of an analysis the language does not support
+    pub fn unsupported_message(&self) -> String {
+        format!(
+            "The text appears to be {} ({}), which analysis does not support yet; it was not analyzed.",
+            self.name, self.code
+        )
+    }
+}
+
+/// Profile analysis should use for a detection, with English for undetected text
+pub fn analysis_profile(detected: Option<&DetectedLanguage>) -> &'static LanguageProfile {
+    detected.and_then(DetectedLanguage::profile).unwrap_or(&ENGLISH)
+}
+
+/// Detect the main language of a text.
+///
+/// Returns `None` when the text is too short or has no distinguishing words, so
+/// callers can fall back to their default instead of trusting a guess.
+pub fn detect(text: &str) -> Option<DetectedLanguage> {
+    let mut scripts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(Script::of) {
+        *scripts.entry(script).or_default() += 1;
+    }
+    let letters: usize = scripts.values().sum();
+    if letters < MIN_LETTERS {
+        return None;
+    }
+
+    // Kana only appears in Japanese, which also writes most nouns in Han characters
+    let kana = scripts.get(&Script::Kana).copied().unwrap_or(0);
+    let han = scripts.get(&Script::Han).copied().unwrap_or(0);
+    if kana > 0 {
+        scripts.remove(&Script::Han);
+        scripts.insert(Script::Kana, kana + han);
+    }
+
+    let (&script, &count) = scripts.iter().max_by_key(|(_, count)| **count)?;
+    let script_share = count as f32 / letters as f32;
+
+    let candidates: Vec<&'static LanguageProfile> =
+        LANGUAGES.iter().copied().filter(|profile| profile.script == script).collect();
+    if let [only] = candidates.as_slice() {
+        return Some(DetectedLanguage::new(only, script_share));
+    }
+
+    let words = words(text);
+    let mut scores: Vec<(&'static LanguageProfile, f32)> = candidates
+        .iter()
+        .map(|profile| (*profile, word_evidence(profile, &words)))
+        .collect();
+    scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    let (best, best_score) = *scores.first()?;
+    if best_score < MIN_EVIDENCE {
+        // A Latin text with hardly any function words is usually a label or a command, not prose
+        return if script == Script::Latin {
+            None
+        } else {
+            Some(DetectedLanguage::new(best, 0.5 * script_share))
+        };
+    }
+
+    let runner_up = scores.get(1).map_or(0.0, |(_, score)| *score);
+    let margin = best_score / (best_score + runner_up);
+    let evidence = (best_score / 3.0).min(1.0);
+    Some(DetectedLanguage::new(best, margin * evidence * script_share))
+}
+
+/// Languages making up a text, each with its share of the words, most common first.
+///
+/// Lines are detected separately so a German document with English code comments
+/// comes back as both languages.
+pub fn detect_mix(text: &str) -> Vec<(&'static LanguageProfile, f32)> {
+    let mut counts: Vec<(&'static LanguageProfile, usize)> = Vec::new();
+    for line in text.lines() {
+        let Some(profile) = detect(line).and_then(|detected| detected.profile()) else {
+            continue;
+        };
+        let line_words = line.split_whitespace().count();
+        match counts.iter_mut().find(|(known, _)| known.code == profile.code) {
+            Some((_, count)) => *count += line_words,
+            None => counts.push((profile, line_words)),
+        }
+    }
+
+    // Lines too short to judge alone can still add up to a detectable text
+    if counts.is_empty() {
+        if let Some(profile) = detect(text).and_then(|detected| detected.profile()) {
+            return vec![(profile, 1.0)];
+        }
+        return Vec::new();
+    }
+
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    let mut mix: Vec<(&'static LanguageProfile, f32)> = counts
+        .into_iter()
+        .map(|(profile, count)| (profile, count as f32 / total as f32))
+        .filter(|(_, share)| *share >= MIX_MIN_SHARE)
+        .collect();
+    mix.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    mix.truncate(MAX_COMBINED_PACKS);
+    mix
+}
+
+/// Tesseract language string (`deu+eng`) for a detected mix.
+///
+/// Packs missing from `available` are skipped; an empty `available` allows every
+/// pack in the language table. Returns `None` when nothing usable was detected.
+pub fn tesseract_languages(mix: &[(&LanguageProfile, f32)], available: &[String]) -> Option<String> {
+    let packs: Vec<&str> = mix
+        .iter()
+        .map(|(profile, _)| profile.tesseract)
+        .filter(|pack| available.is_empty() || available.iter().any(|known| known == pack))
+        .collect();
+    if packs.is_empty() {
+        None
+    } else {
+        Some(packs.join("+"))
+    }
+}
+
+/// Lowercased words of a text, without surrounding punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// How strongly a text's words point to a language: one point per function word and
+/// half a point per word spelled with one of its characteristic letters
+fn word_evidence(profile: &LanguageProfile, words: &[String]) -> f32 {
+    words
+        .iter()
+        .map(|word| {
+            let mut score = 0.0;
+            if profile.stopwords.contains(&word.as_str()) {
+                score += 1.0;
+            }
+            if word.chars().any(|c| profile.letters.contains(c)) {
+                score += 0.5;
+            }
+            score
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_latin_languages_by_function_words() {
+        let english = detect("The build failed because the config file is missing from the repository.").unwrap();
+        assert_eq!(english.code, "en");
+        assert!(english.supported);
+
+        let german = detect("Die Datei wurde nicht gefunden, und der Build ist auf dem Server fehlgeschlagen.").unwrap();
+        assert_eq!(german.code, "de");
+        assert!(german.confidence > 0.5);
+
+        let spanish = detect("El archivo no está en el servidor y la compilación falló por eso.").unwrap();
+        assert_eq!(spanish.code, "es");
+
+        // A terse label has nothing to tell languages apart by
+        assert!(detect("Save").is_none());
+        assert!(detect("Compile Deploy Rollback").is_none());
+    }
+
+    #[test]
+    fn test_non_latin_scripts_are_detected_but_unsupported() {
+        let russian = detect("Файл не найден на сервере, и сборка не удалась.").unwrap();
+        assert_eq!(russian.code, "ru");
+        assert!(!russian.supported);
+        assert!(russian.unsupported_message().contains("Russian"));
+
+        let japanese = detect("設定ファイルが見つかりませんでした。もう一度試してください。").unwrap();
+        assert_eq!(japanese.code, "ja");
+
+        let chinese = detect("服务器上找不到配置文件，构建失败了。请再试一次。").unwrap();
+        assert_eq!(chinese.code, "zh");
+        assert!(!chinese.supported);
+    }
+
+    #[test]
+    fn test_mixed_text_combines_tesseract_packs() {
+        let text = "Die Datei wurde nicht gefunden und der Build ist fehlgeschlagen.\n\
+                    Bitte prüfen Sie die Konfiguration auf dem Server.\n\
+                    The error says that the file is missing from the archive.";
+        let mix = detect_mix(text);
+        let codes: Vec<&str> = mix.iter().map(|(profile, _)| profile.code).collect();
+        assert_eq!(codes, vec!["de", "en"]);
+
+        assert_eq!(tesseract_languages(&mix, &[]), Some("deu+eng".to_string()));
+        // Only installed packs are used
+        assert_eq!(tesseract_languages(&mix, &["eng".to_string()]), Some("eng".to_string()));
+        assert_eq!(tesseract_languages(&mix, &["fra".to_string()]), None);
+    }
+}
@@ -2,6 +2,22 @@ pub mod ocr;
 pub mod audio;
 pub mod llm;
 pub mod analysis;
+pub mod store;
+pub mod export;
+pub mod fusion;
+pub mod language;
+pub mod batch;
+pub mod document;
+pub mod code;
+pub mod diagnostics;
+pub mod rules;
+pub mod redact;
+pub mod confidence;
+pub mod meeting;
+pub mod tasks;
+pub mod subtitles;
+pub mod tts;
+pub mod vocabulary;
 
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -13,36 +29,359 @@ pub struct LearningService {
     audio_transcriber: Arc<audio::AudioTranscriber>,
     llm_engine: Arc<llm::LocalLLM>,
     analysis_engine: Arc<analysis::AnalysisEngine>,
+    frame_differ: Mutex<ocr::diff::FrameDiffer>,
+    vocabulary: Arc<vocabulary::VocabularyStore>,
 }
 
 impl LearningService {
     /// Create a new learning service with all components initialized
     pub async fn new() -> Result<Self, anyhow::Error> {
-        let ocr_engine = Arc::new(ocr::OCREngine::new()?);
-        let audio_transcriber = Arc::new(audio::AudioTranscriber::new()?);
+        Self::with_vocabulary(Arc::new(vocabulary::VocabularyStore::in_memory())).await
+    }
+
+    /// Create a learning service whose OCR and transcription use a user vocabulary
+    pub async fn with_vocabulary(vocabulary: Arc<vocabulary::VocabularyStore>) -> Result<Self, anyhow::Error> {
+        Self::build(None, vocabulary, audio::AudioConfig::default()).await
+    }
+
+    /// Create a learning service whose OCR and transcription use a user vocabulary, and
+    /// whose live transcription captures with the given audio settings and input device
+    pub async fn with_vocabulary_and_audio(
+        vocabulary: Arc<vocabulary::VocabularyStore>,
+        audio: audio::AudioConfig,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(None, vocabulary, audio).await
+    }
+
+    /// Create a learning service whose analysis sessions are persisted to a store
+    pub async fn with_store(store: Arc<store::SessionStore>) -> Result<Self, anyhow::Error> {
+        Self::build(
+            Some(store),
+            Arc::new(vocabulary::VocabularyStore::in_memory()),
+            audio::AudioConfig::default(),
+        )
+        .await
+    }
+
+    /// Create a learning service that persists its sessions and redaction audit to a
+    /// store, and whose OCR and transcription use a user vocabulary
+    pub async fn with_store_and_vocabulary(
+        store: Arc<store::SessionStore>,
+        vocabulary: Arc<vocabulary::VocabularyStore>,
+    ) -> Result<Self, anyhow::Error> {
+        Self::build(Some(store), vocabulary, audio::AudioConfig::default()).await
+    }
+
+    async fn build(
+        store: Option<Arc<store::SessionStore>>,
+        vocabulary: Arc<vocabulary::VocabularyStore>,
+        audio: audio::AudioConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let ocr_engine = Arc::new(ocr::OCREngine::new()?.with_vocabulary(Arc::clone(&vocabulary)));
+        let audio_transcriber = Arc::new(audio::AudioTranscriber::with_config(audio).with_vocabulary(Arc::clone(&vocabulary)));
         let llm_engine = Arc::new(llm::LocalLLM::new().await?);
-        let analysis_engine = Arc::new(analysis::AnalysisEngine::new(
-            Arc::clone(&llm_engine),
-        ));
+        let analysis_engine = Arc::new(match store {
+            Some(store) => analysis::AnalysisEngine::with_store(
+                Arc::clone(&llm_engine),
+                analysis::AnalysisConfig::default(),
+                store,
+            ),
+            None => analysis::AnalysisEngine::new(Arc::clone(&llm_engine)),
+        });
 
         Ok(Self {
             ocr_engine,
             audio_transcriber,
             llm_engine,
             analysis_engine,
+            frame_differ: Mutex::new(ocr::diff::FrameDiffer::new()),
+            vocabulary,
         })
     }
 
-    /// Analyze a screenshot and extract relevant information
+    /// Analyze a screenshot and extract relevant information.
+    ///
+    /// Tables, code blocks and OCR confidence are attached before the result is
+    /// redacted, cached and recorded, so the stored turn matches what is returned.
     pub async fn analyze_screenshot(&self, image_path: &str) -> Result<analysis::AnalysisResult, anyhow::Error> {
-        let text_content = self.ocr_engine.extract_text(image_path).await?;
-        self.analysis_engine.analyze_text(&text_content).await
+        // Tesseract reads the image once; text, formulas, tables and code all come from its words
+        let image = image::open(image_path)?;
+        let recognition = self.ocr_engine.recognize_image(&image).await?;
+        let config = self.ocr_engine.config();
+        let code_blocks = if config.detect_code || config.recognize_math {
+            self.ocr_engine.code_blocks_from_words(&recognition.words)
+        } else {
+            Vec::new()
+        };
+        let text_content = if config.recognize_math {
+            self.ocr_engine.text_with_math_from_words(&recognition, &image, &code_blocks)?
+        } else {
+            recognition.text.clone()
+        };
+
+        // Tesseract's word confidences feed into the result's calibrated confidence
+        let mut attachments = analysis::Attachments {
+            signals: recognition.confidence_signal().into_iter().collect(),
+            ..Default::default()
+        };
+
+        // Attach spreadsheet and documentation tables as structured rows and CSV
+        if config.detect_tables {
+            let tables = self.ocr_engine.tables_from_words(&recognition.words, &image);
+            if !tables.is_empty() {
+                attachments.metadata.insert(
+                    ocr::table::TABLES_METADATA_KEY.to_string(),
+                    ocr::table::tables_metadata(&tables),
+                );
+            }
+        }
+
+        // Route code on screen to code-specific analysis, with its layout preserved
+        if config.detect_code {
+            let mut analyzed = Vec::new();
+            for block in code_blocks {
+                let code_analysis = code::analyze(&block.text);
+                attachments.insights.push(code_analysis.summary.clone());
+                attachments.recommendations.extend(code_analysis.recommendations());
+                analyzed.push(serde_json::json!({
+                    "bbox": block.bbox,
+                    "text": block.text,
+                    "analysis": code_analysis,
+                }));
+            }
+            if !analyzed.is_empty() {
+                attachments.metadata.insert(
+                    ocr::code::CODE_BLOCKS_METADATA_KEY.to_string(),
+                    serde_json::Value::Array(analyzed),
+                );
+            }
+        }
+
+        self.analysis_engine
+            .analyze_text_with(&text_content, recognition.language, attachments)
+            .await
+    }
+
+    /// Analyze a pasted or captured code snippet
+    pub async fn analyze_code(&self, code: &str) -> Result<analysis::AnalysisResult, anyhow::Error> {
+        self.analysis_engine.analyze_code(code).await
+    }
+
+    /// Analyze a frame from continuous screen capture.
+    ///
+    /// Only the regions that changed since the previous frame are OCR'd; returns
+    /// `None` when the frame is a near-duplicate and nothing was analyzed.
+    pub async fn analyze_frame(&self, image_bytes: &[u8]) -> Result<Option<analysis::AnalysisResult>, anyhow::Error> {
+        let changed = {
+            let mut differ = self.frame_differ.lock().await;
+            self.ocr_engine.extract_changed_text(image_bytes, &mut differ).await?
+        };
+        let Some((frame_diff, recognition)) = changed else {
+            return Ok(None);
+        };
+        if recognition.text.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(ocr::diff::FRAME_HASH_METADATA_KEY.to_string(), frame_diff.hash.to_string());
+        metadata.insert("changed_regions".to_string(), frame_diff.regions.len().to_string());
+
+        let extracted = analysis::ExtractedText {
+            source: analysis::ContentType::Screenshot,
+            confidence: self.extraction_confidence(recognition.confidence_signal()),
+            text: recognition.text,
+            timestamp: chrono::Utc::now(),
+            metadata,
+            time_range: None,
+            language: recognition.language,
+        };
+
+        self.analysis_engine.analyze_frame(extracted).await
+    }
+
+    /// Extract page-numbered text from a PDF, DOCX, PPTX, HTML, EPUB or Markdown document,
+    /// OCR'ing pages that have no text layer
+    pub async fn ingest_document(&self, path: &str) -> Result<Vec<analysis::ExtractedText>, anyhow::Error> {
+        let ingestor = document::DocumentIngestor::with_ocr(Arc::clone(&self.ocr_engine));
+        let document = ingestor.ingest(std::path::Path::new(path)).await?;
+        Ok(document.to_extracted_texts())
+    }
+
+    /// Ingest a document and analyze its sections together
+    pub async fn analyze_document(&self, path: &str) -> Result<analysis::AnalysisResult, anyhow::Error> {
+        let sections = self.ingest_document(path).await?;
+        self.analysis_engine.analyze_combined(sections).await
     }
 
     /// Transcribe audio and analyze the content
     pub async fn analyze_audio(&self, audio_path: &str) -> Result<analysis::AnalysisResult, anyhow::Error> {
-        let text_content = self.audio_transcriber.transcribe(audio_path).await?;
-        self.analysis_engine.analyze_text(&text_content).await
+        let segments = self.audio_transcriber.transcribe_segments(audio_path).await?;
+        self.analyze_transcript(&segments).await
+    }
+
+    /// Listen for wake phrases on `source` and analyze what is said after each one.
+    ///
+    /// Every detected phrase starts a capture of the next `capture` of audio, which is
+    /// transcribed, analyzed and sent on `results`. Returns when the source ends or
+    /// nobody is receiving results anymore.
+    pub async fn listen_for_wake_phrase(
+        &self,
+        source: Box<dyn audio::source::AudioSource>,
+        config: audio::keyword::KeywordConfig,
+        capture: std::time::Duration,
+        results: tokio::sync::mpsc::UnboundedSender<audio::keyword::WakeAnalysis>,
+    ) -> Result<(), anyhow::Error> {
+        let mut spotter = audio::keyword::KeywordSpotter::new(config)?;
+        let (captures_tx, mut captures) = tokio::sync::mpsc::unbounded_channel();
+        let listener = tokio::spawn(async move {
+            let mut source = source;
+            spotter.listen(source.as_mut(), capture, captures_tx).await
+        });
+
+        while let Some(wake) = captures.recv().await {
+            let analyzed = async {
+                let segments = self.audio_transcriber.transcribe_samples(&wake.samples).await?;
+                let analysis = self.analyze_transcript(&segments).await?;
+                Ok::<_, anyhow::Error>(audio::keyword::WakeAnalysis {
+                    event: wake.event,
+                    transcript: join_segments(&segments),
+                    analysis,
+                })
+            }
+            .await;
+
+            // A live source never ends on its own, so stop listening explicitly
+            match analyzed {
+                Ok(result) if results.send(result).is_ok() => {}
+                Ok(_) => {
+                    listener.abort();
+                    return Ok(());
+                }
+                Err(e) => {
+                    listener.abort();
+                    return Err(e);
+                }
+            }
+        }
+
+        listener.await??;
+        Ok(())
+    }
+
+    /// Analyze transcript segments, scoring the result with their recognition confidence
+    async fn analyze_transcript(
+        &self,
+        segments: &[audio::TranscriptSegment],
+    ) -> Result<analysis::AnalysisResult, anyhow::Error> {
+        let text_content = join_segments(segments);
+        let attachments = analysis::Attachments {
+            signals: confidence::Signal::asr_segments("transcription", segments).into_iter().collect(),
+            ..Default::default()
+        };
+        self.analysis_engine.analyze_text_with(&text_content, None, attachments).await
+    }
+
+    /// Transcribe a diarized meeting recording and extract its minutes
+    pub async fn analyze_meeting(&self, audio_path: &str) -> Result<meeting::MeetingAnalysis, anyhow::Error> {
+        let segments = self.audio_transcriber.transcribe_segments(audio_path).await?;
+        let signal = confidence::Signal::asr_segments("transcription", &segments);
+
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert(export::AUDIO_PATH_KEY.to_string(), audio_path.to_string());
+
+        let transcript = analysis::ExtractedText {
+            source: analysis::ContentType::Audio,
+            text: meeting::render_transcript(&meeting::utterances_from_segments(&segments)),
+            confidence: self.extraction_confidence(signal),
+            timestamp: chrono::Utc::now(),
+            metadata,
+            time_range: None,
+            // Speaker labels would skew detection, so the raw transcript is used
+            language: language::detect(&join_segments(&segments)),
+        };
+        self.analysis_engine
+            .analyze_meeting(transcript, chrono::Local::now().date_naive())
+            .await
+    }
+
+    /// Extract text from an image with a calibrated confidence, per-field breakdown and
+    /// the language it was recognized in. `languages` are Tesseract packs to read the
+    /// image with first, when the caller knows them.
+    pub async fn extract_text_scored(
+        &self,
+        image_path: &str,
+        languages: Option<&str>,
+    ) -> Result<(String, confidence::ConfidenceReport, Option<language::DetectedLanguage>), anyhow::Error> {
+        let recognition = match languages {
+            Some(languages) => self.ocr_engine.recognize_in(image_path, languages).await?,
+            None => self.ocr_engine.recognize(image_path).await?,
+        };
+        let report = self
+            .analysis_engine
+            .confidence_model()
+            .score(recognition.confidence_signal().into_iter().collect());
+        Ok((recognition.text, report, recognition.language))
+    }
+
+    /// Calibrated confidence of an extraction stage, 0.0 when it produced no scores
+    fn extraction_confidence(&self, signal: Option<confidence::Signal>) -> f32 {
+        signal.map_or(0.0, |signal| self.analysis_engine.confidence_model().score(vec![signal]).score)
+    }
+
+    /// Analyze many screenshots, recordings and documents, at most `request.max_concurrency` at a time.
+    ///
+    /// `files` is `batch::collect_paths(&request.paths)`. Each item runs on its own task
+    /// and OCR runs on the blocking pool, so items are analyzed in parallel. Progress
+    /// events are sent on `progress` as items start and finish; failures are collected
+    /// in the returned report instead of aborting the batch.
+    pub async fn analyze_batch(
+        self: &Arc<Self>,
+        request: &batch::BatchRequest,
+        files: Vec<std::path::PathBuf>,
+        progress: Option<tokio::sync::mpsc::UnboundedSender<batch::BatchProgress>>,
+    ) -> Result<batch::BatchReport, anyhow::Error> {
+        let service = Arc::clone(self);
+        batch::run(request, files, progress, move |kind, path| {
+            let service = Arc::clone(&service);
+            async move {
+                let path_str = path.to_string_lossy().to_string();
+                let (source, text, signal, language, path_key) = match kind {
+                    batch::BatchItemKind::Screenshot => {
+                        let ocr_engine = Arc::clone(&service.ocr_engine);
+                        let image_path = path_str.clone();
+                        let recognition =
+                            tokio::task::spawn_blocking(move || ocr_engine.recognize_blocking(&image_path)).await??;
+                        let signal = recognition.confidence_signal();
+                        let source = analysis::ContentType::Screenshot;
+                        (source, recognition.text, signal, recognition.language, export::SCREENSHOT_PATH_KEY)
+                    }
+                    batch::BatchItemKind::Audio => {
+                        let segments = service.audio_transcriber.transcribe_segments(&path_str).await?;
+                        let signal = confidence::Signal::asr_segments("transcription", &segments);
+                        let text = join_segments(&segments);
+                        let language = language::detect(&text);
+                        (analysis::ContentType::Audio, text, signal, language, export::AUDIO_PATH_KEY)
+                    }
+                    batch::BatchItemKind::Document => return service.analyze_document(&path_str).await,
+                };
+
+                let mut metadata = std::collections::HashMap::new();
+                metadata.insert(path_key.to_string(), path_str);
+
+                let extracted = analysis::ExtractedText {
+                    source,
+                    text,
+                    confidence: service.extraction_confidence(signal),
+                    timestamp: chrono::Utc::now(),
+                    metadata,
+                    time_range: None,
+                    language,
+                };
+                service.analysis_engine.analyze_extracted_text(extracted).await
+            }
+        })
+        .await
     }
 
     /// Get the underlying LLM engine for direct access
@@ -50,10 +389,35 @@ impl LearningService {
         Arc::clone(&self.llm_engine)
     }
 
+    /// Get the OCR engine for direct access
+    pub fn ocr_engine(&self) -> Arc<ocr::OCREngine> {
+        Arc::clone(&self.ocr_engine)
+    }
+
+    /// Get the audio transcriber for direct access
+    pub fn audio_transcriber(&self) -> Arc<audio::AudioTranscriber> {
+        Arc::clone(&self.audio_transcriber)
+    }
+
     /// Get the analysis engine for custom analysis
     pub fn analysis_engine(&self) -> Arc<analysis::AnalysisEngine> {
         Arc::clone(&self.analysis_engine)
     }
+
+    /// Get the vocabulary shared by OCR and transcription
+    pub fn vocabulary(&self) -> Arc<vocabulary::VocabularyStore> {
+        Arc::clone(&self.vocabulary)
+    }
+}
+
+/// Text of transcript segments in order
+fn join_segments(segments: &[audio::TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| segment.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 /// Configuration for the learning service
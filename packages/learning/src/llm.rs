@@ -15,6 +15,8 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokenizers::Tokenizer;
 
+use crate::language::{LanguageProfile, ENGLISH};
+
 /// Configuration for the local LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
@@ -166,6 +168,17 @@ impl LocalLLM {
 
     /// Analyze sentiment of text
     pub async fn analyze_sentiment(&self, text: &str) -> Result<SentimentResult> {
+        self.analyze_sentiment_in(text, &ENGLISH).await
+    }
+
+    /// Analyze sentiment of text written in `language`.
+    ///
+    /// The sentiment model is trained on English only; other languages use their own
+    /// word lists rather than having the model guess at them.
+    pub async fn analyze_sentiment_in(&self, text: &str, language: &LanguageProfile) -> Result<SentimentResult> {
+        if language.code != ENGLISH.code {
+            return self.lexicon_sentiment_analysis(text, language).await;
+        }
         if let Some(analyzer) = &self.sentiment_analyzer {
             let output = analyzer.predict(&[text])?;
             let sentiment = &output[0];
@@ -239,6 +252,11 @@ impl LocalLLM {
 
     /// Analyze text using the context window
     pub async fn analyze_with_context(&self, text: &str) -> Result<AnalysisResult> {
+        self.analyze_with_context_in(text, &ENGLISH).await
+    }
+
+    /// Analyze text written in `language` using the context window
+    pub async fn analyze_with_context_in(&self, text: &str, language: &LanguageProfile) -> Result<AnalysisResult> {
         let context = self.get_context().await;
         let context_text = context.join("\n");
         
@@ -250,8 +268,8 @@ impl LocalLLM {
         };
 
         // Perform various analyses
-        let sentiment = self.analyze_sentiment(text).await?;
-        let topics = self.extract_topics(&full_text).await?;
+        let sentiment = self.analyze_sentiment_in(text, language).await?;
+        let topics = self.extract_topics(&full_text, language).await?;
         let summary = self.generate_summary(&full_text).await?;
         let insights = self.generate_insights(&full_text).await?;
 
@@ -314,16 +332,21 @@ impl LocalLLM {
 
     /// Simple sentiment analysis fallback
     async fn simple_sentiment_analysis(&self, text: &str) -> Result<SentimentResult> {
+        self.lexicon_sentiment_analysis(text, &ENGLISH).await
+    }
+
+    /// Sentiment from the positive and negative words of a language
+    async fn lexicon_sentiment_analysis(&self, text: &str, language: &LanguageProfile) -> Result<SentimentResult> {
         let text_lower = text.to_lowercase();
         
-        let positive_words = ["good", "great", "excellent", "amazing", "wonderful", "happy", "love", "like"];
-        let negative_words = ["bad", "terrible", "awful", "hate", "dislike", "sad", "angry", "frustrated"];
+        let positive_words = language.positive;
+        let negative_words = language.negative;
         
         let positive_count = positive_words.iter()
-            .filter(|word| text_lower.contains(word))
+            .filter(|word| text_lower.contains(**word))
             .count();
         let negative_count = negative_words.iter()
-            .filter(|word| text_lower.contains(word))
+            .filter(|word| text_lower.contains(**word))
             .count();
         
         let (label, score) = if positive_count > negative_count {
@@ -383,20 +406,27 @@ impl LocalLLM {
     }
 
     /// Extract topics from text
-    async fn extract_topics(&self, text: &str) -> Result<Vec<String>> {
-        let topics = vec![
-            "technology".to_string(),
-            "business".to_string(),
-            "education".to_string(),
-            "health".to_string(),
-            "entertainment".to_string(),
-        ];
+    ///
+    /// Topics are always reported by their English label. Non-English text is matched
+    /// against the language's own keywords, since the zero-shot model only reads English.
+    async fn extract_topics(&self, text: &str, language: &LanguageProfile) -> Result<Vec<String>> {
+        let labels: Vec<String> = language.topics.iter().map(|(_, keyword)| keyword.to_string()).collect();
         
-        let classification = self.classify_text(text, &topics).await?;
+        let classification = if language.code == ENGLISH.code {
+            self.classify_text(text, &labels).await?
+        } else {
+            self.simple_classification(text, &labels).await?
+        };
         let relevant_topics: Vec<String> = classification
             .into_iter()
             .filter(|(_, score)| *score > 0.3)
-            .map(|(topic, _)| topic)
+            .filter_map(|(keyword, _)| {
+                language
+                    .topics
+                    .iter()
+                    .find(|(_, candidate)| *candidate == keyword)
+                    .map(|(topic, _)| topic.to_string())
+            })
             .collect();
         
         Ok(relevant_topics)
@@ -500,6 +530,24 @@ mod tests {
         assert!(sentiment.score > 0.5);
     }
 
+    #[tokio::test]
+    async fn test_non_english_text_uses_its_own_word_lists() {
+        let llm = LocalLLM::new().await.unwrap();
+        let german = crate::language::profile("de").unwrap();
+
+        let sentiment = llm
+            .analyze_sentiment_in("Das neue Update ist wirklich toll, ich liebe es!", german)
+            .await
+            .unwrap();
+        assert_eq!(sentiment.label, "POSITIVE");
+
+        let analysis = llm
+            .analyze_with_context_in("Die Gesundheit der Mitarbeiter ist wichtig.", german)
+            .await
+            .unwrap();
+        assert_eq!(analysis.topics, vec!["health".to_string()]);
+    }
+
     #[tokio::test]
     async fn test_context_management() {
         let llm = LocalLLM::new().await.unwrap();
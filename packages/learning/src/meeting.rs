@@ -0,0 +1,650 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use crate::analysis::{AnalysisResult, Priority, Recommendation, RecommendationCategory};
+use crate::audio::TranscriptSegment;
+
+/// Metadata key under which the minutes of a meeting analysis are stored
+pub const MEETING_MINUTES_METADATA_KEY: &str = "meeting_minutes";
+/// `ExtractedText` metadata key holding the ISO date a meeting was held; its presence
+/// turns on meeting analysis
+pub const MEETING_DATE_METADATA_KEY: &str = "meeting_date";
+
+/// One turn of speech in a meeting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Utterance {
+    pub speaker: Option<String>,
+    pub text: String,
+    /// Offset from the start of the meeting, when known
+    pub start_secs: Option<f64>,
+}
+
+/// A date a commitment is due, with the words it was parsed from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deadline {
+    pub phrase: String,
+    pub date: NaiveDate,
+}
+
+/// Something the meeting settled on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Decision {
+    pub text: String,
+    pub speaker: Option<String>,
+    pub start_secs: Option<f64>,
+}
+
+/// A task someone committed to or was asked to do
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub task: String,
+    pub assignee: Option<String>,
+    pub deadline: Option<Deadline>,
+    /// Who raised the item
+    pub speaker: Option<String>,
+    pub start_secs: Option<f64>,
+}
+
+/// A question nobody answered, or whose answer was "we don't know yet"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenQuestion {
+    pub question: String,
+    pub asked_by: Option<String>,
+    pub start_secs: Option<f64>,
+}
+
+/// A topic to come back to later without a clear owner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FollowUp {
+    pub text: String,
+    pub speaker: Option<String>,
+    pub start_secs: Option<f64>,
+}
+
+/// Structured minutes of a meeting
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MeetingMinutes {
+    /// Speakers in order of first appearance
+    pub participants: Vec<String>,
+    pub decisions: Vec<Decision>,
+    pub action_items: Vec<ActionItem>,
+    pub open_questions: Vec<OpenQuestion>,
+    pub follow_ups: Vec<FollowUp>,
+}
+
+impl MeetingMinutes {
+    /// One line per decision, action item and open question, for result insights
+    pub fn highlights(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for decision in &self.decisions {
+            lines.push(format!("Decision: {}", decision.text));
+        }
+        for item in &self.action_items {
+            let owner = item.assignee.as_deref().unwrap_or("Unassigned");
+            match &item.deadline {
+                Some(deadline) => lines.push(format!("Action ({}, due {}): {}", owner, deadline.date, item.task)),
+                None => lines.push(format!("Action ({}): {}", owner, item.task)),
+            }
+        }
+        for question in &self.open_questions {
+            lines.push(format!("Open question: {}", question.question));
+        }
+        lines
+    }
+
+    /// Recommendations to track the action items and resolve the open questions
+    pub fn recommendations(&self) -> Vec<Recommendation> {
+        let mut recommendations = Vec::new();
+        if !self.action_items.is_empty() {
+            let dated = self.action_items.iter().filter(|item| item.deadline.is_some()).count();
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::Productivity,
+                title: format!("Track {} action items from this meeting", self.action_items.len()),
+                description: format!(
+                    "{} of them have a deadline; unassigned items need an owner before they slip.",
+                    dated
+                ),
+                priority: if dated > 0 { Priority::High } else { Priority::Medium },
+                action_items: self
+                    .highlights()
+                    .into_iter()
+                    .filter(|line| line.starts_with("Action"))
+                    .collect(),
+                confidence: 0.8,
+            });
+        }
+        if !self.open_questions.is_empty() {
+            recommendations.push(Recommendation {
+                category: RecommendationCategory::Communication,
+                title: "Resolve open questions".to_string(),
+                description: "These questions were raised without an answer.".to_string(),
+                priority: Priority::Medium,
+                action_items: self.open_questions.iter().map(|question| question.question.clone()).collect(),
+                confidence: 0.7,
+            });
+        }
+        recommendations
+    }
+
+    /// Restore the minutes stored on an analysis result
+    pub fn from_metadata(metadata: &std::collections::HashMap<String, serde_json::Value>) -> Option<Self> {
+        metadata
+            .get(MEETING_MINUTES_METADATA_KEY)
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Action items assigned to a participant, matched case-insensitively
+    pub fn action_items_for(&self, participant: &str) -> Vec<&ActionItem> {
+        self.action_items
+            .iter()
+            .filter(|item| item.assignee.as_deref().is_some_and(|assignee| assignee.eq_ignore_ascii_case(participant)))
+            .collect()
+    }
+}
+
+/// A meeting's regular analysis together with its minutes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeetingAnalysis {
+    pub analysis: AnalysisResult,
+    pub minutes: MeetingMinutes,
+}
+
+/// Turn diarized transcript segments into utterances, merging consecutive segments
+/// from the same speaker
+pub fn utterances_from_segments(segments: &[TranscriptSegment]) -> Vec<Utterance> {
+    let mut utterances: Vec<Utterance> = Vec::new();
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match utterances.last_mut() {
+            Some(last) if last.speaker.is_some() && last.speaker == segment.speaker => {
+                last.text.push(' ');
+                last.text.push_str(text);
+            }
+            _ => utterances.push(Utterance {
+                speaker: segment.speaker.clone(),
+                text: text.to_string(),
+                start_secs: Some(segment.start_secs),
+            }),
+        }
+    }
+    utterances
+}
+
+/// A transcript line: optional `[hh:mm:ss]` timestamp, optional speaker, then the text
+static TURN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:\[(?:(\d+):)?(\d{1,2}):(\d{2})\]\s*)?(?:([A-Z][\w.'-]*(?: [A-Z][\w.'-]*){0,2}):\s+)?(.+)$").unwrap()
+});
+
+/// Parse a transcript with one `Speaker: text` turn per line, optionally prefixed by a
+/// `[hh:mm:ss]` or `[mm:ss]` timestamp. Lines without a speaker continue the previous turn.
+pub fn utterances_from_text(text: &str) -> Vec<Utterance> {
+    let mut utterances: Vec<Utterance> = Vec::new();
+
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some(captures) = TURN.captures(line) else {
+            continue;
+        };
+        let number = |index: usize| captures.get(index).and_then(|m| m.as_str().parse::<f64>().ok());
+        let start_secs = number(3).map(|secs| number(1).unwrap_or(0.0) * 3600.0 + number(2).unwrap_or(0.0) * 60.0 + secs);
+        let speaker = captures.get(4).map(|m| m.as_str().to_string());
+        let body = captures[5].trim().to_string();
+
+        match (utterances.last_mut(), &speaker, start_secs) {
+            (Some(last), None, None) => {
+                last.text.push(' ');
+                last.text.push_str(&body);
+            }
+            _ => utterances.push(Utterance { speaker, text: body, start_secs }),
+        }
+    }
+    utterances
+}
+
+/// Render utterances as a `Speaker: text` transcript that `utterances_from_text` reads back
+pub fn render_transcript(utterances: &[Utterance]) -> String {
+    utterances
+        .iter()
+        .map(|utterance| {
+            let mut line = String::new();
+            if let Some(secs) = utterance.start_secs {
+                let secs = secs.max(0.0) as u64;
+                line.push_str(&format!("[{:02}:{:02}:{:02}] ", secs / 3600, secs / 60 % 60, secs % 60));
+            }
+            if let Some(speaker) = &utterance.speaker {
+                line.push_str(speaker);
+                line.push_str(": ");
+            }
+            line.push_str(&utterance.text);
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+static PATTERNS: LazyLock<Patterns> = LazyLock::new(Patterns::new);
+
+struct Patterns {
+    sentence_end: Regex,
+    decision: Regex,
+    action_cue: Regex,
+    self_assigned: Regex,
+    named_assignee: Regex,
+    addressed: Regex,
+    mention: Regex,
+    request: Regex,
+    follow_up: Regex,
+    uncertain: Regex,
+    affirmative: Regex,
+}
+
+impl Patterns {
+    fn new() -> Self {
+        let regex = |pattern: &str| Regex::new(pattern).unwrap();
+        Self {
+            sentence_end: regex(r"[.!?](?:\s+|$)"),
+            decision: regex(
+                r"(?i)\b(?:we(?:'ve| have)? decided|decision(?: is)?:|let's go with|we'll go with|we(?:'re| are) going with|(?:we )?agreed (?:to|on|that)|final answer|going forward,? we|settled on)\b",
+            ),
+            action_cue: regex(
+                r"(?i)\b(?:action items?|todo|to-do|i'll|i will|i can take|i'm going to|let me|will (?:send|write|draft|prepare|update|fix|review|schedule|share|check|set up|look into|follow up)|needs? to|please|can you|could you|take care of|assign(?:ed)? to)\b",
+            ),
+            self_assigned: regex(r"(?i)^(?:ok(?:ay)?,?\s*|so,?\s*|sure,?\s*)?(?:i'll|i will|i can take|i'm going to|let me)\s+(.+)$"),
+            named_assignee: regex(
+                r"^(?:(?i:action items?):?\s*)?@?([A-Z][a-z]+)(?:\s+(?:will|is going to|can|to|needs to|should)\s+)(.+)$",
+            ),
+            addressed: regex(r"^@?([A-Z][a-z]+),\s*(?i:can you|could you|please|would you)\s+(.+)$"),
+            mention: regex(r"@([A-Za-z][\w.-]*)"),
+            request: regex(r"(?i)^(?:can|could|would) you\s+(.+)$"),
+            follow_up: regex(
+                r"(?i)\b(?:follow[- ]up|circle back|get back to|next (?:meeting|sync|week's meeting)|revisit|take (?:this|it) offline|park (?:this|that))\b",
+            ),
+            uncertain: regex(r"(?i)\b(?:not sure|don't know|do not know|no idea|tbd|unclear|find out|get back to you|need to check)\b"),
+            affirmative: regex(r"(?i)^(?:sure|yes|yeah|yep|ok(?:ay)?|will do|on it|can do|i'll|i will|no problem)\b"),
+        }
+    }
+
+    fn sentences<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        for boundary in self.sentence_end.find_iter(text) {
+            sentences.push(text[start..boundary.end()].trim());
+            start = boundary.end();
+        }
+        if start < text.len() {
+            sentences.push(text[start..].trim());
+        }
+        sentences.into_iter().filter(|sentence| !sentence.is_empty()).collect()
+    }
+}
+
+/// Extract decisions, action items, open questions and follow-ups from a meeting.
+///
+/// `held_on` anchors relative deadlines such as "by Friday" or "next week".
+pub fn extract_minutes(utterances: &[Utterance], held_on: NaiveDate) -> MeetingMinutes {
+    let patterns = &*PATTERNS;
+    let mut minutes = MeetingMinutes::default();
+
+    for utterance in utterances {
+        if let Some(speaker) = &utterance.speaker {
+            if !minutes.participants.contains(speaker) {
+                minutes.participants.push(speaker.clone());
+            }
+        }
+    }
+
+    for (index, utterance) in utterances.iter().enumerate() {
+        let reply = utterances[index + 1..]
+            .iter()
+            .find(|next| next.speaker.is_none() || next.speaker != utterance.speaker);
+
+        for sentence in patterns.sentences(&utterance.text) {
+            let is_request = patterns.request.is_match(sentence) || patterns.addressed.is_match(sentence);
+            let is_question = sentence.ends_with('?') && !is_request;
+
+            if patterns.decision.is_match(sentence) {
+                minutes.decisions.push(Decision {
+                    text: clean_clause(sentence),
+                    speaker: utterance.speaker.clone(),
+                    start_secs: utterance.start_secs,
+                });
+            } else if is_question {
+                let answered = reply.is_some_and(|reply| {
+                    !reply.text.trim_end().ends_with('?') && !patterns.uncertain.is_match(&reply.text)
+                });
+                if !answered {
+                    minutes.open_questions.push(OpenQuestion {
+                        question: sentence.to_string(),
+                        asked_by: utterance.speaker.clone(),
+                        start_secs: utterance.start_secs,
+                    });
+                }
+            } else if patterns.action_cue.is_match(sentence) || is_request {
+                let (assignee, task) = assign(&patterns, sentence, utterance, reply, &minutes.participants);
+                minutes.action_items.push(ActionItem {
+                    deadline: parse_deadline(sentence, held_on),
+                    task,
+                    assignee,
+                    speaker: utterance.speaker.clone(),
+                    start_secs: utterance.start_secs,
+                });
+            } else if patterns.follow_up.is_match(sentence) {
+                minutes.follow_ups.push(FollowUp {
+                    text: clean_clause(sentence),
+                    speaker: utterance.speaker.clone(),
+                    start_secs: utterance.start_secs,
+                });
+            }
+        }
+    }
+
+    minutes
+}
+
+/// Work out who owns an action item and what the task is
+fn assign(
+    patterns: &Patterns,
+    sentence: &str,
+    utterance: &Utterance,
+    reply: Option<&Utterance>,
+    participants: &[String],
+) -> (Option<String>, String) {
+    let sentence = sentence.trim();
+    let participant = |name: &str| {
+        participants
+            .iter()
+            .find(|participant| {
+                participant.eq_ignore_ascii_case(name)
+                    || participant.split_whitespace().next().is_some_and(|first| first.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    };
+
+    if let Some(captures) = patterns.self_assigned.captures(sentence) {
+        return (utterance.speaker.clone(), clean_clause(&captures[1]));
+    }
+    if let Some(captures) = patterns.addressed.captures(sentence) {
+        let name = participant(&captures[1]).unwrap_or_else(|| captures[1].to_string());
+        return (Some(name), clean_clause(&captures[2]));
+    }
+    if let Some(captures) = patterns.named_assignee.captures(sentence) {
+        // Sentence-initial capitalized words are only names when they are not ordinary words
+        let name = &captures[1];
+        if participant(name).is_some() || !COMMON_WORDS.contains(&name.to_lowercase().as_str()) {
+            let name = participant(name).unwrap_or_else(|| name.to_string());
+            return (Some(name), clean_clause(&captures[2]));
+        }
+    }
+    if let Some(captures) = patterns.mention.captures(sentence) {
+        let name = participant(&captures[1]).unwrap_or_else(|| captures[1].to_string());
+        return (Some(name), clean_clause(&patterns.mention.replace(sentence, "$1")));
+    }
+    if let Some(captures) = patterns.request.captures(sentence) {
+        // "Can you ...?" is owned by whoever answers yes
+        let assignee = reply
+            .filter(|reply| patterns.affirmative.is_match(reply.text.trim()))
+            .and_then(|reply| reply.speaker.clone());
+        return (assignee, clean_clause(&captures[1]));
+    }
+
+    (None, clean_clause(sentence))
+}
+
+/// Capitalized sentence starters that are not names
+const COMMON_WORDS: &[&str] = &[
+    "we", "someone", "somebody", "everyone", "everybody", "this", "that", "it", "they", "he", "she", "you", "the",
+    "team", "engineering", "design", "marketing", "also", "then", "so", "and", "but", "next", "action", "who",
+];
+
+fn clean_clause(text: &str) -> String {
+    let text = text.trim().trim_end_matches(['.', '!', '?', ',']).trim();
+    let text = text
+        .strip_prefix("Action item:")
+        .or_else(|| text.strip_prefix("Action items:"))
+        .or_else(|| text.strip_prefix("TODO:"))
+        .unwrap_or(text)
+        .trim();
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Parse the first natural-language deadline in a sentence, relative to `today`
+pub fn parse_deadline(text: &str, today: NaiveDate) -> Option<Deadline> {
+    let lower = text.to_lowercase();
+    let deadline = |phrase: &str, date: NaiveDate| {
+        Some(Deadline {
+            phrase: phrase.to_string(),
+            date,
+        })
+    };
+
+    if let Some(captures) = ISO_DATE.captures(&lower) {
+        if let Some(date) = NaiveDate::from_ymd_opt(captures[1].parse().ok()?, captures[2].parse().ok()?, captures[3].parse().ok()?) {
+            return deadline(&captures[0], date);
+        }
+    }
+
+    if let Some(captures) = MONTH_DAY.captures(&lower) {
+        let month = month_number(captures.get(1).or(captures.get(4))?.as_str())?;
+        let day: u32 = captures.get(2).or(captures.get(3))?.as_str().parse().ok()?;
+        let mut date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+        if date < today {
+            date = NaiveDate::from_ymd_opt(today.year() + 1, month, day)?;
+        }
+        return deadline(&captures[0], date);
+    }
+
+    let captures = RELATIVE_DEADLINE.captures(&lower)?;
+    let phrase = captures[0].trim();
+
+    if let (Some(count), Some(unit)) = (captures.get(1), captures.get(2)) {
+        // A count too large for the calendar is not a deadline
+        let count = word_number(count.as_str())?;
+        let date = match unit.as_str().trim_end_matches('s') {
+            "day" => today.checked_add_signed(Duration::try_days(count)?)?,
+            "week" => today.checked_add_signed(Duration::try_weeks(count)?)?,
+            _ => add_months(today, u32::try_from(count).ok()?)?,
+        };
+        return deadline(phrase, date);
+    }
+    if let (Some(which), Some(unit)) = (captures.get(3), captures.get(4)) {
+        let next = which.as_str() == "next";
+        let date = match unit.as_str() {
+            "week" if next => today + Duration::days(7 - today.weekday().num_days_from_monday() as i64),
+            "week" => end_of_week(today),
+            "month" if next => add_months(first_of_month(today), 1)?,
+            "month" => end_of_month(today)?,
+            day => {
+                let upcoming = upcoming_weekday(today, weekday(day)?);
+                // "next Friday" said on a Tuesday means Friday of the following week
+                if next && upcoming.iso_week() == today.iso_week() {
+                    upcoming + Duration::weeks(1)
+                } else {
+                    upcoming
+                }
+            }
+        };
+        return deadline(phrase, date);
+    }
+    if let Some(day) = captures.get(5) {
+        return deadline(phrase, upcoming_weekday(today, weekday(day.as_str())?));
+    }
+    let date = match captures.get(6).or(captures.get(7))?.as_str() {
+        "day" | "eod" | "today" | "tonight" => today,
+        "tomorrow" => today + Duration::days(1),
+        "week" | "eow" => end_of_week(today),
+        "month" | "eom" => end_of_month(today)?,
+        _ => return None,
+    };
+    deadline(phrase, date)
+}
+
+const MONTH_PATTERN: &str =
+    "jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?";
+
+static ISO_DATE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b(\d{4})-(\d{2})-(\d{2})\b").unwrap());
+static MONTH_DAY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(
+        r"\b(?:({months})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?|(\d{{1,2}})(?:st|nd|rd|th)?\s+(?:of\s+)?({months}))\b",
+        months = MONTH_PATTERN
+    ))
+    .unwrap()
+});
+static RELATIVE_DEADLINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?:in (\d+|one|two|three|four|five|six|seven|eight|nine|ten) (days?|weeks?|months?)|(next|this) (monday|tuesday|wednesday|thursday|friday|saturday|sunday|week|month)|(?:by |on |before |until )(monday|tuesday|wednesday|thursday|friday|saturday|sunday)|end of (?:the )?(day|week|month)|\b(eod|eow|eom|today|tonight|tomorrow)\b)",
+    )
+    .unwrap()
+});
+
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+    MONTHS.iter().position(|month| name.starts_with(month)).map(|index| index as u32 + 1)
+}
+
+fn word_number(word: &str) -> Option<i64> {
+    const WORDS: [&str; 10] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"];
+    word.parse()
+        .ok()
+        .or_else(|| WORDS.iter().position(|w| *w == word).map(|index| index as i64 + 1))
+}
+
+fn weekday(name: &str) -> Option<Weekday> {
+    name.parse().ok()
+}
+
+/// The given weekday on or after `today`
+fn upcoming_weekday(today: NaiveDate, day: Weekday) -> NaiveDate {
+    let ahead = (7 + day.num_days_from_monday() as i64 - today.weekday().num_days_from_monday() as i64) % 7;
+    today + Duration::days(ahead)
+}
+
+/// Friday of the current week, or today on weekends
+fn end_of_week(today: NaiveDate) -> NaiveDate {
+    if today.weekday().num_days_from_monday() > Weekday::Fri.num_days_from_monday() {
+        today
+    } else {
+        upcoming_weekday(today, Weekday::Fri)
+    }
+}
+
+fn first_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+fn end_of_month(date: NaiveDate) -> Option<NaiveDate> {
+    add_months(first_of_month(date), 1).map(|next| next - Duration::days(1))
+}
+
+fn add_months(date: NaiveDate, months: u32) -> Option<NaiveDate> {
+    date.checked_add_months(chrono::Months::new(months))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tuesday
+    fn meeting_day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 12).unwrap()
+    }
+
+    #[test]
+    fn test_parse_deadlines() {
+        let today = meeting_day();
+        let date = |text: &str| parse_deadline(text, today).map(|deadline| deadline.date);
+
+        assert_eq!(date("send it by Friday"), NaiveDate::from_ymd_opt(2024, 3, 15));
+        assert_eq!(date("let's review next Friday"), NaiveDate::from_ymd_opt(2024, 3, 22));
+        assert_eq!(date("ship it next week"), NaiveDate::from_ymd_opt(2024, 3, 18));
+        assert_eq!(date("done by end of the month"), NaiveDate::from_ymd_opt(2024, 3, 31));
+        assert_eq!(date("in two weeks"), NaiveDate::from_ymd_opt(2024, 3, 26));
+        assert_eq!(date("draft due tomorrow"), NaiveDate::from_ymd_opt(2024, 3, 13));
+        assert_eq!(date("launch on April 2nd"), NaiveDate::from_ymd_opt(2024, 4, 2));
+        assert_eq!(date("renewal on 5 January"), NaiveDate::from_ymd_opt(2025, 1, 5));
+        assert_eq!(date("freeze on 2024-06-01"), NaiveDate::from_ymd_opt(2024, 6, 1));
+        assert_eq!(date("we talked about the weekly sync"), None);
+    }
+
+    #[test]
+    fn test_deadline_past_the_calendar_is_ignored() {
+        let today = meeting_day();
+        assert!(parse_deadline("ship it in 999999999 days", today).is_none());
+        assert!(parse_deadline("ship it in 999999999999999 weeks", today).is_none());
+        assert!(parse_deadline("ship it in 99999999999 months", today).is_none());
+    }
+
+    #[test]
+    fn test_extract_minutes_from_transcript() {
+        let transcript = "\
+[00:00:05] Alice: Thanks for joining. We decided to ship the beta on the new auth flow.
+[00:00:20] Bob: I'll update the migration guide by Friday.
+[00:00:41] Alice: Carol, can you review the rate limiter before next Monday?
+[00:00:50] Carol: Sure.
+[00:01:10] Bob: Who owns the billing integration?
+[00:01:15] Alice: Not sure yet, we need to check with finance.
+[00:01:30] Carol: Does the beta need SSO?
+[00:01:34] Bob: No, password login is enough for the beta.
+[00:01:50] Alice: Let's circle back on pricing at the next meeting.";
+
+        let utterances = utterances_from_text(transcript);
+        assert_eq!(utterances.len(), 9);
+        assert_eq!(utterances[1].start_secs, Some(20.0));
+
+        let minutes = extract_minutes(&utterances, meeting_day());
+        assert_eq!(minutes.participants, vec!["Alice", "Bob", "Carol"]);
+        assert_eq!(minutes.decisions.len(), 1);
+        assert!(minutes.decisions[0].text.contains("ship the beta"));
+
+        let bob = minutes.action_items_for("bob");
+        assert_eq!(bob.len(), 1);
+        assert_eq!(bob[0].task, "Update the migration guide by Friday");
+        assert_eq!(bob[0].deadline.as_ref().unwrap().date, NaiveDate::from_ymd_opt(2024, 3, 15).unwrap());
+
+        let carol = minutes.action_items_for("Carol");
+        assert_eq!(carol[0].task, "Review the rate limiter before next Monday");
+        assert_eq!(carol[0].deadline.as_ref().unwrap().date, NaiveDate::from_ymd_opt(2024, 3, 18).unwrap());
+
+        // Answered questions are not open; "not sure yet" is not an answer
+        assert_eq!(minutes.open_questions.len(), 1);
+        assert_eq!(minutes.open_questions[0].question, "Who owns the billing integration?");
+        assert_eq!(minutes.follow_ups.len(), 1);
+        assert_eq!(minutes.highlights().len(), minutes.action_items.len() + 2);
+    }
+
+    #[test]
+    fn test_request_owned_by_whoever_accepts() {
+        let segments = [
+            ("Dana", "Can you send the recording to the team?"),
+            ("Eli", "Will do."),
+            ("Eli", "Also the notes."),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (speaker, text))| TranscriptSegment {
+            start_secs: i as f64 * 5.0,
+            end_secs: i as f64 * 5.0 + 4.0,
+            text: text.to_string(),
+            speaker: Some(speaker.to_string()),
+            avg_logprob: -0.2,
+            no_speech_prob: 0.0,
+        })
+        .collect::<Vec<_>>();
+
+        let utterances = utterances_from_segments(&segments);
+        assert_eq!(utterances.len(), 2);
+        assert_eq!(utterances[1].text, "Will do. Also the notes.");
+        assert_eq!(utterances_from_text(&render_transcript(&utterances)), utterances);
+
+        let minutes = extract_minutes(&utterances, meeting_day());
+        assert_eq!(minutes.action_items[0].assignee.as_deref(), Some("Eli"));
+        assert_eq!(minutes.action_items[0].task, "Send the recording to the team");
+    }
+}
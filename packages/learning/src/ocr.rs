@@ -1,74 +1,390 @@
 use anyhow::Result;
 use image::{DynamicImage, ImageBuffer, Rgb};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use tesseract::{Tesseract, Image};
 
+pub mod code;
+pub mod diff;
+pub mod math;
+pub mod table;
+
+use crate::confidence::Signal;
+use crate::language::{self, DetectedLanguage};
+use crate::vocabulary::VocabularyStore;
+use code::{CodeBlock, CodeConfig};
+use diff::FrameDiffer;
+use math::{FormulaRecognizer, MathConfig};
+use table::{BoundingBox, Table, TableConfig, WordBox};
+
 /// Configuration for OCR processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OCRConfig {
+    /// Tesseract languages for the first pass over each image, such as `eng` or `eng+rus`
     pub language: String,
+    /// Detect the language of each image and re-run Tesseract with the matching packs
+    #[serde(default = "default_detect_language")]
+    pub detect_language: bool,
+    /// Tesseract packs that detection may pick; empty allows any pack it knows
+    #[serde(default)]
+    pub available_languages: Vec<String>,
     pub confidence_threshold: f32,
     pub preprocess_image: bool,
     pub extract_structured_data: bool,
+    /// Reconstruct tables from word positions when analyzing screenshots
+    #[serde(default = "default_detect_tables")]
+    pub detect_tables: bool,
+    /// Recognize formulas and embed them in the text as LaTeX
+    #[serde(default = "default_recognize_math")]
+    pub recognize_math: bool,
+    #[serde(default)]
+    pub math: MathConfig,
+    /// Detect source code and keep its indentation and symbols intact
+    #[serde(default = "default_detect_code")]
+    pub detect_code: bool,
+    #[serde(default)]
+    pub code: CodeConfig,
+}
+
+fn default_detect_language() -> bool {
+    true
+}
+
+fn default_detect_tables() -> bool {
+    true
+}
+
+fn default_recognize_math() -> bool {
+    true
+}
+
+fn default_detect_code() -> bool {
+    true
 }
 
 impl Default for OCRConfig {
     fn default() -> Self {
         Self {
             language: "eng".to_string(),
+            detect_language: true,
+            available_languages: Vec::new(),
             confidence_threshold: 0.6,
             preprocess_image: true,
             extract_structured_data: true,
+            detect_tables: true,
+            recognize_math: true,
+            math: MathConfig::default(),
+            detect_code: true,
+            code: CodeConfig::default(),
         }
     }
 }
 
+/// Text recognized in an image together with Tesseract's per-word results
+#[derive(Debug, Clone)]
+pub struct Recognition {
+    pub text: String,
+    pub words: Vec<WordBox>,
+    /// Language of the text and the Tesseract packs that read it
+    pub language: Option<DetectedLanguage>,
+}
+
+impl Recognition {
+    /// Word confidences as a signal for the `ocr` field of a result
+    pub fn confidence_signal(&self) -> Option<Signal> {
+        Signal::ocr_words("ocr", &self.words)
+    }
+}
+
 /// OCR Engine for extracting text from images
 pub struct OCREngine {
     tesseract: Tesseract,
+    /// Engines for language combinations picked by detection; `None` marks packs that failed to load
+    language_engines: Mutex<HashMap<String, Option<Tesseract>>>,
+    formula_recognizer: FormulaRecognizer,
     config: OCRConfig,
+    vocabulary: Arc<VocabularyStore>,
 }
 
 impl OCREngine {
     /// Create a new OCR engine
     pub fn new() -> Result<Self> {
-        let config = OCRConfig::default();
-        let tesseract = Tesseract::new(None, Some(&config.language))?;
-        
-        Ok(Self { tesseract, config })
+        Self::with_config(OCRConfig::default())
     }
 
     /// Create OCR engine with custom configuration
     pub fn with_config(config: OCRConfig) -> Result<Self> {
         let tesseract = Tesseract::new(None, Some(&config.language))?;
-        Ok(Self { tesseract, config })
+        let formula_recognizer = FormulaRecognizer::with_config(config.math.clone())?;
+        Ok(Self {
+            tesseract,
+            language_engines: Mutex::new(HashMap::new()),
+            formula_recognizer,
+            config,
+            vocabulary: Arc::new(VocabularyStore::in_memory()),
+        })
+    }
+
+    /// Correct recognized text toward the terms of a user vocabulary
+    pub fn with_vocabulary(mut self, vocabulary: Arc<VocabularyStore>) -> Self {
+        self.vocabulary = vocabulary;
+        self
+    }
+
+    /// Get the active OCR configuration
+    pub fn config(&self) -> &OCRConfig {
+        &self.config
     }
 
     /// Extract text from an image file
     pub async fn extract_text(&self, image_path: &str) -> Result<String> {
         let image = self.load_and_preprocess_image(image_path)?;
-        let text = self.tesseract.recognize(&image)?;
-        
-        if self.config.extract_structured_data {
-            self.post_process_text(&text.text)
-        } else {
-            Ok(text.text)
-        }
+        Ok(self.recognize_prepared(&image, None)?.text)
     }
 
     /// Extract text from raw image data
     pub async fn extract_text_from_bytes(&self, image_bytes: &[u8]) -> Result<String> {
         let image = self.preprocess_image_bytes(image_bytes)?;
-        let text = self.tesseract.recognize(&image)?;
-        
+        Ok(self.recognize_prepared(&image, None)?.text)
+    }
+
+    /// Extract text from an image file along with the word confidences behind it
+    pub async fn recognize(&self, image_path: &str) -> Result<Recognition> {
+        self.recognize_blocking(image_path)
+    }
+
+    /// Same as [`OCREngine::recognize`], for callers that run OCR on a blocking thread
+    pub fn recognize_blocking(&self, image_path: &str) -> Result<Recognition> {
+        let image = self.load_and_preprocess_image(image_path)?;
+        self.recognize_prepared(&image, None)
+    }
+
+    /// Extract text from an image file, reading it first with the given Tesseract packs,
+    /// such as `deu` or `deu+eng`, instead of `OCRConfig.language`. Detection may still
+    /// re-read the image with the packs it finds.
+    pub async fn recognize_in(&self, image_path: &str, languages: &str) -> Result<Recognition> {
+        self.check_languages(languages)?;
+        let image = self.load_and_preprocess_image(image_path)?;
+        self.recognize_prepared(&image, Some(languages))
+    }
+
+    /// Packs asked for by callers must be known Tesseract names, and among
+    /// `available_languages` when that is set
+    fn check_languages(&self, languages: &str) -> Result<()> {
+        for pack in languages.split('+') {
+            let known = !pack.is_empty() && pack.chars().all(|c| c.is_ascii_lowercase() || c == '_');
+            let available = self.config.available_languages.is_empty()
+                || self.config.available_languages.iter().any(|available| available == pack);
+            if !known || !available {
+                return Err(anyhow::anyhow!("Unsupported OCR language: {}", pack));
+            }
+        }
+        Ok(())
+    }
+
+    /// Extract text from an already decoded image along with its word confidences
+    pub async fn recognize_image(&self, img: &DynamicImage) -> Result<Recognition> {
+        let processed_img = if self.config.preprocess_image {
+            self.preprocess_image(img)
+        } else {
+            img.clone()
+        };
+        self.recognize_prepared(&Image::from_dynamic_image(&processed_img), None)
+    }
+
+    fn recognize_prepared(&self, image: &Image, first_pass: Option<&str>) -> Result<Recognition> {
+        let mut recognition = self.recognize_detecting_language(image, first_pass)?;
         if self.config.extract_structured_data {
-            self.post_process_text(&text.text)
+            recognition.text = self.post_process_text(&recognition.text)?;
+        }
+        Ok(recognition)
+    }
+
+    /// Recognize an image, then read it again with the Tesseract packs for the
+    /// languages found in the first pass, one pack per language on mixed images.
+    ///
+    /// The first pass uses `first_pass` when given and installed, `OCRConfig.language` otherwise.
+    fn recognize_detecting_language(&self, image: &Image, first_pass: Option<&str>) -> Result<Recognition> {
+        let hinted = match first_pass.filter(|packs| *packs != self.config.language) {
+            Some(packs) => self.recognize_with_languages(image, packs)?.map(|first| (first, packs.to_string())),
+            None => None,
+        };
+        let (first, first_packs) = match hinted {
+            Some(hinted) => hinted,
+            None => (self.recognize_raw(image)?, self.config.language.clone()),
+        };
+        if !self.config.detect_language {
+            return Ok(first);
+        }
+
+        let mix = language::detect_mix(&first.text);
+        let packs = language::tesseract_languages(&mix, &self.config.available_languages);
+        let (mut recognition, packs) = match packs {
+            Some(packs) if packs != first_packs => match self.recognize_with_languages(image, &packs)? {
+                Some(second) => (second, packs),
+                None => (first, first_packs),
+            },
+            _ => (first, first_packs),
+        };
+
+        recognition.language = language::detect(&recognition.text).map(|detected| detected.with_ocr_languages(packs));
+        Ok(recognition)
+    }
+
+    /// Run Tesseract with a language combination such as `deu+eng`. Returns `None`
+    /// when its packs are not installed, so the first pass is kept.
+    fn recognize_with_languages(&self, image: &Image, packs: &str) -> Result<Option<Recognition>> {
+        let mut engines = self
+            .language_engines
+            .lock()
+            .map_err(|_| anyhow::anyhow!("OCR language engines lock poisoned"))?;
+        let engine = engines
+            .entry(packs.to_string())
+            .or_insert_with(|| Tesseract::new(None, Some(packs)).ok());
+        match engine {
+            Some(tesseract) => Ok(Some(Self::recognize_with(tesseract, image)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Run Tesseract without post-processing the text
+    fn recognize_raw(&self, image: &Image) -> Result<Recognition> {
+        Self::recognize_with(&self.tesseract, image)
+    }
+
+    fn recognize_with(tesseract: &Tesseract, image: &Image) -> Result<Recognition> {
+        let result = tesseract.recognize(image)?;
+        let words = result
+            .words()
+            .iter()
+            .map(|word| {
+                let (x, y, width, height) = word.bounding_box();
+                WordBox {
+                    text: word.text().to_string(),
+                    bbox: BoundingBox { x, y, width, height },
+                    // Tesseract reports 0-100, and -1 for words it did not score
+                    confidence: (word.confidence() / 100.0).clamp(0.0, 1.0),
+                }
+            })
+            .collect();
+
+        Ok(Recognition { text: result.text, words, language: None })
+    }
+
+    /// Find ruled and whitespace-aligned tables among recognized words and rebuild their
+    /// rows and columns. `image` is the one the words were recognized in.
+    pub fn tables_from_words(&self, words: &[WordBox], image: &DynamicImage) -> Vec<Table> {
+        let config = TableConfig::default();
+        let rules = table::detect_rules(&image.to_luma8(), &config);
+        table::detect_tables(words, &rules, &config)
+    }
+
+    /// Find blocks of source code among recognized words.
+    ///
+    /// The text of each block is rebuilt from word positions and skips post-processing,
+    /// so indentation and symbols come through as they appeared on screen.
+    pub fn code_blocks_from_words(&self, words: &[WordBox]) -> Vec<CodeBlock> {
+        code::detect_code_blocks(words, &self.config.code)
+    }
+
+    /// Text of a recognition with formulas recognized separately and embedded as `$$ LaTeX $$`.
+    ///
+    /// Lines inside `code_blocks` are never treated as formulas. Images without
+    /// formula-like lines keep Tesseract's own layout.
+    pub fn text_with_math_from_words(
+        &self,
+        recognition: &Recognition,
+        image: &DynamicImage,
+        code_blocks: &[CodeBlock],
+    ) -> Result<String> {
+        let excluded: Vec<BoundingBox> = code_blocks.iter().map(|block| block.bbox).collect();
+        let regions = math::detect_math_regions(&recognition.words, self.formula_recognizer.config(), &excluded);
+        if regions.is_empty() {
+            return Ok(recognition.text.clone());
+        }
+
+        let mut formulas = Vec::new();
+        for region in regions {
+            let latex = self.formula_recognizer.recognize(image, &region)?;
+            formulas.push((region, latex));
+        }
+
+        let text = math::compose_text(&recognition.words, &formulas);
+        if self.config.extract_structured_data {
+            self.post_process_text(&text)
         } else {
-            Ok(text.text)
+            Ok(text)
         }
     }
 
+    /// Extract text from an already decoded image, such as a rasterized document page
+    pub async fn extract_text_from_image(&self, img: &DynamicImage) -> Result<String> {
+        let processed_img = if self.config.preprocess_image {
+            self.preprocess_image(img)
+        } else {
+            img.clone()
+        };
+        Ok(self.recognize_prepared(&Image::from_dynamic_image(&processed_img), None)?.text)
+    }
+
+    /// Extract text only from the parts of a captured frame that changed since the
+    /// last accepted frame. Returns `None` when the frame is a near-duplicate.
+    pub async fn extract_changed_text(
+        &self,
+        image_bytes: &[u8],
+        differ: &mut FrameDiffer,
+    ) -> Result<Option<(diff::FrameDiff, Recognition)>> {
+        let img = image::load_from_memory(image_bytes)?;
+        let frame_diff = differ.compare(&img);
+        if frame_diff.is_duplicate {
+            return Ok(None);
+        }
+
+        let mut texts = Vec::new();
+        let mut words = Vec::new();
+        let mut packs: Vec<String> = Vec::new();
+        for region in &frame_diff.regions {
+            let crop = img.crop_imm(region.x, region.y, region.width, region.height);
+            let processed = if self.config.preprocess_image {
+                self.preprocess_image(&crop)
+            } else {
+                crop
+            };
+
+            let recognition = self.recognize_detecting_language(&Image::from_dynamic_image(&processed), None)?;
+            if let Some(packs_used) = recognition.language.and_then(|detected| detected.ocr_languages) {
+                packs.extend(packs_used.split('+').map(str::to_string));
+            }
+            words.extend(recognition.words);
+            if !recognition.text.trim().is_empty() {
+                texts.push(recognition.text);
+            }
+        }
+
+        let text = texts.join("\n");
+        let text = if self.config.extract_structured_data {
+            self.post_process_text(&text)?
+        } else {
+            text
+        };
+
+        // Regions pick their packs one by one; the frame reports its main language and
+        // every pack that read part of it
+        packs.sort();
+        packs.dedup();
+        let language = language::detect(&text).map(|detected| {
+            if packs.is_empty() {
+                detected
+            } else {
+                detected.with_ocr_languages(packs.join("+"))
+            }
+        });
+
+        Ok(Some((frame_diff, Recognition { text, words, language })))
+    }
+
     /// Load and preprocess image for better OCR results
     fn load_and_preprocess_image(&self, image_path: &str) -> Result<Image> {
         let img = image::open(Path::new(image_path))?;
@@ -184,7 +500,10 @@ impl OCREngine {
                 // Add context-aware replacement logic here
             }
         }
-        
+
+        // Misread course names, product names and jargon
+        fixed = self.vocabulary.correct_ocr(&fixed);
+
         fixed
     }
 
@@ -246,6 +565,10 @@ mod tests {
         assert_eq!(config.confidence_threshold, 0.6);
         assert!(config.preprocess_image);
         assert!(config.extract_structured_data);
+        assert!(config.detect_tables);
+        assert!(config.recognize_math);
+        assert!(config.math.model_path.is_none());
+        assert!(config.detect_code);
     }
 
     #[tokio::test]
@@ -253,4 +576,18 @@ mod tests {
         let engine = OCREngine::new();
         assert!(engine.is_ok());
     }
+
+    #[test]
+    fn test_requested_languages_are_checked() {
+        let engine = OCREngine::with_config(OCRConfig {
+            available_languages: vec!["eng".to_string(), "deu".to_string()],
+            ..OCRConfig::default()
+        })
+        .unwrap();
+        assert!(engine.check_languages("deu").is_ok());
+        assert!(engine.check_languages("deu+eng").is_ok());
+        assert!(engine.check_languages("rus").is_err());
+        assert!(engine.check_languages("deu+").is_err());
+        assert!(engine.check_languages("../eng").is_err());
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+
+use super::table::{reading_lines, BoundingBox, WordBox};
+use crate::code::{self, LanguageGuess};
+
+/// Metadata key under which code blocks found in an image are listed
+pub const CODE_BLOCKS_METADATA_KEY: &str = "code_blocks";
+
+/// Configuration for code region detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeConfig {
+    /// Score at or above which an OCR line is treated as code
+    pub min_code_score: f32,
+    /// Minimum number of lines in a code block; single lines must score above `single_line_score`
+    pub min_lines: usize,
+    /// Score a lone line needs to count as a code block on its own
+    pub single_line_score: f32,
+    /// Vertical gap, in multiples of the line height, that ends a code block
+    pub line_gap_ratio: f32,
+}
+
+impl Default for CodeConfig {
+    fn default() -> Self {
+        Self {
+            min_code_score: 0.45,
+            min_lines: 2,
+            single_line_score: 0.8,
+            line_gap_ratio: 2.0,
+        }
+    }
+}
+
+/// A block of source code recognized in an image, with its layout preserved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeBlock {
+    pub bbox: BoundingBox,
+    /// Code with indentation and spacing rebuilt from word positions
+    pub text: String,
+    pub score: f32,
+    pub language: Option<LanguageGuess>,
+}
+
+/// Characters that are rare in prose but make up much of source code
+const CODE_SYMBOLS: &[char] = &[
+    '{', '}', '[', ']', '(', ')', ';', '=', '<', '>', '&', '|', '!', ':', '_', '#', '$', '*', '/', '\\', '"', '\'',
+];
+
+/// Tokens that start or define statements in common languages
+const CODE_KEYWORDS: &[&str] = &[
+    "fn", "let", "mut", "def", "class", "return", "if", "else", "elif", "for", "while", "import", "from", "const",
+    "var", "function", "public", "private", "static", "void", "func", "package", "struct", "impl", "use", "match",
+    "try", "except", "catch", "async", "await", "#include", "SELECT", "FROM", "WHERE", "echo", "int", "string",
+];
+
+/// How strongly a line of OCR words looks like source code, in `0.0..=1.0`
+pub fn code_score(words: &[&WordBox]) -> f32 {
+    let text = words.iter().map(|word| word.text.trim()).collect::<Vec<_>>().join(" ");
+    let visible: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    if visible.is_empty() {
+        return 0.0;
+    }
+
+    let symbol_density = visible.iter().filter(|c| CODE_SYMBOLS.contains(c)).count() as f32 / visible.len() as f32;
+    let has_keyword = words.iter().any(|word| {
+        let token = word.text.trim().trim_end_matches([':', '(', '{']);
+        CODE_KEYWORDS.contains(&token)
+    });
+    let code_ending = text.ends_with([';', '{', '}', ':', ')', ',']) || text == "}";
+    let identifier_call = text.contains("::") || text.contains("->") || text.contains("=>") || text.contains("().");
+
+    let mut score = (symbol_density * 2.5).min(0.5);
+    if has_keyword {
+        score += 0.25;
+    }
+    if code_ending {
+        score += 0.15;
+    }
+    if identifier_call {
+        score += 0.1;
+    }
+    score.min(1.0)
+}
+
+/// Find runs of code-like lines and rebuild their text with indentation preserved
+pub fn detect_code_blocks(words: &[WordBox], config: &CodeConfig) -> Vec<CodeBlock> {
+    let lines: Vec<Vec<&WordBox>> = reading_lines(words).into_iter().map(|(_, line)| line).collect();
+    let scores: Vec<f32> = lines.iter().map(|line| code_score(line)).collect();
+
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while start < lines.len() {
+        if scores[start] < config.min_code_score {
+            start += 1;
+            continue;
+        }
+
+        // Extend the run through code lines, tolerating a single prose-looking line
+        // (a comment or a bare identifier) between two code lines
+        let mut end = start + 1;
+        while end < lines.len() {
+            let gap = lines[end][0].bbox.y.saturating_sub(lines[end - 1][0].bbox.bottom()) as f32;
+            if gap > lines[end - 1][0].bbox.height as f32 * config.line_gap_ratio {
+                break;
+            }
+            let bridged = scores[end] < config.min_code_score
+                && scores.get(end + 1).is_some_and(|next| *next >= config.min_code_score);
+            if scores[end] >= config.min_code_score || bridged {
+                end += 1;
+            } else {
+                break;
+            }
+        }
+
+        let run = &lines[start..end];
+        let score = scores[start..end].iter().sum::<f32>() / run.len() as f32;
+        if run.len() >= config.min_lines || score >= config.single_line_score {
+            let text = layout_text(run);
+            blocks.push(CodeBlock {
+                bbox: run
+                    .iter()
+                    .flatten()
+                    .skip(1)
+                    .fold(run[0][0].bbox, |acc, word| acc.union(&word.bbox)),
+                language: code::detect_language(&text),
+                text,
+                score,
+            });
+        }
+        start = end;
+    }
+
+    blocks
+}
+
+/// Rebuild lines of words as text, turning horizontal offsets back into spaces.
+///
+/// Code is usually set in a monospace font, so the median glyph width converts pixel
+/// offsets into columns and indentation survives recognition.
+pub fn layout_text(lines: &[Vec<&WordBox>]) -> String {
+    let mut widths: Vec<f32> = lines
+        .iter()
+        .flatten()
+        .filter(|word| !word.text.trim().is_empty())
+        .map(|word| word.bbox.width as f32 / word.text.trim().chars().count() as f32)
+        .collect();
+    if widths.is_empty() {
+        return String::new();
+    }
+    widths.sort_by(|a, b| a.total_cmp(b));
+    let char_width = widths[widths.len() / 2].max(1.0);
+    let left = lines.iter().flatten().map(|word| word.bbox.x).min().unwrap_or(0);
+
+    lines
+        .iter()
+        .map(|line| {
+            let mut text = String::new();
+            let mut column = 0usize;
+            for word in line {
+                let target = ((word.bbox.x - left) as f32 / char_width).round() as usize;
+                let spaces = if text.is_empty() { target } else { target.saturating_sub(column).max(1) };
+                text.push_str(&" ".repeat(spaces));
+                text.push_str(word.text.trim());
+                column = text.chars().count();
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lay out words in a 10px-per-character monospace grid
+    fn monospace(lines: &[&str]) -> Vec<WordBox> {
+        let mut words = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            let mut column = 0;
+            for token in line.split(' ') {
+                if !token.is_empty() {
+                    words.push(WordBox {
+                        text: token.to_string(),
+                        bbox: BoundingBox {
+                            x: 20 + column as u32 * 10,
+                            y: 20 + row as u32 * 24,
+                            width: token.chars().count() as u32 * 10,
+                            height: 16,
+                        },
+                        confidence: 0.9,
+                    });
+                }
+                column += token.chars().count() + 1;
+            }
+        }
+        words
+    }
+
+    #[test]
+    fn test_layout_preserves_indentation() {
+        let source = ["def total(items):", "    result = 0", "    for item in items:", "        result += item", "    return result"];
+        let words = monospace(&source);
+        let lines: Vec<Vec<&WordBox>> = reading_lines(&words).into_iter().map(|(_, line)| line).collect();
+
+        assert_eq!(layout_text(&lines), source.join("\n"));
+    }
+
+    #[test]
+    fn test_code_block_separated_from_prose() {
+        let mut words = monospace(&["Here is the function I wrote yesterday", "", "fn main() {", "    let mut count = 0;", "    count += 1;", "}"]);
+        words.extend(monospace(&["", "", "", "", "", "", "", "", "", "", "Thanks for taking a look at it"]));
+
+        let blocks = detect_code_blocks(&words, &CodeConfig::default());
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].text.starts_with("fn main() {\n    let mut count = 0;"));
+        assert_eq!(blocks[0].language.unwrap().language, crate::code::ProgrammingLanguage::Rust);
+    }
+}
@@ -0,0 +1,380 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Metadata key under which the perceptual hash of a captured frame is stored
+pub const FRAME_HASH_METADATA_KEY: &str = "frame_hash";
+
+/// Configuration for screen-change detection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameDiffConfig {
+    /// Hamming distance at or below which two frame hashes are considered the same screen
+    pub hash_threshold: u32,
+    /// Number of columns the frame is split into for region diffing
+    pub grid_columns: u32,
+    /// Number of rows the frame is split into for region diffing
+    pub grid_rows: u32,
+    /// Mean absolute luminance difference (0-255) above which a cell counts as changed
+    pub cell_threshold: f32,
+    /// Fraction of changed cells below which a frame is treated as a near-duplicate
+    pub min_changed_fraction: f32,
+    /// Fraction of changed cells above which the whole frame is re-OCR'd instead of regions
+    pub full_frame_fraction: f32,
+}
+
+impl Default for FrameDiffConfig {
+    fn default() -> Self {
+        Self {
+            hash_threshold: 4,
+            grid_columns: 16,
+            grid_rows: 12,
+            cell_threshold: 12.0,
+            min_changed_fraction: 0.005,
+            full_frame_fraction: 0.5,
+        }
+    }
+}
+
+/// 64-bit difference hash of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PerceptualHash(pub u64);
+
+impl PerceptualHash {
+    /// Compute the difference hash (dHash) of an image
+    pub fn compute(image: &DynamicImage) -> Self {
+        let small = imageops::resize(&image.to_luma8(), 9, 8, FilterType::Triangle);
+        let mut bits = 0u64;
+
+        for y in 0..8 {
+            for x in 0..8 {
+                bits <<= 1;
+                if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                    bits |= 1;
+                }
+            }
+        }
+
+        Self(bits)
+    }
+
+    /// Number of differing bits between two hashes
+    pub fn distance(&self, other: &PerceptualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    /// Parse a hash previously rendered with `to_string`
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        u64::from_str_radix(hex, 16).ok().map(Self)
+    }
+}
+
+impl fmt::Display for PerceptualHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Rectangle of a frame, in source pixel coordinates, whose content changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Outcome of comparing a frame against the previously accepted one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameDiff {
+    pub hash: PerceptualHash,
+    /// Distance to the previous frame's hash, `None` for the first frame
+    pub hash_distance: Option<u32>,
+    /// Fraction of grid cells whose content changed
+    pub changed_fraction: f32,
+    /// Changed areas, merged from adjacent changed cells
+    pub regions: Vec<ChangedRegion>,
+    /// True when the frame adds nothing over the previous one
+    pub is_duplicate: bool,
+    /// True when the whole frame should be OCR'd rather than individual regions
+    pub full_frame: bool,
+}
+
+/// Reference frame kept between comparisons
+struct ReferenceFrame {
+    hash: PerceptualHash,
+    width: u32,
+    height: u32,
+    thumbnail: GrayImage,
+}
+
+/// Detects material changes between consecutive screen captures
+pub struct FrameDiffer {
+    config: FrameDiffConfig,
+    reference: Option<ReferenceFrame>,
+}
+
+impl FrameDiffer {
+    /// Create a new frame differ
+    pub fn new() -> Self {
+        Self::with_config(FrameDiffConfig::default())
+    }
+
+    /// Create frame differ with custom configuration
+    pub fn with_config(config: FrameDiffConfig) -> Self {
+        Self {
+            config,
+            reference: None,
+        }
+    }
+
+    /// Compare a frame against the last accepted frame.
+    ///
+    /// Frames that are not duplicates become the new reference; duplicates leave
+    /// the reference untouched so slow drifts still accumulate into a change.
+    pub fn compare(&mut self, image: &DynamicImage) -> FrameDiff {
+        let hash = PerceptualHash::compute(image);
+        let thumbnail = self.thumbnail(image);
+        let (width, height) = (image.width(), image.height());
+
+        let diff = match &self.reference {
+            Some(reference) if reference.width == width && reference.height == height => {
+                let hash_distance = hash.distance(&reference.hash);
+                let changed = self.changed_cells(&reference.thumbnail, &thumbnail);
+                let changed_fraction =
+                    changed.len() as f32 / (self.config.grid_columns * self.config.grid_rows) as f32;
+                let is_duplicate = changed_fraction < self.config.min_changed_fraction
+                    && hash_distance <= self.config.hash_threshold;
+                let full_frame = changed_fraction >= self.config.full_frame_fraction;
+                let regions = if is_duplicate {
+                    Vec::new()
+                } else if full_frame {
+                    vec![Self::whole_frame(width, height)]
+                } else {
+                    self.merge_cells(&changed, width, height)
+                };
+
+                FrameDiff {
+                    hash,
+                    hash_distance: Some(hash_distance),
+                    changed_fraction,
+                    regions,
+                    is_duplicate,
+                    full_frame,
+                }
+            }
+            _ => FrameDiff {
+                hash,
+                hash_distance: None,
+                changed_fraction: 1.0,
+                regions: vec![Self::whole_frame(width, height)],
+                is_duplicate: false,
+                full_frame: true,
+            },
+        };
+
+        if !diff.is_duplicate {
+            self.reference = Some(ReferenceFrame {
+                hash,
+                width,
+                height,
+                thumbnail,
+            });
+        }
+
+        diff
+    }
+
+    /// Forget the reference frame so the next capture is treated as new
+    pub fn reset(&mut self) {
+        self.reference = None;
+    }
+
+    /// Downscale a frame so each grid cell covers an 8x8 block of pixels
+    fn thumbnail(&self, image: &DynamicImage) -> GrayImage {
+        imageops::resize(
+            &image.to_luma8(),
+            self.config.grid_columns * 8,
+            self.config.grid_rows * 8,
+            FilterType::Triangle,
+        )
+    }
+
+    /// Grid cells whose mean absolute luminance difference exceeds the threshold
+    fn changed_cells(&self, previous: &GrayImage, current: &GrayImage) -> Vec<(u32, u32)> {
+        let mut changed = Vec::new();
+
+        for row in 0..self.config.grid_rows {
+            for col in 0..self.config.grid_columns {
+                let mut total = 0u32;
+                for y in row * 8..(row + 1) * 8 {
+                    for x in col * 8..(col + 1) * 8 {
+                        let a = previous.get_pixel(x, y)[0] as i32;
+                        let b = current.get_pixel(x, y)[0] as i32;
+                        total += (a - b).unsigned_abs();
+                    }
+                }
+
+                if total as f32 / 64.0 > self.config.cell_threshold {
+                    changed.push((col, row));
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Merge adjacent changed cells into bounding boxes in source pixel coordinates
+    fn merge_cells(&self, cells: &[(u32, u32)], width: u32, height: u32) -> Vec<ChangedRegion> {
+        let pending: HashSet<(u32, u32)> = cells.iter().copied().collect();
+        let mut visited = HashSet::new();
+        let mut regions = Vec::new();
+
+        for &start in cells {
+            if !visited.insert(start) {
+                continue;
+            }
+
+            let (mut min_col, mut min_row, mut max_col, mut max_row) = (start.0, start.1, start.0, start.1);
+            let mut stack = vec![start];
+            while let Some((col, row)) = stack.pop() {
+                min_col = min_col.min(col);
+                min_row = min_row.min(row);
+                max_col = max_col.max(col);
+                max_row = max_row.max(row);
+
+                let neighbours = [
+                    (col.wrapping_sub(1), row),
+                    (col + 1, row),
+                    (col, row.wrapping_sub(1)),
+                    (col, row + 1),
+                ];
+                for neighbour in neighbours {
+                    if pending.contains(&neighbour) && visited.insert(neighbour) {
+                        stack.push(neighbour);
+                    }
+                }
+            }
+
+            // Pad by half a cell so text straddling a cell border is not clipped
+            let cell_width = width as f32 / self.config.grid_columns as f32;
+            let cell_height = height as f32 / self.config.grid_rows as f32;
+            let x0 = ((min_col as f32 - 0.5) * cell_width).max(0.0) as u32;
+            let y0 = ((min_row as f32 - 0.5) * cell_height).max(0.0) as u32;
+            let x1 = (((max_col + 1) as f32 + 0.5) * cell_width).min(width as f32) as u32;
+            let y1 = (((max_row + 1) as f32 + 0.5) * cell_height).min(height as f32) as u32;
+
+            regions.push(ChangedRegion {
+                x: x0,
+                y: y0,
+                width: x1.saturating_sub(x0).max(1),
+                height: y1.saturating_sub(y0).max(1),
+            });
+        }
+
+        regions.sort_by_key(|region| (region.y, region.x));
+        regions
+    }
+
+    fn whole_frame(width: u32, height: u32) -> ChangedRegion {
+        ChangedRegion {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+}
+
+impl Default for FrameDiffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Word-level Jaccard similarity between two OCR'd texts, in `0.0..=1.0`
+pub fn text_similarity(a: &str, b: &str) -> f32 {
+    let words = |text: &str| -> HashSet<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect()
+    };
+
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let union = a.union(&b).count();
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, RgbImage, Rgb};
+
+    fn slide(with_bullet: bool) -> DynamicImage {
+        let mut img = RgbImage::from_pixel(320, 240, Rgb([255, 255, 255]));
+        // Title bar
+        for y in 10..40 {
+            for x in 20..300 {
+                img.put_pixel(x, y, Rgb([0, 0, 0]));
+            }
+        }
+        // A bullet added further down the slide
+        if with_bullet {
+            for y in 180..200 {
+                for x in 40..120 {
+                    img.put_pixel(x, y, Rgb([0, 0, 0]));
+                }
+            }
+        }
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_identical_frames_are_duplicates() {
+        let mut differ = FrameDiffer::new();
+        let first = differ.compare(&slide(false));
+        assert!(!first.is_duplicate);
+        assert!(first.full_frame);
+
+        let second = differ.compare(&slide(false));
+        assert!(second.is_duplicate);
+        assert_eq!(second.hash_distance, Some(0));
+        assert!(second.regions.is_empty());
+    }
+
+    #[test]
+    fn test_only_changed_region_reported() {
+        let mut differ = FrameDiffer::new();
+        differ.compare(&slide(false));
+
+        let diff = differ.compare(&slide(true));
+        assert!(!diff.is_duplicate);
+        assert!(!diff.full_frame);
+        assert_eq!(diff.regions.len(), 1);
+
+        let region = diff.regions[0];
+        assert!(region.x <= 40 && region.x + region.width >= 120);
+        assert!(region.y <= 180 && region.y + region.height >= 200);
+        assert!(region.y > 40, "Unchanged title should not be re-OCR'd");
+    }
+
+    #[test]
+    fn test_hash_round_trip_and_distance() {
+        let blank = DynamicImage::ImageLuma8(GrayImage::from_pixel(64, 64, Luma([255])));
+        let gradient = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 64, |x, _| Luma([(x * 4) as u8])));
+
+        let a = PerceptualHash::compute(&blank);
+        let b = PerceptualHash::compute(&gradient);
+        assert!(a.distance(&b) > 32);
+        assert_eq!(PerceptualHash::from_hex(&b.to_string()), Some(b));
+
+        assert!(text_similarity("Binary search trees", "binary  search trees!") > 0.99);
+        assert!(text_similarity("Binary search trees", "Hash tables") < 0.2);
+    }
+}
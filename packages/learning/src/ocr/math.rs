@@ -0,0 +1,372 @@
+use anyhow::{anyhow, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use tokenizers::Tokenizer;
+
+use super::table::{reading_lines, reading_order, BoundingBox, WordBox};
+
+/// Marker placed before and after each recognized formula in extracted text
+pub const MATH_DELIMITER: &str = "$$";
+
+/// Metadata key under which the formulas found in a text are listed
+pub const EQUATIONS_METADATA_KEY: &str = "equations";
+
+/// Configuration for formula detection and recognition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MathConfig {
+    /// TorchScript image-to-LaTeX model; without one, formulas are transcribed from the OCR text
+    pub model_path: Option<String>,
+    /// `tokenizer.json` matching the model's output vocabulary
+    pub tokenizer_path: Option<String>,
+    /// Height formula crops are resized to before inference
+    pub input_height: u32,
+    /// Score at or above which an OCR line is treated as a formula
+    pub min_math_score: f32,
+}
+
+impl Default for MathConfig {
+    fn default() -> Self {
+        Self {
+            model_path: None,
+            tokenizer_path: None,
+            input_height: 64,
+            min_math_score: 0.5,
+        }
+    }
+}
+
+/// A line of the image that looks like a formula
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MathRegion {
+    pub bbox: BoundingBox,
+    /// Tesseract's reading of the region, used as a fallback transcription
+    pub ocr_text: String,
+    pub score: f32,
+    /// Index of the first word of the region in reading order
+    pub first_word: usize,
+    /// Number of words covered by the region
+    pub word_count: usize,
+}
+
+/// Symbols that rarely appear in prose but are common in equations
+const MATH_SYMBOLS: &[char] = &[
+    '=', '+', '^', '_', '√', '∑', '∫', '∏', '∂', '∞', '≤', '≥', '≠', '≈', '±', '×', '÷', '·', 'π', 'θ', 'λ', 'μ',
+    'σ', 'α', 'β', 'γ', 'δ', 'Δ', 'Σ', '∈', '→', '/', '<', '>', '|',
+];
+
+/// Unicode symbols and their LaTeX commands, used for the model-less fallback
+const LATEX_SYMBOLS: &[(&str, &str)] = &[
+    ("≤", "\\leq "),
+    ("≥", "\\geq "),
+    ("≠", "\\neq "),
+    ("≈", "\\approx "),
+    ("±", "\\pm "),
+    ("×", "\\times "),
+    ("÷", "\\div "),
+    ("·", "\\cdot "),
+    ("∞", "\\infty "),
+    ("∑", "\\sum "),
+    ("Σ", "\\sum "),
+    ("∏", "\\prod "),
+    ("∫", "\\int "),
+    ("∂", "\\partial "),
+    ("∈", "\\in "),
+    ("→", "\\to "),
+    ("π", "\\pi "),
+    ("θ", "\\theta "),
+    ("λ", "\\lambda "),
+    ("μ", "\\mu "),
+    ("σ", "\\sigma "),
+    ("α", "\\alpha "),
+    ("β", "\\beta "),
+    ("γ", "\\gamma "),
+    ("δ", "\\delta "),
+    ("Δ", "\\Delta "),
+];
+
+/// How strongly a line of OCR words looks like a formula, in `0.0..=1.0`.
+///
+/// Combines the share of math-looking tokens with Tesseract's low confidence on
+/// equations, which it routinely misreads.
+pub fn math_score(words: &[&WordBox]) -> f32 {
+    if words.is_empty() {
+        return 0.0;
+    }
+
+    let mathy = words
+        .iter()
+        .filter(|word| {
+            let text = word.text.trim();
+            let symbols = text.chars().filter(|c| MATH_SYMBOLS.contains(c)).count();
+            let single_variable = text.chars().count() == 1 && text.chars().all(|c| c.is_alphabetic());
+            let numeric = text.chars().all(|c| c.is_ascii_digit() || c == '.') && !text.is_empty();
+            symbols > 0 || single_variable || numeric
+        })
+        .count() as f32
+        / words.len() as f32;
+
+    let has_relation = words.iter().any(|word| word.text.contains(['=', '≤', '≥', '≈', '≠', '<', '>']));
+    let mean_confidence = words.iter().map(|word| word.confidence).sum::<f32>() / words.len() as f32;
+
+    let mut score = mathy * 0.7;
+    if has_relation {
+        score += 0.2;
+    }
+    if mean_confidence < 0.6 {
+        score += 0.1;
+    }
+    score.min(1.0)
+}
+
+/// Find lines whose words look like formulas.
+///
+/// Lines inside `excluded` areas, such as detected code blocks, are left alone even
+/// when they look like equations (`let x = 5;`).
+pub fn detect_math_regions(words: &[WordBox], config: &MathConfig, excluded: &[BoundingBox]) -> Vec<MathRegion> {
+    reading_lines(words)
+        .into_iter()
+        .filter_map(|(first_word, line)| {
+            let score = math_score(&line);
+            // A lone number or variable is not worth sending through the model
+            if line.len() < 2 || score < config.min_math_score {
+                return None;
+            }
+
+            let bbox = line
+                .iter()
+                .skip(1)
+                .fold(line[0].bbox, |acc, word| acc.union(&word.bbox));
+            let (center_x, center_y) = (bbox.x + bbox.width / 2, bbox.y + bbox.height / 2);
+            let is_excluded = excluded.iter().any(|area| {
+                (area.x..=area.right()).contains(&center_x) && (area.y..=area.bottom()).contains(&center_y)
+            });
+            if is_excluded {
+                return None;
+            }
+
+            Some(MathRegion {
+                bbox,
+                ocr_text: line.iter().map(|word| word.text.trim()).collect::<Vec<_>>().join(" "),
+                score,
+                first_word,
+                word_count: line.len(),
+            })
+        })
+        .collect()
+}
+
+/// Best-effort LaTeX from Tesseract's reading of a formula
+pub fn text_to_latex(text: &str) -> String {
+    let mut latex = text.trim().to_string();
+    for (symbol, command) in LATEX_SYMBOLS {
+        latex = latex.replace(symbol, command);
+    }
+
+    // √x and √(x + 1) become \sqrt{...}
+    while let Some(start) = latex.find('√') {
+        let rest = &latex[start + '√'.len_utf8()..];
+        let (argument, consumed) = if let Some(inner) = rest.strip_prefix('(') {
+            let end = inner.find(')').unwrap_or(inner.len());
+            (inner[..end].to_string(), (end + 2).min(rest.len()))
+        } else {
+            let end = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+            (rest[..end].to_string(), end)
+        };
+        latex = format!("{}\\sqrt{{{}}}{}", &latex[..start], argument.trim(), &rest[consumed..]);
+    }
+
+    latex.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Wrap LaTeX in the math markers used in extracted text
+pub fn embed_latex(latex: &str) -> String {
+    format!("{} {} {}", MATH_DELIMITER, latex.trim(), MATH_DELIMITER)
+}
+
+/// LaTeX of every formula embedded in a text with `embed_latex`
+pub fn extract_latex(text: &str) -> Vec<String> {
+    text.split(MATH_DELIMITER)
+        .skip(1)
+        .step_by(2)
+        .map(|latex| latex.trim().to_string())
+        .filter(|latex| !latex.is_empty())
+        .collect()
+}
+
+/// Rebuild the text of an image from its words, replacing formula lines with marked LaTeX
+pub fn compose_text(words: &[WordBox], formulas: &[(MathRegion, String)]) -> String {
+    let ordered = reading_order(words);
+    let mut lines: Vec<String> = Vec::new();
+
+    for (first_word, line) in reading_lines(words) {
+        match formulas.iter().find(|(region, _)| region.first_word == first_word) {
+            Some((_, latex)) => lines.push(embed_latex(latex)),
+            None => lines.push(
+                ordered[first_word..first_word + line.len()]
+                    .iter()
+                    .map(|word| word.text.trim())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Image-to-LaTeX recognizer running a TorchScript model on the CPU
+pub struct FormulaRecognizer {
+    config: MathConfig,
+    model: Option<tch::CModule>,
+    tokenizer: Option<Tokenizer>,
+}
+
+impl FormulaRecognizer {
+    /// Create a recognizer that transcribes formulas from OCR text only
+    pub fn new() -> Self {
+        Self {
+            config: MathConfig::default(),
+            model: None,
+            tokenizer: None,
+        }
+    }
+
+    /// Create a recognizer, loading the image-to-LaTeX model when one is configured
+    pub fn with_config(config: MathConfig) -> Result<Self> {
+        let (model, tokenizer) = match (&config.model_path, &config.tokenizer_path) {
+            (Some(model_path), Some(tokenizer_path)) => {
+                let model = tch::CModule::load_on_device(model_path, tch::Device::Cpu)?;
+                let tokenizer = Tokenizer::from_file(tokenizer_path).map_err(|e| anyhow!("{}", e))?;
+                (Some(model), Some(tokenizer))
+            }
+            _ => (None, None),
+        };
+
+        Ok(Self {
+            config,
+            model,
+            tokenizer,
+        })
+    }
+
+    pub fn config(&self) -> &MathConfig {
+        &self.config
+    }
+
+    /// Whether an image-to-LaTeX model is loaded
+    pub fn has_model(&self) -> bool {
+        self.model.is_some()
+    }
+
+    /// Recognize the formula in a cropped region, falling back to the OCR reading
+    pub fn recognize(&self, image: &DynamicImage, region: &MathRegion) -> Result<String> {
+        let (Some(model), Some(tokenizer)) = (&self.model, &self.tokenizer) else {
+            return Ok(text_to_latex(&region.ocr_text));
+        };
+
+        let crop = image.crop_imm(region.bbox.x, region.bbox.y, region.bbox.width, region.bbox.height);
+        let input = self.prepare_input(&crop.to_luma8());
+        let output = model.forward_ts(&[input])?;
+        let ids: Vec<u32> = Vec::<i64>::try_from(output.view([-1]))?
+            .into_iter()
+            .map(|id| id as u32)
+            .collect();
+
+        let latex = tokenizer.decode(&ids, true).map_err(|e| anyhow!("{}", e))?;
+        if latex.trim().is_empty() {
+            Ok(text_to_latex(&region.ocr_text))
+        } else {
+            Ok(latex.trim().to_string())
+        }
+    }
+
+    /// Resize to the model's input height, pad the width to a multiple of 32 and scale to 0..1
+    fn prepare_input(&self, crop: &GrayImage) -> tch::Tensor {
+        let height = self.config.input_height;
+        let width = ((crop.width() as f32 * height as f32 / crop.height().max(1) as f32) as u32).max(1);
+        let resized = image::imageops::resize(crop, width, height, FilterType::Triangle);
+        let padded_width = width.div_ceil(32) * 32;
+
+        let mut pixels = vec![1.0f32; (padded_width * height) as usize];
+        for (x, y, pixel) in resized.enumerate_pixels() {
+            pixels[(y * padded_width + x) as usize] = pixel[0] as f32 / 255.0;
+        }
+
+        tch::Tensor::from_slice(&pixels).view([1, 1, height as i64, padded_width as i64])
+    }
+}
+
+impl Default for FormulaRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(texts: &[&str], y: u32, confidence: f32) -> Vec<WordBox> {
+        let mut x = 0;
+        texts
+            .iter()
+            .map(|text| {
+                let word = WordBox {
+                    text: text.to_string(),
+                    bbox: BoundingBox {
+                        x,
+                        y,
+                        width: text.chars().count() as u32 * 8,
+                        height: 14,
+                    },
+                    confidence,
+                };
+                x += word.bbox.width + 6;
+                word
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_formula_lines_only() {
+        let mut words = line(&["The", "quadratic", "formula", "gives", "the", "roots"], 0, 0.92);
+        words.extend(line(&["x", "=", "(-b", "±", "√(b^2", "-", "4ac))", "/", "2a"], 30, 0.45));
+
+        let regions = detect_math_regions(&words, &MathConfig::default(), &[]);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].first_word, 6);
+        assert!(regions[0].bbox.y >= 30);
+
+        // The same line inside a code block stays code
+        let code_block = regions[0].bbox;
+        assert!(detect_math_regions(&words, &MathConfig::default(), &[code_block]).is_empty());
+    }
+
+    #[test]
+    fn test_text_to_latex_fallback() {
+        assert_eq!(text_to_latex("a² ≤ √(b + c) × π"), "a² \\leq \\sqrt{b + c} \\times \\pi");
+        assert_eq!(text_to_latex("∑ x_i ≠ ∞"), "\\sum x_i \\neq \\infty");
+    }
+
+    #[test]
+    fn test_formulas_embedded_with_markers() {
+        let mut words = line(&["Euler's", "identity"], 0, 0.9);
+        words.extend(line(&["e^(iπ)", "+", "1", "=", "0"], 30, 0.5));
+
+        let regions = detect_math_regions(&words, &MathConfig::default(), &[]);
+        let recognizer = FormulaRecognizer::new();
+        let image = DynamicImage::new_luma8(200, 60);
+        let formulas: Vec<(MathRegion, String)> = regions
+            .into_iter()
+            .map(|region| {
+                let latex = recognizer.recognize(&image, &region).unwrap();
+                (region, latex)
+            })
+            .collect();
+
+        let text = compose_text(&words, &formulas);
+        assert_eq!(text, "Euler's identity\n$$ e^(i\\pi ) + 1 = 0 $$");
+        assert_eq!(extract_latex(&text), vec!["e^(i\\pi ) + 1 = 0"]);
+    }
+}
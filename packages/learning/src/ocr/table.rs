@@ -0,0 +1,597 @@
+use image::GrayImage;
+use serde::{Deserialize, Serialize};
+
+/// Metadata key under which detected tables are attached to an analysis result
+pub const TABLES_METADATA_KEY: &str = "tables";
+
+/// Configuration for table recognition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableConfig {
+    /// Minimum number of rows for a region to count as a table
+    pub min_rows: usize,
+    /// Minimum number of columns for a region to count as a table
+    pub min_columns: usize,
+    /// Horizontal gap, in multiples of the line height, that separates two cells
+    pub cell_gap_ratio: f32,
+    /// Vertical gap, in multiples of the line height, that ends a table
+    pub row_gap_ratio: f32,
+    /// Luminance below which a pixel counts as ink when looking for ruling lines
+    pub rule_darkness: u8,
+    /// Minimum length of a ruling line as a fraction of the image width or height
+    pub min_rule_fraction: f32,
+}
+
+impl Default for TableConfig {
+    fn default() -> Self {
+        Self {
+            min_rows: 2,
+            min_columns: 2,
+            cell_gap_ratio: 1.2,
+            row_gap_ratio: 2.5,
+            rule_darkness: 128,
+            min_rule_fraction: 0.25,
+        }
+    }
+}
+
+/// Axis-aligned box in image pixel coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BoundingBox {
+    pub fn right(&self) -> u32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> u32 {
+        self.y + self.height
+    }
+
+    fn center_x(&self) -> f32 {
+        self.x as f32 + self.width as f32 / 2.0
+    }
+
+    fn center_y(&self) -> f32 {
+        self.y as f32 + self.height as f32 / 2.0
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        BoundingBox {
+            x,
+            y,
+            width: self.right().max(other.right()) - x,
+            height: self.bottom().max(other.bottom()) - y,
+        }
+    }
+}
+
+/// A recognized word and where it sits on the page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WordBox {
+    pub text: String,
+    pub bbox: BoundingBox,
+    /// Recognition confidence in `0.0..=1.0`
+    pub confidence: f32,
+}
+
+/// Words sorted top-to-bottom by line, then left-to-right
+pub fn reading_order(words: &[WordBox]) -> Vec<&WordBox> {
+    let mut sorted: Vec<&WordBox> = words.iter().filter(|word| !word.text.trim().is_empty()).collect();
+    sorted.sort_by(|a, b| {
+        let (ay, by) = (a.bbox.center_y(), b.bbox.center_y());
+        if (ay - by).abs() <= a.bbox.height.min(b.bbox.height) as f32 / 2.0 {
+            a.bbox.x.cmp(&b.bbox.x)
+        } else {
+            ay.total_cmp(&by)
+        }
+    });
+    sorted
+}
+
+/// Words grouped into lines in reading order, with the index of each line's first word
+pub(crate) fn reading_lines(words: &[WordBox]) -> Vec<(usize, Vec<&WordBox>)> {
+    let mut lines: Vec<(usize, Vec<&WordBox>)> = Vec::new();
+
+    for (index, word) in reading_order(words).into_iter().enumerate() {
+        match lines.last_mut() {
+            Some((_, line)) if (word.bbox.center_y() - line[0].bbox.center_y()).abs() <= line[0].bbox.height as f32 / 2.0 => {
+                line.push(word)
+            }
+            _ => lines.push((index, vec![word])),
+        }
+    }
+    lines
+}
+
+/// How a table's structure was recovered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableKind {
+    /// Cells delimited by drawn ruling lines
+    Ruled,
+    /// Cells delimited by aligned whitespace
+    Whitespace,
+}
+
+/// A table reconstructed from word bounding boxes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+    pub kind: TableKind,
+    pub bbox: BoundingBox,
+    /// Cell text, row by row; every row has the same number of columns
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn column_count(&self) -> usize {
+        self.rows.first().map(|row| row.len()).unwrap_or(0)
+    }
+
+    /// Render as RFC 4180 CSV
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            csv.push_str(&cells.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// Render as JSON records keyed by the header row
+    pub fn to_json(&self) -> serde_json::Value {
+        let Some((header, body)) = self.rows.split_first() else {
+            return serde_json::json!({ "columns": [], "records": [] });
+        };
+
+        let records: Vec<serde_json::Map<String, serde_json::Value>> = body
+            .iter()
+            .map(|row| {
+                header
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let key = if column.is_empty() { format!("column_{}", i + 1) } else { column.clone() };
+                        (key, serde_json::Value::String(row[i].clone()))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        serde_json::json!({ "columns": header, "records": records })
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Metadata value for a set of tables, carrying both the structured rows and CSV
+pub fn tables_metadata(tables: &[Table]) -> serde_json::Value {
+    serde_json::Value::Array(
+        tables
+            .iter()
+            .map(|table| {
+                serde_json::json!({
+                    "kind": table.kind,
+                    "bbox": table.bbox,
+                    "rows": table.rows,
+                    "csv": table.to_csv(),
+                    "json": table.to_json(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Horizontal and vertical ruling lines found in an image
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleLines {
+    /// Horizontal rules as (y, x_start, x_end)
+    pub horizontal: Vec<(u32, u32, u32)>,
+    /// Vertical rules as (x, y_start, y_end)
+    pub vertical: Vec<(u32, u32, u32)>,
+}
+
+/// Find long straight runs of dark pixels, merging adjacent pixel rows/columns into one rule
+pub fn detect_rules(image: &GrayImage, config: &TableConfig) -> RuleLines {
+    let (width, height) = image.dimensions();
+    let is_ink = |x: u32, y: u32| image.get_pixel(x, y)[0] < config.rule_darkness;
+
+    let min_horizontal = ((width as f32 * config.min_rule_fraction) as u32).max(2);
+    let mut horizontal: Vec<(u32, u32, u32)> = Vec::new();
+    for y in 0..height {
+        if let Some((start, end)) = longest_run((0..width).map(|x| is_ink(x, y))) {
+            if end - start >= min_horizontal {
+                match horizontal.last_mut() {
+                    // Thick rules span several pixel rows
+                    Some(last) if last.0 + 1 == y || last.0 + 2 == y => last.0 = y,
+                    _ => horizontal.push((y, start, end)),
+                }
+            }
+        }
+    }
+
+    let min_vertical = ((height as f32 * config.min_rule_fraction) as u32).max(2);
+    let mut vertical: Vec<(u32, u32, u32)> = Vec::new();
+    for x in 0..width {
+        if let Some((start, end)) = longest_run((0..height).map(|y| is_ink(x, y))) {
+            if end - start >= min_vertical {
+                match vertical.last_mut() {
+                    Some(last) if last.0 + 1 == x || last.0 + 2 == x => last.0 = x,
+                    _ => vertical.push((x, start, end)),
+                }
+            }
+        }
+    }
+
+    RuleLines { horizontal, vertical }
+}
+
+fn longest_run(pixels: impl Iterator<Item = bool>) -> Option<(u32, u32)> {
+    let mut best: Option<(u32, u32)> = None;
+    let mut start = None;
+
+    // A trailing `false` closes a run that reaches the image edge
+    for (i, ink) in pixels.chain(std::iter::once(false)).enumerate() {
+        let i = i as u32;
+        match (ink, start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if best.is_none_or(|(bs, be)| i - s > be - bs) {
+                    best = Some((s, i));
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    best
+}
+
+/// Reconstruct tables from OCR word boxes, using ruling lines when the image has them
+pub fn detect_tables(words: &[WordBox], rules: &RuleLines, config: &TableConfig) -> Vec<Table> {
+    let mut tables = Vec::new();
+    let mut used = vec![false; words.len()];
+
+    if let Some(table) = ruled_table(words, rules, config, &mut used) {
+        tables.push(table);
+    }
+
+    let remaining: Vec<&WordBox> = words
+        .iter()
+        .zip(&used)
+        .filter(|(_, used)| !**used)
+        .map(|(word, _)| word)
+        .collect();
+    tables.extend(whitespace_tables(&remaining, config));
+
+    tables.sort_by_key(|table| table.bbox.y);
+    tables
+}
+
+/// Build a grid from the horizontal and vertical rules and drop each word into its cell
+fn ruled_table(words: &[WordBox], rules: &RuleLines, config: &TableConfig, used: &mut [bool]) -> Option<Table> {
+    // Only horizontal rules within the vertical rules' span belong to the grid; others
+    // are underlines or separators elsewhere on the screen
+    let span_top = rules.vertical.iter().map(|rule| rule.1).min()?;
+    let span_bottom = rules.vertical.iter().map(|rule| rule.2).max()?;
+    let horizontal: Vec<(u32, u32, u32)> = rules
+        .horizontal
+        .iter()
+        .copied()
+        .filter(|rule| rule.0 + 3 >= span_top && rule.0 <= span_bottom + 3)
+        .collect();
+    if horizontal.len() < 2 {
+        return None;
+    }
+
+    let top = horizontal.first()?.0;
+    let bottom = horizontal.last()?.0;
+    let left = horizontal.iter().map(|rule| rule.1).min()?;
+    let right = horizontal.iter().map(|rule| rule.2).max()?;
+
+    let mut column_edges: Vec<u32> = vec![left];
+    for &(x, y_start, y_end) in &rules.vertical {
+        // Only rules that cross the table body separate columns
+        if x > left + 2 && x + 2 < right && y_start <= top + 2 && y_end + 2 >= bottom {
+            column_edges.push(x);
+        }
+    }
+    column_edges.push(right);
+    column_edges.dedup_by(|a, b| a.abs_diff(*b) <= 2);
+
+    let row_edges: Vec<u32> = horizontal.iter().map(|rule| rule.0).collect();
+    let row_count = row_edges.len() - 1;
+    let column_count = column_edges.len() - 1;
+    if row_count < config.min_rows || column_count < config.min_columns {
+        return None;
+    }
+
+    let mut cells = vec![vec![Vec::<&WordBox>::new(); column_count]; row_count];
+    for (word, used) in words.iter().zip(used.iter_mut()) {
+        let (cx, cy) = (word.bbox.center_x(), word.bbox.center_y());
+        let row = row_edges.windows(2).position(|w| cy >= w[0] as f32 && cy < w[1] as f32);
+        let column = column_edges.windows(2).position(|w| cx >= w[0] as f32 && cx < w[1] as f32);
+        if let (Some(row), Some(column)) = (row, column) {
+            cells[row][column].push(word);
+            *used = true;
+        }
+    }
+
+    let rows: Vec<Vec<String>> = cells
+        .into_iter()
+        .map(|row| row.into_iter().map(|cell| cell_text(&cell)).collect())
+        .filter(|row: &Vec<String>| row.iter().any(|cell| !cell.is_empty()))
+        .collect();
+    if rows.len() < config.min_rows {
+        return None;
+    }
+
+    Some(Table {
+        kind: TableKind::Ruled,
+        bbox: BoundingBox {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        },
+        rows,
+    })
+}
+
+/// A run of words on one line separated from its neighbours by a wide gap
+struct Cell<'a> {
+    words: Vec<&'a WordBox>,
+    bbox: BoundingBox,
+}
+
+/// Words sharing a baseline, split into cells
+struct Line<'a> {
+    cells: Vec<Cell<'a>>,
+    bbox: BoundingBox,
+}
+
+/// Find runs of consecutive lines whose cells line up into consistent columns
+fn whitespace_tables(words: &[&WordBox], config: &TableConfig) -> Vec<Table> {
+    let lines = group_lines(words, config);
+    let mut tables = Vec::new();
+    let mut run: Vec<&Line> = Vec::new();
+
+    for line in &lines {
+        let continues = line.cells.len() >= config.min_columns
+            && run.last().is_none_or(|previous| {
+                let gap = line.bbox.y.saturating_sub(previous.bbox.bottom()) as f32;
+                gap <= previous.bbox.height as f32 * config.row_gap_ratio
+            });
+
+        if continues {
+            run.push(line);
+        } else {
+            tables.extend(table_from_lines(&run, config));
+            run.clear();
+            if line.cells.len() >= config.min_columns {
+                run.push(line);
+            }
+        }
+    }
+    tables.extend(table_from_lines(&run, config));
+
+    tables
+}
+
+/// Group words into lines by vertical overlap, then split each line into cells at wide gaps
+fn group_lines<'a>(words: &[&'a WordBox], config: &TableConfig) -> Vec<Line<'a>> {
+    let mut sorted: Vec<&WordBox> = words.iter().copied().filter(|word| !word.text.trim().is_empty()).collect();
+    sorted.sort_by(|a, b| a.bbox.center_y().total_cmp(&b.bbox.center_y()));
+
+    let mut rows: Vec<Vec<&WordBox>> = Vec::new();
+    for word in sorted {
+        match rows.last_mut() {
+            Some(row) if {
+                let reference = row[0].bbox;
+                (word.bbox.center_y() - reference.center_y()).abs() <= reference.height as f32 / 2.0
+            } => row.push(word),
+            _ => rows.push(vec![word]),
+        }
+    }
+
+    rows.into_iter()
+        .map(|mut row| {
+            row.sort_by_key(|word| word.bbox.x);
+            let height = row.iter().map(|word| word.bbox.height).max().unwrap_or(1) as f32;
+            let mut cells: Vec<Cell> = Vec::new();
+
+            for word in row {
+                match cells.last_mut() {
+                    Some(cell) if (word.bbox.x as f32 - cell.bbox.right() as f32) < height * config.cell_gap_ratio => {
+                        cell.bbox = cell.bbox.union(&word.bbox);
+                        cell.words.push(word);
+                    }
+                    _ => cells.push(Cell {
+                        words: vec![word],
+                        bbox: word.bbox,
+                    }),
+                }
+            }
+
+            let bbox = cells
+                .iter()
+                .skip(1)
+                .fold(cells[0].bbox, |acc, cell| acc.union(&cell.bbox));
+            Line { cells, bbox }
+        })
+        .collect()
+}
+
+/// Derive columns from the union of cell extents and lay the cells out on that grid.
+///
+/// A cell that straddles two columns means the lines do not really align, so the run
+/// is rejected rather than producing a misleading table.
+fn table_from_lines(lines: &[&Line], config: &TableConfig) -> Option<Table> {
+    if lines.len() < config.min_rows {
+        return None;
+    }
+
+    let mut spans: Vec<(u32, u32)> = lines
+        .iter()
+        .flat_map(|line| line.cells.iter().map(|cell| (cell.bbox.x, cell.bbox.right())))
+        .collect();
+    spans.sort();
+    let mut columns: Vec<(u32, u32)> = Vec::new();
+    for (start, end) in spans {
+        match columns.last_mut() {
+            Some(column) if start <= column.1 => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    if columns.len() < config.min_columns {
+        return None;
+    }
+
+    let mut rows = Vec::new();
+    for line in lines {
+        let mut row = vec![String::new(); columns.len()];
+        for cell in &line.cells {
+            let column = columns
+                .iter()
+                .position(|&(start, end)| cell.bbox.x >= start && cell.bbox.right() <= end)?;
+            if !row[column].is_empty() {
+                return None;
+            }
+            row[column] = cell_text(&cell.words);
+        }
+        rows.push(row);
+    }
+
+    let bbox = lines
+        .iter()
+        .skip(1)
+        .fold(lines[0].bbox, |acc, line| acc.union(&line.bbox));
+
+    Some(Table {
+        kind: TableKind::Whitespace,
+        bbox,
+        rows,
+    })
+}
+
+fn cell_text(words: &[&WordBox]) -> String {
+    let mut words = words.to_vec();
+    words.sort_by(|a, b| {
+        // Reading order within a cell: top-to-bottom lines, then left-to-right
+        let same_line = (a.bbox.center_y() - b.bbox.center_y()).abs() <= a.bbox.height as f32 / 2.0;
+        if same_line {
+            a.bbox.x.cmp(&b.bbox.x)
+        } else {
+            a.bbox.y.cmp(&b.bbox.y)
+        }
+    });
+    words.iter().map(|word| word.text.trim()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn word(text: &str, x: u32, y: u32) -> WordBox {
+        WordBox {
+            text: text.to_string(),
+            bbox: BoundingBox {
+                x,
+                y,
+                width: text.len() as u32 * 8,
+                height: 12,
+            },
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_whitespace_aligned_table() {
+        let words = vec![
+            word("Quarterly", 10, 0),
+            word("report", 90, 0),
+            word("Name", 10, 40),
+            word("Score", 200, 40),
+            word("Grade", 320, 40),
+            word("Ada", 10, 60),
+            word("Lovelace", 42, 60),
+            word("98", 200, 60),
+            word("A", 320, 60),
+            word("Alan", 10, 80),
+            word("91,5", 200, 80),
+            word("A-", 320, 80),
+        ];
+
+        let tables = detect_tables(&words, &RuleLines::default(), &TableConfig::default());
+        assert_eq!(tables.len(), 1, "The title line is prose, not part of the table");
+
+        let table = &tables[0];
+        assert_eq!(table.kind, TableKind::Whitespace);
+        assert_eq!(table.rows[1], vec!["Ada Lovelace", "98", "A"]);
+        assert_eq!(table.to_csv(), "Name,Score,Grade\r\nAda Lovelace,98,A\r\nAlan,\"91,5\",A-\r\n");
+        assert_eq!(table.to_json()["records"][1]["Score"], "91,5");
+    }
+
+    #[test]
+    fn test_ruled_table_uses_grid_lines() {
+        // 2x2 grid with the second column's words sitting close to the first
+        let mut image = GrayImage::from_pixel(200, 100, Luma([255]));
+        for x in 10..190 {
+            for y in [10, 50, 90] {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+        for y in 10..=90 {
+            for x in [10, 60, 189] {
+                image.put_pixel(x, y, Luma([0]));
+            }
+        }
+
+        let config = TableConfig::default();
+        let rules = detect_rules(&image, &config);
+        assert_eq!(rules.horizontal.len(), 3);
+        assert_eq!(rules.vertical.len(), 3);
+
+        let words = vec![
+            word("Term", 14, 24),
+            word("Meaning", 64, 24),
+            word("O(n)", 14, 64),
+            word("Linear", 64, 64),
+            word("time", 116, 64),
+        ];
+        let tables = detect_tables(&words, &rules, &config);
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].kind, TableKind::Ruled);
+        assert_eq!(tables[0].rows, vec![vec!["Term", "Meaning"], vec!["O(n)", "Linear time"]]);
+    }
+
+    #[test]
+    fn test_prose_is_not_a_table() {
+        let words = vec![
+            word("Binary", 10, 0),
+            word("search", 66, 0),
+            word("halves", 122, 0),
+            word("the", 10, 20),
+            word("range", 42, 20),
+            word("each", 90, 20),
+            word("step", 130, 20),
+        ];
+
+        assert!(detect_tables(&words, &RuleLines::default(), &TableConfig::default()).is_empty());
+    }
+}
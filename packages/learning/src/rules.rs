@@ -0,0 +1,677 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use std::time::SystemTime;
+
+use crate::analysis::{ContentType, Priority, Recommendation, RecommendationCategory};
+use crate::code;
+use crate::diagnostics;
+use crate::llm::SentimentResult;
+
+/// Rule pack used when no rules path is configured
+const BUILTIN_RULES: &str = include_str!("../rules/default.toml");
+
+/// A declarative recommendation rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    /// Conditions that must all hold for the rule to fire
+    #[serde(default)]
+    pub when: RuleCondition,
+    /// Emit one recommendation per matching topic or entity instead of one per analysis
+    #[serde(default)]
+    pub for_each: Option<ForEach>,
+    pub category: RecommendationCategory,
+    pub priority: Priority,
+    /// Title template; `{{name}}` placeholders are filled from the analysis
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+    #[serde(default)]
+    pub confidence: RuleConfidence,
+}
+
+/// Conditions on an analysis; empty conditions always match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleCondition {
+    /// Content types the rule applies to
+    pub content_types: Vec<ContentType>,
+    /// Sentiment labels, such as `POSITIVE` or `NEGATIVE`
+    pub sentiment: Vec<String>,
+    pub min_sentiment_score: Option<f32>,
+    /// Topic names, matched case-insensitively; `*` matches any topic
+    pub topics: Vec<String>,
+    /// Entity kinds, such as `url`, `email`, `error_code` or `programming_language`
+    pub entities: Vec<String>,
+    /// Substrings of the analyzed text, matched case-insensitively
+    pub text_contains: Vec<String>,
+    /// Metadata keys that must be present
+    pub metadata_present: Vec<String>,
+    /// Metadata keys that must hold exactly these values
+    pub metadata_equals: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForEach {
+    Topic,
+    Entity,
+}
+
+/// A fixed confidence, or one taken from the analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RuleConfidence {
+    Fixed(f32),
+    From(ConfidenceSource),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidenceSource {
+    SentimentScore,
+}
+
+impl Default for RuleConfidence {
+    fn default() -> Self {
+        RuleConfidence::Fixed(0.7)
+    }
+}
+
+/// Something named in the analyzed text that rules can match on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    pub kind: String,
+    pub value: String,
+}
+
+static URL_PATTERN: LazyLock<regex::Regex> = LazyLock::new(|| regex::Regex::new(r"https?://[^\s)>\]]+").unwrap());
+static EMAIL_PATTERN: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b").unwrap());
+
+/// Pull URLs, email addresses, error codes, exception types, source files and the
+/// programming language out of a text
+pub fn extract_entities(text: &str) -> Vec<Entity> {
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut push = |kind: &str, value: &str| {
+        let entity = Entity {
+            kind: kind.to_string(),
+            value: value.to_string(),
+        };
+        if !entities.contains(&entity) {
+            entities.push(entity);
+        }
+    };
+
+    for url in URL_PATTERN.find_iter(text) {
+        push("url", url.as_str());
+    }
+    for email in EMAIL_PATTERN.find_iter(text) {
+        push("email", email.as_str());
+    }
+
+    for diagnostic in diagnostics::parse(text) {
+        if let Some(code) = &diagnostic.code {
+            push("error_code", code);
+        }
+        if let Some(kind) = &diagnostic.kind {
+            push("exception", kind);
+        }
+        if let Some(file) = &diagnostic.file {
+            push("file", file);
+        }
+    }
+
+    if let Some(guess) = code::detect_language(text) {
+        push("programming_language", guess.language.name());
+    }
+
+    entities
+}
+
+/// Everything a rule can look at
+#[derive(Debug, Clone)]
+pub struct RuleContext<'a> {
+    pub text: &'a str,
+    pub content_type: ContentType,
+    pub sentiment: Option<&'a SentimentResult>,
+    pub topics: &'a [String],
+    pub entities: Vec<Entity>,
+    pub metadata: &'a HashMap<String, serde_json::Value>,
+}
+
+impl<'a> RuleContext<'a> {
+    /// Context for an analysis, with entities extracted from the text
+    pub fn new(
+        text: &'a str,
+        content_type: ContentType,
+        sentiment: Option<&'a SentimentResult>,
+        topics: &'a [String],
+        metadata: &'a HashMap<String, serde_json::Value>,
+    ) -> Self {
+        Self {
+            text,
+            content_type,
+            sentiment,
+            topics,
+            entities: extract_entities(text),
+            metadata,
+        }
+    }
+
+    fn variables(&self) -> HashMap<String, String> {
+        let mut variables = HashMap::new();
+        variables.insert("content_type".to_string(), format!("{:?}", self.content_type));
+        if let Some(sentiment) = self.sentiment {
+            variables.insert("sentiment".to_string(), sentiment.label.clone());
+            variables.insert("sentiment_score".to_string(), format!("{:.2}", sentiment.score));
+        }
+        for (key, value) in self.metadata {
+            let value = match value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            variables.insert(format!("metadata.{}", key), value);
+        }
+        variables
+    }
+}
+
+impl RuleCondition {
+    fn matches(&self, context: &RuleContext) -> bool {
+        if !self.content_types.is_empty() && !self.content_types.contains(&context.content_type) {
+            return false;
+        }
+
+        if !self.sentiment.is_empty()
+            && !context
+                .sentiment
+                .is_some_and(|sentiment| self.sentiment.iter().any(|label| label.eq_ignore_ascii_case(&sentiment.label)))
+        {
+            return false;
+        }
+        if let Some(min_score) = self.min_sentiment_score {
+            if !context.sentiment.is_some_and(|sentiment| sentiment.score >= min_score) {
+                return false;
+            }
+        }
+
+        if !self.topics.is_empty() && self.matching_topics(context).is_empty() {
+            return false;
+        }
+        if !self.entities.is_empty() && self.matching_entities(context).is_empty() {
+            return false;
+        }
+
+        if !self.text_contains.is_empty() {
+            let text = context.text.to_lowercase();
+            if !self.text_contains.iter().any(|needle| text.contains(&needle.to_lowercase())) {
+                return false;
+            }
+        }
+
+        self.metadata_present.iter().all(|key| context.metadata.contains_key(key))
+            && self
+                .metadata_equals
+                .iter()
+                .all(|(key, expected)| context.metadata.get(key) == Some(expected))
+    }
+
+    fn matching_topics<'c>(&self, context: &RuleContext<'c>) -> Vec<&'c String> {
+        context
+            .topics
+            .iter()
+            .filter(|topic| {
+                self.topics.is_empty()
+                    || self
+                        .topics
+                        .iter()
+                        .any(|pattern| pattern == "*" || pattern.eq_ignore_ascii_case(topic))
+            })
+            .collect()
+    }
+
+    fn matching_entities<'c>(&self, context: &'c RuleContext) -> Vec<&'c Entity> {
+        context
+            .entities
+            .iter()
+            .filter(|entity| self.entities.is_empty() || self.entities.contains(&entity.kind))
+            .collect()
+    }
+}
+
+/// Fill `{{name}}` placeholders; unknown names are left as they are
+fn render(template: &str, variables: &HashMap<String, String>) -> String {
+    let mut rendered = template.to_string();
+    for (name, value) in variables {
+        rendered = rendered.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    rendered
+}
+
+impl Rule {
+    /// Recommendations produced by this rule, empty when its conditions do not hold
+    pub fn evaluate(&self, context: &RuleContext) -> Vec<Recommendation> {
+        if !self.when.matches(context) {
+            return Vec::new();
+        }
+
+        let variables = context.variables();
+        let bindings: Vec<HashMap<String, String>> = match self.for_each {
+            None => vec![variables],
+            Some(ForEach::Topic) => self
+                .when
+                .matching_topics(context)
+                .into_iter()
+                .map(|topic| {
+                    let mut variables = variables.clone();
+                    variables.insert("topic".to_string(), topic.clone());
+                    variables
+                })
+                .collect(),
+            Some(ForEach::Entity) => self
+                .when
+                .matching_entities(context)
+                .into_iter()
+                .map(|entity| {
+                    let mut variables = variables.clone();
+                    variables.insert("entity".to_string(), entity.value.clone());
+                    variables.insert("entity_kind".to_string(), entity.kind.clone());
+                    variables
+                })
+                .collect(),
+        };
+
+        let confidence = match &self.confidence {
+            RuleConfidence::Fixed(confidence) => *confidence,
+            RuleConfidence::From(ConfidenceSource::SentimentScore) => {
+                context.sentiment.map(|sentiment| sentiment.score).unwrap_or(0.5)
+            }
+        };
+
+        bindings
+            .iter()
+            .map(|variables| Recommendation {
+                category: self.category.clone(),
+                title: render(&self.title, variables),
+                description: render(&self.description, variables),
+                priority: self.priority.clone(),
+                action_items: self.action_items.iter().map(|item| render(item, variables)).collect(),
+                confidence,
+            })
+            .collect()
+    }
+}
+
+/// An ordered collection of rules, as loaded from one or more files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleSet {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// The rule pack shipped with the crate
+    pub fn builtin() -> Self {
+        Self::from_toml(BUILTIN_RULES).expect("built-in rule pack is valid")
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let rules: RuleSet = toml::from_str(source)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    pub fn from_yaml(source: &str) -> Result<Self> {
+        let rules: RuleSet = serde_yaml::from_str(source)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Load a `.toml`, `.yaml` or `.yml` rule file, or every rule file in a directory
+    /// in file name order
+    pub fn load(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            let mut rules = RuleSet::default();
+            for file in rule_files(path)? {
+                rules.rules.extend(Self::load(&file)?.rules);
+            }
+            rules.validate()?;
+            return Ok(rules);
+        }
+
+        let source = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&source),
+            Some("yaml") | Some("yml") => Self::from_yaml(&source),
+            _ => return Err(anyhow!("unsupported rule file {}", path.display())),
+        };
+        parsed.with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// Recommendations from every rule, in rule order
+    pub fn evaluate(&self, context: &RuleContext) -> Vec<Recommendation> {
+        self.rules.iter().flat_map(|rule| rule.evaluate(context)).collect()
+    }
+
+    fn validate(&self) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for rule in &self.rules {
+            if !seen.insert(rule.id.as_str()) {
+                return Err(anyhow!("duplicate rule id `{}`", rule.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rule_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            // Test case files live next to the rules they exercise
+            !name.contains(".cases.")
+                && matches!(path.extension().and_then(|ext| ext.to_str()), Some("toml" | "yaml" | "yml"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Latest modification time of a rule file or directory and the files in it
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let own = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    if !path.is_dir() {
+        return own;
+    }
+    rule_files(path)
+        .ok()?
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).and_then(|metadata| metadata.modified()).ok())
+        .chain(own)
+        .max()
+}
+
+struct LoadedRules {
+    rules: RuleSet,
+    modified: Option<SystemTime>,
+    last_error: Option<String>,
+}
+
+/// Rules loaded from disk and reloaded whenever the files change
+pub struct RuleEngine {
+    path: Option<PathBuf>,
+    state: RwLock<LoadedRules>,
+}
+
+impl RuleEngine {
+    /// Engine running the built-in rule pack
+    pub fn new() -> Self {
+        Self {
+            path: None,
+            state: RwLock::new(LoadedRules {
+                rules: RuleSet::builtin(),
+                modified: None,
+                last_error: None,
+            }),
+        }
+    }
+
+    /// Engine running the rules in a file or directory
+    pub fn from_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let rules = RuleSet::load(&path)?;
+        Ok(Self {
+            state: RwLock::new(LoadedRules {
+                rules,
+                modified: last_modified(&path),
+                last_error: None,
+            }),
+            path: Some(path),
+        })
+    }
+
+    /// Reload the rules if their files changed since the last load.
+    ///
+    /// A pack that fails to parse keeps the previous rules active; the error is
+    /// available from `last_error`. Returns whether new rules were loaded.
+    pub fn reload_if_changed(&self) -> bool {
+        let Some(path) = &self.path else {
+            return false;
+        };
+        let modified = last_modified(path);
+        if modified == self.state.read().unwrap().modified {
+            return false;
+        }
+
+        let mut state = self.state.write().unwrap();
+        state.modified = modified;
+        match RuleSet::load(path) {
+            Ok(rules) => {
+                state.rules = rules;
+                state.last_error = None;
+                true
+            }
+            Err(e) => {
+                state.last_error = Some(format!("{:#}", e));
+                false
+            }
+        }
+    }
+
+    /// Evaluate the current rules, picking up any edits first
+    pub fn evaluate(&self, context: &RuleContext) -> Vec<Recommendation> {
+        self.reload_if_changed();
+        self.state.read().unwrap().rules.evaluate(context)
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.state.read().unwrap().rules.rules.len()
+    }
+
+    /// Why the most recent reload failed, if it did
+    pub fn last_error(&self) -> Option<String> {
+        self.state.read().unwrap().last_error.clone()
+    }
+}
+
+impl Default for RuleEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An analysis fed to a rule pack together with the recommendations it should produce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleCase {
+    pub name: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default = "default_case_content_type")]
+    pub content_type: ContentType,
+    pub sentiment: Option<String>,
+    #[serde(default = "default_case_sentiment_score")]
+    pub sentiment_score: f32,
+    #[serde(default)]
+    pub topics: Vec<String>,
+    #[serde(default)]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// Titles that must be among the recommendations
+    #[serde(default)]
+    pub expect: Vec<String>,
+    /// Titles that must not be among the recommendations
+    #[serde(default)]
+    pub forbid: Vec<String>,
+}
+
+fn default_case_content_type() -> ContentType {
+    ContentType::Text
+}
+
+fn default_case_sentiment_score() -> f32 {
+    0.9
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RuleCaseFile {
+    #[serde(default)]
+    cases: Vec<RuleCase>,
+}
+
+/// A case whose recommendations did not match its expectations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseFailure {
+    pub name: String,
+    pub missing: Vec<String>,
+    pub unexpected: Vec<String>,
+    /// Titles the rules actually produced
+    pub produced: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarnessReport {
+    pub passed: usize,
+    pub failures: Vec<CaseFailure>,
+}
+
+impl HarnessReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Load rule pack test cases from a `.toml`, `.yaml` or `.yml` file with a `cases` list
+pub fn load_cases(path: &Path) -> Result<Vec<RuleCase>> {
+    let source = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let file: RuleCaseFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&source)?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&source)?,
+        _ => return Err(anyhow!("unsupported case file {}", path.display())),
+    };
+    Ok(file.cases)
+}
+
+/// Run test cases against a rule pack
+pub fn run_cases(rules: &RuleSet, cases: &[RuleCase]) -> HarnessReport {
+    let mut report = HarnessReport::default();
+
+    for case in cases {
+        let sentiment = case.sentiment.as_ref().map(|label| SentimentResult {
+            label: label.clone(),
+            score: case.sentiment_score,
+            text: case.text.clone(),
+        });
+        let context = RuleContext::new(
+            &case.text,
+            case.content_type.clone(),
+            sentiment.as_ref(),
+            &case.topics,
+            &case.metadata,
+        );
+        let produced: Vec<String> = rules.evaluate(&context).into_iter().map(|rec| rec.title).collect();
+
+        let missing: Vec<String> = case.expect.iter().filter(|title| !produced.contains(title)).cloned().collect();
+        let unexpected: Vec<String> = case.forbid.iter().filter(|title| produced.contains(title)).cloned().collect();
+        if missing.is_empty() && unexpected.is_empty() {
+            report.passed += 1;
+        } else {
+            report.failures.push(CaseFailure {
+                name: case.name.clone(),
+                missing,
+                unexpected,
+                produced,
+            });
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_pack_passes_its_cases() {
+        let cases = load_cases(&Path::new(env!("CARGO_MANIFEST_DIR")).join("rules/default.cases.toml")).unwrap();
+        let report = run_cases(&RuleSet::builtin(), &cases);
+        assert!(report.is_success(), "{:#?}", report.failures);
+        assert_eq!(report.passed, cases.len());
+    }
+
+    #[test]
+    fn test_yaml_rules_with_entities_and_metadata() {
+        let rules = RuleSet::from_yaml(
+            r#"
+rules:
+  - id: link-followup
+    when:
+      entities: [url]
+      content_types: [Screenshot]
+    for_each: entity
+    category: Productivity
+    priority: Low
+    title: "Bookmark {{entity}}"
+  - id: tables
+    when:
+      metadata_present: [tables]
+      metadata_equals: { word_count: 3 }
+    category: General
+    priority: Medium
+    title: "Export {{metadata.word_count}}-word table"
+"#,
+        )
+        .unwrap();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("tables".to_string(), serde_json::json!([]));
+        metadata.insert("word_count".to_string(), serde_json::json!(3));
+        let topics = Vec::new();
+        let context = RuleContext::new("see https://docs.rs", ContentType::Screenshot, None, &topics, &metadata);
+
+        let titles: Vec<String> = rules.evaluate(&context).into_iter().map(|rec| rec.title).collect();
+        assert_eq!(titles, vec!["Bookmark https://docs.rs", "Export 3-word table"]);
+
+        let no_metadata = HashMap::new();
+        let audio = RuleContext::new("see https://docs.rs", ContentType::Audio, None, &topics, &no_metadata);
+        assert!(rules.evaluate(&audio).is_empty());
+    }
+
+    #[test]
+    fn test_hot_reload_keeps_last_good_rules() {
+        let dir = std::env::temp_dir().join(format!("rules-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("pack.toml");
+        // Explicit, increasing modification times so the test does not depend on
+        // filesystem timestamp granularity
+        let base = SystemTime::now() + std::time::Duration::from_secs(60);
+        let write = |contents: &str, step: u64| {
+            std::fs::write(&file, contents).unwrap();
+            let modified = base + std::time::Duration::from_secs(step);
+            std::fs::File::options().write(true).open(&file).unwrap().set_modified(modified).unwrap();
+        };
+        let rule = |id: &str| format!("[[rules]]\nid = \"{}\"\ncategory = \"General\"\npriority = \"Low\"\ntitle = \"{}\"\n", id, id);
+
+        write(&rule("a"), 1);
+        let engine = RuleEngine::from_path(&dir).unwrap();
+        assert_eq!(engine.rule_count(), 1);
+        assert!(!engine.reload_if_changed());
+
+        write(&format!("{}{}", rule("a"), rule("b")), 2);
+        assert!(engine.reload_if_changed());
+        assert_eq!(engine.rule_count(), 2);
+
+        write("[[rules]]\nid = \"broken\"\n", 3);
+        assert!(!engine.reload_if_changed());
+        assert_eq!(engine.rule_count(), 2);
+        assert!(engine.last_error().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
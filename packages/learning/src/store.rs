@@ -0,0 +1,739 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::analysis::{AnalysisResult, ConversationTurn, ExtractedText, SessionContext};
+use crate::redact::{AuditEntry, RedactionAudit};
+
+/// File name of the session database inside the data directory
+pub const SESSION_DB_FILE: &str = "sessions.db";
+
+/// Schema migrations, applied in order. The index of each entry + 1 is the
+/// `user_version` the database reports once that migration has run.
+const MIGRATIONS: &[&str] = &[
+    // v1: sessions, conversation turns, extracted texts and analysis results
+    r#"
+    CREATE TABLE sessions (
+        session_id TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL,
+        last_updated TEXT NOT NULL,
+        analysis_summary TEXT
+    );
+
+    CREATE TABLE conversation_turns (
+        turn_id TEXT PRIMARY KEY,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        content_type TEXT NOT NULL,
+        content TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_turns_session ON conversation_turns(session_id, timestamp);
+
+    CREATE TABLE extracted_texts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        source TEXT NOT NULL,
+        text TEXT NOT NULL,
+        confidence REAL NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_texts_session ON extracted_texts(session_id, timestamp);
+
+    CREATE TABLE analysis_results (
+        analysis_id TEXT PRIMARY KEY,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        content_type TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_results_session ON analysis_results(session_id, timestamp);
+    "#,
+    // v2: audit of redacted PII and secrets; values are stored as fingerprints only
+    r#"
+    CREATE TABLE redaction_audit (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        analysis_id TEXT,
+        timestamp TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        policy TEXT NOT NULL,
+        field TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_redactions_session ON redaction_audit(session_id, timestamp);
+    "#,
+    // v3: turns and results are keyed per session, since a cached analysis can be
+    // recorded into several sessions; extracted texts recorded with an analysis are
+    // linked to it so re-recording replaces them
+    r#"
+    CREATE TABLE conversation_turns_v3 (
+        turn_id TEXT NOT NULL,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        content_type TEXT NOT NULL,
+        content TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (session_id, turn_id)
+    );
+    INSERT INTO conversation_turns_v3 (turn_id, session_id, timestamp, content_type, content, data)
+        SELECT turn_id, session_id, timestamp, content_type, content, data FROM conversation_turns;
+    DROP TABLE conversation_turns;
+    ALTER TABLE conversation_turns_v3 RENAME TO conversation_turns;
+    CREATE INDEX idx_turns_session ON conversation_turns(session_id, timestamp);
+
+    CREATE TABLE analysis_results_v3 (
+        analysis_id TEXT NOT NULL,
+        session_id TEXT NOT NULL REFERENCES sessions(session_id) ON DELETE CASCADE,
+        timestamp TEXT NOT NULL,
+        content_type TEXT NOT NULL,
+        summary TEXT NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (session_id, analysis_id)
+    );
+    INSERT INTO analysis_results_v3 (analysis_id, session_id, timestamp, content_type, summary, data)
+        SELECT analysis_id, session_id, timestamp, content_type, summary, data FROM analysis_results;
+    DROP TABLE analysis_results;
+    ALTER TABLE analysis_results_v3 RENAME TO analysis_results;
+    CREATE INDEX idx_results_session ON analysis_results(session_id, timestamp);
+
+    ALTER TABLE extracted_texts ADD COLUMN analysis_id TEXT;
+    CREATE UNIQUE INDEX idx_texts_analysis ON extracted_texts(session_id, analysis_id);
+    "#,
+];
+
+/// Pagination parameters for listing queries (pages start at 1)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Pagination {
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: 20,
+        }
+    }
+}
+
+impl Pagination {
+    /// Create pagination parameters, clamping to sane bounds
+    pub fn new(page: usize, per_page: usize) -> Self {
+        Self {
+            page: page.max(1),
+            per_page: per_page.clamp(1, 200),
+        }
+    }
+
+    fn offset(&self) -> i64 {
+        ((self.page.max(1) - 1) * self.per_page) as i64
+    }
+
+    fn limit(&self) -> i64 {
+        self.per_page as i64
+    }
+}
+
+/// Lightweight session row returned by list and search queries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionListing {
+    pub session_id: String,
+    pub created_at: DateTime<Utc>,
+    pub last_updated: DateTime<Utc>,
+    pub analysis_summary: Option<String>,
+    pub turn_count: usize,
+    pub preview: Option<String>,
+}
+
+/// A page of session listings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPage {
+    pub sessions: Vec<SessionListing>,
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+/// A conversation turn that matched a search query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnMatch {
+    pub session_id: String,
+    pub turn: ConversationTurn,
+}
+
+/// Embedded SQLite store for learning sessions
+pub struct SessionStore {
+    conn: Mutex<Connection>,
+    path: Option<PathBuf>,
+}
+
+impl SessionStore {
+    /// Open (or create) the session database at the given path
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = Connection::open(path)?;
+        Self::from_connection(conn, Some(path.to_path_buf()))
+    }
+
+    /// Open (or create) the session database inside a data directory
+    pub fn open_in_dir(data_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open(data_dir.as_ref().join(SESSION_DB_FILE))
+    }
+
+    /// Open a throwaway in-memory database (useful for tests)
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+        Self::from_connection(conn, None)
+    }
+
+    fn from_connection(mut conn: Connection, path: Option<PathBuf>) -> Result<Self> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        Self::migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            path,
+        })
+    }
+
+    /// Apply any pending schema migrations
+    fn migrate(conn: &mut Connection) -> Result<()> {
+        let current: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        if current as usize > MIGRATIONS.len() {
+            return Err(anyhow::anyhow!(
+                "Session database schema v{} is newer than supported v{}",
+                current,
+                MIGRATIONS.len()
+            ));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (index + 1) as u32)?;
+            tx.commit()?;
+        }
+
+        Ok(())
+    }
+
+    /// Path of the database file, if it is file-backed
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Current schema version
+    pub fn schema_version(&self) -> Result<u32> {
+        let conn = self.lock()?;
+        Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+    }
+
+    /// Create the session row if it does not exist yet
+    pub fn ensure_session(&self, session_id: &str, created_at: DateTime<Utc>) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, created_at, last_updated) VALUES (?1, ?2, ?2)",
+            params![session_id, created_at.to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Persist an analysis result as a turn of the given session.
+    /// The session row is created on first use. Recording the same analysis again
+    /// replaces the turn within that session; other sessions keep their own copy.
+    pub fn record_analysis(
+        &self,
+        session_id: &str,
+        created_at: DateTime<Utc>,
+        analysis: &AnalysisResult,
+    ) -> Result<()> {
+        let turn = ConversationTurn {
+            turn_id: analysis.analysis_id.clone(),
+            timestamp: analysis.timestamp,
+            content_type: analysis.content_type.clone(),
+            content: analysis.original_content.clone(),
+            analysis: Some(analysis.clone()),
+            user_feedback: None,
+        };
+
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, created_at, last_updated) VALUES (?1, ?2, ?2)",
+            params![session_id, created_at.to_rfc3339()],
+        )?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO conversation_turns
+                (turn_id, session_id, timestamp, content_type, content, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                turn.turn_id,
+                session_id,
+                turn.timestamp.to_rfc3339(),
+                format!("{:?}", turn.content_type),
+                turn.content,
+                serde_json::to_string(&turn)?,
+            ],
+        )?;
+
+        tx.execute(
+            "INSERT OR REPLACE INTO analysis_results
+                (analysis_id, session_id, timestamp, content_type, summary, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                analysis.analysis_id,
+                session_id,
+                analysis.timestamp.to_rfc3339(),
+                format!("{:?}", analysis.content_type),
+                analysis.summary,
+                serde_json::to_string(analysis)?,
+            ],
+        )?;
+
+        if let Some(extracted_text) = &analysis.extracted_text {
+            Self::insert_extracted_text(&tx, session_id, Some(&analysis.analysis_id), extracted_text)?;
+        }
+
+        tx.execute(
+            "UPDATE sessions SET last_updated = ?2 WHERE session_id = ?1",
+            params![session_id, Utc::now().to_rfc3339()],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Persist a standalone extracted text for a session
+    pub fn save_extracted_text(&self, session_id: &str, extracted_text: &ExtractedText) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, created_at, last_updated) VALUES (?1, ?2, ?2)",
+            params![session_id, Utc::now().to_rfc3339()],
+        )?;
+        Self::insert_extracted_text(&conn, session_id, None, extracted_text)
+    }
+
+    /// Insert an extracted text; one recorded with an analysis replaces the text
+    /// previously recorded with it in the same session
+    fn insert_extracted_text(
+        conn: &Connection,
+        session_id: &str,
+        analysis_id: Option<&str>,
+        extracted_text: &ExtractedText,
+    ) -> Result<()> {
+        conn.execute(
+            "INSERT INTO extracted_texts (session_id, analysis_id, timestamp, source, text, confidence, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(session_id, analysis_id) DO UPDATE SET
+                timestamp = excluded.timestamp,
+                source = excluded.source,
+                text = excluded.text,
+                confidence = excluded.confidence,
+                data = excluded.data",
+            params![
+                session_id,
+                analysis_id,
+                extracted_text.timestamp.to_rfc3339(),
+                format!("{:?}", extracted_text.source),
+                extracted_text.text,
+                extracted_text.confidence as f64,
+                serde_json::to_string(extracted_text)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Persist what was redacted from an analysis of the given session
+    pub fn record_redactions(&self, session_id: &str, audit: &RedactionAudit) -> Result<()> {
+        let mut conn = self.lock()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT OR IGNORE INTO sessions (session_id, created_at, last_updated) VALUES (?1, ?2, ?2)",
+            params![session_id, audit.timestamp.to_rfc3339()],
+        )?;
+
+        for entry in &audit.entries {
+            tx.execute(
+                "INSERT INTO redaction_audit (session_id, analysis_id, timestamp, kind, policy, field, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    session_id,
+                    audit.analysis_id,
+                    audit.timestamp.to_rfc3339(),
+                    entry.kind.name(),
+                    format!("{:?}", entry.policy),
+                    entry.field,
+                    serde_json::to_string(entry)?,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Load the redaction audit of a session, grouped by analysis in recording order
+    pub fn load_redactions(&self, session_id: &str) -> Result<Vec<RedactionAudit>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT analysis_id, timestamp, data FROM redaction_audit WHERE session_id = ?1 ORDER BY id",
+        )?;
+        let rows = stmt
+            .query_map(params![session_id], |row| {
+                Ok((
+                    row.get::<_, Option<String>>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut audits: Vec<RedactionAudit> = Vec::new();
+        for (analysis_id, timestamp, data) in rows {
+            let entry: AuditEntry = serde_json::from_str(&data)?;
+            let timestamp = parse_timestamp(&timestamp)?;
+            match audits.last_mut() {
+                Some(audit) if audit.analysis_id == analysis_id && audit.timestamp == timestamp => {
+                    audit.entries.push(entry)
+                }
+                _ => audits.push(RedactionAudit {
+                    analysis_id,
+                    timestamp,
+                    entries: vec![entry],
+                }),
+            }
+        }
+        Ok(audits)
+    }
+
+    /// Update the stored summary of a session
+    pub fn set_session_summary(&self, session_id: &str, summary: &str) -> Result<()> {
+        let conn = self.lock()?;
+        conn.execute(
+            "UPDATE sessions SET analysis_summary = ?2, last_updated = ?3 WHERE session_id = ?1",
+            params![session_id, summary, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Load a full session with its history
+    pub fn load_session(&self, session_id: &str) -> Result<Option<SessionContext>> {
+        let conn = self.lock()?;
+
+        let row = conn
+            .query_row(
+                "SELECT created_at, last_updated, analysis_summary FROM sessions WHERE session_id = ?1",
+                params![session_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let (created_at, last_updated, analysis_summary) = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT data FROM conversation_turns WHERE session_id = ?1 ORDER BY timestamp, turn_id",
+        )?;
+        let conversation_history = stmt
+            .query_map(params![session_id], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str::<ConversationTurn>(&data?)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT data FROM extracted_texts WHERE session_id = ?1 ORDER BY timestamp, id",
+        )?;
+        let extracted_texts = stmt
+            .query_map(params![session_id], |row| row.get::<_, String>(0))?
+            .map(|data| Ok(serde_json::from_str::<ExtractedText>(&data?)?))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(SessionContext {
+            session_id: session_id.to_string(),
+            conversation_history,
+            extracted_texts,
+            analysis_summary,
+            created_at: parse_timestamp(&created_at)?,
+            last_updated: parse_timestamp(&last_updated)?,
+        }))
+    }
+
+    /// Load a single stored analysis result, the most recently recorded copy if it
+    /// was recorded into several sessions
+    pub fn load_analysis(&self, analysis_id: &str) -> Result<Option<AnalysisResult>> {
+        let conn = self.lock()?;
+        let data = conn
+            .query_row(
+                "SELECT data FROM analysis_results WHERE analysis_id = ?1 ORDER BY timestamp DESC LIMIT 1",
+                params![analysis_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+
+        data.map(|data| Ok(serde_json::from_str(&data)?)).transpose()
+    }
+
+    /// List sessions, most recently updated first
+    pub fn list_sessions(&self, pagination: Pagination) -> Result<SessionPage> {
+        self.query_sessions(None, pagination)
+    }
+
+    /// List sessions whose summary or turn content contains the query
+    pub fn search_sessions(&self, query: &str, pagination: Pagination) -> Result<SessionPage> {
+        self.query_sessions(Some(query), pagination)
+    }
+
+    fn query_sessions(&self, query: Option<&str>, pagination: Pagination) -> Result<SessionPage> {
+        let conn = self.lock()?;
+        let pattern = query.map(like_pattern);
+
+        let filter = "WHERE ?1 IS NULL
+               OR s.analysis_summary LIKE ?1 ESCAPE '\\'
+               OR EXISTS (SELECT 1 FROM conversation_turns m
+                          WHERE m.session_id = s.session_id AND m.content LIKE ?1 ESCAPE '\\')";
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM sessions s {}", filter),
+            params![pattern],
+            |row| row.get(0),
+        )?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT s.session_id, s.created_at, s.last_updated, s.analysis_summary,
+                    (SELECT COUNT(*) FROM conversation_turns t WHERE t.session_id = s.session_id),
+                    (SELECT substr(t.content, 1, 200) FROM conversation_turns t
+                     WHERE t.session_id = s.session_id ORDER BY t.timestamp DESC LIMIT 1)
+             FROM sessions s {}
+             ORDER BY s.last_updated DESC
+             LIMIT ?2 OFFSET ?3",
+            filter
+        ))?;
+
+        let rows = stmt.query_map(
+            params![pattern, pagination.limit(), pagination.offset()],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            },
+        )?;
+
+        let mut sessions = Vec::new();
+        for row in rows {
+            let (session_id, created_at, last_updated, analysis_summary, turn_count, preview) = row?;
+            sessions.push(SessionListing {
+                session_id,
+                created_at: parse_timestamp(&created_at)?,
+                last_updated: parse_timestamp(&last_updated)?,
+                analysis_summary,
+                turn_count: turn_count as usize,
+                preview,
+            });
+        }
+
+        Ok(SessionPage {
+            sessions,
+            total: total as usize,
+            page: pagination.page,
+            per_page: pagination.per_page,
+        })
+    }
+
+    /// Search individual conversation turns across all sessions
+    pub fn search_turns(&self, query: &str, pagination: Pagination) -> Result<Vec<TurnMatch>> {
+        let conn = self.lock()?;
+        let mut stmt = conn.prepare(
+            "SELECT session_id, data FROM conversation_turns
+             WHERE content LIKE ?1 ESCAPE '\\'
+             ORDER BY timestamp DESC
+             LIMIT ?2 OFFSET ?3",
+        )?;
+
+        let rows = stmt.query_map(
+            params![like_pattern(query), pagination.limit(), pagination.offset()],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )?;
+
+        let mut matches = Vec::new();
+        for row in rows {
+            let (session_id, data) = row?;
+            matches.push(TurnMatch {
+                session_id,
+                turn: serde_json::from_str(&data)?,
+            });
+        }
+
+        Ok(matches)
+    }
+
+    /// Delete a session and everything recorded for it.
+    /// Returns false if the session did not exist.
+    pub fn delete_session(&self, session_id: &str) -> Result<bool> {
+        let conn = self.lock()?;
+        let deleted = conn.execute("DELETE FROM sessions WHERE session_id = ?1", params![session_id])?;
+        Ok(deleted > 0)
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Session store lock poisoned"))
+    }
+}
+
+/// Build a LIKE pattern that matches the query as a literal substring
+fn like_pattern(query: &str) -> String {
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::ContentType;
+
+    fn sample_analysis(id: &str, content: &str) -> AnalysisResult {
+        AnalysisResult {
+            analysis_id: id.to_string(),
+            timestamp: Utc::now(),
+            content_type: ContentType::Text,
+            original_content: content.to_string(),
+            extracted_text: Some(ExtractedText {
+                source: ContentType::Text,
+                text: content.to_string(),
+                confidence: 0.9,
+                timestamp: Utc::now(),
+                metadata: std::collections::HashMap::new(),
+                time_range: None,
+                language: None,
+            }),
+            sentiment: None,
+            topics: vec!["education".to_string()],
+            summary: content.to_string(),
+            insights: vec![],
+            confidence: 0.9,
+            context_used: false,
+            recommendations: vec![],
+            metadata: std::collections::HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_migrations_applied() {
+        let store = SessionStore::open_in_memory().unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_record_and_load_session() {
+        let store = SessionStore::open_in_memory().unwrap();
+        store.record_analysis("s1", Utc::now(), &sample_analysis("a1", "Ownership in Rust")).unwrap();
+        store.record_analysis("s1", Utc::now(), &sample_analysis("a2", "Borrowing rules")).unwrap();
+
+        let session = store.load_session("s1").unwrap().expect("session should exist");
+        assert_eq!(session.conversation_history.len(), 2);
+        assert_eq!(session.extracted_texts.len(), 2);
+        assert!(store.load_analysis("a2").unwrap().is_some());
+        assert!(store.load_session("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_same_analysis_recorded_into_two_sessions() {
+        let store = SessionStore::open_in_memory().unwrap();
+        let analysis = sample_analysis("a1", "Ownership in Rust");
+        store.record_analysis("s1", Utc::now(), &analysis).unwrap();
+        store.record_analysis("s1", Utc::now(), &analysis).unwrap();
+        store.record_analysis("s2", Utc::now(), &analysis).unwrap();
+
+        // Re-recording replaces the turn and its text, and never moves them to another session
+        for session_id in ["s1", "s2"] {
+            let session = store.load_session(session_id).unwrap().unwrap();
+            assert_eq!(session.conversation_history.len(), 1);
+            assert_eq!(session.extracted_texts.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_list_search_paginate_delete() {
+        let store = SessionStore::open_in_memory().unwrap();
+        for i in 0..5 {
+            let content = if i == 3 { "Lecture on 100% coverage" } else { "Lecture notes" };
+            store
+                .record_analysis(&format!("s{}", i), Utc::now(), &sample_analysis(&format!("a{}", i), content))
+                .unwrap();
+        }
+
+        let page = store.list_sessions(Pagination::new(2, 2)).unwrap();
+        assert_eq!(page.total, 5);
+        assert_eq!(page.sessions.len(), 2);
+
+        let found = store.search_sessions("100%", Pagination::default()).unwrap();
+        assert_eq!(found.total, 1);
+        assert_eq!(found.sessions[0].session_id, "s3");
+
+        let turns = store.search_turns("coverage", Pagination::default()).unwrap();
+        assert_eq!(turns.len(), 1);
+
+        assert!(store.delete_session("s3").unwrap());
+        assert!(!store.delete_session("s3").unwrap());
+        assert!(store.search_turns("coverage", Pagination::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_redaction_audit_round_trip() {
+        let store = SessionStore::open_in_memory().unwrap();
+        let redactor = crate::redact::Redactor::new();
+        let mut analysis = sample_analysis("a1", "Contact me at jane@example.com, password: hunter2");
+        let audit = redactor.redact_analysis(&mut analysis);
+        store.record_analysis("s1", Utc::now(), &analysis).unwrap();
+        store.record_redactions("s1", &audit).unwrap();
+
+        let loaded = store.load_redactions("s1").unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].analysis_id.as_deref(), Some("a1"));
+        assert_eq!(loaded[0].entries, audit.entries);
+
+        let session = store.load_session("s1").unwrap().unwrap();
+        let stored = serde_json::to_string(&session).unwrap();
+        assert!(!stored.contains("jane@example.com") && !stored.contains("hunter2"));
+
+        assert!(store.delete_session("s1").unwrap());
+        assert!(store.load_redactions("s1").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,522 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::TranscriptSegment;
+
+/// Caption file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    WebVtt,
+}
+
+impl SubtitleFormat {
+    /// Parse a format name such as "srt", "vtt" or "webvtt"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "srt" | "subrip" => Some(Self::Srt),
+            "vtt" | "webvtt" => Some(Self::WebVtt),
+            _ => None,
+        }
+    }
+
+    /// File extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Srt => "srt",
+            Self::WebVtt => "vtt",
+        }
+    }
+
+    /// MIME type for the format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Srt => "application/x-subrip",
+            Self::WebVtt => "text/vtt; charset=utf-8",
+        }
+    }
+}
+
+/// One caption shown on screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub speaker: Option<String>,
+    /// Caption lines, already wrapped
+    pub lines: Vec<String>,
+}
+
+impl Cue {
+    /// The caption text on a single line
+    pub fn text(&self) -> String {
+        self.lines.join(" ")
+    }
+}
+
+/// Layout limits for generated captions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleConfig {
+    /// Characters per caption line; longer words are kept whole
+    pub max_line_chars: usize,
+    pub max_lines: usize,
+    /// Longest time a single cue stays on screen
+    pub max_cue_secs: f64,
+    /// Prefix cues with the diarized speaker
+    pub speaker_tags: bool,
+}
+
+impl Default for SubtitleConfig {
+    fn default() -> Self {
+        Self {
+            max_line_chars: 42,
+            max_lines: 2,
+            max_cue_secs: 7.0,
+            speaker_tags: true,
+        }
+    }
+}
+
+/// Lays out transcript segments as captions and reads/writes SRT and WebVTT
+pub struct SubtitleWriter {
+    config: SubtitleConfig,
+}
+
+impl SubtitleWriter {
+    pub fn new() -> Self {
+        Self::with_config(SubtitleConfig::default())
+    }
+
+    pub fn with_config(config: SubtitleConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &SubtitleConfig {
+        &self.config
+    }
+
+    /// Split segments into cues that respect the line length, line count and duration limits
+    pub fn cues(&self, segments: &[TranscriptSegment]) -> Vec<Cue> {
+        segments.iter().flat_map(|segment| self.segment_cues(segment)).collect()
+    }
+
+    /// Lay out segments and render them in the requested format
+    pub fn write(&self, segments: &[TranscriptSegment], format: SubtitleFormat) -> String {
+        let cues = self.cues(segments);
+        match format {
+            SubtitleFormat::Srt => self.write_srt(&cues),
+            SubtitleFormat::WebVtt => self.write_vtt(&cues),
+        }
+    }
+
+    /// Render cues as SubRip
+    pub fn write_srt(&self, cues: &[Cue]) -> String {
+        let mut output = String::new();
+        for (i, cue) in cues.iter().enumerate() {
+            output.push_str(&format!(
+                "{}\n{} --> {}\n",
+                i + 1,
+                format_timestamp(cue.start_secs, ','),
+                format_timestamp(cue.end_secs, ',')
+            ));
+            for (n, line) in cue.lines.iter().enumerate() {
+                match (&cue.speaker, n) {
+                    (Some(speaker), 0) if self.config.speaker_tags => {
+                        output.push_str(&format!("[{}] {}\n", speaker, line))
+                    }
+                    _ => output.push_str(&format!("{}\n", line)),
+                }
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Render cues as WebVTT, tagging speakers with voice spans
+    pub fn write_vtt(&self, cues: &[Cue]) -> String {
+        let mut output = String::from("WEBVTT\n\n");
+        for cue in cues {
+            output.push_str(&format!(
+                "{} --> {}\n",
+                format_timestamp(cue.start_secs, '.'),
+                format_timestamp(cue.end_secs, '.')
+            ));
+            let text = cue.lines.iter().map(|line| escape_vtt(line)).collect::<Vec<_>>().join("\n");
+            match &cue.speaker {
+                Some(speaker) if self.config.speaker_tags => {
+                    output.push_str(&format!("<v {}>{}</v>\n", escape_vtt(speaker), text))
+                }
+                _ => output.push_str(&format!("{}\n", text)),
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn segment_cues(&self, segment: &TranscriptSegment) -> Vec<Cue> {
+        let words: Vec<&str> = segment.text.split_whitespace().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let duration = (segment.end_secs - segment.start_secs).max(0.0);
+        let max_cue_secs = self.config.max_cue_secs.max(0.1);
+        let max_lines = self.config.max_lines.max(1);
+
+        // Enough even chunks to stay under the duration limit, then more if the text
+        // doesn't fit on the allowed number of lines
+        let chunk_count = ((duration / max_cue_secs).ceil() as usize).clamp(1, words.len());
+        let chunks: Vec<Vec<String>> = split_evenly(&words, chunk_count)
+            .into_iter()
+            .flat_map(|chunk| {
+                let lines = wrap(&chunk, self.config.max_line_chars);
+                lines.chunks(max_lines).map(|lines| lines.to_vec()).collect::<Vec<_>>()
+            })
+            .collect();
+
+        // Share the segment's time out by the number of characters in each cue
+        let total_chars: usize = chunks.iter().map(|lines| chars_in(lines)).sum();
+        let mut start = segment.start_secs;
+        let mut cues = Vec::with_capacity(chunks.len());
+        for (i, lines) in chunks.iter().enumerate() {
+            let end = if i + 1 == chunks.len() {
+                segment.end_secs.max(start)
+            } else {
+                start + duration * chars_in(lines) as f64 / total_chars.max(1) as f64
+            };
+            cues.push(Cue {
+                start_secs: start,
+                end_secs: end.min(start + max_cue_secs),
+                speaker: segment.speaker.clone(),
+                lines: lines.clone(),
+            });
+            start = end;
+        }
+        cues
+    }
+}
+
+impl Default for SubtitleWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse captions in the given format
+pub fn read(text: &str, format: SubtitleFormat) -> Result<Vec<Cue>> {
+    match format {
+        SubtitleFormat::Srt => read_srt(text),
+        SubtitleFormat::WebVtt => read_vtt(text),
+    }
+}
+
+/// Parse a SubRip file; a leading `[Speaker]` tag becomes the cue's speaker
+pub fn read_srt(text: &str) -> Result<Vec<Cue>> {
+    let mut cues = Vec::new();
+    for block in blocks(text) {
+        let mut lines = block.iter().copied();
+        let mut timing = lines.next().unwrap_or_default();
+        // The cue number is optional in practice
+        if !timing.contains("-->") {
+            timing = lines.next().unwrap_or_default();
+        }
+        let (start_secs, end_secs) = parse_timing(timing)?;
+
+        let mut speaker = None;
+        let mut text_lines: Vec<String> = lines.map(str::to_string).collect();
+        if let Some(first) = text_lines.first_mut() {
+            if let Some((tag, rest)) = first.strip_prefix('[').and_then(|rest| rest.split_once("] ")) {
+                speaker = Some(tag.to_string());
+                *first = rest.to_string();
+            }
+        }
+        cues.push(Cue {
+            start_secs,
+            end_secs,
+            speaker,
+            lines: text_lines,
+        });
+    }
+    Ok(cues)
+}
+
+/// Parse a WebVTT file, skipping NOTE, STYLE and REGION blocks and cue settings.
+///
+/// A `<v Speaker>` span becomes the cue's speaker; other markup is dropped.
+pub fn read_vtt(text: &str) -> Result<Vec<Cue>> {
+    let text = text.trim_start_matches('\u{feff}');
+    if !text.starts_with("WEBVTT") {
+        return Err(anyhow::anyhow!("Not a WebVTT file: missing WEBVTT header"));
+    }
+
+    let mut cues = Vec::new();
+    for block in blocks(text).into_iter().skip(1) {
+        let Some(timing_index) = block.iter().position(|line| line.contains("-->")) else {
+            continue;
+        };
+        if ["NOTE", "STYLE", "REGION"].iter().any(|kind| block[0].starts_with(kind)) {
+            continue;
+        }
+        let (start_secs, end_secs) = parse_timing(block[timing_index])?;
+
+        let mut speaker = None;
+        let lines = block[timing_index + 1..]
+            .iter()
+            .map(|line| {
+                let (line_speaker, plain) = strip_vtt_markup(line);
+                speaker = speaker.take().or(line_speaker);
+                plain
+            })
+            .collect();
+        cues.push(Cue {
+            start_secs,
+            end_secs,
+            speaker,
+            lines,
+        });
+    }
+    Ok(cues)
+}
+
+/// Turn cues back into transcript segments, one per cue
+pub fn to_segments(cues: &[Cue]) -> Vec<TranscriptSegment> {
+    cues.iter()
+        .map(|cue| TranscriptSegment {
+            start_secs: cue.start_secs,
+            end_secs: cue.end_secs,
+            text: cue.text(),
+            speaker: cue.speaker.clone(),
+            avg_logprob: 0.0,
+            no_speech_prob: 0.0,
+        })
+        .collect()
+}
+
+/// Format seconds as `HH:MM:SS,mmm` (SRT) or `HH:MM:SS.mmm` (WebVTT)
+fn format_timestamp(secs: f64, separator: char) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// Parse `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or `MM:SS.mmm`
+fn parse_timestamp(text: &str) -> Result<f64> {
+    let invalid = || anyhow::anyhow!("Invalid timestamp: {}", text);
+    let (clock, millis) = text.trim().split_once([',', '.']).ok_or_else(invalid)?;
+    let parts = clock
+        .split(':')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+    let secs = match parts.as_slice() {
+        [h, m, s] => h * 3600 + m * 60 + s,
+        [m, s] => m * 60 + s,
+        _ => return Err(invalid()),
+    };
+    let millis: u64 = millis.parse().map_err(|_| invalid())?;
+    Ok((secs * 1000 + millis) as f64 / 1000.0)
+}
+
+fn parse_timing(line: &str) -> Result<(f64, f64)> {
+    let (start, rest) = line
+        .split_once("-->")
+        .ok_or_else(|| anyhow::anyhow!("Invalid cue timing: {}", line))?;
+    // WebVTT cue settings follow the end time
+    let end = rest.split_whitespace().next().unwrap_or_default();
+    Ok((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Split a caption file into blank-line separated blocks of lines
+fn blocks(text: &str) -> Vec<Vec<&str>> {
+    let mut blocks = Vec::new();
+    let mut current = Vec::new();
+    for line in text.lines().map(|line| line.trim_end_matches('\r')) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                blocks.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        blocks.push(current);
+    }
+    blocks
+}
+
+/// Split words into `count` runs of roughly equal character length
+fn split_evenly<'a>(words: &[&'a str], count: usize) -> Vec<Vec<&'a str>> {
+    let total: usize = words.iter().map(|word| word.len() + 1).sum();
+    let target = total as f64 / count as f64;
+    let mut chunks = vec![Vec::new()];
+    let mut length = 0usize;
+    for (i, word) in words.iter().enumerate() {
+        let remaining_words = words.len() - i;
+        let remaining_chunks = count - chunks.len();
+        let full = length as f64 >= target * 0.999;
+        if !chunks.last().is_some_and(Vec::is_empty) && remaining_chunks > 0 && (full || remaining_words == remaining_chunks) {
+            chunks.push(Vec::new());
+            length = 0;
+        }
+        chunks.last_mut().unwrap().push(*word);
+        length += word.len() + 1;
+    }
+    chunks
+}
+
+/// Greedily wrap words onto lines of at most `width` characters
+fn wrap(words: &[&str], width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+fn chars_in(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.chars().count()).sum()
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn unescape_vtt(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+/// Drop cue markup from a WebVTT line, returning the voice span's speaker if there is one
+fn strip_vtt_markup(line: &str) -> (Option<String>, String) {
+    let mut speaker = None;
+    let mut plain = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('<') {
+        plain.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            plain.push_str(&rest[open..]);
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        // `<v Name>` or `<v.class Name>`: the name follows the first space
+        if tag.starts_with("v ") || tag.starts_with("v.") {
+            if let Some((_, name)) = tag.split_once(' ') {
+                speaker = Some(unescape_vtt(name.trim()));
+            }
+        }
+        rest = &rest[open + close + 1..];
+    }
+    plain.push_str(rest);
+    (speaker, unescape_vtt(&plain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start_secs: f64, end_secs: f64, speaker: Option<&str>, text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_secs,
+            end_secs,
+            text: text.to_string(),
+            speaker: speaker.map(str::to_string),
+            avg_logprob: -0.2,
+            no_speech_prob: 0.01,
+        }
+    }
+
+    fn lecture() -> Vec<TranscriptSegment> {
+        vec![
+            segment(0.0, 2.5, Some("Dr. Lee"), "Welcome back, everyone."),
+            segment(
+                2.5,
+                19.0,
+                Some("Dr. Lee"),
+                "Today we cover the <html> parser & tokenizer, starting with how the input stream is split into tokens and why error recovery matters so much for real pages.",
+            ),
+            segment(19.0, 21.25, None, "Any questions?"),
+        ]
+    }
+
+    #[test]
+    fn test_srt_round_trip() {
+        let writer = SubtitleWriter::new();
+        let cues = writer.cues(&lecture());
+        let srt = writer.write_srt(&cues);
+
+        assert!(srt.starts_with("1\n00:00:00,000 --> 00:00:02,500\n[Dr. Lee] Welcome back, everyone.\n"));
+        assert_eq!(read_srt(&srt).unwrap(), round_millis(&cues));
+    }
+
+    #[test]
+    fn test_vtt_round_trip_with_speakers_and_escapes() {
+        let writer = SubtitleWriter::new();
+        let cues = writer.cues(&lecture());
+        let vtt = writer.write(&lecture(), SubtitleFormat::WebVtt);
+
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.500\n<v Dr. Lee>Welcome back, everyone.</v>\n"));
+        assert!(vtt.contains("&lt;html&gt;") && vtt.contains("parser &amp;"));
+
+        let parsed = read(&vtt, SubtitleFormat::WebVtt).unwrap();
+        assert_eq!(parsed, round_millis(&cues));
+        assert_eq!(to_segments(&parsed).last().unwrap().text, "Any questions?");
+    }
+
+    #[test]
+    fn test_cues_respect_layout_limits() {
+        let config = SubtitleConfig::default();
+        let cues = SubtitleWriter::new().cues(&lecture());
+
+        assert!(cues.len() > 3);
+        for cue in &cues {
+            assert!(cue.lines.len() <= config.max_lines);
+            assert!(cue.lines.iter().all(|line| line.chars().count() <= config.max_line_chars));
+            assert!(cue.end_secs - cue.start_secs <= config.max_cue_secs + 1e-9);
+            assert!(cue.end_secs >= cue.start_secs);
+        }
+        let words = |text: &str| text.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        let rebuilt: Vec<String> = cues.iter().flat_map(|cue| words(&cue.text())).collect();
+        let original: Vec<String> = lecture().iter().flat_map(|segment| words(&segment.text)).collect();
+        assert_eq!(rebuilt, original);
+    }
+
+    #[test]
+    fn test_reads_third_party_vtt() {
+        let vtt = "\u{feff}WEBVTT - lecture 4\n\nNOTE exported by a video editor\n\nSTYLE\n::cue { color: yellow }\n\nintro\n01:02.500 --> 01:04.000 align:start position:10%\n<v.loud Ana Maria>Hello <i>there</i>\n\n00:01:05.000 --> 00:01:06.000\nAll &amp; sundry\n";
+        let cues = read_vtt(vtt).unwrap();
+
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].start_secs, 62.5);
+        assert_eq!(cues[0].speaker.as_deref(), Some("Ana Maria"));
+        assert_eq!(cues[0].lines, vec!["Hello there"]);
+        assert_eq!(cues[1].text(), "All & sundry");
+        assert!(read_vtt("1\n00:00:01,000 --> 00:00:02,000\nhi\n").is_err());
+    }
+
+    /// Cues as they come back from a file, with times at millisecond precision
+    fn round_millis(cues: &[Cue]) -> Vec<Cue> {
+        cues.iter()
+            .map(|cue| Cue {
+                start_secs: (cue.start_secs * 1000.0).round() / 1000.0,
+                end_secs: (cue.end_secs * 1000.0).round() / 1000.0,
+                ..cue.clone()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,399 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{ConversationTurn, SessionContext};
+use crate::export::EXPORT_SCHEMA_VERSION;
+use crate::meeting::MeetingMinutes;
+
+/// Product identifier written into exported calendars
+pub const ICS_PRODUCT_ID: &str = "-//Learning Engine//Task Export//EN";
+
+/// Output format of a task export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskFormat {
+    Ics,
+    Markdown,
+    Json,
+}
+
+impl TaskFormat {
+    /// Parse a format name such as "ics", "ical", "md" or "json"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ics" | "ical" | "icalendar" => Some(Self::Ics),
+            "md" | "markdown" => Some(Self::Markdown),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// File extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Ics => "ics",
+            Self::Markdown => "md",
+            Self::Json => "json",
+        }
+    }
+
+    /// MIME type for the format
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Ics => "text/calendar; charset=utf-8",
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+/// Where an exported task was extracted from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    ActionItem,
+    FollowUp,
+}
+
+/// A task extracted from a session, linked to the turn it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedTask {
+    /// Stable across exports of the same session: `<turn_id>-<n>`
+    pub id: String,
+    pub kind: TaskKind,
+    pub title: String,
+    pub assignee: Option<String>,
+    pub due: Option<NaiveDate>,
+    /// The words the due date was parsed from
+    pub due_phrase: Option<String>,
+    pub raised_by: Option<String>,
+    /// 1-based position of the source turn, matching the session export
+    pub turn_index: usize,
+    pub turn_id: String,
+    pub turn_timestamp: DateTime<Utc>,
+    /// Offset into the recording the task was mentioned at, when known
+    pub start_secs: Option<f64>,
+}
+
+/// Stable, versioned JSON task feed of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskFeed {
+    pub schema_version: String,
+    pub session_id: String,
+    pub exported_at: DateTime<Utc>,
+    pub tasks: Vec<ExportedTask>,
+}
+
+/// Renders the tasks extracted from a session as a calendar, checklist or JSON feed
+pub struct TaskExporter;
+
+impl TaskExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Collect the action items and follow-ups of every turn in the session
+    pub fn build(&self, context: &SessionContext) -> TaskFeed {
+        let tasks = context
+            .conversation_history
+            .iter()
+            .enumerate()
+            .flat_map(|(i, turn)| Self::turn_tasks(i + 1, turn))
+            .collect();
+
+        TaskFeed {
+            schema_version: EXPORT_SCHEMA_VERSION.to_string(),
+            session_id: context.session_id.clone(),
+            exported_at: Utc::now(),
+            tasks,
+        }
+    }
+
+    /// Render a session's tasks in the requested format
+    pub fn export(&self, context: &SessionContext, format: TaskFormat) -> Result<String> {
+        let feed = self.build(context);
+        self.render(&feed, format)
+    }
+
+    /// Render an already built feed in the requested format
+    pub fn render(&self, feed: &TaskFeed, format: TaskFormat) -> Result<String> {
+        match format {
+            TaskFormat::Json => Ok(serde_json::to_string_pretty(feed)?),
+            TaskFormat::Markdown => Ok(render_markdown(feed)),
+            TaskFormat::Ics => Ok(render_ics(feed)),
+        }
+    }
+
+    fn turn_tasks(index: usize, turn: &ConversationTurn) -> Vec<ExportedTask> {
+        let Some(minutes) = turn
+            .analysis
+            .as_ref()
+            .and_then(|analysis| MeetingMinutes::from_metadata(&analysis.metadata))
+        else {
+            return Vec::new();
+        };
+
+        let task = |kind: TaskKind, title: &str| ExportedTask {
+            id: String::new(),
+            kind,
+            title: title.to_string(),
+            assignee: None,
+            due: None,
+            due_phrase: None,
+            raised_by: None,
+            turn_index: index,
+            turn_id: turn.turn_id.clone(),
+            turn_timestamp: turn.timestamp,
+            start_secs: None,
+        };
+
+        let action_items = minutes.action_items.iter().map(|item| ExportedTask {
+            assignee: item.assignee.clone(),
+            due: item.deadline.as_ref().map(|deadline| deadline.date),
+            due_phrase: item.deadline.as_ref().map(|deadline| deadline.phrase.clone()),
+            raised_by: item.speaker.clone(),
+            start_secs: item.start_secs,
+            ..task(TaskKind::ActionItem, &item.task)
+        });
+        let follow_ups = minutes.follow_ups.iter().map(|follow_up| ExportedTask {
+            raised_by: follow_up.speaker.clone(),
+            start_secs: follow_up.start_secs,
+            ..task(TaskKind::FollowUp, &follow_up.text)
+        });
+
+        action_items
+            .chain(follow_ups)
+            .enumerate()
+            .map(|(n, exported)| ExportedTask {
+                id: format!("{}-{}", turn.turn_id, n + 1),
+                ..exported
+            })
+            .collect()
+    }
+}
+
+impl Default for TaskExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn render_markdown(feed: &TaskFeed) -> String {
+    let mut output = format!("# Tasks — {}\n\n", feed.session_id);
+    if feed.tasks.is_empty() {
+        output.push_str("_No tasks were extracted from this session._\n");
+        return output;
+    }
+
+    for task in &feed.tasks {
+        output.push_str(&format!("- [ ] {}", task.title));
+        if let Some(assignee) = &task.assignee {
+            output.push_str(&format!(" — @{}", assignee));
+        }
+        if let Some(due) = task.due {
+            output.push_str(&format!(" — due {}", due.format("%Y-%m-%d")));
+        }
+        if task.kind == TaskKind::FollowUp {
+            output.push_str(" — follow-up");
+        }
+        output.push_str(&format!(" _(turn {}, `{}`)_\n", task.turn_index, task.turn_id));
+    }
+    output
+}
+
+fn render_ics(feed: &TaskFeed) -> String {
+    let stamp = feed.exported_at.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", ICS_PRODUCT_ID),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_ics_text(&format!("Tasks — {}", feed.session_id))),
+    ];
+
+    for task in &feed.tasks {
+        let mut description = format!("From turn {} ({})", task.turn_index, task.turn_id);
+        if let Some(raised_by) = &task.raised_by {
+            description.push_str(&format!(", raised by {}", raised_by));
+        }
+        if let Some(phrase) = &task.due_phrase {
+            description.push_str(&format!(", due \"{}\"", phrase));
+        }
+
+        let mut common = vec![
+            format!("DTSTAMP:{}", stamp),
+            format!("SUMMARY:{}", escape_ics_text(&task.title)),
+            format!("DESCRIPTION:{}", escape_ics_text(&description)),
+            format!("X-LEARNING-SESSION-ID:{}", escape_ics_text(&feed.session_id)),
+            format!("X-LEARNING-TURN-ID:{}", escape_ics_text(&task.turn_id)),
+        ];
+        if let Some(assignee) = &task.assignee {
+            common.push(format!("X-LEARNING-ASSIGNEE:{}", escape_ics_text(assignee)));
+        }
+
+        // Task ids are only unique within a session, and UIDs must be unique across calendars
+        let uid = escape_ics_text(&format!("{}-{}", feed.session_id, task.id));
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}@learning", uid));
+        lines.extend(common.iter().cloned());
+        lines.push("STATUS:NEEDS-ACTION".to_string());
+        if let Some(due) = task.due {
+            lines.push(format!("DUE;VALUE=DATE:{}", due.format("%Y%m%d")));
+        }
+        lines.push("END:VTODO".to_string());
+
+        // Dated tasks also show up on the calendar as an all-day event on the due date
+        if let Some(due) = task.due {
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}-due@learning", uid));
+            lines.extend(common);
+            lines.push(format!("DTSTART;VALUE=DATE:{}", due.format("%Y%m%d")));
+            lines.push(format!("DTEND;VALUE=DATE:{}", (due + Duration::days(1)).format("%Y%m%d")));
+            lines.push("TRANSP:TRANSPARENT".to_string());
+            lines.push("END:VEVENT".to_string());
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_ics_line(line)).collect::<Vec<_>>().join("")
+}
+
+/// Escape a TEXT value as required by RFC 5545
+pub fn escape_ics_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fold a content line at 75 octets without splitting a UTF-8 character, ending it with CRLF
+fn fold_ics_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{AnalysisResult, ContentType};
+    use crate::meeting::{ActionItem, Deadline, FollowUp, MEETING_MINUTES_METADATA_KEY};
+    use std::collections::HashMap;
+
+    fn meeting_session() -> SessionContext {
+        let now = Utc::now();
+        let minutes = MeetingMinutes {
+            participants: vec!["Priya".to_string(), "Sam".to_string()],
+            action_items: vec![
+                ActionItem {
+                    task: "Email the customers, then update the FAQ".to_string(),
+                    assignee: Some("Sam".to_string()),
+                    deadline: Some(Deadline {
+                        phrase: "by friday".to_string(),
+                        date: NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+                    }),
+                    speaker: Some("Sam".to_string()),
+                    start_secs: Some(12.0),
+                },
+                ActionItem {
+                    task: "Find an owner for the mobile app".to_string(),
+                    assignee: None,
+                    deadline: None,
+                    speaker: Some("Priya".to_string()),
+                    start_secs: None,
+                },
+            ],
+            follow_ups: vec![FollowUp {
+                text: "Revisit pricing next quarter".to_string(),
+                speaker: Some("Priya".to_string()),
+                start_secs: None,
+            }],
+            ..Default::default()
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert(MEETING_MINUTES_METADATA_KEY.to_string(), serde_json::to_value(&minutes).unwrap());
+        let analysis = AnalysisResult {
+            analysis_id: "analysis_7".to_string(),
+            timestamp: now,
+            content_type: ContentType::Audio,
+            original_content: "Sam: I'll email the customers by Friday.".to_string(),
+            extracted_text: None,
+            sentiment: None,
+            topics: vec![],
+            summary: String::new(),
+            insights: vec![],
+            confidence: 0.9,
+            context_used: false,
+            recommendations: vec![],
+            metadata,
+        };
+
+        SessionContext {
+            session_id: "standup".to_string(),
+            conversation_history: vec![ConversationTurn {
+                turn_id: "analysis_7".to_string(),
+                timestamp: now,
+                content_type: ContentType::Audio,
+                content: analysis.original_content.clone(),
+                analysis: Some(analysis),
+                user_feedback: None,
+            }],
+            extracted_texts: vec![],
+            analysis_summary: None,
+            created_at: now,
+            last_updated: now,
+        }
+    }
+
+    #[test]
+    fn test_tasks_link_back_to_turns() {
+        let feed = TaskExporter::new().build(&meeting_session());
+
+        assert_eq!(feed.tasks.len(), 3);
+        assert!(feed.tasks.iter().all(|task| task.turn_id == "analysis_7" && task.turn_index == 1));
+        assert_eq!(feed.tasks[0].id, "analysis_7-1");
+        assert_eq!(feed.tasks[2].kind, TaskKind::FollowUp);
+
+        let json = TaskExporter::new().render(&feed, TaskFormat::Json).unwrap();
+        let parsed: TaskFeed = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.tasks[0].due, NaiveDate::from_ymd_opt(2024, 3, 15));
+
+        let markdown = TaskExporter::new().render(&feed, TaskFormat::Markdown).unwrap();
+        assert!(markdown.contains("- [ ] Email the customers, then update the FAQ — @Sam — due 2024-03-15 _(turn 1, `analysis_7`)_"));
+    }
+
+    #[test]
+    fn test_ics_export_is_well_formed() {
+        let ics = TaskExporter::new().export(&meeting_session(), TaskFormat::Ics).unwrap();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VTODO").count(), 3);
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains("DUE;VALUE=DATE:20240315\r\n"));
+        assert!(ics.contains("DTEND;VALUE=DATE:20240316\r\n"));
+        assert!(ics.contains("SUMMARY:Email the customers\\, then update the FAQ\r\n"));
+        assert!(ics.contains("X-LEARNING-TURN-ID:analysis_7\r\n"));
+        assert!(ics.contains("UID:standup-analysis_7-1@learning\r\n"));
+        assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    }
+}
@@ -0,0 +1,1089 @@
+use anyhow::{anyhow, Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Slowest and fastest speaking rates, relative to the voice's natural rate
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 2.0;
+
+/// Sample rate of the built-in voices
+const BUILTIN_SAMPLE_RATE: u32 = 22050;
+/// Built-in voices and their base pitch in Hz
+const BUILTIN_VOICES: &[(&str, f32)] = &[("formant-low", 110.0), ("formant-high", 195.0)];
+
+/// Configuration for speech synthesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TtsConfig {
+    /// Directory of neural voices: TorchScript models named `<voice>.pt`, each with a
+    /// `<voice>.json` describing its sample rate and input symbols. Without one, only
+    /// the built-in voices are available.
+    #[serde(default)]
+    pub model_dir: Option<PathBuf>,
+    /// Voice used when a request does not name one; defaults to the first neural voice
+    #[serde(default)]
+    pub voice: Option<String>,
+    /// Speaking rate used when a request does not set one
+    pub speed: f32,
+}
+
+impl Default for TtsConfig {
+    fn default() -> Self {
+        Self {
+            model_dir: None,
+            voice: None,
+            speed: 1.0,
+        }
+    }
+}
+
+/// What to read aloud
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SpeechContent {
+    Summary { text: String },
+    Answer { text: String },
+    Flashcard { front: String, back: String },
+}
+
+impl SpeechContent {
+    /// The text spoken for this content
+    pub fn script(&self) -> String {
+        match self {
+            SpeechContent::Summary { text } | SpeechContent::Answer { text } => text.clone(),
+            SpeechContent::Flashcard { front, back } => format!("Question. {}.\nAnswer. {}.", front.trim(), back.trim()),
+        }
+    }
+}
+
+/// Per-request voice and speed, overriding the configured defaults
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeechOptions {
+    #[serde(default)]
+    pub voice: Option<String>,
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+/// Encodings synthesized speech can be returned in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// 16-bit mono WAV
+    Wav,
+    /// Headerless 16-bit little-endian mono samples
+    Pcm,
+}
+
+impl AudioFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wav" | "wave" => Some(AudioFormat::Wav),
+            "pcm" | "raw" | "s16le" => Some(AudioFormat::Pcm),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceKind {
+    /// A TorchScript model from the model directory
+    Neural,
+    /// A formant voice that needs no model
+    Builtin,
+}
+
+/// A voice that can be synthesized with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoiceInfo {
+    pub name: String,
+    pub kind: VoiceKind,
+    pub sample_rate: u32,
+}
+
+/// Synthesized mono speech
+#[derive(Debug, Clone)]
+pub struct Speech {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub voice: String,
+}
+
+impl Speech {
+    pub fn duration_secs(&self) -> f32 {
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+
+    pub fn encode(&self, format: AudioFormat) -> Result<Vec<u8>> {
+        match format {
+            AudioFormat::Wav => self.to_wav(),
+            AudioFormat::Pcm => Ok(self.to_pcm()),
+        }
+    }
+
+    /// Content type of the encoded audio
+    pub fn content_type(&self, format: AudioFormat) -> String {
+        match format {
+            AudioFormat::Wav => "audio/wav".to_string(),
+            AudioFormat::Pcm => format!("audio/pcm;rate={};channels=1;format=s16le", self.sample_rate),
+        }
+    }
+
+    pub fn to_wav(&self) -> Result<Vec<u8>> {
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut cursor, spec)?;
+        for &sample in &self.samples {
+            writer.write_sample(to_i16(sample))?;
+        }
+        writer.finalize()?;
+        Ok(cursor.into_inner())
+    }
+
+    pub fn to_pcm(&self) -> Vec<u8> {
+        self.samples.iter().flat_map(|&sample| to_i16(sample).to_le_bytes()).collect()
+    }
+}
+
+fn to_i16(sample: f32) -> i16 {
+    (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Description of a neural voice, stored as `<voice>.json` next to its model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct NeuralVoiceConfig {
+    sample_rate: u32,
+    /// Input symbols in id order; id 0 is padding, so the first symbol has id 1
+    symbols: String,
+}
+
+struct NeuralVoice {
+    config: NeuralVoiceConfig,
+    model: tch::CModule,
+}
+
+impl NeuralVoice {
+    fn load(model_path: &Path) -> Result<Self> {
+        let config_path = model_path.with_extension("json");
+        let config: NeuralVoiceConfig = serde_json::from_str(
+            &std::fs::read_to_string(&config_path).with_context(|| format!("reading {}", config_path.display()))?,
+        )?;
+        let model = tch::CModule::load_on_device(model_path, tch::Device::Cpu)?;
+        Ok(Self { config, model })
+    }
+
+    /// Run the model on symbol ids. It takes the ids, their count and a length scale
+    /// (higher is slower) and returns the waveform.
+    fn synthesize(&self, text: &str, speed: f32) -> Result<Vec<f32>> {
+        let ids: Vec<i64> = text
+            .chars()
+            .filter_map(|c| self.config.symbols.chars().position(|symbol| symbol == c))
+            .map(|index| index as i64 + 1)
+            .collect();
+        if ids.is_empty() {
+            return Err(anyhow!("Nothing the voice can pronounce"));
+        }
+
+        let inputs = [
+            tch::Tensor::from_slice(&ids).view([1, ids.len() as i64]),
+            tch::Tensor::from_slice(&[ids.len() as i64]),
+            tch::Tensor::from_slice(&[1.0 / speed]),
+        ];
+        let output = self.model.forward_ts(&inputs)?;
+        Ok(Vec::<f32>::try_from(output.view([-1]))?)
+    }
+}
+
+/// Text-to-speech on the CPU
+pub struct SpeechSynthesizer {
+    config: TtsConfig,
+    voices: Vec<VoiceInfo>,
+    /// Neural voices found in the model directory, loaded on first use
+    neural: Mutex<HashMap<String, NeuralVoice>>,
+}
+
+impl SpeechSynthesizer {
+    /// Create a synthesizer with only the built-in voices
+    pub fn new() -> Self {
+        Self::with_config(TtsConfig::default()).expect("built-in voices need no model")
+    }
+
+    /// Create a synthesizer, listing the neural voices in the model directory
+    pub fn with_config(config: TtsConfig) -> Result<Self> {
+        let mut voices = match &config.model_dir {
+            Some(dir) => neural_voices(dir)?,
+            None => Vec::new(),
+        };
+        voices.extend(BUILTIN_VOICES.iter().map(|(name, _)| VoiceInfo {
+            name: name.to_string(),
+            kind: VoiceKind::Builtin,
+            sample_rate: BUILTIN_SAMPLE_RATE,
+        }));
+
+        if let Some(voice) = &config.voice {
+            if !voices.iter().any(|info| &info.name == voice) {
+                return Err(anyhow!("Unknown voice: {}", voice));
+            }
+        }
+
+        Ok(Self {
+            config,
+            voices,
+            neural: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn config(&self) -> &TtsConfig {
+        &self.config
+    }
+
+    /// Available voices, neural voices first
+    pub fn voices(&self) -> &[VoiceInfo] {
+        &self.voices
+    }
+
+    /// Read a summary, answer or flashcard aloud
+    pub fn speak(&self, content: &SpeechContent, options: &SpeechOptions) -> Result<Speech> {
+        self.synthesize(&content.script(), options)
+    }
+
+    /// Synthesize text with the requested or configured voice and speed
+    pub fn synthesize(&self, text: &str, options: &SpeechOptions) -> Result<Speech> {
+        let speed = options.speed.unwrap_or(self.config.speed);
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(anyhow!("Speed must be between {} and {}", MIN_SPEED, MAX_SPEED));
+        }
+
+        let name = options.voice.as_ref().or(self.config.voice.as_ref());
+        let voice = match name {
+            Some(name) => self
+                .voices
+                .iter()
+                .find(|info| &info.name == name)
+                .ok_or_else(|| anyhow!("Unknown voice: {}", name))?,
+            None => &self.voices[0],
+        };
+
+        let chunks = normalize_text(text);
+        if !chunks.iter().any(|chunk| matches!(chunk, Chunk::Word(_))) {
+            return Err(anyhow!("Nothing to say"));
+        }
+
+        let samples = match voice.kind {
+            VoiceKind::Neural => self.synthesize_neural(&voice.name, &spoken_text(&chunks), speed)?,
+            VoiceKind::Builtin => {
+                let pitch = BUILTIN_VOICES
+                    .iter()
+                    .find(|(name, _)| *name == voice.name)
+                    .map(|(_, pitch)| *pitch)
+                    .unwrap_or(BUILTIN_VOICES[0].1);
+                FormantVoice::new(BUILTIN_SAMPLE_RATE, pitch).render(&chunks, speed)
+            }
+        };
+
+        Ok(Speech {
+            samples,
+            sample_rate: voice.sample_rate,
+            voice: voice.name.clone(),
+        })
+    }
+
+    fn synthesize_neural(&self, name: &str, text: &str, speed: f32) -> Result<Vec<f32>> {
+        let mut neural = self.neural.lock().unwrap();
+        if !neural.contains_key(name) {
+            let dir = self.config.model_dir.as_ref().ok_or_else(|| anyhow!("No model directory"))?;
+            neural.insert(name.to_string(), NeuralVoice::load(&dir.join(format!("{}.pt", name)))?);
+        }
+        neural[name].synthesize(text, speed)
+    }
+}
+
+impl Default for SpeechSynthesizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Neural voices in a model directory; a missing directory has none
+fn neural_voices(dir: &Path) -> Result<Vec<VoiceInfo>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut voices = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("pt") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        let config_path = path.with_extension("json");
+        let Ok(source) = std::fs::read_to_string(&config_path) else {
+            continue;
+        };
+        let config: NeuralVoiceConfig =
+            serde_json::from_str(&source).with_context(|| format!("parsing {}", config_path.display()))?;
+        voices.push(VoiceInfo {
+            name: name.to_string(),
+            kind: VoiceKind::Neural,
+            sample_rate: config.sample_rate,
+        });
+    }
+    voices.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(voices)
+}
+
+/// Normalized text: lowercase words and the pauses between them
+#[derive(Debug, Clone, PartialEq)]
+enum Chunk {
+    Word(String),
+    /// A pause after a phrase, in milliseconds at normal speed
+    Pause { ms: f32, question: bool },
+}
+
+const SHORT_PAUSE_MS: f32 = 180.0;
+const LONG_PAUSE_MS: f32 = 420.0;
+
+/// Split text into words and pauses, spelling out numbers and symbols and dropping
+/// markdown
+fn normalize_text(text: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut word = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_alphabetic() || (c == '\'' && !word.is_empty()) {
+            word.push(c.to_ascii_lowercase());
+            i += 1;
+            continue;
+        }
+        flush_word(&mut word, &mut chunks);
+
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while i < chars.len() {
+                let next_is_digit = chars.get(i + 1).is_some_and(char::is_ascii_digit);
+                match chars[i] {
+                    d if d.is_ascii_digit() => digits.push(d),
+                    // Thousands separators
+                    ',' if next_is_digit => {}
+                    _ => break,
+                }
+                i += 1;
+            }
+            push_number(&digits, &mut chunks);
+            // Decimals are read digit by digit
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                chunks.push(Chunk::Word("point".to_string()));
+                i += 1;
+                while let Some(d) = chars.get(i).filter(|d| d.is_ascii_digit()) {
+                    push_number(&d.to_string(), &mut chunks);
+                    i += 1;
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '.' | '!' | '\n' => push_pause(&mut chunks, LONG_PAUSE_MS, false),
+            '?' => push_pause(&mut chunks, LONG_PAUSE_MS, true),
+            ',' | ';' | ':' | '(' | ')' | '—' | '–' => push_pause(&mut chunks, SHORT_PAUSE_MS, false),
+            '%' => chunks.push(Chunk::Word("percent".to_string())),
+            '&' => chunks.push(Chunk::Word("and".to_string())),
+            '+' => chunks.push(Chunk::Word("plus".to_string())),
+            '=' => chunks.push(Chunk::Word("equals".to_string())),
+            _ => {}
+        }
+        i += 1;
+    }
+    flush_word(&mut word, &mut chunks);
+    chunks
+}
+
+fn flush_word(word: &mut String, chunks: &mut Vec<Chunk>) {
+    let trimmed = word.trim_end_matches('\'');
+    if !trimmed.is_empty() {
+        chunks.push(Chunk::Word(trimmed.to_string()));
+    }
+    word.clear();
+}
+
+/// Add a pause, merging it with a pause right before it
+fn push_pause(chunks: &mut Vec<Chunk>, ms: f32, question: bool) {
+    match chunks.last_mut() {
+        Some(Chunk::Pause { ms: last, question: last_question }) => {
+            *last = last.max(ms);
+            *last_question |= question;
+        }
+        Some(Chunk::Word(_)) => chunks.push(Chunk::Pause { ms, question }),
+        // Nothing to pause after
+        None => {}
+    }
+}
+
+fn push_number(digits: &str, chunks: &mut Vec<Chunk>) {
+    let words = match digits.parse::<u64>() {
+        Ok(n) if n < 1_000_000_000_000 && !(digits.len() > 1 && digits.starts_with('0')) => number_words(n),
+        // Long numbers and ones with leading zeros are read digit by digit
+        _ => digits
+            .chars()
+            .flat_map(|d| number_words(d.to_digit(10).unwrap_or(0) as u64))
+            .collect(),
+    };
+    chunks.extend(words.into_iter().map(Chunk::Word));
+}
+
+fn number_words(n: u64) -> Vec<String> {
+    const ONES: &[&str] = &[
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven", "twelve",
+        "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen",
+    ];
+    const TENS: &[&str] = &["", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+    const SCALES: &[(u64, &str)] = &[(1_000_000_000, "billion"), (1_000_000, "million"), (1_000, "thousand")];
+
+    for &(scale, name) in SCALES {
+        if n >= scale {
+            let mut words = number_words(n / scale);
+            words.push(name.to_string());
+            if !n.is_multiple_of(scale) {
+                words.extend(number_words(n % scale));
+            }
+            return words;
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut rest = n;
+    if rest >= 100 {
+        words.push(ONES[(rest / 100) as usize].to_string());
+        words.push("hundred".to_string());
+        rest %= 100;
+        if rest == 0 {
+            return words;
+        }
+    }
+    if rest >= 20 {
+        words.push(TENS[(rest / 10) as usize].to_string());
+        if !rest.is_multiple_of(10) {
+            words.push(ONES[(rest % 10) as usize].to_string());
+        }
+    } else if rest > 0 || words.is_empty() {
+        words.push(ONES[rest as usize].to_string());
+    }
+    words
+}
+
+/// Normalized text as plain words and punctuation, for neural voices
+fn spoken_text(chunks: &[Chunk]) -> String {
+    let mut text = String::new();
+    for chunk in chunks {
+        match chunk {
+            Chunk::Word(word) => {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(word);
+            }
+            Chunk::Pause { question: true, .. } => text.push('?'),
+            Chunk::Pause { ms, .. } if *ms >= LONG_PAUSE_MS => text.push('.'),
+            Chunk::Pause { .. } => text.push(','),
+        }
+    }
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Vowel,
+    /// Liquids and glides
+    Approximant,
+    Nasal,
+    Fricative,
+    Stop,
+    Affricate,
+    Aspirate,
+}
+
+/// Acoustic targets of a phoneme
+#[derive(Debug, Clone, Copy)]
+struct PhoneDef {
+    name: &'static str,
+    class: Class,
+    voiced: bool,
+    /// Formants at the start and end; they differ for diphthongs
+    start: [f32; 3],
+    end: [f32; 3],
+    ms: f32,
+    /// Centre and bandwidth of frication or burst noise
+    noise: (f32, f32),
+}
+
+const fn phone(name: &'static str, class: Class, voiced: bool, start: [f32; 3], end: [f32; 3], ms: f32) -> PhoneDef {
+    PhoneDef { name, class, voiced, start, end, ms, noise: (0.0, 0.0) }
+}
+
+const fn noisy(
+    name: &'static str,
+    class: Class,
+    voiced: bool,
+    formants: [f32; 3],
+    ms: f32,
+    noise: (f32, f32),
+) -> PhoneDef {
+    PhoneDef { name, class, voiced, start: formants, end: formants, ms, noise }
+}
+
+const NEUTRAL: [f32; 3] = [500.0, 1500.0, 2500.0];
+
+/// Formant targets of an adult voice, after Peterson and Barney for the vowels
+const PHONES: &[PhoneDef] = &[
+    phone("IY", Class::Vowel, true, [270.0, 2290.0, 3010.0], [270.0, 2290.0, 3010.0], 120.0),
+    phone("IH", Class::Vowel, true, [390.0, 1990.0, 2550.0], [390.0, 1990.0, 2550.0], 90.0),
+    phone("EH", Class::Vowel, true, [530.0, 1840.0, 2480.0], [530.0, 1840.0, 2480.0], 100.0),
+    phone("AE", Class::Vowel, true, [660.0, 1720.0, 2410.0], [660.0, 1720.0, 2410.0], 120.0),
+    phone("AA", Class::Vowel, true, [730.0, 1090.0, 2440.0], [730.0, 1090.0, 2440.0], 120.0),
+    phone("AO", Class::Vowel, true, [570.0, 840.0, 2410.0], [570.0, 840.0, 2410.0], 120.0),
+    phone("UH", Class::Vowel, true, [440.0, 1020.0, 2240.0], [440.0, 1020.0, 2240.0], 90.0),
+    phone("UW", Class::Vowel, true, [300.0, 870.0, 2240.0], [300.0, 870.0, 2240.0], 120.0),
+    phone("AH", Class::Vowel, true, [640.0, 1190.0, 2390.0], [640.0, 1190.0, 2390.0], 80.0),
+    phone("ER", Class::Vowel, true, [490.0, 1350.0, 1690.0], [490.0, 1350.0, 1690.0], 120.0),
+    phone("EY", Class::Vowel, true, [480.0, 1720.0, 2520.0], [330.0, 2200.0, 2600.0], 150.0),
+    phone("AY", Class::Vowel, true, [700.0, 1200.0, 2500.0], [350.0, 2100.0, 2700.0], 170.0),
+    phone("OW", Class::Vowel, true, [540.0, 1000.0, 2400.0], [350.0, 800.0, 2300.0], 150.0),
+    phone("AW", Class::Vowel, true, [700.0, 1250.0, 2500.0], [400.0, 900.0, 2400.0], 170.0),
+    phone("OY", Class::Vowel, true, [550.0, 900.0, 2400.0], [380.0, 2000.0, 2600.0], 170.0),
+    phone("L", Class::Approximant, true, [360.0, 1300.0, 2700.0], [360.0, 1300.0, 2700.0], 65.0),
+    phone("R", Class::Approximant, true, [420.0, 1300.0, 1600.0], [420.0, 1300.0, 1600.0], 65.0),
+    phone("W", Class::Approximant, true, [300.0, 610.0, 2200.0], [300.0, 610.0, 2200.0], 60.0),
+    phone("Y", Class::Approximant, true, [260.0, 2070.0, 3020.0], [260.0, 2070.0, 3020.0], 60.0),
+    phone("M", Class::Nasal, true, [280.0, 1100.0, 2200.0], [280.0, 1100.0, 2200.0], 75.0),
+    phone("N", Class::Nasal, true, [280.0, 1700.0, 2600.0], [280.0, 1700.0, 2600.0], 70.0),
+    phone("NG", Class::Nasal, true, [280.0, 2300.0, 2750.0], [280.0, 2300.0, 2750.0], 80.0),
+    noisy("S", Class::Fricative, false, NEUTRAL, 110.0, (5500.0, 1500.0)),
+    noisy("Z", Class::Fricative, true, NEUTRAL, 95.0, (5500.0, 1500.0)),
+    noisy("SH", Class::Fricative, false, NEUTRAL, 115.0, (2700.0, 900.0)),
+    noisy("ZH", Class::Fricative, true, NEUTRAL, 95.0, (2700.0, 900.0)),
+    noisy("F", Class::Fricative, false, NEUTRAL, 100.0, (4500.0, 3000.0)),
+    noisy("V", Class::Fricative, true, NEUTRAL, 80.0, (4500.0, 3000.0)),
+    noisy("TH", Class::Fricative, false, NEUTRAL, 100.0, (4000.0, 3000.0)),
+    noisy("DH", Class::Fricative, true, NEUTRAL, 60.0, (4000.0, 3000.0)),
+    noisy("P", Class::Stop, false, [400.0, 900.0, 2200.0], 85.0, (900.0, 800.0)),
+    noisy("B", Class::Stop, true, [400.0, 900.0, 2200.0], 70.0, (900.0, 800.0)),
+    noisy("T", Class::Stop, false, [400.0, 1700.0, 2600.0], 80.0, (4000.0, 1500.0)),
+    noisy("D", Class::Stop, true, [400.0, 1700.0, 2600.0], 65.0, (4000.0, 1500.0)),
+    noisy("K", Class::Stop, false, [400.0, 1900.0, 2400.0], 85.0, (1900.0, 800.0)),
+    noisy("G", Class::Stop, true, [400.0, 1900.0, 2400.0], 70.0, (1900.0, 800.0)),
+    noisy("CH", Class::Affricate, false, NEUTRAL, 120.0, (2700.0, 900.0)),
+    noisy("JH", Class::Affricate, true, NEUTRAL, 100.0, (2700.0, 900.0)),
+    noisy("HH", Class::Aspirate, false, NEUTRAL, 60.0, (1500.0, 2000.0)),
+];
+
+fn phone_def(name: &str) -> &'static PhoneDef {
+    PHONES.iter().find(|def| def.name == name).expect("phoneme is defined")
+}
+
+/// Common words English spelling rules get wrong
+const PRONUNCIATIONS: &[(&str, &str)] = &[
+    ("a", "AH"),
+    ("the", "DH AH"),
+    ("i", "AY"),
+    ("to", "T UW"),
+    ("do", "D UW"),
+    ("of", "AH V"),
+    ("you", "Y UW"),
+    ("is", "IH Z"),
+    ("was", "W AH Z"),
+    ("are", "AA R"),
+    ("one", "W AH N"),
+    ("two", "T UW"),
+    ("what", "W AH T"),
+    ("he", "HH IY"),
+    ("she", "SH IY"),
+    ("we", "W IY"),
+    ("me", "M IY"),
+    ("be", "B IY"),
+    ("have", "HH AE V"),
+    ("give", "G IH V"),
+    ("some", "S AH M"),
+    ("come", "K AH M"),
+    ("done", "D AH N"),
+    ("said", "S EH D"),
+    ("says", "S EH Z"),
+    ("question", "K W EH S CH AH N"),
+    ("answer", "AE N S ER"),
+];
+
+/// Letter groups and their phonemes, longest first
+const SPELLING_RULES: &[(&str, &str)] = &[
+    ("tion", "SH AH N"),
+    ("sion", "ZH AH N"),
+    ("ough", "AO"),
+    ("igh", "AY"),
+    ("tch", "CH"),
+    ("th", "TH"),
+    ("sh", "SH"),
+    ("ch", "CH"),
+    ("ph", "F"),
+    ("ng", "NG"),
+    ("ck", "K"),
+    ("qu", "K W"),
+    ("wh", "W"),
+    ("ee", "IY"),
+    ("ea", "IY"),
+    ("oo", "UW"),
+    ("ai", "EY"),
+    ("ay", "EY"),
+    ("ey", "EY"),
+    ("oa", "OW"),
+    ("ou", "AW"),
+    ("ow", "OW"),
+    ("oi", "OY"),
+    ("oy", "OY"),
+    ("au", "AO"),
+    ("aw", "AO"),
+    ("er", "ER"),
+    ("ir", "ER"),
+    ("ur", "ER"),
+    ("ar", "AA R"),
+    ("or", "AO R"),
+];
+
+fn is_vowel_letter(c: u8) -> bool {
+    matches!(c, b'a' | b'e' | b'i' | b'o' | b'u')
+}
+
+/// Phonemes of a word by English spelling rules
+fn pronounce(word: &str) -> Vec<&'static PhoneDef> {
+    if let Some((_, phones)) = PRONUNCIATIONS.iter().find(|(spelling, _)| *spelling == word) {
+        return phones.split_whitespace().map(phone_def).collect();
+    }
+
+    let mut letters: Vec<u8> = word.bytes().filter(u8::is_ascii_lowercase).collect();
+    // A final silent "e" lengthens the vowel before the last consonant ("code", "time")
+    let mut long_vowel = None;
+    let n = letters.len();
+    if n > 2 && letters[n - 1] == b'e' && !is_vowel_letter(letters[n - 2]) {
+        letters.pop();
+        if n > 3 && is_vowel_letter(letters[n - 3]) && !is_vowel_letter(letters[n - 4]) {
+            long_vowel = Some(n - 3);
+        }
+    }
+
+    let mut phones: Vec<&'static PhoneDef> = Vec::new();
+    let mut i = 0;
+    while i < letters.len() {
+        let rest = &letters[i..];
+        if let Some((spelling, sounds)) = SPELLING_RULES.iter().find(|(spelling, _)| rest.starts_with(spelling.as_bytes())) {
+            phones.extend(sounds.split_whitespace().map(phone_def));
+            i += spelling.len();
+            continue;
+        }
+
+        let next = letters.get(i + 1).copied();
+        let sounds = match letters[i] {
+            b'a' if long_vowel == Some(i) => "EY",
+            b'e' if long_vowel == Some(i) => "IY",
+            b'i' if long_vowel == Some(i) => "AY",
+            b'o' if long_vowel == Some(i) => "OW",
+            b'u' if long_vowel == Some(i) => "UW",
+            b'a' => "AE",
+            b'e' if i + 1 == letters.len() && letters.len() <= 3 => "IY",
+            b'e' => "EH",
+            b'i' => "IH",
+            b'o' => "AA",
+            b'u' => "AH",
+            b'y' if i == 0 => "Y",
+            b'y' if i + 1 == letters.len() => "IY",
+            b'y' => "IH",
+            b'c' if matches!(next, Some(b'e' | b'i' | b'y')) => "S",
+            b'c' | b'k' | b'q' => "K",
+            b'g' if matches!(next, Some(b'e' | b'i')) && i > 0 => "JH",
+            b'g' => "G",
+            b'x' => "K S",
+            b'b' => "B",
+            b'd' => "D",
+            b'f' => "F",
+            b'h' => "HH",
+            b'j' => "JH",
+            b'l' => "L",
+            b'm' => "M",
+            b'n' => "N",
+            b'p' => "P",
+            b'r' => "R",
+            b's' if i > 0 && i + 1 == letters.len() && !matches!(letters[i - 1], b's' | b'p' | b't' | b'k' | b'f') => "Z",
+            b's' => "S",
+            b't' => "T",
+            b'v' => "V",
+            b'w' => "W",
+            b'z' => "Z",
+            _ => "",
+        };
+        for sound in sounds.split_whitespace().map(phone_def) {
+            // Double consonants ("ll", "ss") are one sound
+            if !(sound.class != Class::Vowel && phones.last().is_some_and(|last| last.name == sound.name)) {
+                phones.push(sound);
+            }
+        }
+        i += 1;
+    }
+    phones
+}
+
+/// Two-pole resonator, as in a Klatt synthesizer
+#[derive(Default)]
+struct Resonator {
+    a: f32,
+    b: f32,
+    c: f32,
+    y1: f32,
+    y2: f32,
+}
+
+impl Resonator {
+    fn set(&mut self, frequency: f32, bandwidth: f32, sample_rate: f32) {
+        let frequency = frequency.min(sample_rate * 0.45);
+        let r = (-PI * bandwidth / sample_rate).exp();
+        self.c = -r * r;
+        self.b = 2.0 * r * (2.0 * PI * frequency / sample_rate).cos();
+        self.a = 1.0 - self.b - self.c;
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.a * x + self.b * self.y1 + self.c * self.y2;
+        self.y2 = self.y1;
+        self.y1 = y;
+        y
+    }
+}
+
+/// A phoneme placed on the output timeline
+struct PlacedPhone {
+    def: &'static PhoneDef,
+    start: usize,
+    len: usize,
+    /// Formants the phone glides in from
+    from: [f32; 3],
+}
+
+/// Rule-based formant synthesizer used when no neural voice is available
+struct FormantVoice {
+    sample_rate: u32,
+    pitch: f32,
+}
+
+const FORMANT_BANDWIDTHS: [f32; 3] = [60.0, 90.0, 120.0];
+/// Time formants take to move from one phone's targets to the next
+const TRANSITION_MS: f32 = 30.0;
+
+impl FormantVoice {
+    fn new(sample_rate: u32, pitch: f32) -> Self {
+        Self { sample_rate, pitch }
+    }
+
+    fn samples(&self, ms: f32, speed: f32) -> usize {
+        (ms / 1000.0 / speed * self.sample_rate as f32) as usize
+    }
+
+    fn render(&self, chunks: &[Chunk], speed: f32) -> Vec<f32> {
+        // Lay out the phones of each phrase between pauses
+        let mut phones = Vec::new();
+        let mut phrases = Vec::new();
+        let mut cursor = self.samples(60.0, 1.0);
+        let mut phrase_start = cursor;
+        let mut from = NEUTRAL;
+        for chunk in chunks {
+            match chunk {
+                Chunk::Word(word) => {
+                    for def in pronounce(word) {
+                        let len = self.samples(def.ms, speed);
+                        phones.push(PlacedPhone { def, start: cursor, len, from });
+                        from = def.end;
+                        cursor += len;
+                    }
+                }
+                Chunk::Pause { ms, question } => {
+                    phrases.push((phrase_start, cursor, *question));
+                    cursor += self.samples(*ms, speed);
+                    phrase_start = cursor;
+                    from = NEUTRAL;
+                }
+            }
+        }
+        if cursor > phrase_start {
+            phrases.push((phrase_start, cursor, false));
+        }
+        let total = cursor + self.samples(120.0, 1.0);
+
+        let voiced = self.voiced_branch(&phones, &phrases, total);
+        let reference = rms(&voiced);
+        let mut output = voiced;
+        self.add_noise(&phones, reference, &mut output);
+
+        let peak = output.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        if peak > 0.0 {
+            output.iter_mut().for_each(|s| *s *= 0.8 / peak);
+        }
+        output
+    }
+
+    /// Glottal pulses through a cascade of formant resonators
+    fn voiced_branch(&self, phones: &[PlacedPhone], phrases: &[(usize, usize, bool)], total: usize) -> Vec<f32> {
+        let rate = self.sample_rate as f32;
+        let mut output = vec![0.0; total];
+        let mut resonators: [Resonator; 3] = Default::default();
+        let transition = self.samples(TRANSITION_MS, 1.0).max(1);
+        // Voicing fades in and out over 10ms, including into pauses
+        let ramp = 1.0 / self.samples(10.0, 1.0).max(1) as f32;
+        let mut phase = 0.0f32;
+        let mut previous_pulse = 0.0f32;
+        let mut amplitude = 0.0f32;
+        let mut current = 0;
+        let mut phrase = 0;
+
+        for (t, out) in output.iter_mut().enumerate() {
+            while current < phones.len() && t >= phones[current].start + phones[current].len {
+                current += 1;
+            }
+            while phrase < phrases.len() && t >= phrases[phrase].1 {
+                phrase += 1;
+            }
+
+            let target_amplitude = match phones.get(current).filter(|phone| t >= phone.start) {
+                Some(phone) => {
+                    // Formants glide in from the previous phone, then along any diphthong
+                    let n = t - phone.start;
+                    let progress = n as f32 / phone.len as f32;
+                    let glide = (n as f32 / transition as f32).min(1.0);
+                    for (k, resonator) in resonators.iter_mut().enumerate() {
+                        let target = phone.def.start[k] + (phone.def.end[k] - phone.def.start[k]) * progress;
+                        resonator.set(phone.from[k] + (target - phone.from[k]) * glide, FORMANT_BANDWIDTHS[k], rate);
+                    }
+                    match (phone.def.class, phone.def.voiced) {
+                        (Class::Vowel, _) => 1.0,
+                        (Class::Approximant, _) => 0.6,
+                        (Class::Nasal, _) => 0.45,
+                        (Class::Fricative | Class::Affricate, true) => 0.2,
+                        (Class::Stop, true) => 0.1,
+                        _ => 0.0,
+                    }
+                }
+                // Between phrases the last formants ring out
+                None => 0.0,
+            };
+            if amplitude == 0.0 && target_amplitude == 0.0 {
+                *out = resonators.iter_mut().fold(0.0, |sample, resonator| resonator.process(sample));
+                continue;
+            }
+
+            // Pitch falls across a phrase and rises at the end of a question
+            let (phrase_start, phrase_end, question) = phrases.get(phrase).copied().unwrap_or((t, t + 1, false));
+            let position = (t.saturating_sub(phrase_start) as f32 / (phrase_end - phrase_start).max(1) as f32).min(1.0);
+            let mut pitch = self.pitch * (1.15 - 0.3 * position);
+            if question && position > 0.7 {
+                pitch *= 1.0 + (position - 0.7) * 1.5;
+            }
+
+            phase += pitch / rate;
+            if phase >= 1.0 {
+                phase -= 1.0;
+            }
+            let pulse = glottal_pulse(phase);
+            // Lip radiation differentiates the source
+            let source = pulse - previous_pulse;
+            previous_pulse = pulse;
+
+            amplitude += (target_amplitude - amplitude).clamp(-ramp, ramp);
+            *out = resonators.iter_mut().fold(source * amplitude, |sample, resonator| resonator.process(sample));
+        }
+        output
+    }
+
+    /// Frication, bursts and aspiration, each scaled against the voiced level
+    fn add_noise(&self, phones: &[PlacedPhone], reference: f32, output: &mut [f32]) {
+        let rate = self.sample_rate as f32;
+        let mut noise = Noise::new(0x2545_f491);
+
+        for phone in phones {
+            let def = phone.def;
+            // (offset, length, centre, bandwidth, level) of the noisy part of the phone
+            let parts: Vec<(usize, usize, f32, f32, f32)> = match def.class {
+                Class::Fricative => {
+                    let level = if def.noise.1 >= 3000.0 { 0.15 } else { 0.4 };
+                    let level = if def.voiced { level * 0.6 } else { level };
+                    vec![(0, phone.len, def.noise.0, def.noise.1, level)]
+                }
+                Class::Stop => {
+                    let closure = phone.len * 55 / 100;
+                    let burst = phone.len / 8;
+                    let mut parts = vec![(closure, burst, def.noise.0, def.noise.1, 0.5)];
+                    if !def.voiced {
+                        parts.push((closure + burst, phone.len - closure - burst, 1500.0, 2500.0, 0.12));
+                    }
+                    parts
+                }
+                Class::Affricate => {
+                    let closure = phone.len * 35 / 100;
+                    vec![(closure, phone.len - closure, def.noise.0, def.noise.1, 0.4)]
+                }
+                Class::Aspirate => vec![(0, phone.len, def.noise.0, def.noise.1, 0.12)],
+                _ => continue,
+            };
+
+            for (offset, len, centre, bandwidth, level) in parts {
+                if len == 0 {
+                    continue;
+                }
+                let mut resonator = Resonator::default();
+                resonator.set(centre, bandwidth, rate);
+                let filtered: Vec<f32> = (0..len).map(|_| resonator.process(noise.sample())).collect();
+                let gain = level * reference / rms(&filtered).max(f32::EPSILON);
+                let fade = self.samples(5.0, 1.0).clamp(1, len.div_ceil(2));
+                for (n, sample) in filtered.into_iter().enumerate() {
+                    let edge = n.min(len - 1 - n);
+                    let envelope = (edge as f32 / fade as f32).min(1.0);
+                    output[phone.start + offset + n] += sample * gain * envelope;
+                }
+            }
+        }
+    }
+}
+
+/// Rosenberg glottal pulse over one pitch period
+fn glottal_pulse(phase: f32) -> f32 {
+    if phase < 0.4 {
+        0.5 * (1.0 - (PI * phase / 0.4).cos())
+    } else if phase < 0.56 {
+        (PI * (phase - 0.4) / 0.32).cos()
+    } else {
+        0.0
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// Deterministic white noise, so the same text always renders the same audio
+struct Noise(u32);
+
+impl Noise {
+    fn new(seed: u32) -> Self {
+        Self(seed)
+    }
+
+    fn sample(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> SpeechContent {
+        SpeechContent::Summary {
+            text: "## Summary\n- Binary search halves the range on every step.\n- It needs 20 comparisons for 1,000,000 items. Why?"
+                .to_string(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_voice_writes_well_formed_wav() {
+        let synthesizer = SpeechSynthesizer::new();
+        let speech = synthesizer.speak(&summary(), &SpeechOptions::default()).unwrap();
+        let wav = speech.to_wav().unwrap();
+
+        // RIFF header with sizes matching the data
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(u32::from_le_bytes(wav[4..8].try_into().unwrap()) as usize, wav.len() - 8);
+
+        let mut reader = hound::WavReader::new(Cursor::new(&wav)).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 1);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, SampleFormat::Int);
+        assert_eq!(spec.sample_rate, BUILTIN_SAMPLE_RATE);
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        assert_eq!(samples.len(), speech.samples.len());
+
+        // Audible, unclipped and about as long as the text takes to say
+        assert!(speech.samples.iter().all(|s| s.is_finite() && s.abs() <= 1.0));
+        assert!(rms(&speech.samples) > 0.05);
+        assert!((3.0..15.0).contains(&speech.duration_secs()), "{}", speech.duration_secs());
+        assert_eq!(speech.voice, "formant-low");
+    }
+
+    #[test]
+    fn test_voice_and_speed_settings() {
+        let synthesizer = SpeechSynthesizer::new();
+        let text = "Flashcards work best when you review them every day.";
+        let normal = synthesizer.synthesize(text, &SpeechOptions::default()).unwrap();
+        let fast = synthesizer
+            .synthesize(
+                text,
+                &SpeechOptions {
+                    voice: Some("formant-high".to_string()),
+                    speed: Some(2.0),
+                },
+            )
+            .unwrap();
+
+        let ratio = fast.duration_secs() / normal.duration_secs();
+        assert!((0.45..0.6).contains(&ratio), "{}", ratio);
+        assert_eq!(fast.voice, "formant-high");
+
+        let unknown = SpeechOptions {
+            voice: Some("robot".to_string()),
+            speed: None,
+        };
+        assert!(synthesizer.synthesize(text, &unknown).is_err());
+        let too_fast = SpeechOptions {
+            voice: None,
+            speed: Some(3.0),
+        };
+        assert!(synthesizer.synthesize(text, &too_fast).is_err());
+        assert!(synthesizer.synthesize("  ...  ", &SpeechOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_flashcard_pcm_matches_wav_data() {
+        let card = SpeechContent::Flashcard {
+            front: "What is the time complexity of binary search".to_string(),
+            back: "O of log n".to_string(),
+        };
+        assert_eq!(
+            spoken_text(&normalize_text(&card.script())),
+            "question. what is the time complexity of binary search. answer. o of log n."
+        );
+
+        let speech = SpeechSynthesizer::new().speak(&card, &SpeechOptions::default()).unwrap();
+        let pcm = speech.encode(AudioFormat::Pcm).unwrap();
+        let wav = speech.encode(AudioFormat::Wav).unwrap();
+        assert_eq!(pcm.len(), speech.samples.len() * 2);
+        // A 16-bit mono WAV is a 44-byte header followed by the same samples
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[44..], &pcm[..]);
+        assert_eq!(speech.content_type(AudioFormat::Pcm), "audio/pcm;rate=22050;channels=1;format=s16le");
+    }
+}
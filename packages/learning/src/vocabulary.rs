@@ -0,0 +1,523 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// File the vocabulary is stored in under the data dir
+pub const VOCABULARY_FILE_NAME: &str = "vocabulary.json";
+
+/// Shortest phonetic key matched by sound alone. Shorter terms ("Rust", "TTA") would
+/// collide with everyday words, so they are only matched through their spellings,
+/// aliases and pronunciations.
+const MIN_PHONETIC_KEY: usize = 4;
+/// Shortest phonetic key that may also match one edit away
+const MIN_FUZZY_PHONETIC_KEY: usize = 8;
+/// Shortest OCR-normalized spelling that may match one edit away, and two edits away
+const MIN_FUZZY_OCR_LEN: usize = 6;
+const MIN_FUZZY_OCR_LEN_2: usize = 10;
+
+/// A course name, product name or piece of jargon with the ways it gets mangled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VocabularyTerm {
+    /// The term as it should be written
+    pub term: String,
+    /// How the term sounds, spelled with ordinary words ("cooper netties")
+    #[serde(default)]
+    pub pronunciations: Vec<String>,
+    /// Other spellings and abbreviations to rewrite to the term
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl VocabularyTerm {
+    pub fn new(term: impl Into<String>) -> Self {
+        Self {
+            term: term.into(),
+            pronunciations: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+
+    pub fn with_pronunciation(mut self, pronunciation: impl Into<String>) -> Self {
+        self.pronunciations.push(pronunciation.into());
+        self
+    }
+
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
+    /// Forms the term can be heard as
+    fn spoken_forms(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.term.as_str())
+            .chain(self.aliases.iter().map(String::as_str))
+            .chain(self.pronunciations.iter().map(String::as_str))
+    }
+
+    /// Forms the term can be written as
+    fn written_forms(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.term.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+}
+
+/// A user's vocabulary, applied to transcripts and OCR text
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vocabulary {
+    #[serde(default)]
+    pub terms: Vec<VocabularyTerm>,
+}
+
+impl Vocabulary {
+    /// Load a vocabulary file; a missing file is an empty vocabulary
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let source = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let vocabulary: Vocabulary =
+            serde_json::from_str(&source).with_context(|| format!("parsing {}", path.display()))?;
+        vocabulary.validate()?;
+        Ok(vocabulary)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    /// The entry for a term, compared case-insensitively
+    pub fn get(&self, term: &str) -> Option<&VocabularyTerm> {
+        self.terms.iter().find(|entry| entry.term.eq_ignore_ascii_case(term.trim()))
+    }
+
+    /// Add a term, replacing any entry with the same name
+    pub fn upsert(&mut self, term: VocabularyTerm) {
+        match self.terms.iter_mut().find(|entry| entry.term.eq_ignore_ascii_case(&term.term)) {
+            Some(entry) => *entry = term,
+            None => self.terms.push(term),
+        }
+    }
+
+    /// Remove a term; returns whether it was present
+    pub fn remove(&mut self, term: &str) -> bool {
+        let before = self.terms.len();
+        self.terms.retain(|entry| !entry.term.eq_ignore_ascii_case(term.trim()));
+        self.terms.len() != before
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for entry in &self.terms {
+            if entry.term.trim().is_empty() {
+                return Err(anyhow!("vocabulary terms cannot be empty"));
+            }
+            if !seen.insert(entry.term.to_lowercase()) {
+                return Err(anyhow!("duplicate vocabulary term `{}`", entry.term));
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite words of a transcript that sound like a term to the term.
+    ///
+    /// This rescoring runs over the decoded hypothesis, favouring a term whenever a
+    /// run of words spells one of its spoken forms or shares its phonetic key.
+    pub fn bias_transcript(&self, text: &str) -> String {
+        if self.terms.is_empty() {
+            return text.to_string();
+        }
+        let forms: Vec<(&str, &str)> = self
+            .terms
+            .iter()
+            .flat_map(|entry| entry.spoken_forms().map(move |form| (entry.term.as_str(), form)))
+            .collect();
+
+        rewrite_words(text, max_words(&forms) + 1, |candidate| {
+            let squashed = squash(candidate);
+            let key = phonetic_key(candidate);
+            let mut best: Option<(usize, &str)> = None;
+            for &(term, form) in &forms {
+                let distance = if squash(form) == squashed {
+                    0
+                } else {
+                    let form_key = phonetic_key(form);
+                    if form_key.len() < MIN_PHONETIC_KEY {
+                        continue;
+                    }
+                    match edit_distance(&form_key, &key) {
+                        0 => 1,
+                        1 if form_key.len() >= MIN_FUZZY_PHONETIC_KEY => 2,
+                        _ => continue,
+                    }
+                };
+                if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                    best = Some((distance, term));
+                }
+            }
+            best.map(|(_, term)| term)
+        })
+    }
+
+    /// Rewrite misrecognized spellings of terms in OCR text to the term.
+    ///
+    /// Characters OCR commonly confuses ("0"/"O", "1"/"l", "rn"/"m") compare equal, and
+    /// longer terms also match a character or two away.
+    pub fn correct_ocr(&self, text: &str) -> String {
+        if self.terms.is_empty() {
+            return text.to_string();
+        }
+        let forms: Vec<(&str, String)> = self
+            .terms
+            .iter()
+            .flat_map(|entry| entry.written_forms().map(move |form| (entry.term.as_str(), ocr_normalize(form))))
+            .collect();
+        let max = self
+            .terms
+            .iter()
+            .flat_map(|entry| entry.written_forms())
+            .map(|form| form.split_whitespace().count())
+            .max()
+            .unwrap_or(1);
+
+        rewrite_words(text, max, |candidate| {
+            let normalized = ocr_normalize(candidate);
+            let mut best: Option<(usize, &str)> = None;
+            for (term, form) in &forms {
+                let allowed = if form.len() >= MIN_FUZZY_OCR_LEN_2 {
+                    2
+                } else if form.len() >= MIN_FUZZY_OCR_LEN {
+                    1
+                } else {
+                    0
+                };
+                let distance = edit_distance(form, &normalized);
+                if distance <= allowed && best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                    best = Some((distance, *term));
+                }
+            }
+            best.map(|(_, term)| term)
+        })
+    }
+}
+
+/// A vocabulary shared by the recognizers and persisted as it is edited
+#[derive(Debug, Default)]
+pub struct VocabularyStore {
+    path: Option<PathBuf>,
+    vocabulary: RwLock<Vocabulary>,
+}
+
+impl VocabularyStore {
+    /// A store that is not persisted
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open the vocabulary in a file, starting empty if it does not exist yet
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let vocabulary = Vocabulary::load(&path)?;
+        Ok(Self {
+            path: Some(path),
+            vocabulary: RwLock::new(vocabulary),
+        })
+    }
+
+    /// Open the vocabulary file in a data directory
+    pub fn open_in_dir(data_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::open(data_dir.as_ref().join(VOCABULARY_FILE_NAME))
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// A copy of the current vocabulary
+    pub fn vocabulary(&self) -> Vocabulary {
+        self.vocabulary.read().unwrap().clone()
+    }
+
+    /// Add or replace a term
+    pub fn upsert(&self, term: VocabularyTerm) -> Result<()> {
+        if term.term.trim().is_empty() {
+            return Err(anyhow!("vocabulary terms cannot be empty"));
+        }
+        let mut vocabulary = self.vocabulary.write().unwrap();
+        let mut updated = vocabulary.clone();
+        updated.upsert(term);
+        self.persist(&updated)?;
+        *vocabulary = updated;
+        Ok(())
+    }
+
+    /// Remove a term; returns whether it was present
+    pub fn remove(&self, term: &str) -> Result<bool> {
+        let mut vocabulary = self.vocabulary.write().unwrap();
+        let mut updated = vocabulary.clone();
+        if !updated.remove(term) {
+            return Ok(false);
+        }
+        self.persist(&updated)?;
+        *vocabulary = updated;
+        Ok(true)
+    }
+
+    /// Replace the whole vocabulary
+    pub fn replace(&self, replacement: Vocabulary) -> Result<()> {
+        replacement.validate()?;
+        let mut vocabulary = self.vocabulary.write().unwrap();
+        self.persist(&replacement)?;
+        *vocabulary = replacement;
+        Ok(())
+    }
+
+    pub fn bias_transcript(&self, text: &str) -> String {
+        self.vocabulary.read().unwrap().bias_transcript(text)
+    }
+
+    pub fn correct_ocr(&self, text: &str) -> String {
+        self.vocabulary.read().unwrap().correct_ocr(text)
+    }
+
+    fn persist(&self, vocabulary: &Vocabulary) -> Result<()> {
+        match &self.path {
+            Some(path) => vocabulary.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A word of the text with the span of its letters, without surrounding punctuation
+struct WordSpan {
+    start: usize,
+    end: usize,
+    /// Punctuation before or after the word, which ends a multi-word match
+    leading: bool,
+    trailing: bool,
+    /// Looks like a URL, email address or path, which are never rewritten
+    verbatim: bool,
+}
+
+fn word_spans(text: &str) -> Vec<WordSpan> {
+    let mut spans = Vec::new();
+    let mut offset = 0;
+    for token in text.split_inclusive(char::is_whitespace) {
+        let word = token.trim_end();
+        let start = offset + (word.len() - word.trim_start_matches(is_edge_punctuation).len());
+        let end = offset + word.trim_end_matches(is_edge_punctuation).len();
+        if end > start {
+            spans.push(WordSpan {
+                start,
+                end,
+                leading: start > offset,
+                trailing: end < offset + word.len(),
+                verbatim: word.contains("://") || word.contains(['@', '/', '\\', '_', '=']),
+            });
+        }
+        offset += token.len();
+    }
+    spans
+}
+
+fn is_edge_punctuation(c: char) -> bool {
+    matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | '(' | ')' | '[' | ']' | '"' | '\'')
+}
+
+/// Replace runs of up to `max_words` words for which `matcher` returns a term,
+/// preferring the longest run at each position
+fn rewrite_words<'a>(text: &str, max_words: usize, mut matcher: impl FnMut(&str) -> Option<&'a str>) -> String {
+    let spans = word_spans(text);
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < spans.len() {
+        let mut matched = 0;
+        for n in (1..=max_words.min(spans.len() - i)).rev() {
+            let window = &spans[i..i + n];
+            if !joinable(text, window) {
+                continue;
+            }
+            let candidate = &text[window[0].start..window[n - 1].end];
+            if !candidate.chars().any(char::is_alphabetic) {
+                continue;
+            }
+            if let Some(term) = matcher(candidate) {
+                out.push_str(&text[copied..window[0].start]);
+                out.push_str(term);
+                copied = window[n - 1].end;
+                matched = n;
+                break;
+            }
+        }
+        i += matched.max(1);
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+/// Whether a run of words can be matched as one: on one line, without verbatim words
+/// or punctuation between them
+fn joinable(text: &str, window: &[WordSpan]) -> bool {
+    window.iter().all(|span| !span.verbatim)
+        && window.windows(2).all(|pair| {
+            !pair[0].trailing && !pair[1].leading && text[pair[0].end..pair[1].start].chars().all(|c| c == ' ')
+        })
+}
+
+fn max_words(forms: &[(&str, &str)]) -> usize {
+    forms
+        .iter()
+        .map(|(_, form)| form.split_whitespace().count())
+        .max()
+        .unwrap_or(1)
+}
+
+/// Lowercase letters and digits only
+fn squash(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A key that sounds-alike spellings share: voicing is ignored, vowels after the first
+/// letter are dropped and repeated sounds collapse ("kubernetes" and "cooper net ease"
+/// are both "kprnts")
+fn phonetic_key(text: &str) -> String {
+    let letters: Vec<char> = squash(text).chars().filter(char::is_ascii_alphabetic).collect();
+    let mut sounds = Vec::with_capacity(letters.len());
+    let mut i = 0;
+    while i < letters.len() {
+        let next = letters.get(i + 1).copied();
+        let (sound, width) = match (letters[i], next) {
+            ('p', Some('h')) => ('f', 2),
+            ('c', Some('k')) => ('k', 2),
+            ('c', Some('e' | 'i' | 'y')) => ('s', 1),
+            ('c' | 'q' | 'g', _) => ('k', 1),
+            ('x', _) => {
+                sounds.push('k');
+                ('s', 1)
+            }
+            ('z', _) => ('s', 1),
+            ('d', _) => ('t', 1),
+            ('b', _) => ('p', 1),
+            ('v', _) => ('f', 1),
+            ('a' | 'e' | 'i' | 'o' | 'u', _) if i == 0 => ('a', 1),
+            ('a' | 'e' | 'i' | 'o' | 'u' | 'h' | 'w' | 'y', _) => {
+                i += 1;
+                continue;
+            }
+            (letter, _) => (letter, 1),
+        };
+        if sounds.last() != Some(&sound) {
+            sounds.push(sound);
+        }
+        i += width;
+    }
+    sounds.into_iter().collect()
+}
+
+/// Spelling with characters OCR confuses mapped to one of them
+fn ocr_normalize(text: &str) -> String {
+    squash(text)
+        .replace("rn", "m")
+        .replace("vv", "w")
+        .replace("cl", "d")
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | 'i' => 'l',
+            '5' => 's',
+            '8' => 'b',
+            _ => c,
+        })
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vocabulary() -> Vocabulary {
+        Vocabulary {
+            terms: vec![
+                VocabularyTerm::new("Kubernetes").with_alias("k8s"),
+                VocabularyTerm::new("TTAWin").with_pronunciation("tee tee ay win"),
+                VocabularyTerm::new("CS 101 Data Structures").with_pronunciation("cs one oh one data structures"),
+                VocabularyTerm::new("Rust"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_transcripts_are_biased_toward_terms() {
+        let biased = vocabulary().bias_transcript(
+            "we deploy it on cooper net ease, then open tee tee ay win. \
+             cs one oh one data structures starts after a rest",
+        );
+
+        assert_eq!(
+            biased,
+            "we deploy it on Kubernetes, then open TTAWin. \
+             CS 101 Data Structures starts after a rest"
+        );
+        // Short terms only match their spelling
+        assert_eq!(vocabulary().bias_transcript("rust and k8s"), "Rust and Kubernetes");
+    }
+
+    #[test]
+    fn test_ocr_confusions_are_corrected() {
+        let corrected = vocabulary().correct_ocr(
+            "Deploying to Kubemetes\n(see CS 1O1 Data Structures)\nhttps://k8s.io/docs TTAVVin 2.0",
+        );
+
+        assert_eq!(
+            corrected,
+            "Deploying to Kubernetes\n(see CS 101 Data Structures)\nhttps://k8s.io/docs TTAWin 2.0"
+        );
+        // Nothing is rewritten across a line break
+        assert_eq!(vocabulary().correct_ocr("CS 101\nData Structures"), "CS 101\nData Structures");
+    }
+
+    #[test]
+    fn test_store_persists_edits() {
+        let dir = std::env::temp_dir().join(format!("vocabulary-{}", uuid::Uuid::new_v4()));
+        let store = VocabularyStore::open_in_dir(&dir).unwrap();
+        assert!(store.vocabulary().terms.is_empty());
+
+        store.upsert(VocabularyTerm::new("Figma")).unwrap();
+        store.upsert(VocabularyTerm::new("figma").with_alias("figjam")).unwrap();
+        store.upsert(VocabularyTerm::new("Postgres")).unwrap();
+        assert!(store.remove("POSTGRES").unwrap());
+        assert!(!store.remove("Postgres").unwrap());
+        assert!(store.upsert(VocabularyTerm::new("  ")).is_err());
+
+        let reopened = VocabularyStore::open_in_dir(&dir).unwrap();
+        assert_eq!(reopened.vocabulary().terms, vec![VocabularyTerm::new("figma").with_alias("figjam")]);
+        assert_eq!(reopened.correct_ocr("Open figjam"), "Open figma");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -4,6 +4,7 @@ use learning::{
     audio::AudioTranscriber,
     llm::LocalLLM,
     analysis::{AnalysisEngine, ContentType, ExtractedText},
+    fusion::FusedTimeline,
 };
 use std::sync::Arc;
 
@@ -72,6 +73,8 @@ async fn test_extracted_text_analysis() {
         confidence: 0.95,
         timestamp: chrono::Utc::now(),
         metadata: std::collections::HashMap::new(),
+        time_range: None,
+        language: None,
     };
     
     let result = analysis_engine.analyze_extracted_text(extracted_text).await;
@@ -79,7 +82,11 @@ async fn test_extracted_text_analysis() {
     
     let analysis = result.unwrap();
     assert_eq!(analysis.content_type, ContentType::Text);
-    assert_eq!(analysis.confidence, 0.95);
+    // Confidence combines the extraction score with the model's own signals
+    let breakdown = &analysis.metadata["confidence"];
+    assert_eq!(breakdown["fields"][0]["field"], "input");
+    assert!((breakdown["score"].as_f64().unwrap() as f32 - analysis.confidence).abs() < 1e-6);
+    assert!(analysis.confidence > 0.0 && analysis.confidence < 1.0);
 }
 
 #[tokio::test]
@@ -249,4 +256,37 @@ async fn test_error_handling() {
     let long_text = "This is a very long text. ".repeat(1000);
     let result = analysis_engine.analyze_text(&long_text).await;
     assert!(result.is_ok(), "Long text should be handled gracefully");
-} 
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_combined_analysis_preserves_alignments() {
+    // Slides and speech captured at the same time should be related to each other
+    let llm = LocalLLM::new().await.unwrap();
+    let analysis_engine = AnalysisEngine::new(Arc::new(llm));
+
+    let start = chrono::Utc::now();
+    let slide = ExtractedText {
+        source: ContentType::Screenshot,
+        text: "Dynamic programming: memoization".to_string(),
+        confidence: 0.9,
+        timestamp: start,
+        metadata: std::collections::HashMap::new(),
+        time_range: None,
+        language: None,
+    };
+    let speech = ExtractedText {
+        source: ContentType::Audio,
+        text: "Memoization caches results of overlapping subproblems".to_string(),
+        confidence: 0.8,
+        timestamp: start + chrono::Duration::seconds(3),
+        metadata: std::collections::HashMap::new(),
+        time_range: None,
+        language: None,
+    };
+
+    let analysis = analysis_engine.analyze_combined(vec![speech, slide]).await.unwrap();
+    let alignments = FusedTimeline::alignments_from_metadata(&analysis.metadata);
+    assert_eq!(alignments.len(), 1, "The slide should be aligned with the speech");
+    assert!(alignments[0].shared_terms.contains(&"memoization".to_string()));
+    assert!(analysis.insights.iter().any(|i| i.contains("Slide 1")));
+}
@@ -23,7 +23,7 @@ async fn main() -> Result<(), ServiceError> {
     // Initialize services
     println!("🔧 Initializing services...");
     
-    let learning_service = LearningService::new(config.learning.clone())?;
+    let learning_service = LearningService::new(config.learning.clone(), config.stream.audio_config(), &config.settings.data_dir)?;
     println!("   ✅ Learning service initialized");
     
     let payment_service = PaymentService::new(config.payments.clone())?;
@@ -32,7 +32,7 @@ async fn main() -> Result<(), ServiceError> {
     let settings_service = SettingsService::new(config.settings.clone())?;
     println!("   ✅ Settings service initialized");
     
-    let stream_service = StreamService::new(config.stream.clone())?;
+    let stream_service = StreamService::new(config.stream.clone())?.with_transcriber(learning_service.audio_transcriber());
     println!("   ✅ Stream service initialized");
     println!();
 
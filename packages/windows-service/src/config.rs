@@ -26,6 +26,9 @@ pub struct LearningConfig {
     pub max_concurrent_analyses: usize,
     pub enable_gpu: bool,
     pub log_level: String,
+    /// Read-aloud defaults; neural voices come from `model_path/tts` unless `model_dir` is set
+    #[serde(default)]
+    pub speech: learning::tts::TtsConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +53,22 @@ pub struct StreamConfig {
     pub buffer_size: usize,
     pub sample_rate: u32,
     pub channels: u16,
+    /// Name of the preferred input device; the default input is used when unset or missing
+    #[serde(default)]
+    pub input_device: Option<String>,
+}
+
+impl StreamConfig {
+    /// Capture settings for the learning engine's live transcription
+    pub fn audio_config(&self) -> learning::audio::AudioConfig {
+        learning::audio::AudioConfig {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            buffer_size: self.buffer_size,
+            input_device: self.input_device.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 impl ServiceConfig {
@@ -119,6 +138,7 @@ impl Default for ServiceConfig {
                 max_concurrent_analyses: 4,
                 enable_gpu: false,
                 log_level: "info".to_string(),
+                speech: learning::tts::TtsConfig::default(),
             },
             payments: PaymentConfig {
                 stripe_secret_key: None,
@@ -142,6 +162,7 @@ impl Default for ServiceConfig {
                 buffer_size: 4096,
                 sample_rate: 16000,
                 channels: 1,
+                input_device: None,
             },
         }
     }
@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Json},
     routing::{get, post, put, delete},
     Router,
@@ -11,6 +11,9 @@ use tokio::sync::oneshot;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 
+use learning::tts::SpeechContent;
+use learning::vocabulary::VocabularyTerm;
+
 use crate::config::ServerConfig;
 use crate::error::ServiceError;
 use crate::services::{LearningService, PaymentService, SettingsService, StreamService};
@@ -76,10 +79,19 @@ impl HttpServer {
             .route("/learning/analyze", post(Self::analyze_content))
             .route("/learning/ocr", post(Self::extract_text))
             .route("/learning/audio", post(Self::transcribe_audio))
+            .route("/learning/meeting", post(Self::analyze_meeting))
             .route("/learning/summary", post(Self::generate_summary))
             .route("/learning/insights", post(Self::generate_insights))
             .route("/learning/session/:session_id", get(Self::get_session))
             .route("/learning/session/:session_id", delete(Self::clear_session))
+            .route("/learning/session/:session_id/tasks", get(Self::export_tasks))
+            .route("/learning/session/:session_id/recording/:transcript", get(Self::get_recording_playback))
+            .route("/learning/sessions", get(Self::list_sessions))
+            .route("/learning/sessions/search", get(Self::search_sessions))
+            .route("/learning/batch", post(Self::start_batch))
+            .route("/learning/batch/:batch_id", get(Self::get_batch))
+            .route("/learning/speech", post(Self::synthesize_speech))
+            .route("/learning/speech/voices", get(Self::list_voices))
             
             // Payment endpoints
             .route("/payments/process", post(Self::process_payment))
@@ -97,6 +109,10 @@ impl HttpServer {
             .route("/settings/restore", post(Self::restore_backup))
             .route("/settings/sync", post(Self::sync_settings))
             .route("/settings/reset", post(Self::reset_settings))
+            .route("/settings/vocabulary", get(Self::get_vocabulary))
+            .route("/settings/vocabulary", put(Self::replace_vocabulary))
+            .route("/settings/vocabulary/terms", post(Self::upsert_vocabulary_term))
+            .route("/settings/vocabulary/terms/:term", delete(Self::remove_vocabulary_term))
             
             // Stream endpoints
             .route("/stream/start", post(Self::start_stream))
@@ -104,6 +120,8 @@ impl HttpServer {
             .route("/stream/status", get(Self::get_stream_status))
             .route("/stream/audio", get(Self::get_audio_stream))
             .route("/stream/transcribe", post(Self::stream_transcribe))
+            .route("/stream/devices", get(Self::list_input_devices))
+            .route("/stream/device", put(Self::select_input_device))
             
             // File management endpoints
             .route("/files/upload", post(Self::upload_file))
@@ -168,6 +186,17 @@ impl HttpServer {
         Ok(Json(result))
     }
 
+    async fn analyze_meeting(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<MeetingRequest>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Analyzing meeting");
+        
+        let result = state.learning_service.analyze_meeting(&payload).await?;
+        
+        Ok(Json(result))
+    }
+
     async fn generate_summary(
         State(state): State<Arc<Self>>,
         Json(payload): Json<SummaryRequest>,
@@ -210,12 +239,99 @@ impl HttpServer {
     ) -> Result<impl IntoResponse, ServiceError> {
         tracing::info!("Clearing session: {}", session_id);
         
-        // TODO: Implement session clearing logic
         state.learning_service.clear_session(&session_id).await?;
         
         Ok(StatusCode::NO_CONTENT)
     }
 
+    async fn export_tasks(
+        State(state): State<Arc<Self>>,
+        Path(session_id): Path<String>,
+        Query(query): Query<ExportTasksQuery>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Exporting tasks for session: {}", session_id);
+        
+        let (content_type, body) = state.learning_service.export_tasks(&session_id, &query).await?;
+        
+        Ok(([(header::CONTENT_TYPE, content_type)], body))
+    }
+
+    async fn get_recording_playback(
+        State(state): State<Arc<Self>>,
+        Path((session_id, transcript)): Path<(String, usize)>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Getting recording playback for session: {}", session_id);
+        
+        let result = state.learning_service.recording_playback(&session_id, transcript).await?;
+        
+        Ok(Json(result))
+    }
+
+    async fn list_sessions(
+        State(state): State<Arc<Self>>,
+        Query(query): Query<ListSessionsQuery>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Listing sessions");
+        
+        let result = state.learning_service.list_sessions(&query).await?;
+        
+        Ok(Json(result))
+    }
+
+    async fn search_sessions(
+        State(state): State<Arc<Self>>,
+        Query(query): Query<SearchSessionsQuery>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Searching sessions");
+        
+        let result = state.learning_service.search_sessions(&query).await?;
+        
+        Ok(Json(result))
+    }
+
+    async fn start_batch(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<BatchAnalyzeRequest>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Starting batch analysis");
+        
+        let result = state.learning_service.start_batch(&payload).await?;
+        
+        Ok(Json(result))
+    }
+
+    async fn get_batch(
+        State(state): State<Arc<Self>>,
+        Path(batch_id): Path<String>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Getting batch: {}", batch_id);
+        
+        let result = state.learning_service.get_batch(&batch_id).await?;
+        
+        Ok(Json(result))
+    }
+
+    async fn synthesize_speech(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<SpeechRequest>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Synthesizing speech");
+
+        let (content_type, body) = state.learning_service.synthesize_speech(&payload).await?;
+
+        Ok(([(header::CONTENT_TYPE, content_type)], body))
+    }
+
+    async fn list_voices(
+        State(state): State<Arc<Self>>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Listing voices");
+
+        let result = state.learning_service.list_voices().await?;
+
+        Ok(Json(result))
+    }
+
     // Payment endpoints
     async fn process_payment(
         State(state): State<Arc<Self>>,
@@ -371,6 +487,49 @@ impl HttpServer {
         Ok(StatusCode::NO_CONTENT)
     }
 
+    async fn get_vocabulary(
+        State(state): State<Arc<Self>>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Getting vocabulary");
+
+        let result = state.learning_service.get_vocabulary().await?;
+
+        Ok(Json(result))
+    }
+
+    async fn replace_vocabulary(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<ReplaceVocabularyRequest>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Replacing vocabulary");
+
+        let result = state.learning_service.replace_vocabulary(&payload).await?;
+
+        Ok(Json(result))
+    }
+
+    async fn upsert_vocabulary_term(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<VocabularyTerm>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Saving vocabulary term: {}", payload.term);
+
+        let result = state.learning_service.upsert_vocabulary_term(&payload).await?;
+
+        Ok(Json(result))
+    }
+
+    async fn remove_vocabulary_term(
+        State(state): State<Arc<Self>>,
+        Path(term): Path<String>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Removing vocabulary term: {}", term);
+
+        state.learning_service.remove_vocabulary_term(&term).await?;
+
+        Ok(StatusCode::NO_CONTENT)
+    }
+
     // Stream endpoints
     async fn start_stream(
         State(state): State<Arc<Self>>,
@@ -429,6 +588,27 @@ impl HttpServer {
         Ok(Json(result))
     }
 
+    async fn list_input_devices(
+        State(state): State<Arc<Self>>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Listing input devices");
+
+        let result = state.stream_service.list_devices().await?;
+
+        Ok(Json(result))
+    }
+
+    async fn select_input_device(
+        State(state): State<Arc<Self>>,
+        Json(payload): Json<SelectDeviceRequest>,
+    ) -> Result<impl IntoResponse, ServiceError> {
+        tracing::info!("Selecting input device");
+
+        let result = state.stream_service.select_device(&payload).await?;
+
+        Ok(Json(result))
+    }
+
     // File management endpoints
     async fn upload_file(
         State(state): State<Arc<Self>>,
@@ -555,6 +735,7 @@ pub struct AnalyzeRequest {
 #[derive(Debug, Deserialize)]
 pub struct OcrRequest {
     pub image_path: String,
+    /// Tesseract packs to read the image with first, such as `deu` or `deu+eng`
     pub language: Option<String>,
 }
 
@@ -562,6 +743,15 @@ pub struct OcrRequest {
 pub struct AudioRequest {
     pub audio_path: String,
     pub format: Option<String>,
+    /// Keep the audio as this session's recording, so transcript lines can be played back
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MeetingRequest {
+    pub audio_path: String,
+    /// Session the minutes are recorded into, so `/learning/session/{id}/tasks` can export them
+    pub session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -576,6 +766,45 @@ pub struct InsightsRequest {
     pub insight_types: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ListSessionsQuery {
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+    pub q: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportTasksQuery {
+    /// "ics", "md" or "json"; defaults to JSON
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpeechRequest {
+    /// `{"kind": "summary" | "answer", "text": ...}` or `{"kind": "flashcard", "front": ..., "back": ...}`
+    #[serde(flatten)]
+    pub content: SpeechContent,
+    /// Voice from `/learning/speech/voices`; defaults to the configured voice
+    pub voice: Option<String>,
+    /// Speaking rate from 0.5 to 2.0; defaults to the configured speed
+    pub speed: Option<f32>,
+    /// "wav" or "pcm" (16-bit little-endian mono); defaults to WAV
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchSessionsQuery {
+    pub q: String,
+    pub page: Option<usize>,
+    pub per_page: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchAnalyzeRequest {
+    pub paths: Vec<String>,
+    pub session_id: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PaymentRequest {
     pub amount: u64,
@@ -614,6 +843,11 @@ pub struct RestoreRequest {
     pub restore_config: bool,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ReplaceVocabularyRequest {
+    pub terms: Vec<VocabularyTerm>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StartStreamRequest {
     pub stream_type: String,
@@ -626,6 +860,12 @@ pub struct StreamTranscribeRequest {
     pub format: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct SelectDeviceRequest {
+    /// Device name or id from `/stream/devices`; `None` returns to the default input
+    pub device: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ListFilesQuery {
     pub path: Option<String>,
@@ -27,8 +27,8 @@ pub fn default_config() -> ServiceConfig {
 
 /// Create a new learning service with default configuration
 pub async fn create_learning_service() -> Result<LearningService, ServiceError> {
-    let config = config::LearningConfig::default();
-    LearningService::new(config)
+    let config = ServiceConfig::default();
+    LearningService::new(config.learning, config.stream.audio_config(), &config.settings.data_dir)
 }
 
 /// Create a new payment service with default configuration
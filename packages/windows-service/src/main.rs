@@ -126,10 +126,16 @@ fn run_standalone(mode: RunMode) -> Result<(), ServiceError> {
     tracing::info!("Service configuration loaded: {:?}", config);
 
     // Create service instances
-    let learning_service = Arc::new(LearningService::new(config.learning.clone())?);
+    let learning_service = Arc::new(LearningService::new(
+        config.learning.clone(),
+        config.stream.audio_config(),
+        &config.settings.data_dir,
+    )?);
     let payment_service = Arc::new(PaymentService::new(config.payments.clone())?);
     let settings_service = Arc::new(SettingsService::new(config.settings.clone())?);
-    let stream_service = Arc::new(StreamService::new(config.stream.clone())?);
+    let stream_service = Arc::new(
+        StreamService::new(config.stream.clone())?.with_transcriber(learning_service.audio_transcriber()),
+    );
 
     // Create HTTP server
     let http_server = HttpServer::new(
@@ -2,34 +2,89 @@ use std::sync::Arc;
 use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
-
-use crate::config::{LearningConfig, PaymentConfig, SettingsConfig, StreamConfig};
+use learning::audio::longform::LongFormConfig;
+use learning::audio::{AudioConfig, AudioTranscriber};
+use learning::audio::recorder::{valid_session_id, RecorderConfig, RecordingIndex, SessionRecorder};
+use learning::batch::{BatchItemStatus, BatchProgress, BatchReport, BatchRequest};
+use learning::store::{Pagination, SessionStore};
+use learning::tasks::{TaskExporter, TaskFormat};
+use learning::tts::{AudioFormat, SpeechOptions, SpeechSynthesizer, TtsConfig, MAX_SPEED, MIN_SPEED};
+use learning::vocabulary::{Vocabulary, VocabularyStore, VocabularyTerm};
+
+use crate::config::{LearningConfig, PaymentConfig, ServiceConfig, SettingsConfig, StreamConfig};
 use crate::error::{ServiceError, LearningError, PaymentError, SettingsError, StreamError};
 use crate::http_server::*;
 
+/// Session ids name recording directories, so only letters, digits, `_` and `-` are allowed
+fn check_session_id(session_id: &str) -> Result<(), ServiceError> {
+    if !valid_session_id(session_id) {
+        return Err(ServiceError::invalid_request(format!(
+            "Invalid session id: {} (use 1-64 letters, digits, '_' or '-')",
+            session_id
+        )));
+    }
+    Ok(())
+}
+
 // Learning Service
 pub struct LearningService {
     config: LearningConfig,
     learning_engine: Arc<learning::LearningService>,
-    sessions: Arc<Mutex<std::collections::HashMap<String, SessionData>>>,
+    session_store: Arc<SessionStore>,
+    speech: Arc<SpeechSynthesizer>,
+    batch_jobs: Arc<Mutex<std::collections::HashMap<String, BatchJob>>>,
+    /// Session recordings are kept under `<data dir>/recordings`
+    data_dir: std::path::PathBuf,
 }
 
 impl LearningService {
-    pub fn new(config: LearningConfig) -> Result<Self, ServiceError> {
-        // Initialize the learning engine
+    pub fn new(config: LearningConfig, audio: AudioConfig, data_dir: &std::path::Path) -> Result<Self, ServiceError> {
+        // Open the persistent session store in the data directory
+        let session_store = Arc::new(
+            SessionStore::open_in_dir(data_dir)
+                .map_err(|e| ServiceError::Database(e.to_string()))?,
+        );
+
+        // The user's vocabulary lives next to the sessions
+        let vocabulary = Arc::new(
+            VocabularyStore::open_in_dir(data_dir)
+                .map_err(|e| ServiceError::invalid_request(format!("Invalid vocabulary file: {:#}", e)))?,
+        );
+
+        // Initialize the learning engine without the store: analyses are only persisted
+        // into the session a request names, by `record_in_session`. Live transcription
+        // captures with the stream settings, on the saved input device
         let learning_engine = Arc::new(
             tokio::runtime::Runtime::new()?
-                .block_on(learning::LearningService::new())
+                .block_on(learning::LearningService::with_vocabulary_and_audio(vocabulary, audio))
                 .map_err(|e| ServiceError::Learning(LearningError::AnalysisFailed(e.to_string())))?,
         );
 
+        // Voices load lazily, so this only lists what is in the model directory
+        let speech = Arc::new(
+            SpeechSynthesizer::with_config(TtsConfig {
+                model_dir: Some(config.speech.model_dir.clone().unwrap_or_else(|| config.model_path.join("tts"))),
+                ..config.speech.clone()
+            })
+            .map_err(|e| ServiceError::Learning(LearningError::ModelNotFound(e.to_string())))?,
+        );
+
         Ok(Self {
             config,
             learning_engine,
-            sessions: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            session_store,
+            speech,
+            batch_jobs: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            data_dir: data_dir.to_path_buf(),
         })
     }
 
+    /// The engine's transcriber, shared with the stream service so live capture follows
+    /// the selected input device
+    pub fn audio_transcriber(&self) -> Arc<AudioTranscriber> {
+        self.learning_engine.audio_transcriber()
+    }
+
     pub async fn analyze_content(&self, request: &AnalyzeRequest) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Analyzing content of type: {}", request.content_type);
 
@@ -56,14 +111,9 @@ impl LearningService {
             }
         };
 
-        // Store session data if session_id is provided
+        // Persist the analysis as a session turn if session_id is provided
         if let Some(session_id) = &request.session_id {
-            let mut sessions = self.sessions.lock().await;
-            sessions.insert(session_id.clone(), SessionData {
-                content: request.content.clone(),
-                analysis: analysis_result.clone(),
-                timestamp: chrono::Utc::now(),
-            });
+            self.record_in_session(session_id, &analysis_result).await?;
         }
 
         Ok(serde_json::json!({
@@ -74,18 +124,72 @@ impl LearningService {
         }))
     }
 
+    /// Transcribe a meeting recording and extract its minutes
+    pub async fn analyze_meeting(&self, request: &MeetingRequest) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Analyzing meeting: {}", request.audio_path);
+
+        let meeting = self.learning_engine.analyze_meeting(&request.audio_path).await
+            .map_err(|e| ServiceError::Learning(LearningError::AudioError(e.to_string())))?;
+
+        // The minutes travel in the turn's metadata, where the tasks export finds them
+        if let Some(session_id) = &request.session_id {
+            self.record_in_session(session_id, &meeting.analysis).await?;
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "analysis": meeting.analysis,
+            "minutes": meeting.minutes,
+            "session_id": request.session_id,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    /// Record an analysis as a turn of a requested session
+    async fn record_in_session(
+        &self,
+        session_id: &str,
+        analysis: &learning::analysis::AnalysisResult,
+    ) -> Result<(), ServiceError> {
+        self.session_store
+            .record_analysis(session_id, chrono::Utc::now(), analysis)
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        // The engine only keeps its redaction audit in memory; the requested session stores a copy
+        let audits = self.learning_engine.analysis_engine().redaction_audit().await;
+        if let Some(audit) = audits
+            .iter()
+            .rev()
+            .find(|audit| audit.analysis_id.as_deref() == Some(analysis.analysis_id.as_str()))
+        {
+            self.session_store
+                .record_redactions(session_id, audit)
+                .map_err(|e| ServiceError::Database(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     pub async fn extract_text(&self, request: &OcrRequest) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Extracting text from image: {}", request.image_path);
 
-        // TODO: Implement actual OCR logic using the learning engine
-        let ocr_result = self.learning_engine.ocr_engine().extract_text(&request.image_path).await
+        let (ocr_result, confidence, detected) = self.learning_engine
+            .extract_text_scored(&request.image_path, request.language.as_deref()).await
             .map_err(|e| ServiceError::Learning(LearningError::OCRError(e.to_string())))?;
+        // Tesseract packs the text was read with; too little text to detect a language
+        // reports the ones that were asked for
+        let language = detected.as_ref()
+            .and_then(|detected| detected.ocr_languages.as_deref())
+            .or(request.language.as_deref())
+            .unwrap_or("eng");
 
         Ok(serde_json::json!({
             "success": true,
             "text": ocr_result,
-            "confidence": 0.95,
-            "language": request.language.as_deref().unwrap_or("eng"),
+            "confidence": confidence.score,
+            "low_confidence": confidence.low_confidence,
+            "confidence_breakdown": confidence.fields,
+            "language": language,
+            "detected_language": detected,
             "timestamp": chrono::Utc::now().to_rfc3339()
         }))
     }
@@ -93,6 +197,10 @@ impl LearningService {
     pub async fn transcribe_audio(&self, request: &AudioRequest) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Transcribing audio: {}", request.audio_path);
 
+        if let Some(session_id) = &request.session_id {
+            return self.transcribe_recorded(&request.audio_path, session_id).await;
+        }
+
         // TODO: Implement actual audio transcription logic using the learning engine
         let transcription_result = self.learning_engine.audio_transcriber().transcribe(&request.audio_path).await
             .map_err(|e| ServiceError::Learning(LearningError::AudioError(e.to_string())))?;
@@ -105,6 +213,52 @@ impl LearningService {
         }))
     }
 
+    /// Transcribe a recording in chunks and add it to the session's recording
+    async fn transcribe_recorded(&self, audio_path: &str, session_id: &str) -> Result<serde_json::Value, ServiceError> {
+        check_session_id(session_id)?;
+        let recorder = SessionRecorder::create(&self.data_dir, session_id, RecorderConfig::default())
+            .map_err(|e| ServiceError::internal(format!("Failed to start recording: {}", e)))?;
+
+        let (transcript, index) = self.learning_engine.audio_transcriber()
+            .transcribe_long_form_recorded(audio_path, &LongFormConfig::default(), None, recorder)
+            .await
+            .map_err(|e| ServiceError::Learning(LearningError::AudioError(e.to_string())))?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "transcription": transcript.text(),
+            "segments": transcript.segments,
+            "session_id": session_id,
+            "recording": {
+                "duration_secs": index.duration_secs(),
+                "segments": index.segments.len(),
+                "transcripts": index.transcripts.len(),
+            },
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    /// Segment file spans to play back one transcript line of a session recording
+    pub async fn recording_playback(&self, session_id: &str, transcript: usize) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Getting playback for transcript {} of session: {}", transcript, session_id);
+
+        check_session_id(session_id)?;
+        let index = RecordingIndex::load(&self.data_dir, session_id)
+            .map_err(|_| ServiceError::not_found(format!("No recording for session: {}", session_id)))?;
+        let dir = RecordingIndex::session_dir(&self.data_dir, session_id);
+        let playback = index.playback_for(&dir, transcript)
+            .ok_or_else(|| ServiceError::not_found(format!("Transcript {} not found in recording", transcript)))?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "session_id": session_id,
+            "transcript": index.transcripts[transcript],
+            "format": index.format,
+            "playback": playback,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
     pub async fn generate_summary(&self, request: &SummaryRequest) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Generating summary");
 
@@ -140,8 +294,10 @@ impl LearningService {
     pub async fn get_session(&self, session_id: &str) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Getting session: {}", session_id);
 
-        let sessions = self.sessions.lock().await;
-        if let Some(session_data) = sessions.get(session_id) {
+        let session_data = self.session_store.load_session(session_id)
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        if let Some(session_data) = session_data {
             Ok(serde_json::json!({
                 "success": true,
                 "session_id": session_id,
@@ -153,21 +309,312 @@ impl LearningService {
         }
     }
 
+    pub async fn list_sessions(&self, query: &ListSessionsQuery) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Listing sessions");
+
+        let pagination = Pagination::new(query.page.unwrap_or(1), query.per_page.unwrap_or(20));
+        let page = match query.q.as_deref().filter(|q| !q.trim().is_empty()) {
+            Some(q) => self.session_store.search_sessions(q, pagination),
+            None => self.session_store.list_sessions(pagination),
+        }
+        .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "data": page.sessions,
+            "total": page.total,
+            "page": page.page,
+            "per_page": page.per_page,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn search_sessions(&self, query: &SearchSessionsQuery) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Searching session turns for: {}", query.q);
+
+        if query.q.trim().is_empty() {
+            return Err(ServiceError::invalid_request("Search query must not be empty"));
+        }
+
+        let pagination = Pagination::new(query.page.unwrap_or(1), query.per_page.unwrap_or(20));
+        let matches = self.session_store.search_turns(&query.q, pagination)
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "data": matches,
+            "query": query.q,
+            "page": pagination.page,
+            "per_page": pagination.per_page,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    /// Render the action items of a stored session, returning the content type and body
+    pub async fn export_tasks(
+        &self,
+        session_id: &str,
+        query: &ExportTasksQuery,
+    ) -> Result<(&'static str, String), ServiceError> {
+        tracing::info!("Exporting tasks for session: {}", session_id);
+
+        let format = match query.format.as_deref() {
+            Some(name) => TaskFormat::from_name(name)
+                .ok_or_else(|| ServiceError::invalid_request(format!("Unsupported task format: {}", name)))?,
+            None => TaskFormat::Json,
+        };
+
+        let context = self.session_store.load_session(session_id)
+            .map_err(|e| ServiceError::Database(e.to_string()))?
+            .ok_or_else(|| ServiceError::not_found(format!("Session not found: {}", session_id)))?;
+
+        let body = TaskExporter::new().export(&context, format)
+            .map_err(|e| ServiceError::Learning(LearningError::AnalysisFailed(e.to_string())))?;
+
+        Ok((format.mime_type(), body))
+    }
+
     pub async fn clear_session(&self, session_id: &str) -> Result<(), ServiceError> {
         tracing::info!("Clearing session: {}", session_id);
 
-        let mut sessions = self.sessions.lock().await;
-        sessions.remove(session_id);
-        
+        let deleted = self.session_store.delete_session(session_id)
+            .map_err(|e| ServiceError::Database(e.to_string()))?;
+
+        if !deleted {
+            return Err(ServiceError::not_found(format!("Session not found: {}", session_id)));
+        }
+
+        Ok(())
+    }
+
+    pub async fn start_batch(&self, request: &BatchAnalyzeRequest) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Starting batch analysis of {} paths", request.paths.len());
+
+        if request.paths.is_empty() {
+            return Err(ServiceError::invalid_request("Batch must contain at least one path"));
+        }
+
+        let paths: Vec<std::path::PathBuf> = request.paths.iter().map(std::path::PathBuf::from).collect();
+        let files = learning::batch::collect_paths(&paths)
+            .map_err(|e| ServiceError::invalid_request(format!("Failed to read batch paths: {}", e)))?;
+        if files.is_empty() {
+            return Err(ServiceError::invalid_request("No supported files found in batch paths"));
+        }
+
+        let total = files.len();
+        let batch_request = BatchRequest::new(paths, self.config.max_concurrent_analyses);
+        let batch_id = batch_request.batch_id.clone();
+        {
+            let mut jobs = self.batch_jobs.lock().await;
+            evict_finished_batches(&mut jobs);
+            jobs.insert(batch_id.clone(), BatchJob {
+                batch_id: batch_id.clone(),
+                status: "running".to_string(),
+                total,
+                completed: 0,
+                failed: 0,
+                items: Vec::new(),
+                error: None,
+                report: None,
+                started_at: chrono::Utc::now(),
+                finished_at: None,
+            });
+        }
+
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<BatchProgress>();
+
+        // Fold progress events into the job so it can be polled
+        let jobs = Arc::clone(&self.batch_jobs);
+        let progress_batch_id = batch_id.clone();
+        let folding = tokio::spawn(async move {
+            while let Some(event) = progress_rx.recv().await {
+                if let Some(job) = jobs.lock().await.get_mut(&progress_batch_id) {
+                    job.completed = event.completed;
+                    job.failed = event.failed;
+                    match job.items.iter_mut().find(|item| item.index == event.index) {
+                        Some(item) => *item = event,
+                        None => job.items.push(event),
+                    }
+                }
+            }
+        });
+
+        let engine = Arc::clone(&self.learning_engine);
+        let store = Arc::clone(&self.session_store);
+        let jobs = Arc::clone(&self.batch_jobs);
+        let session_id = request.session_id.clone();
+        tokio::spawn(async move {
+            let outcome = engine.analyze_batch(&batch_request, files, Some(progress_tx)).await;
+
+            // The sender is gone once the batch returns; let the last events land
+            // before the final status so they cannot overwrite it
+            if let Err(e) = folding.await {
+                tracing::error!("Batch progress task failed: {}", e);
+            }
+
+            // Record successful items as turns of the requested session
+            if let (Ok(report), Some(session_id)) = (&outcome, &session_id) {
+                for analysis in report.items.iter().filter_map(|item| item.analysis.as_ref()) {
+                    if let Err(e) = store.record_analysis(session_id, report.started_at, analysis) {
+                        tracing::error!("Failed to record batch analysis: {}", e);
+                    }
+                }
+            }
+
+            if let Some(job) = jobs.lock().await.get_mut(&batch_request.batch_id) {
+                match outcome {
+                    Ok(report) => {
+                        job.status = "completed".to_string();
+                        job.completed = report.total;
+                        job.failed = report.failed;
+                        job.report = Some(report);
+                    }
+                    Err(e) => {
+                        tracing::error!("Batch {} failed: {}", batch_request.batch_id, e);
+                        job.status = "failed".to_string();
+                        job.error = Some(e.to_string());
+                    }
+                }
+                job.finished_at = Some(chrono::Utc::now());
+            }
+        });
+
+        Ok(serde_json::json!({
+            "success": true,
+            "batch_id": batch_id,
+            "status": "running",
+            "total": total,
+            "max_concurrency": self.config.max_concurrent_analyses.max(1),
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn get_batch(&self, batch_id: &str) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Getting batch status: {}", batch_id);
+
+        let jobs = self.batch_jobs.lock().await;
+        let job = jobs.get(batch_id)
+            .ok_or_else(|| ServiceError::not_found(format!("Batch not found: {}", batch_id)))?;
+
+        let running = job.items.iter().filter(|item| item.status == BatchItemStatus::Running).count();
+
+        Ok(serde_json::json!({
+            "success": true,
+            "batch": job,
+            "running": running,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn list_voices(&self) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Listing voices");
+
+        Ok(serde_json::json!({
+            "success": true,
+            "voices": self.speech.voices(),
+            "default_speed": self.speech.config().speed,
+            "min_speed": MIN_SPEED,
+            "max_speed": MAX_SPEED,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    /// Read content aloud, returning the content type and the encoded audio
+    pub async fn synthesize_speech(&self, request: &SpeechRequest) -> Result<(String, Vec<u8>), ServiceError> {
+        tracing::info!("Synthesizing speech");
+
+        let format = match request.format.as_deref() {
+            Some(name) => AudioFormat::from_name(name)
+                .ok_or_else(|| ServiceError::invalid_request(format!("Unsupported audio format: {}", name)))?,
+            None => AudioFormat::Wav,
+        };
+        if let Some(voice) = &request.voice {
+            if !self.speech.voices().iter().any(|info| &info.name == voice) {
+                return Err(ServiceError::invalid_request(format!("Unknown voice: {}", voice)));
+            }
+        }
+        if let Some(speed) = request.speed {
+            if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+                return Err(ServiceError::invalid_request(format!(
+                    "Speed must be between {} and {}", MIN_SPEED, MAX_SPEED
+                )));
+            }
+        }
+
+        // Synthesis is CPU-bound, so keep it off the async workers
+        let speech = Arc::clone(&self.speech);
+        let content = request.content.clone();
+        let options = SpeechOptions {
+            voice: request.voice.clone(),
+            speed: request.speed,
+        };
+        let speech = tokio::task::spawn_blocking(move || speech.speak(&content, &options))
+            .await
+            .map_err(|e| ServiceError::internal(e.to_string()))?
+            .map_err(|e| ServiceError::Learning(LearningError::AnalysisFailed(e.to_string())))?;
+
+        let body = speech.encode(format)
+            .map_err(|e| ServiceError::internal(e.to_string()))?;
+
+        Ok((speech.content_type(format), body))
+    }
+
+    pub async fn get_vocabulary(&self) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Getting vocabulary");
+
+        let vocabulary = self.learning_engine.vocabulary();
+
+        Ok(serde_json::json!({
+            "success": true,
+            "terms": vocabulary.vocabulary().terms,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn replace_vocabulary(&self, request: &ReplaceVocabularyRequest) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Replacing vocabulary with {} terms", request.terms.len());
+
+        let replacement = Vocabulary { terms: request.terms.clone() };
+        replacement.validate()
+            .map_err(|e| ServiceError::invalid_request(e.to_string()))?;
+        self.learning_engine.vocabulary().replace(replacement)?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "terms": request.terms,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn upsert_vocabulary_term(&self, term: &VocabularyTerm) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Saving vocabulary term: {}", term.term);
+
+        if term.term.trim().is_empty() {
+            return Err(ServiceError::invalid_request("Vocabulary terms cannot be empty"));
+        }
+        self.learning_engine.vocabulary().upsert(term.clone())?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "term": term,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    pub async fn remove_vocabulary_term(&self, term: &str) -> Result<(), ServiceError> {
+        tracing::info!("Removing vocabulary term: {}", term);
+
+        if !self.learning_engine.vocabulary().remove(term)? {
+            return Err(ServiceError::not_found(format!("Vocabulary term not found: {}", term)));
+        }
+
         Ok(())
     }
 
     pub async fn dispose(&self) -> Result<(), ServiceError> {
         tracing::info!("Disposing learning service");
         
-        // Clear all sessions
-        let mut sessions = self.sessions.lock().await;
-        sessions.clear();
+        // Sessions are persisted in the session store and survive restarts
         
         // TODO: Clean up learning engine resources
         
@@ -558,17 +1005,79 @@ pub struct StreamService {
     config: StreamConfig,
     stream_active: Arc<Mutex<bool>>,
     audio_buffer: Arc<Mutex<Vec<u8>>>,
+    input_device: Arc<Mutex<Option<String>>>,
+    /// Transcriber whose live capture follows the selected input device
+    transcriber: Option<Arc<AudioTranscriber>>,
 }
 
 impl StreamService {
     pub fn new(config: StreamConfig) -> Result<Self, ServiceError> {
         Ok(Self {
+            input_device: Arc::new(Mutex::new(config.input_device.clone())),
             config,
             stream_active: Arc::new(Mutex::new(false)),
             audio_buffer: Arc::new(Mutex::new(Vec::new())),
+            transcriber: None,
         })
     }
 
+    /// Move the transcriber's live capture along when another input device is selected
+    pub fn with_transcriber(mut self, transcriber: Arc<AudioTranscriber>) -> Self {
+        self.transcriber = Some(transcriber);
+        self
+    }
+
+    pub async fn list_devices(&self) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Listing input devices");
+
+        let devices = learning::audio::devices::list_input_devices()
+            .map_err(|e| ServiceError::service_unavailable(format!("Audio device error: {}", e)))?;
+        let selected = self.input_device.lock().await.clone();
+        let selected_available = selected
+            .as_deref()
+            .map(|name| devices.iter().any(|device| device.name == name));
+
+        Ok(serde_json::json!({
+            "success": true,
+            "devices": devices,
+            "selected": selected,
+            // False when the saved device is unplugged and capture falls back to the default
+            "selected_available": selected_available,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
+    /// Select the input device by name or id and persist the choice by name
+    pub async fn select_device(&self, request: &SelectDeviceRequest) -> Result<serde_json::Value, ServiceError> {
+        tracing::info!("Selecting input device: {:?}", request.device);
+
+        let name = match request.device.as_deref() {
+            Some(selector) => {
+                let devices = learning::audio::devices::list_input_devices()
+                    .map_err(|e| ServiceError::service_unavailable(format!("Audio device error: {}", e)))?;
+                let names: Vec<String> = devices.into_iter().map(|device| device.name).collect();
+                let index = learning::audio::devices::match_device(&names, selector)
+                    .ok_or_else(|| ServiceError::not_found(format!("Input device not found: {}", selector)))?;
+                Some(names[index].clone())
+            }
+            None => None,
+        };
+
+        let mut config = ServiceConfig::load()?;
+        config.stream.input_device = name.clone();
+        config.save()?;
+        *self.input_device.lock().await = name.clone();
+        if let Some(transcriber) = &self.transcriber {
+            transcriber.set_input_device(name.clone());
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "selected": name,
+            "timestamp": chrono::Utc::now().to_rfc3339()
+        }))
+    }
+
     pub async fn start_stream(&self, request: &StartStreamRequest) -> Result<serde_json::Value, ServiceError> {
         tracing::info!("Starting stream: {}", request.stream_type);
 
@@ -608,6 +1117,7 @@ impl StreamService {
                 "buffer_size": self.config.buffer_size,
                 "sample_rate": self.config.sample_rate,
                 "channels": self.config.channels,
+                "input_device": self.input_device.lock().await.clone(),
             },
             "timestamp": chrono::Utc::now().to_rfc3339()
         }))
@@ -660,11 +1170,27 @@ impl StreamService {
 }
 
 // Data structures
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SessionData {
-    pub content: String,
-    pub analysis: serde_json::Value,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchJob {
+    pub batch_id: String,
+    pub status: String,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub items: Vec<BatchProgress>,
+    pub error: Option<String>,
+    pub report: Option<BatchReport>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// How long a finished batch stays available for polling, in seconds
+const BATCH_JOB_TTL_SECS: i64 = 60 * 60;
+
+/// Drop batches that finished more than [`BATCH_JOB_TTL_SECS`] ago
+fn evict_finished_batches(jobs: &mut std::collections::HashMap<String, BatchJob>) {
+    let cutoff = chrono::Utc::now() - chrono::Duration::seconds(BATCH_JOB_TTL_SECS);
+    jobs.retain(|_, job| job.finished_at.map_or(true, |finished_at| finished_at > cutoff));
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
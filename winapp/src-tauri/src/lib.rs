@@ -542,6 +542,50 @@ async fn stop_audio_stream(audio_stream: tauri::State<'_, AudioStream>) -> Resul
     Ok(audio_stream.stop_stream().await)
 }
 
+/// Input devices and the selected one, as saved in the service's stream settings
+#[tauri::command]
+async fn list_input_devices() -> Result<ApiResponse<serde_json::Value>, String> {
+    Ok(match get_service_client().list_input_devices().await {
+        Ok(devices) => ApiResponse::success(devices),
+        Err(e) => ApiResponse::error(format!("Failed to list input devices: {}", e)),
+    })
+}
+
+/// The saved input device and the one currently used for capture
+#[tauri::command]
+async fn get_input_device(audio_stream: tauri::State<'_, AudioStream>) -> Result<ApiResponse<serde_json::Value>, String> {
+    let input = audio_stream.input();
+    Ok(ApiResponse::success(serde_json::json!({
+        "selected": input.get_preferred_device().await,
+        "active": input.get_device_name().await,
+        "sample_rate": input.get_sample_rate().await,
+    })))
+}
+
+/// Select an input device by name or id (`None` for the default).
+///
+/// The service matches the device and saves the choice in its stream settings; capture
+/// here follows the name it resolved to.
+#[tauri::command]
+async fn select_input_device(
+    audio_stream: tauri::State<'_, AudioStream>,
+    device: Option<String>,
+) -> Result<ApiResponse<Option<String>>, String> {
+    let response = match get_service_client().select_input_device(device.as_deref()).await {
+        Ok(response) => response,
+        Err(e) => return Ok(ApiResponse::error(format!("Failed to select input device: {}", e))),
+    };
+
+    let name = selected_device(&response);
+    audio_stream.use_input_device(name.as_deref()).await;
+    Ok(ApiResponse::success(name))
+}
+
+/// The device name the service reports as selected
+fn selected_device(response: &serde_json::Value) -> Option<String> {
+    response.get("selected").and_then(|name| name.as_str()).map(str::to_string)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     // Initialize the service client and bridge
@@ -554,6 +598,20 @@ pub fn run() {
         .setup(|app| {
             eprintln!("Setting up overlay");
             let app_handle = app.handle();
+            app.manage(AudioStream::default());
+
+            // Capture from the device saved in the service's settings once it answers
+            let audio_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                match get_service_client().list_input_devices().await {
+                    Ok(devices) => {
+                        let name = selected_device(&devices);
+                        audio_handle.state::<AudioStream>().use_input_device(name.as_deref()).await;
+                    }
+                    Err(e) => eprintln!("Using the default input, service unavailable: {}", e),
+                }
+            });
+
             let hotkey_manager = HotkeyManager::new(app_handle.clone());
 
             if let Some(window) = app.get_webview_window("main") {
@@ -612,6 +670,9 @@ pub fn run() {
             hide_overlay,
             start_audio_stream,
             stop_audio_stream,
+            list_input_devices,
+            get_input_device,
+            select_input_device,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -382,6 +382,27 @@ impl ServiceClient {
             .await
     }
 
+    /// List input devices and the device selected for capture
+    pub async fn list_input_devices(&self) -> Result<serde_json::Value, String> {
+        self.make_request(reqwest::Method::GET, "/stream/devices", None::<()>)
+            .await
+    }
+
+    /// Select the input device by name or id (`None` for the default); the service saves the choice
+    pub async fn select_input_device(&self, device: Option<&str>) -> Result<serde_json::Value, String> {
+        #[derive(Serialize)]
+        struct SelectDeviceRequest {
+            device: Option<String>,
+        }
+
+        let request = SelectDeviceRequest {
+            device: device.map(str::to_string),
+        };
+
+        self.make_request(reqwest::Method::PUT, "/stream/device", Some(request))
+            .await
+    }
+
     /// Get stream transcription
     pub async fn get_stream_transcription(
         &self,
@@ -1,43 +1,107 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{SampleFormat, SampleRate, StreamConfig};
+use tokio::task::JoinHandle;
+use cpal::traits::DeviceTrait;
+use learning::audio::devices;
+use learning::audio::source::{AudioSource, CpalSource, FileSource, Pacing};
 
 use crate::api_response::ApiResponse;
 
+/// Frames per block requested from input devices
+const CAPTURE_BUFFER_FRAMES: usize = 1024;
+
+/// Where the input stream captures from when it starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputSelection {
+    /// The host's default input device
+    DefaultDevice,
+    /// An input device by the exact name the service resolved the selection to; the
+    /// default input is used while it isn't connected
+    Device(String),
+    /// A WAV or FLAC recording, so the pipeline runs the same without a mic
+    File { path: PathBuf, pacing: Pacing },
+}
+
+impl InputSelection {
+    /// Open the selected source; capture starts as soon as it is open
+    fn open(&self) -> Result<Box<dyn AudioSource>, String> {
+        match self {
+            Self::File { path, pacing } => {
+                let source = FileSource::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+                Ok(Box::new(source.with_pacing(*pacing)))
+            }
+            Self::Device(name) => open_device(Some(name)),
+            Self::DefaultDevice => open_device(None),
+        }
+    }
+}
+
+/// Open an input device at its default configuration, falling back to the default input
+fn open_device(name: Option<&str>) -> Result<Box<dyn AudioSource>, String> {
+    let resolved = devices::resolve_input_device(name).map_err(|e| e.to_string())?;
+    if resolved.fell_back {
+        eprintln!("Input device {} not found, using {}", name.unwrap_or_default(), resolved.name);
+    }
+    let config = resolved
+        .device
+        .default_input_config()
+        .map_err(|e| format!("Failed to read the configuration of {}: {}", resolved.name, e))?;
+    let source = CpalSource::open(resolved.device, config.sample_rate().0, config.channels(), CAPTURE_BUFFER_FRAMES)
+        .map_err(|e| format!("Failed to start capturing from {}: {}", resolved.name, e))?;
+    Ok(Box::new(source))
+}
+
+/// The source being captured from while the stream runs
+#[derive(Debug, Clone)]
+struct ActiveSource {
+    name: String,
+    sample_rate: u32,
+}
+
 pub struct InputStream {
-    device: Option<cpal::Device>,
-    config: Option<StreamConfig>,
+    selection: Arc<Mutex<InputSelection>>,
+    active: Arc<Mutex<Option<ActiveSource>>>,
     is_recording: Arc<Mutex<bool>>,
+    /// Task pulling blocks from the source into the output stream
+    pump: Arc<Mutex<Option<JoinHandle<()>>>>,
 }
 
 impl InputStream {
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let host = cpal::default_host();
-        let device = host.default_input_device()
-            .ok_or("No default input device found")?;
-        
-        let config = device.default_input_config()?;
-        
-        Ok(Self {
-            device: Some(device),
-            config: Some(config.into()),
+    pub fn new(selection: InputSelection) -> Self {
+        Self {
+            selection: Arc::new(Mutex::new(selection)),
+            active: Arc::new(Mutex::new(None)),
             is_recording: Arc::new(Mutex::new(false)),
-        })
+            pump: Arc::new(Mutex::new(None)),
+        }
     }
 
-    pub fn get_device_name(&self) -> Option<String> {
-        self.device.as_ref().and_then(|d| d.name().ok())
+    pub async fn get_device_name(&self) -> Option<String> {
+        self.active.lock().await.as_ref().map(|active| active.name.clone())
     }
 
-    pub fn get_config(&self) -> Option<&StreamConfig> {
-        self.config.as_ref()
+    pub async fn get_sample_rate(&self) -> Option<u32> {
+        self.active.lock().await.as_ref().map(|active| active.sample_rate)
+    }
+
+    pub async fn get_preferred_device(&self) -> Option<String> {
+        match &*self.selection.lock().await {
+            InputSelection::Device(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    pub async fn is_recording(&self) -> bool {
+        *self.is_recording.lock().await
     }
 }
 
 pub struct OutputStream {
     buffer: Arc<Mutex<Vec<f32>>>,
     is_processing: Arc<Mutex<bool>>,
+    /// Samples handed to processing so far
+    processed: Arc<Mutex<usize>>,
 }
 
 impl OutputStream {
@@ -45,9 +109,14 @@ impl OutputStream {
         Self {
             buffer: Arc::new(Mutex::new(Vec::new())),
             is_processing: Arc::new(Mutex::new(false)),
+            processed: Arc::new(Mutex::new(0)),
         }
     }
 
+    pub async fn processed_samples(&self) -> usize {
+        *self.processed.lock().await
+    }
+
     pub async fn add_audio_data(&self, data: Vec<f32>) {
         let mut buffer = self.buffer.lock().await;
         buffer.extend(data);
@@ -55,6 +124,7 @@ impl OutputStream {
         // Process data in chunks (e.g., every 1024 samples)
         if buffer.len() >= 1024 {
             let chunk: Vec<f32> = buffer.drain(..1024).collect();
+            *self.processed.lock().await += chunk.len();
             self.process_chunk(chunk).await;
         }
     }
@@ -74,19 +144,43 @@ pub struct AudioStream {
     output_stream: OutputStream,
 }
 
-impl AudioStream {
-    pub fn default() -> Self {
-        Self::new().expect("Failed to create audio stream")
+impl Default for AudioStream {
+    /// Capture from the default input device
+    fn default() -> Self {
+        Self::with_input(InputStream::new(InputSelection::DefaultDevice))
     }
+}
 
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let input_stream = InputStream::new()?;
-        let output_stream = OutputStream::new();
-        
-        Ok(Self {
+impl AudioStream {
+    pub fn with_input(input_stream: InputStream) -> Self {
+        Self {
             input_stream,
-            output_stream,
-        })
+            output_stream: OutputStream::new(),
+        }
+    }
+
+    pub fn input(&self) -> &InputStream {
+        &self.input_stream
+    }
+
+    /// Capture from the device the service has selected; `None` selects the default input.
+    ///
+    /// The service matches and saves the choice, so `name` is an exact device name. A
+    /// device that isn't connected is kept as preferred and used once it is plugged in.
+    /// Takes effect the next time the stream starts.
+    pub async fn use_input_device(&self, name: Option<&str>) {
+        *self.input_stream.selection.lock().await = match name {
+            Some(name) => InputSelection::Device(name.to_string()),
+            None => InputSelection::DefaultDevice,
+        };
+    }
+
+    /// Capture from a WAV or FLAC recording instead of a device, from the next start
+    pub async fn use_input_file(&self, path: impl Into<PathBuf>, pacing: Pacing) {
+        *self.input_stream.selection.lock().await = InputSelection::File {
+            path: path.into(),
+            pacing,
+        };
     }
 
     pub async fn start_stream(&self) -> ApiResponse<()> {
@@ -94,43 +188,41 @@ impl AudioStream {
         if *is_recording {
             return ApiResponse::error("Stream is already running".to_string());
         }
-        *is_recording =! *is_recording;
-        drop(is_recording);
 
-        let device = match self.input_stream.device.as_ref() {
-            Some(d) => d,
-            None => return ApiResponse::error("No input device available".to_string()),
-        };
-        let config = match self.input_stream.config.as_ref() {
-            Some(c) => c,
-            None => return ApiResponse::error("No stream config available".to_string()),
+        let selection = self.input_stream.selection.lock().await.clone();
+        let mut source = match selection.open() {
+            Ok(source) => source,
+            Err(e) => return ApiResponse::error(e),
         };
+        let name = source.name();
+        *self.input_stream.active.lock().await = Some(ActiveSource {
+            name: name.clone(),
+            sample_rate: source.sample_rate(),
+        });
 
         let output_stream = self.output_stream.clone();
-
-        let data_fn = move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let output_stream = output_stream.clone();
-            let audio_data: Vec<f32> = data.to_vec();
-            tokio::spawn(async move {
-                output_stream.add_audio_data(audio_data).await;
-            });
-        };
-
-        let error_fn = |err| {
-            eprintln!("Audio stream error: {}", err);
-        };
-
-        match device.build_input_stream(config, data_fn, error_fn, None) {
-            Ok(stream) => {
-                if let Err(e) = stream.play() {
-                    return ApiResponse::error(format!("Failed to start stream: {}", e));
+        let recording = self.input_stream.is_recording.clone();
+        let active = self.input_stream.active.clone();
+        let pump = tokio::spawn(async move {
+            loop {
+                match source.next_block().await {
+                    Ok(Some(block)) => output_stream.add_audio_data(block).await,
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!("Audio capture error: {}", e);
+                        break;
+                    }
                 }
-                
-                println!("Audio stream started successfully");
-                ApiResponse::success(())
             }
-            Err(e) => ApiResponse::error(format!("Failed to build stream: {}", e)),
-        }
+            // The recording ran out or the device went away
+            *recording.lock().await = false;
+            *active.lock().await = None;
+        });
+        *self.input_stream.pump.lock().await = Some(pump);
+        *is_recording = true;
+
+        println!("Audio stream started successfully from {}", name);
+        ApiResponse::success(())
     }
 
     pub async fn stop_stream(&self) -> ApiResponse<()> {
@@ -141,6 +233,12 @@ impl AudioStream {
         *is_recording = false;
         drop(is_recording);
 
+        // Dropping the source stops its capture
+        if let Some(pump) = self.input_stream.pump.lock().await.take() {
+            pump.abort();
+        }
+        *self.input_stream.active.lock().await = None;
+
         println!("Audio stream stopped successfully");
         ApiResponse::success(())
     }
@@ -149,11 +247,7 @@ impl AudioStream {
 impl Clone for AudioStream {
     fn clone(&self) -> Self {
         Self {
-            input_stream: InputStream {
-                device: self.input_stream.device.clone(),
-                config: self.input_stream.config.clone(),
-                is_recording: self.input_stream.is_recording.clone(),
-            },
+            input_stream: self.input_stream.clone(),
             output_stream: self.output_stream.clone(),
         }
     }
@@ -162,9 +256,10 @@ impl Clone for AudioStream {
 impl Clone for InputStream {
     fn clone(&self) -> Self {
         Self {
-            device: self.device.clone(),
-            config: self.config.clone(),
+            selection: self.selection.clone(),
+            active: self.active.clone(),
             is_recording: self.is_recording.clone(),
+            pump: self.pump.clone(),
         }
     }
 }
@@ -174,6 +269,91 @@ impl Clone for OutputStream {
         Self {
             buffer: self.buffer.clone(),
             is_processing: self.is_processing.clone(),
+            processed: self.processed.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_tone(name: &str, sample_rate: u32, seconds: f32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("winapp-streams-{}-{}.wav", std::process::id(), name));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..(sample_rate as f32 * seconds) as usize {
+            let t = i as f32 / sample_rate as f32;
+            let sample = (t * 440.0 * 2.0 * std::f32::consts::PI).sin() * 0.5;
+            writer.write_sample((sample * i16::MAX as f32) as i16).unwrap();
         }
+        writer.finalize().unwrap();
+        path
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_file_source_feeds_output_until_it_ends() {
+        let path = write_tone("feeds", 16000, 1.0);
+        let stream = AudioStream::default();
+        stream.use_input_file(&path, Pacing::Unthrottled).await;
+
+        assert!(stream.start_stream().await.success);
+        let pump = stream.input_stream.pump.lock().await.take().unwrap();
+        pump.await.unwrap();
+
+        // Whole 1024-sample chunks were processed; the rest waits in the buffer
+        assert_eq!(stream.output_stream.processed_samples().await, 15 * 1024);
+        assert_eq!(stream.output_stream.buffer.lock().await.len(), 16000 - 15 * 1024);
+        // The stream stops by itself once the recording ends
+        assert!(!stream.input().is_recording().await);
+        assert_eq!(stream.input().get_device_name().await, None);
+        assert!(stream.start_stream().await.success);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_stop_while_capturing() {
+        let path = write_tone("stop", 16000, 5.0);
+        let stream = AudioStream::default();
+        stream.use_input_file(&path, Pacing::RealTime).await;
+
+        assert!(stream.start_stream().await.success);
+        assert!(stream.input().is_recording().await);
+        assert_eq!(stream.input().get_sample_rate().await, Some(16000));
+        assert!(!stream.start_stream().await.success);
+
+        assert!(stream.stop_stream().await.success);
+        assert!(!stream.input().is_recording().await);
+        assert_eq!(stream.input().get_device_name().await, None);
+        assert!(!stream.stop_stream().await.success);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_does_not_start() {
+        let stream = AudioStream::default();
+        stream.use_input_file("/nonexistent/recording.wav", Pacing::Unthrottled).await;
+
+        assert!(!stream.start_stream().await.success);
+        assert!(!stream.input().is_recording().await);
+    }
+
+    #[tokio::test]
+    async fn test_device_selection() {
+        let stream = AudioStream::default();
+        assert_eq!(stream.input().get_preferred_device().await, None);
+
+        stream.use_input_device(Some("USB Microphone")).await;
+        assert_eq!(stream.input().get_preferred_device().await, Some("USB Microphone".to_string()));
+
+        stream.use_input_device(None).await;
+        assert_eq!(*stream.input_stream.selection.lock().await, InputSelection::DefaultDevice);
+    }
+}